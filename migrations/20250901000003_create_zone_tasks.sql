@@ -0,0 +1,13 @@
+-- zone_tasks
+CREATE TABLE IF NOT EXISTS zone_tasks (
+  id TEXT PRIMARY KEY,
+  zone_id TEXT NOT NULL,
+  title TEXT NOT NULL,
+  required INTEGER NOT NULL DEFAULT 1,
+  checked_at TEXT,
+  created_at TEXT NOT NULL,
+  updated_at TEXT NOT NULL,
+  deleted_at TEXT,
+  FOREIGN KEY(zone_id) REFERENCES zones(id)
+);
+CREATE INDEX IF NOT EXISTS idx_zone_tasks_zone_id ON zone_tasks(zone_id);
@@ -0,0 +1,7 @@
+-- job_leases
+CREATE TABLE IF NOT EXISTS job_leases (
+  job TEXT PRIMARY KEY,
+  holder TEXT NOT NULL,
+  acquired_at TEXT NOT NULL,
+  expires_at TEXT NOT NULL
+);
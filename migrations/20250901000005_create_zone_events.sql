@@ -0,0 +1,29 @@
+-- zone_events: append-only history of zone mutations
+CREATE TABLE IF NOT EXISTS zone_events (
+  id TEXT PRIMARY KEY,
+  zone_id TEXT NOT NULL,
+  kind TEXT NOT NULL,
+  payload TEXT NOT NULL,
+  occurred_at TEXT NOT NULL,
+  recorded_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_zone_events_zone_id ON zone_events(zone_id, occurred_at);
+CREATE INDEX IF NOT EXISTS idx_zone_events_kind ON zone_events(kind, occurred_at);
+
+-- существующие зоны получают синтетическое событие создания
+INSERT INTO zone_events(id, zone_id, kind, payload, occurred_at, recorded_at)
+SELECT lower(hex(randomblob(16))), id, 'created',
+       json_object('kind', 'created', 'room_id', room_id, 'name', name, 'icon', icon,
+                   'frequency', frequency, 'custom_interval_days', custom_interval_days),
+       created_at, created_at
+FROM zones;
+
+INSERT INTO zone_events(id, zone_id, kind, payload, occurred_at, recorded_at)
+SELECT lower(hex(randomblob(16))), id, 'cleaned', json_object('kind', 'cleaned'),
+       last_cleaned_at, last_cleaned_at
+FROM zones WHERE last_cleaned_at IS NOT NULL;
+
+INSERT INTO zone_events(id, zone_id, kind, payload, occurred_at, recorded_at)
+SELECT lower(hex(randomblob(16))), id, 'deleted', json_object('kind', 'deleted'),
+       deleted_at, deleted_at
+FROM zones WHERE deleted_at IS NOT NULL;
@@ -0,0 +1,3 @@
+-- manual ordering; 0 keeps newest-first among items never reordered
+ALTER TABLE rooms ADD COLUMN sort_order INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE zones ADD COLUMN sort_order INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,19 @@
+-- tags
+CREATE TABLE IF NOT EXISTS tags (
+  id TEXT PRIMARY KEY,
+  name TEXT NOT NULL,
+  color TEXT,
+  created_at TEXT NOT NULL,
+  updated_at TEXT NOT NULL,
+  deleted_at TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_tags_name ON tags(name);
+
+CREATE TABLE IF NOT EXISTS zone_tags (
+  zone_id TEXT NOT NULL,
+  tag_id TEXT NOT NULL,
+  PRIMARY KEY(zone_id, tag_id),
+  FOREIGN KEY(zone_id) REFERENCES zones(id),
+  FOREIGN KEY(tag_id) REFERENCES tags(id)
+);
+CREATE INDEX IF NOT EXISTS idx_zone_tags_tag_id ON zone_tags(tag_id);
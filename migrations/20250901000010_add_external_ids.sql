@@ -0,0 +1,9 @@
+-- mapping to records in other systems for idempotent imports
+ALTER TABLE rooms ADD COLUMN source TEXT;
+ALTER TABLE rooms ADD COLUMN external_id TEXT;
+ALTER TABLE zones ADD COLUMN source TEXT;
+ALTER TABLE zones ADD COLUMN external_id TEXT;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_rooms_external ON rooms(source, external_id)
+  WHERE external_id IS NOT NULL AND deleted_at IS NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_zones_external ON zones(source, external_id)
+  WHERE external_id IS NOT NULL AND deleted_at IS NULL;
@@ -0,0 +1,21 @@
+-- supplies
+CREATE TABLE IF NOT EXISTS supplies (
+  id TEXT PRIMARY KEY,
+  name TEXT NOT NULL,
+  unit TEXT,
+  quantity REAL NOT NULL DEFAULT 0,
+  low_threshold REAL NOT NULL DEFAULT 0,
+  created_at TEXT NOT NULL,
+  updated_at TEXT NOT NULL,
+  deleted_at TEXT
+);
+
+CREATE TABLE IF NOT EXISTS zone_supplies (
+  zone_id TEXT NOT NULL,
+  supply_id TEXT NOT NULL,
+  usage REAL NOT NULL DEFAULT 1,
+  PRIMARY KEY(zone_id, supply_id),
+  FOREIGN KEY(zone_id) REFERENCES zones(id),
+  FOREIGN KEY(supply_id) REFERENCES supplies(id)
+);
+CREATE INDEX IF NOT EXISTS idx_zone_supplies_supply_id ON zone_supplies(supply_id);
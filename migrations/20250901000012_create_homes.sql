@@ -0,0 +1,22 @@
+-- homes: level above rooms (apartment, cottage, ...)
+CREATE TABLE IF NOT EXISTS homes (
+  id TEXT PRIMARY KEY,
+  name TEXT NOT NULL,
+  icon TEXT,
+  is_default INTEGER NOT NULL DEFAULT 0,
+  created_at TEXT NOT NULL,
+  updated_at TEXT NOT NULL,
+  deleted_at TEXT
+);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_homes_default ON homes(is_default)
+  WHERE is_default = 1 AND deleted_at IS NULL;
+
+ALTER TABLE rooms ADD COLUMN home_id TEXT REFERENCES homes(id);
+CREATE INDEX IF NOT EXISTS idx_rooms_home_id ON rooms(home_id);
+
+-- все существующие комнаты переезжают в дом по умолчанию
+INSERT INTO homes(id, name, icon, is_default, created_at, updated_at, deleted_at)
+VALUES (lower(hex(randomblob(16))), 'Home', NULL, 1,
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now'), strftime('%Y-%m-%dT%H:%M:%fZ', 'now'), NULL);
+
+UPDATE rooms SET home_id = (SELECT id FROM homes WHERE is_default = 1);
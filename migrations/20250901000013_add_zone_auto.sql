@@ -0,0 +1,2 @@
+-- зоны, которые убирает техника (робот-пылесос)
+ALTER TABLE zones ADD COLUMN auto INTEGER NOT NULL DEFAULT 0;
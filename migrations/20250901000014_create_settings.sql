@@ -0,0 +1,8 @@
+-- settings: одна строка на инстанс
+CREATE TABLE IF NOT EXISTS settings (
+  id INTEGER PRIMARY KEY CHECK (id = 1),
+  max_zones_per_day INTEGER,
+  updated_at TEXT NOT NULL
+);
+INSERT OR IGNORE INTO settings(id, max_zones_per_day, updated_at)
+VALUES (1, NULL, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'));
@@ -0,0 +1,13 @@
+-- supply_purchases: покупки расходников
+CREATE TABLE IF NOT EXISTS supply_purchases (
+  id TEXT PRIMARY KEY,
+  supply_id TEXT NOT NULL,
+  quantity REAL NOT NULL,
+  price_cents INTEGER NOT NULL,
+  vendor TEXT,
+  purchased_at TEXT NOT NULL,
+  created_at TEXT NOT NULL,
+  FOREIGN KEY(supply_id) REFERENCES supplies(id)
+);
+CREATE INDEX IF NOT EXISTS idx_supply_purchases_supply_id ON supply_purchases(supply_id, purchased_at);
+CREATE INDEX IF NOT EXISTS idx_supply_purchases_purchased_at ON supply_purchases(purchased_at);
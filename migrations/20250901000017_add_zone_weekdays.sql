@@ -0,0 +1,2 @@
+-- для частоты weekdays: бит 0 — понедельник, бит 6 — воскресенье
+ALTER TABLE zones ADD COLUMN weekday_mask INTEGER;
@@ -0,0 +1,2 @@
+-- IANA-имя часового пояса; NULL — сроки считаются по точному времени в UTC
+ALTER TABLE settings ADD COLUMN timezone TEXT;
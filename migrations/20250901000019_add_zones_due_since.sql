@@ -0,0 +1,2 @@
+-- момент, когда планировщик заметил, что зона стала «к уборке»; сбрасывается уборкой
+ALTER TABLE zones ADD COLUMN due_since TEXT;
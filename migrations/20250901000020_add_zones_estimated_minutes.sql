@@ -0,0 +1,2 @@
+-- примерное время уборки зоны в минутах
+ALTER TABLE zones ADD COLUMN estimated_minutes INTEGER;
@@ -0,0 +1,2 @@
+-- зона «на паузе» до указанного момента: не попадает в списки к уборке
+ALTER TABLE zones ADD COLUMN paused_until TEXT;
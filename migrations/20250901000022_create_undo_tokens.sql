@@ -0,0 +1,10 @@
+-- undo_tokens: отмена удаления в течение короткого окна
+CREATE TABLE IF NOT EXISTS undo_tokens (
+  token TEXT PRIMARY KEY,
+  kind TEXT NOT NULL,
+  target_id TEXT NOT NULL,
+  deleted_at TEXT NOT NULL,
+  links TEXT, -- JSON связей, удалённых вместе с объектом (метки, группы, расходники)
+  expires_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_undo_tokens_expires_at ON undo_tokens(expires_at);
@@ -0,0 +1,2 @@
+-- первый день недели для недельной статистики, ISO: 1 = понедельник … 7 = воскресенье
+ALTER TABLE settings ADD COLUMN week_starts_on INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,4 @@
+-- денормализованный срок следующей уборки (с учётом паузы и часового пояса);
+-- NULL — ещё не убиралась, т.е. к уборке сейчас. Заполняется приложением.
+ALTER TABLE zones ADD COLUMN next_due_at TEXT;
+CREATE INDEX IF NOT EXISTS idx_zones_next_due_at ON zones(next_due_at) WHERE deleted_at IS NULL;
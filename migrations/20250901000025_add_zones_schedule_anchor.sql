@@ -0,0 +1,2 @@
+-- с какого момента считать срок после смены расписания (если позже последней уборки)
+ALTER TABLE zones ADD COLUMN schedule_anchor_at TEXT;
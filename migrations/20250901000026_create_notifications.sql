@@ -0,0 +1,11 @@
+-- notifications: история отправленных уведомлений для ленты в приложении
+CREATE TABLE IF NOT EXISTS notifications (
+  id TEXT PRIMARY KEY,
+  event TEXT NOT NULL,
+  summary TEXT NOT NULL,
+  data TEXT NOT NULL, -- JSON, то же, что в поле data вебхука
+  created_at TEXT NOT NULL,
+  read_at TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_notifications_created_at ON notifications(created_at);
+CREATE INDEX IF NOT EXISTS idx_notifications_unread ON notifications(created_at) WHERE read_at IS NULL;
@@ -0,0 +1,9 @@
+-- idempotency_keys: ответы на POST с заголовком Idempotency-Key, чтобы повтор не создавал дубли
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+  key TEXT PRIMARY KEY,
+  request_hash TEXT NOT NULL, -- sha256 метода, пути и тела
+  status INTEGER, -- NULL, пока первый запрос выполняется
+  body TEXT,
+  created_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_idempotency_keys_created_at ON idempotency_keys(created_at);
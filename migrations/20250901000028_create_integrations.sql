@@ -0,0 +1,21 @@
+-- integrations: входящие подписанные вызовы от устройств и сервисов (робот-пылесос, кнопка)
+CREATE TABLE IF NOT EXISTS integrations (
+  id TEXT PRIMARY KEY,
+  name TEXT NOT NULL,
+  secret TEXT NOT NULL,
+  mappings TEXT NOT NULL, -- JSON [{event, zone_id}]
+  active INTEGER NOT NULL DEFAULT 1,
+  created_at TEXT NOT NULL,
+  updated_at TEXT NOT NULL,
+  deleted_at TEXT
+);
+
+-- уже принятые nonce, чтобы перехваченный вызов нельзя было повторить
+CREATE TABLE IF NOT EXISTS integration_nonces (
+  integration_id TEXT NOT NULL,
+  nonce TEXT NOT NULL,
+  seen_at TEXT NOT NULL,
+  PRIMARY KEY (integration_id, nonce),
+  FOREIGN KEY(integration_id) REFERENCES integrations(id)
+);
+CREATE INDEX IF NOT EXISTS idx_integration_nonces_seen_at ON integration_nonces(seen_at);
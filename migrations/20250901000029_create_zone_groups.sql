@@ -0,0 +1,17 @@
+-- zone_groups: рабочие наборы зон из разных комнат («Уборка к выходным», «К приходу гостей»)
+CREATE TABLE IF NOT EXISTS zone_groups (
+  id TEXT PRIMARY KEY,
+  name TEXT NOT NULL,
+  created_at TEXT NOT NULL,
+  updated_at TEXT NOT NULL,
+  deleted_at TEXT
+);
+
+CREATE TABLE IF NOT EXISTS zone_group_members (
+  group_id TEXT NOT NULL,
+  zone_id TEXT NOT NULL,
+  PRIMARY KEY(group_id, zone_id),
+  FOREIGN KEY(group_id) REFERENCES zone_groups(id),
+  FOREIGN KEY(zone_id) REFERENCES zones(id)
+);
+CREATE INDEX IF NOT EXISTS idx_zone_group_members_zone_id ON zone_group_members(zone_id);
@@ -0,0 +1,16 @@
+-- bulk_operations: длинные массовые операции над зонами, выполняются порциями в фоне
+CREATE TABLE IF NOT EXISTS bulk_operations (
+  id TEXT PRIMARY KEY,
+  kind TEXT NOT NULL,
+  status TEXT NOT NULL, -- pending, running, done, failed
+  request TEXT NOT NULL, -- тело запроса (JSON), по нему операция продолжается после перезапуска
+  total INTEGER NOT NULL,
+  processed INTEGER NOT NULL DEFAULT 0,
+  updated INTEGER NOT NULL DEFAULT 0,
+  results TEXT NOT NULL DEFAULT '[]',
+  error TEXT,
+  created_at TEXT NOT NULL,
+  updated_at TEXT NOT NULL,
+  finished_at TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_bulk_operations_status ON bulk_operations(status);
@@ -0,0 +1,29 @@
+-- stats_version: растёт при любом изменении зон, комнат, тегов зон и настроек;
+-- закэшированная статистика хранится под ключом с этой версией
+CREATE TABLE IF NOT EXISTS stats_version (
+  id INTEGER PRIMARY KEY CHECK (id = 1),
+  version INTEGER NOT NULL
+);
+INSERT OR IGNORE INTO stats_version(id, version) VALUES (1, 0);
+
+CREATE TRIGGER IF NOT EXISTS stats_version_zones_insert AFTER INSERT ON zones
+BEGIN UPDATE stats_version SET version = version + 1; END;
+CREATE TRIGGER IF NOT EXISTS stats_version_zones_update AFTER UPDATE ON zones
+BEGIN UPDATE stats_version SET version = version + 1; END;
+CREATE TRIGGER IF NOT EXISTS stats_version_zones_delete AFTER DELETE ON zones
+BEGIN UPDATE stats_version SET version = version + 1; END;
+
+CREATE TRIGGER IF NOT EXISTS stats_version_rooms_insert AFTER INSERT ON rooms
+BEGIN UPDATE stats_version SET version = version + 1; END;
+CREATE TRIGGER IF NOT EXISTS stats_version_rooms_update AFTER UPDATE ON rooms
+BEGIN UPDATE stats_version SET version = version + 1; END;
+CREATE TRIGGER IF NOT EXISTS stats_version_rooms_delete AFTER DELETE ON rooms
+BEGIN UPDATE stats_version SET version = version + 1; END;
+
+CREATE TRIGGER IF NOT EXISTS stats_version_zone_tags_insert AFTER INSERT ON zone_tags
+BEGIN UPDATE stats_version SET version = version + 1; END;
+CREATE TRIGGER IF NOT EXISTS stats_version_zone_tags_delete AFTER DELETE ON zone_tags
+BEGIN UPDATE stats_version SET version = version + 1; END;
+
+CREATE TRIGGER IF NOT EXISTS stats_version_settings_update AFTER UPDATE ON settings
+BEGIN UPDATE stats_version SET version = version + 1; END;
@@ -0,0 +1,12 @@
+-- frequency: только известные значения. CHECK в SQLite не добавить без пересборки
+-- таблицы, на zones ссылаются другие таблицы — проверяем триггерами
+-- неизвестные значения раньше означали «всегда к уборке» — ближе всего daily
+UPDATE zones SET frequency = 'daily'
+WHERE frequency NOT IN ('daily', 'weekly', 'biweekly', 'monthly', 'monthly_weekday', 'quarterly', 'yearly', 'custom', 'weekdays');
+
+CREATE TRIGGER IF NOT EXISTS zones_frequency_insert BEFORE INSERT ON zones
+WHEN NEW.frequency NOT IN ('daily', 'weekly', 'biweekly', 'monthly', 'monthly_weekday', 'quarterly', 'yearly', 'custom', 'weekdays')
+BEGIN SELECT RAISE(ABORT, 'unknown zone frequency'); END;
+CREATE TRIGGER IF NOT EXISTS zones_frequency_update BEFORE UPDATE OF frequency ON zones
+WHEN NEW.frequency NOT IN ('daily', 'weekly', 'biweekly', 'monthly', 'monthly_weekday', 'quarterly', 'yearly', 'custom', 'weekdays')
+BEGIN SELECT RAISE(ABORT, 'unknown zone frequency'); END;
@@ -0,0 +1,33 @@
+-- google_calendars: подключённые календари Google, не больше одного на дом
+CREATE TABLE IF NOT EXISTS google_calendars (
+  id TEXT PRIMARY KEY,
+  home_id TEXT REFERENCES homes(id),
+  calendar_id TEXT NOT NULL DEFAULT 'primary',
+  refresh_token TEXT NOT NULL,
+  access_token TEXT,
+  access_expires_at TEXT,
+  last_synced_at TEXT,
+  last_error TEXT,
+  created_at TEXT NOT NULL,
+  updated_at TEXT NOT NULL
+);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_google_calendars_home ON google_calendars(COALESCE(home_id, ''));
+
+-- выданные на согласие state; живут несколько минут
+CREATE TABLE IF NOT EXISTS google_oauth_states (
+  state TEXT PRIMARY KEY,
+  home_id TEXT,
+  created_at TEXT NOT NULL
+);
+
+-- событие календаря для очередной уборки зоны; event_id NULL — событие удалили
+-- в календаре, и для этого due_at его не создаём заново
+CREATE TABLE IF NOT EXISTS google_calendar_events (
+  zone_id TEXT NOT NULL REFERENCES zones(id),
+  calendar_id TEXT NOT NULL REFERENCES google_calendars(id) ON DELETE CASCADE,
+  event_id TEXT,
+  etag TEXT,
+  due_at TEXT NOT NULL,
+  synced_at TEXT NOT NULL,
+  PRIMARY KEY (calendar_id, zone_id)
+);
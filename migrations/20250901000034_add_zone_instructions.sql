@@ -0,0 +1,2 @@
+-- инструкции пользователя к зоне, показываются рядом с чек-листом
+ALTER TABLE zones ADD COLUMN instructions TEXT;
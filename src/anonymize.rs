@@ -0,0 +1,79 @@
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+
+use crate::{error::AppResult, models::Db};
+
+/// Scrubbing pipeline, run in order on the copy. Rows, ids, schedules and dates
+/// are kept so the data stays realistic; free text, integration ids and
+/// credentials are replaced or dropped.
+pub const STEPS: &[(&str, &str)] = &[
+    ("home names", "UPDATE homes SET name = 'Home ' || substr(id, 1, 4)"),
+    ("zone notes", "UPDATE zones SET notes = 'Note ' || substr(id, 1, 8) WHERE notes IS NOT NULL"),
+    (
+        "zone instructions",
+        "UPDATE zones SET instructions = 'Instructions ' || substr(id, 1, 8) WHERE instructions IS NOT NULL",
+    ),
+    ("zone metadata", "UPDATE zones SET metadata = '{}' WHERE metadata IS NOT NULL"),
+    (
+        "zone external ids",
+        "UPDATE zones SET external_id = 'ext-' || substr(id, 1, 8) WHERE external_id IS NOT NULL",
+    ),
+    (
+        "room external ids",
+        "UPDATE rooms SET external_id = 'ext-' || substr(id, 1, 8) WHERE external_id IS NOT NULL",
+    ),
+    // история зон хранит те же поля в JSON
+    (
+        "event notes",
+        r#"UPDATE zone_events SET payload = json_set(payload, '$.note', 'Note')
+           WHERE json_extract(payload, '$.note') IS NOT NULL"#,
+    ),
+    (
+        "event zone notes",
+        r#"UPDATE zone_events SET payload = json_set(payload, '$.notes', 'Note ' || substr(zone_id, 1, 8))
+           WHERE json_extract(payload, '$.notes') IS NOT NULL"#,
+    ),
+    (
+        "event metadata",
+        r#"UPDATE zone_events SET payload = json_set(payload, '$.metadata', json('{}'))
+           WHERE json_extract(payload, '$.metadata') IS NOT NULL"#,
+    ),
+    (
+        "event external ids",
+        r#"UPDATE zone_events SET payload = json_set(payload, '$.external_id', 'ext-' || substr(zone_id, 1, 8))
+           WHERE json_extract(payload, '$.external_id') IS NOT NULL"#,
+    ),
+    (
+        "notification notes",
+        r#"UPDATE notifications SET data = json_set(data, '$.change.note', 'Note')
+           WHERE json_extract(data, '$.change.note') IS NOT NULL"#,
+    ),
+    ("purchase vendors", "UPDATE supply_purchases SET vendor = 'Vendor' WHERE vendor IS NOT NULL"),
+    (
+        "webhook secrets",
+        "UPDATE webhooks SET secret = '', url = 'https://example.invalid/hook', active = 0",
+    ),
+    ("integration secrets", "UPDATE integrations SET secret = '', active = 0"),
+    ("webhook deliveries", "DELETE FROM webhook_deliveries"),
+    ("job leases", "DELETE FROM job_leases"),
+];
+
+/// Writes a scrubbed copy of the database to `dest`, which must not exist yet.
+/// The source database is only read and must be file-backed (`VACUUM INTO`).
+pub async fn anonymize(pool: &Db, dest: &str) -> AppResult<()> {
+    sqlx::query("VACUUM INTO ?1").bind(dest).execute(pool).await?;
+
+    let copy = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(SqliteConnectOptions::new().filename(dest))
+        .await?;
+    let mut tx = copy.begin().await?;
+    for (step, sql) in STEPS {
+        let rows = sqlx::query(sql).execute(&mut *tx).await?.rows_affected();
+        tracing::info!(step, rows, "anonymize");
+    }
+    tx.commit().await?;
+    // иначе старый текст остаётся в свободных страницах файла
+    sqlx::query("VACUUM").execute(&copy).await?;
+    copy.close().await;
+    Ok(())
+}
@@ -2,53 +2,258 @@ use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
 use super::{
-    rooms,
-    stats::{self, StatsOverview},
-    zones::{self, BulkClean, BulkCleanResponse, CleanBody},
+    groups::{self, GroupClean, GroupProgress},
+    export::{self, CleaningRow, ZoneRow},
+    google_calendar::{self, ConnectUrl},
+    graphql,
+    homes,
+    integrations::{self, InboundEvent, InboundResult},
+    metrics,
+    notifications::{self, UnreadCount},
+    operations,
+    pagination::SortOrder,
+    rooms::{self, RoomSort},
+    settings,
+    stats::{
+        self, Badge, BreakdownGroup, CostBucket, DueCount, Focus, FocusRoom, Heatmap, HeatmapDay,
+        OverdueBucket, OverdueZone, StatsOverview, Streaks, Suggestion, TimeSpent, Today, TrendBucket,
+    },
+    supplies, tags, tasks,
+    undo::{self, Undoable, Undone},
+    voice::{self, VoiceIntent, VoiceReply},
+    webhooks,
+    zones::{
+        self, AutoCleanTrigger, BulkClean, BulkDelete, BulkResponse, BulkUpdate, CleanBody, ZoneSort,
+    },
 };
 
 use crate::models::{
-    Frequency, NewRoom, NewZone, Room, RoomView, UpdateRoom, UpdateZone, Zone, ZoneView,
+    BulkItem, BulkOperation, BulkStatus, Checklist, Frequency, GoogleCalendar, Home, Integration, IntegrationMapping,
+    LinkSupply, NewHome, NewIntegration, NewRoom, NewSupply, NewSupplyPurchase, NewTag,
+    NewWebhook, NewZone, NewZoneGroup, NewZoneTask, Notification, Operation, PauseZone, Reorder,
+    Reschedule, Room, RoomPage, RoomView, Settings, Supply, SupplyPurchase, Tag, UpdateHome,
+    UpdateIntegration, UpdateRoom, UpdateSettings, UpdateSupply, UpdateTag, UpdateWebhook,
+    UpdateZone, UpdateZoneGroup, UpdateZoneTask, Webhook, WebhookDelivery, Zone, ZoneChange,
+    ZoneEvent, ZoneGroup, ZoneInstructions, ZonePage, ZoneSupply, ZoneTask, ZoneView,
 };
 
 #[derive(OpenApi)]
 #[openapi(
     paths(
+        homes::list_homes,
+        homes::create_home,
+        homes::update_home,
+        homes::delete_home,
         rooms::list_rooms,
         rooms::create_room,
         rooms::get_room,
         rooms::update_room,
         rooms::delete_room,
         rooms::restore_room,
+        rooms::reorder_rooms,
         zones::list_zones,
+        zones::list_all_zones,
         zones::create_zone,
+        zones::create_zones,
+        zones::put_zone,
         zones::get_zone,
         zones::update_zone,
         zones::delete_zone,
         zones::clean_zone,
+        zones::pause_zone,
+        zones::resume_zone,
         zones::bulk_clean,
+        zones::bulk_delete,
+        zones::bulk_update,
+        operations::create_operation,
+        operations::get_operation,
+        zones::trigger_auto_clean,
+        zones::list_events,
+        zones::reorder_zones,
+        tasks::list_tasks,
+        tasks::create_task,
+        tasks::update_task,
+        tasks::delete_task,
+        tasks::put_instructions,
+        tags::list_tags,
+        tags::create_tag,
+        tags::update_tag,
+        tags::delete_tag,
+        tags::list_zone_tags,
+        tags::attach_tag,
+        tags::detach_tag,
+        groups::list_groups,
+        groups::create_group,
+        groups::get_group,
+        groups::update_group,
+        groups::delete_group,
+        groups::list_group_zones,
+        groups::add_zone,
+        groups::remove_zone,
+        groups::clean_group,
+        supplies::list_supplies,
+        supplies::low_supplies,
+        supplies::create_supply,
+        supplies::get_supply,
+        supplies::update_supply,
+        supplies::delete_supply,
+        supplies::list_purchases,
+        supplies::create_purchase,
+        supplies::list_zone_supplies,
+        supplies::link_supply,
+        supplies::unlink_supply,
         stats::overview,
+        stats::costs,
+        stats::streaks,
+        stats::heatmap,
+        stats::trends,
+        stats::overdue,
+        stats::time_spent,
+        export::cleanings,
+        export::zones,
+        graphql::execute,
+        stats::breakdown,
         stats::zones_due,
+        stats::today,
+        stats::suggestion,
+        stats::badge,
+        stats::focus,
+        webhooks::list_webhooks,
+        webhooks::create_webhook,
+        webhooks::update_webhook,
+        webhooks::delete_webhook,
+        webhooks::list_deliveries,
+        undo::undo,
+        integrations::list_integrations,
+        integrations::create_integration,
+        integrations::update_integration,
+        integrations::delete_integration,
+        integrations::inbound,
+        google_calendar::list_calendars,
+        google_calendar::connect,
+        google_calendar::callback,
+        google_calendar::disconnect,
+        voice::intents,
+        notifications::list_notifications,
+        notifications::unread_count,
+        notifications::mark_read,
+        notifications::mark_all_read,
+        settings::get_settings,
+        settings::update_settings,
+        metrics::metrics,
     ),
     components(schemas(
+        Home,
+        NewHome,
+        UpdateHome,
         Room,
         RoomView,
+        RoomPage,
         NewRoom,
         UpdateRoom,
         Zone,
         ZoneView,
+        ZonePage,
+        ZoneSort,
+        RoomSort,
+        SortOrder,
         NewZone,
         UpdateZone,
+        ZoneChange,
+        ZoneEvent,
+        ZoneTask,
+        NewZoneTask,
+        UpdateZoneTask,
+        Checklist,
+        ZoneInstructions,
+        Tag,
+        NewTag,
+        UpdateTag,
+        ZoneGroup,
+        NewZoneGroup,
+        UpdateZoneGroup,
+        GroupClean,
+        GroupProgress,
+        Supply,
+        NewSupply,
+        UpdateSupply,
+        ZoneSupply,
+        LinkSupply,
+        SupplyPurchase,
+        NewSupplyPurchase,
         Frequency,
+        Reorder,
+        Reschedule,
+        PauseZone,
         CleanBody,
         BulkClean,
-        BulkCleanResponse,
+        BulkDelete,
+        BulkUpdate,
+        BulkResponse,
+        BulkItem,
+        BulkOperation,
+        Operation,
+        BulkStatus,
+        AutoCleanTrigger,
         StatsOverview,
+        CostBucket,
+        Streaks,
+        Heatmap,
+        HeatmapDay,
+        TrendBucket,
+        OverdueBucket,
+        OverdueZone,
+        TimeSpent,
+        CleaningRow,
+        ZoneRow,
+        BreakdownGroup,
+        DueCount,
+        Today,
+        Suggestion,
+        Badge,
+        Focus,
+        FocusRoom,
+        Settings,
+        UpdateSettings,
+        Webhook,
+        NewWebhook,
+        UpdateWebhook,
+        WebhookDelivery,
+        Undoable,
+        Undone,
+        Integration,
+        IntegrationMapping,
+        NewIntegration,
+        UpdateIntegration,
+        InboundEvent,
+        InboundResult,
+        GoogleCalendar,
+        ConnectUrl,
+        VoiceIntent,
+        VoiceReply,
+        Notification,
+        UnreadCount,
     )),
     tags(
+        (name = "homes", description = "Homes grouping rooms"),
         (name = "rooms", description = "Operations with rooms"),
         (name = "zones", description = "Operations with zones"),
+        (name = "tasks", description = "Checklist tasks inside zones"),
+        (name = "tags", description = "Tags grouping zones across rooms"),
+        (name = "groups", description = "Workflow groups of zones across rooms"),
+        (name = "operations", description = "Bulk changes to zones run in the background"),
+        (name = "supplies", description = "Cleaning supplies inventory"),
         (name = "stats", description = "Statistics overview"),
+        (name = "settings", description = "Instance-wide preferences"),
+        (name = "webhooks", description = "Outgoing event notifications"),
+        (name = "undo", description = "Reversing a delete shortly after it"),
+        (name = "integrations", description = "Signed calls from devices that clean zones"),
+        (name = "google_calendar", description = "Due zones as Google Calendar events, ticked off to clean"),
+        (name = "voice", description = "Intents from voice assistants, answered with speech"),
+        (name = "notifications", description = "History of sent notifications"),
+        (name = "metrics", description = "Product gauges for monitoring"),
+        (name = "export", description = "Cleaning history and zones for spreadsheets"),
+        (name = "graphql", description = "Rooms, zones and stats as one GraphQL schema"),
     ),
     servers((url = "/api/v1"))
 )]
@@ -0,0 +1,276 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use futures_util::{stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use sqlx::{sqlite::SqliteRow, FromRow};
+use tokio::sync::mpsc;
+use tracing::Instrument;
+use utoipa::ToSchema;
+
+use super::homes::{HomeParams, HomeScope};
+use crate::models::{AppState, Frequency};
+
+/// Rows sent to the client per body chunk.
+const ROWS_PER_CHUNK: usize = 100;
+
+/// A row of an export, as CSV cells in the order of `HEADER`.
+pub trait CsvRow {
+    const HEADER: &'static [&'static str];
+    fn cells(&self) -> Vec<String>;
+}
+
+#[derive(Serialize, Deserialize, ToSchema, FromRow)]
+pub struct CleaningRow {
+    pub occurred_at: DateTime<Utc>,
+    pub zone_id: String,
+    pub zone_name: String,
+    pub room_id: String,
+    pub room_name: String,
+    pub note: Option<String>,
+    pub auto: bool,
+    pub cost_cents: Option<i64>,
+    pub duration_minutes: Option<i64>,
+}
+
+impl CsvRow for CleaningRow {
+    const HEADER: &'static [&'static str] = &[
+        "occurred_at",
+        "zone_id",
+        "zone_name",
+        "room_id",
+        "room_name",
+        "note",
+        "auto",
+        "cost_cents",
+        "duration_minutes",
+    ];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.occurred_at.to_rfc3339(),
+            self.zone_id.clone(),
+            self.zone_name.clone(),
+            self.room_id.clone(),
+            self.room_name.clone(),
+            self.note.clone().unwrap_or_default(),
+            self.auto.to_string(),
+            self.cost_cents.map(|c| c.to_string()).unwrap_or_default(),
+            self.duration_minutes.map(|m| m.to_string()).unwrap_or_default(),
+        ]
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema, FromRow)]
+pub struct ZoneRow {
+    pub id: String,
+    pub room_id: String,
+    pub room_name: String,
+    pub name: String,
+    pub frequency: Frequency,
+    pub custom_interval_days: Option<i64>,
+    pub estimated_minutes: Option<i64>,
+    pub last_cleaned_at: Option<DateTime<Utc>>,
+    pub next_due_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl CsvRow for ZoneRow {
+    const HEADER: &'static [&'static str] = &[
+        "id",
+        "room_id",
+        "room_name",
+        "name",
+        "frequency",
+        "custom_interval_days",
+        "estimated_minutes",
+        "last_cleaned_at",
+        "next_due_at",
+        "created_at",
+    ];
+
+    fn cells(&self) -> Vec<String> {
+        let time = |t: Option<DateTime<Utc>>| t.map(|t| t.to_rfc3339()).unwrap_or_default();
+        vec![
+            self.id.clone(),
+            self.room_id.clone(),
+            self.room_name.clone(),
+            self.name.clone(),
+            self.frequency.as_str().to_string(),
+            self.custom_interval_days.map(|d| d.to_string()).unwrap_or_default(),
+            self.estimated_minutes.map(|m| m.to_string()).unwrap_or_default(),
+            time(self.last_cleaned_at),
+            time(self.next_due_at),
+            self.created_at.to_rfc3339(),
+        ]
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Format {
+    Csv,
+    Json,
+}
+
+impl Format {
+    /// JSON when the client asks for it and not for CSV; CSV otherwise.
+    fn negotiate(headers: &HeaderMap) -> Self {
+        let accept = headers.get(header::ACCEPT).and_then(|v| v.to_str().ok()).unwrap_or_default();
+        if accept.contains("application/json") && !accept.contains("text/csv") {
+            Format::Json
+        } else {
+            Format::Csv
+        }
+    }
+
+    fn start<T: CsvRow>(self) -> String {
+        match self {
+            Format::Csv => csv_line(T::HEADER.iter().map(|h| h.to_string())),
+            Format::Json => "[".into(),
+        }
+    }
+
+    fn row<T: CsvRow + Serialize>(self, row: &T, first: bool) -> String {
+        match self {
+            Format::Csv => csv_line(row.cells()),
+            Format::Json => {
+                let json = serde_json::to_string(row).expect("export rows serialize");
+                if first { json } else { format!(",{json}") }
+            }
+        }
+    }
+
+    fn end(self) -> &'static str {
+        match self {
+            Format::Csv => "",
+            Format::Json => "]",
+        }
+    }
+}
+
+/// Cells quoted as RFC 4180 asks: only when they hold a comma, quote or line break.
+fn csv_line(cells: impl IntoIterator<Item = String>) -> String {
+    let mut line = cells
+        .into_iter()
+        .map(|c| match c.contains([',', '"', '\n', '\r']) {
+            true => format!("\"{}\"", c.replace('"', "\"\"")),
+            false => c,
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    line.push_str("\r\n");
+    line
+}
+
+/// Streams the rows of `sql`, with the home bound to `?1`, as they are read;
+/// nothing is buffered beyond a chunk. A database error midway cuts the
+/// response short.
+fn export<T>(
+    state: &AppState,
+    sql: &'static str,
+    home_id: Option<String>,
+    headers: &HeaderMap,
+    filename: &str,
+) -> Response
+where
+    T: for<'r> FromRow<'r, SqliteRow> + CsvRow + Serialize + Send + Unpin + 'static,
+{
+    let format = Format::negotiate(headers);
+    let pool = state.pool.clone();
+    let (tx, rx) = mpsc::channel::<Result<String, sqlx::Error>>(4);
+    tokio::spawn(async move {
+        let mut rows = sqlx::query_as::<_, T>(sql).bind(home_id).fetch(&pool).chunks(ROWS_PER_CHUNK);
+        let mut chunk = format.start::<T>();
+        let mut first = true;
+        while let Some(batch) = rows.next().await {
+            for row in batch {
+                match row {
+                    Ok(row) => chunk.push_str(&format.row(&row, first)),
+                    Err(e) => {
+                        tracing::warn!(error = %e, "export stopped");
+                        let _ = tx.send(Err(e)).await;
+                        return;
+                    }
+                }
+                first = false;
+            }
+            // клиент ушёл — дальше читать незачем
+            if tx.send(Ok(std::mem::take(&mut chunk))).await.is_err() {
+                return;
+            }
+        }
+        chunk.push_str(format.end());
+        let _ = tx.send(Ok(chunk)).await;
+    }
+    .in_current_span());
+
+    let body = Body::from_stream(stream::unfold(rx, |mut rx| async move { rx.recv().await.map(|c| (c, rx)) }));
+    let (content_type, disposition) = match format {
+        Format::Csv => ("text/csv; charset=utf-8", format!("attachment; filename=\"{filename}\"")),
+        Format::Json => ("application/json", "inline".to_string()),
+    };
+    ([(header::CONTENT_TYPE, content_type.to_string()), (header::CONTENT_DISPOSITION, disposition)], body)
+        .into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/export/cleanings.csv",
+    params(HomeParams),
+    responses((status = 200, description = "Every cleaning, oldest first; JSON with `Accept: application/json`",
+        content(("text/csv" = String), ("application/json" = [CleaningRow]))))
+)]
+pub async fn cleanings(
+    State(state): State<Arc<AppState>>,
+    HomeScope(home_id): HomeScope,
+    headers: HeaderMap,
+) -> Response {
+    export::<CleaningRow>(
+        &state,
+        r#"SELECT e.occurred_at, e.zone_id, z.name AS zone_name, z.room_id, r.name AS room_name,
+                  json_extract(e.payload, '$.note') AS note,
+                  COALESCE(json_extract(e.payload, '$.auto'), 0) AS auto,
+                  json_extract(e.payload, '$.cost_cents') AS cost_cents,
+                  json_extract(e.payload, '$.duration_minutes') AS duration_minutes
+           FROM zone_events e
+           JOIN zones z ON z.id = e.zone_id
+           JOIN rooms r ON r.id = z.room_id
+           WHERE e.kind = 'cleaned' AND (?1 IS NULL OR r.home_id = ?1)
+           ORDER BY e.occurred_at ASC, e.id ASC"#,
+        home_id,
+        &headers,
+        "cleanings.csv",
+    )
+}
+
+#[utoipa::path(
+    get,
+    path = "/export/zones.csv",
+    params(HomeParams),
+    responses((status = 200, description = "Every zone that is not deleted; JSON with `Accept: application/json`",
+        content(("text/csv" = String), ("application/json" = [ZoneRow]))))
+)]
+pub async fn zones(
+    State(state): State<Arc<AppState>>,
+    HomeScope(home_id): HomeScope,
+    headers: HeaderMap,
+) -> Response {
+    export::<ZoneRow>(
+        &state,
+        r#"SELECT z.id, z.room_id, r.name AS room_name, z.name, z.frequency, z.custom_interval_days,
+                  z.estimated_minutes, z.last_cleaned_at, z.next_due_at, z.created_at
+           FROM zones z
+           JOIN rooms r ON r.id = z.room_id
+           WHERE z.deleted_at IS NULL AND (?1 IS NULL OR r.home_id = ?1)
+           ORDER BY r.sort_order ASC, r.name ASC, z.sort_order ASC, z.name ASC"#,
+        home_id,
+        &headers,
+        "zones.csv",
+    )
+}
@@ -0,0 +1,63 @@
+use std::{collections::HashMap, sync::Arc};
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Path},
+    http::request::Parts,
+};
+
+use crate::{
+    error::{AppError, AppResult},
+    models::{AppState, Room, Zone, ROOM_COLUMNS, ZONE_COLUMNS},
+};
+
+/// Value of the first of `keys` found among the path segments.
+async fn path_id(parts: &mut Parts, state: &Arc<AppState>, keys: &[&str]) -> AppResult<String> {
+    let Path(params) = Path::<HashMap<String, String>>::from_request_parts(parts, state)
+        .await
+        .map_err(|e| AppError::Validation(e.body_text()))?;
+    keys.iter()
+        .find_map(|k| params.get(*k).cloned())
+        .ok_or_else(|| AppError::Other(anyhow::anyhow!("route has no {} segment", keys.join("/"))))
+}
+
+/// Room named by `:room_id` (or `:id`) in the path; 404 before the handler
+/// runs when it does not exist or was deleted.
+pub struct LiveRoom(pub Room);
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for LiveRoom {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &Arc<AppState>) -> AppResult<Self> {
+        let id = path_id(parts, state, &["room_id", "id"]).await?;
+        sqlx::query_as::<_, Room>(&format!(
+            "SELECT {ROOM_COLUMNS} FROM rooms WHERE id = ?1 AND deleted_at IS NULL"
+        ))
+        .bind(&id)
+        .fetch_optional(&state.pool)
+        .await?
+        .map(LiveRoom)
+        .ok_or(AppError::NotFound)
+    }
+}
+
+/// Zone named by `:id` in the path, like `LiveRoom`.
+pub struct LiveZone(pub Zone);
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for LiveZone {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &Arc<AppState>) -> AppResult<Self> {
+        let id = path_id(parts, state, &["id"]).await?;
+        sqlx::query_as::<_, Zone>(&format!(
+            "SELECT {ZONE_COLUMNS} FROM zones WHERE id = ?1 AND deleted_at IS NULL"
+        ))
+        .bind(&id)
+        .fetch_optional(&state.pool)
+        .await?
+        .map(LiveZone)
+        .ok_or(AppError::NotFound)
+    }
+}
@@ -0,0 +1,73 @@
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Query},
+    http::request::Parts,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use utoipa::IntoParams;
+
+use crate::{
+    error::{AppError, AppResult},
+    models::Page,
+};
+
+#[derive(Deserialize, IntoParams, Default)]
+pub struct FieldsParams {
+    /// Comma-separated top-level fields to return, e.g. `id,name,is_due`;
+    /// every field when unset.
+    pub fields: Option<String>,
+}
+
+/// Field selection from `?fields=`; applied to a response with `one` or `page`.
+pub struct Fields(Option<Vec<String>>);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Fields {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> AppResult<Self> {
+        let Query(p) = Query::<FieldsParams>::try_from_uri(&parts.uri)
+            .map_err(|e| AppError::Validation(e.body_text()))?;
+        let Some(list) = p.fields else {
+            return Ok(Fields(None));
+        };
+        let names: Vec<String> = list
+            .split(',')
+            .map(str::trim)
+            .filter(|f| !f.is_empty())
+            .map(str::to_string)
+            .collect();
+        if names.is_empty() {
+            return Err(AppError::Validation("fields must name at least one field".into()));
+        }
+        Ok(Fields(Some(names)))
+    }
+}
+
+impl Fields {
+    /// `value` as JSON with only the selected fields; an unknown name is a 400.
+    pub fn one<T: Serialize>(&self, value: &T) -> AppResult<Value> {
+        let json = serde_json::to_value(value).map_err(|e| AppError::Other(e.into()))?;
+        let Some(names) = &self.0 else {
+            return Ok(json);
+        };
+        let Value::Object(mut all) = json else {
+            return Err(AppError::Other(anyhow::anyhow!("only objects can be projected")));
+        };
+        let mut picked = Map::with_capacity(names.len());
+        for name in names {
+            let v = all
+                .remove(name)
+                .ok_or_else(|| AppError::Validation(format!("unknown field '{name}'")))?;
+            picked.insert(name.clone(), v);
+        }
+        Ok(Value::Object(picked))
+    }
+
+    /// `page` with each item projected by `one`; `total` and `next_cursor` stay.
+    pub fn page<T: Serialize>(&self, page: Page<T>) -> AppResult<Page<Value>> {
+        let items = page.items.iter().map(|i| self.one(i)).collect::<AppResult<_>>()?;
+        Ok(Page { items, total: page.total, next_cursor: page.next_cursor })
+    }
+}
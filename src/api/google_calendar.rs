@@ -0,0 +1,210 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use super::homes::{HomeParams, HomeScope};
+use crate::{
+    error::{AppError, AppResult},
+    google_calendar::{self, SCOPE},
+    models::{AppState, GoogleCalendar, GOOGLE_CALENDAR_COLUMNS},
+};
+
+/// How long a consent started with `connect` may take.
+const STATE_TTL_MINUTES: i64 = 10;
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ConnectUrl {
+    /// Google's consent page; it sends the browser back to the callback.
+    pub url: String,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct CallbackParams {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    /// Set by Google instead of `code` when consent was not given.
+    pub error: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/integrations/google-calendar",
+    responses((status = 200, description = "Connected calendars", body = [GoogleCalendar]))
+)]
+pub async fn list_calendars(State(state): State<std::sync::Arc<AppState>>) -> AppResult<Json<Vec<GoogleCalendar>>> {
+    let calendars = sqlx::query_as::<_, GoogleCalendar>(&format!(
+        "SELECT {GOOGLE_CALENDAR_COLUMNS} FROM google_calendars ORDER BY created_at ASC"
+    ))
+    .fetch_all(&state.pool)
+    .await?;
+    Ok(Json(calendars))
+}
+
+#[utoipa::path(
+    post,
+    path = "/integrations/google-calendar/connect",
+    params(HomeParams),
+    responses(
+        (status = 200, description = "Open the URL to give access to the primary calendar", body = ConnectUrl),
+        (status = 400, description = "Google Calendar is not configured"),
+    )
+)]
+pub async fn connect(
+    State(state): State<std::sync::Arc<AppState>>,
+    HomeScope(home_id): HomeScope,
+) -> AppResult<Json<ConnectUrl>> {
+    let (client_id, _, redirect_url) = state.google.client()?;
+    let now = Utc::now();
+    let nonce = Uuid::new_v4().simple().to_string();
+    let mut tx = state.writer.begin().await?;
+    sqlx::query("DELETE FROM google_oauth_states WHERE created_at < ?1")
+        .bind(now - chrono::Duration::minutes(STATE_TTL_MINUTES))
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("INSERT INTO google_oauth_states(state, home_id, created_at) VALUES (?1, ?2, ?3)")
+        .bind(&nonce)
+        .bind(&home_id)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    let url = reqwest::Url::parse_with_params(
+        &state.google.auth_url,
+        [
+            ("client_id", client_id),
+            ("redirect_uri", redirect_url),
+            ("response_type", "code"),
+            ("scope", SCOPE),
+            // refresh token нужен для синхронизации без пользователя
+            ("access_type", "offline"),
+            ("prompt", "consent"),
+            ("state", &nonce),
+        ],
+    )
+    .map_err(|e| AppError::Other(anyhow::anyhow!("Google auth URL: {e}")))?;
+    Ok(Json(ConnectUrl { url: url.into() }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/integrations/google-calendar/callback",
+    params(CallbackParams),
+    responses(
+        (status = 200, description = "Calendar connected to the home `connect` was called for", body = GoogleCalendar),
+        (status = 400, description = "Consent refused, or `state` unknown or expired"),
+        (status = 401, description = "Google did not accept the code"),
+    )
+)]
+pub async fn callback(
+    State(state): State<std::sync::Arc<AppState>>,
+    Query(params): Query<CallbackParams>,
+) -> AppResult<Json<GoogleCalendar>> {
+    if let Some(error) = params.error {
+        return Err(AppError::Validation(format!("Google consent was not given: {error}")));
+    }
+    let (_, _, redirect_url) = state.google.client()?;
+    let now = Utc::now();
+    let home: Option<(Option<String>,)> =
+        sqlx::query_as("DELETE FROM google_oauth_states WHERE state = ?1 AND created_at >= ?2 RETURNING home_id")
+            .bind(&params.state)
+            .bind(now - chrono::Duration::minutes(STATE_TTL_MINUTES))
+            .fetch_optional(&state.writer)
+            .await?;
+    let (home_id,) = home.ok_or_else(|| {
+        AppError::Validation("unknown or expired state; start again with POST /integrations/google-calendar/connect".into())
+    })?;
+    let code = params.code.ok_or_else(|| AppError::Validation("code is required".into()))?;
+
+    let tokens = google_calendar::request_tokens(
+        &state,
+        &[("grant_type", "authorization_code"), ("code", &code), ("redirect_uri", redirect_url)],
+    )
+    .await?;
+    let mut tx = state.writer.begin().await?;
+    let existing: Option<(String,)> = sqlx::query_as("SELECT id FROM google_calendars WHERE home_id IS ?1")
+        .bind(&home_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+    let id = match existing {
+        // повторное подключение: новые токены, события остаются свои
+        Some((id,)) => {
+            sqlx::query(
+                r#"UPDATE google_calendars
+                   SET refresh_token = COALESCE(?1, refresh_token), access_token = ?2, access_expires_at = ?3,
+                       last_error = NULL, updated_at = ?4
+                   WHERE id = ?5"#,
+            )
+            .bind(tokens.refresh_token.as_deref().map(|t| state.cipher.seal(t)))
+            .bind(state.cipher.seal(&tokens.access_token))
+            .bind(tokens.expires_at())
+            .bind(now)
+            .bind(&id)
+            .execute(&mut *tx)
+            .await?;
+            id
+        }
+        None => {
+            let refresh_token = tokens.refresh_token.as_deref().ok_or_else(|| {
+                AppError::Validation(
+                    "Google sent no refresh token; remove the app's access in the Google account and connect again"
+                        .into(),
+                )
+            })?;
+            let id = Uuid::new_v4().to_string();
+            sqlx::query(
+                r#"INSERT INTO google_calendars(id, home_id, calendar_id, refresh_token, access_token, access_expires_at,
+                                                created_at, updated_at)
+                   VALUES (?1, ?2, 'primary', ?3, ?4, ?5, ?6, ?6)"#,
+            )
+            .bind(&id)
+            .bind(&home_id)
+            .bind(state.cipher.seal(refresh_token))
+            .bind(state.cipher.seal(&tokens.access_token))
+            .bind(tokens.expires_at())
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+            id
+        }
+    };
+    let calendar = sqlx::query_as::<_, GoogleCalendar>(&format!(
+        "SELECT {GOOGLE_CALENDAR_COLUMNS} FROM google_calendars WHERE id = ?1"
+    ))
+    .bind(&id)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(Json(calendar))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/integrations/google-calendar/{id}",
+    params(("id" = String, Path, description = "Connected calendar id")),
+    responses((status = 204, description = "Disconnected; tokens forgotten, events already created stay"))
+)]
+pub async fn disconnect(
+    State(state): State<std::sync::Arc<AppState>>,
+    Path(id): Path<String>,
+) -> AppResult<axum::http::StatusCode> {
+    let mut tx = state.writer.begin().await?;
+    sqlx::query("DELETE FROM google_calendar_events WHERE calendar_id = ?1")
+        .bind(&id)
+        .execute(&mut *tx)
+        .await?;
+    let res = sqlx::query("DELETE FROM google_calendars WHERE id = ?1")
+        .bind(&id)
+        .execute(&mut *tx)
+        .await?;
+    if res.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    tx.commit().await?;
+    Ok(axum::http::StatusCode::NO_CONTENT)
+}
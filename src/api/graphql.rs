@@ -0,0 +1,334 @@
+//! `/graphql`: rooms with their zones, due zones and stats in one request. The
+//! resolvers reuse the REST queries and write paths, so events, webhooks and
+//! cached stats behave the same.
+
+use std::sync::{Arc, OnceLock};
+
+use async_graphql::{Context, EmptySubscription, ErrorExtensions, Object, Schema, SimpleObject};
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+
+use super::{
+    extract::LiveRoom,
+    homes::{HomeParams, HomeScope},
+    pagination::PageParams,
+    rooms::{self, ListParams},
+    stats::{self, OverviewParams, StatsOverview},
+    zones::{self, CleanBody},
+};
+use chrono::{DateTime, Utc};
+
+use crate::{
+    error::AppError,
+    models::{self, AppState, NewRoom, NewZone, Room, RoomView, Zone, ZoneView, ROOM_COLUMNS, ZONE_COLUMNS},
+};
+
+/// Deepest selection accepted; `rooms { zones { id } }` is three levels.
+const MAX_DEPTH: usize = 6;
+
+pub type CleanerSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
+
+pub fn schema() -> &'static CleanerSchema {
+    static SCHEMA: OnceLock<CleanerSchema> = OnceLock::new();
+    SCHEMA.get_or_init(|| Schema::build(QueryRoot, MutationRoot, EmptySubscription).limit_depth(MAX_DEPTH).finish())
+}
+
+/// Home the request is scoped to, as [`HomeScope`] resolved it.
+struct Home(Option<String>);
+
+type GqlResult<T> = async_graphql::Result<T>;
+
+/// Same message and `code` (in `extensions`) as the REST error body.
+fn gql(e: impl Into<AppError>) -> async_graphql::Error {
+    let e = e.into();
+    let (status, code) = e.status_code();
+    let message = e.to_string();
+    if status.is_server_error() {
+        tracing::error!(code, error = %message, "graphql request failed");
+    }
+    async_graphql::Error::new(message).extend_with(|_, ext| ext.set("code", code))
+}
+
+fn state(ctx: &Context<'_>) -> Arc<AppState> {
+    ctx.data_unchecked::<Arc<AppState>>().clone()
+}
+
+fn home(ctx: &Context<'_>) -> Option<String> {
+    ctx.data_unchecked::<Home>().0.clone()
+}
+
+/// Zones are only loaded when the selection asks for them.
+fn wants_zones(ctx: &Context<'_>) -> bool {
+    ctx.look_ahead().field("zones").exists()
+}
+
+/// Schedule of a zone.
+#[derive(async_graphql::Enum, Clone, Copy, PartialEq, Eq)]
+#[graphql(remote = "models::Frequency")]
+enum Frequency {
+    Daily,
+    Weekly,
+    Biweekly,
+    /// Same day of the month as the last clean, clamped to shorter months.
+    Monthly,
+    /// Same weekday and week of the month as the last clean, e.g. the second
+    /// Tuesday; a clean in the fifth week means the last such weekday.
+    MonthlyWeekday,
+    Quarterly,
+    Yearly,
+    Custom,
+    /// On the days listed in `weekdays`.
+    Weekdays,
+}
+
+// имена типов те же, что были у REST-моделей, чтобы не ломать клиентов
+#[derive(SimpleObject)]
+#[graphql(name = "ZoneView")]
+struct ZoneObject {
+    id: String,
+    room_id: String,
+    name: String,
+    icon: Option<String>,
+    notes: Option<String>,
+    /// Free-form integrator data, e.g. `{"ha_entity": "vacuum.kitchen"}`.
+    metadata: Option<serde_json::Value>,
+    frequency: Frequency,
+    custom_interval_days: Option<i64>,
+    /// ISO weekdays (1 = Monday … 7 = Sunday) of a `weekdays` zone.
+    weekdays: Option<Vec<u8>>,
+    /// Cleaned by a machine: auto-cleaned on schedule and left out of `/zones/due`.
+    auto: bool,
+    /// Rough time a cleaning takes.
+    estimated_minutes: Option<i64>,
+    /// Snoozed until then: not due before it, whatever the schedule says.
+    paused_until: Option<DateTime<Utc>>,
+    /// The schedule counts from here instead of the last clean, if later.
+    schedule_anchor_at: Option<DateTime<Utc>>,
+    last_cleaned_at: Option<DateTime<Utc>>,
+    next_due_at: Option<DateTime<Utc>>,
+    is_due: bool,
+    sort_order: i64,
+    source: Option<String>,
+    external_id: Option<String>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    deleted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "RoomView")]
+struct RoomObject {
+    id: String,
+    home_id: Option<String>,
+    name: String,
+    icon: Option<String>,
+    sort_order: i64,
+    source: Option<String>,
+    external_id: Option<String>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    deleted_at: Option<DateTime<Utc>>,
+    zones_total: Option<i64>,
+    zones_cleaned_count: Option<i64>,
+    last_cleaned_at: Option<DateTime<Utc>>,
+    /// The room's zones, when the selection asks for them.
+    zones: Option<Vec<ZoneObject>>,
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "StatsOverview")]
+struct StatsObject {
+    rooms_total: i64,
+    zones_total: i64,
+    due_zones: i64,
+    /// Zones due within `dueWithin` from now, overdue ones included.
+    due_soon: Option<i64>,
+}
+
+fn zone_obj(z: ZoneView) -> ZoneObject {
+    ZoneObject {
+        id: z.id,
+        room_id: z.room_id,
+        name: z.name,
+        icon: z.icon,
+        notes: z.notes,
+        metadata: z.metadata,
+        frequency: z.frequency.into(),
+        custom_interval_days: z.custom_interval_days,
+        weekdays: z.weekdays,
+        auto: z.auto,
+        estimated_minutes: z.estimated_minutes,
+        paused_until: z.paused_until,
+        schedule_anchor_at: z.schedule_anchor_at,
+        last_cleaned_at: z.last_cleaned_at,
+        next_due_at: z.next_due_at,
+        is_due: z.is_due,
+        sort_order: z.sort_order,
+        source: z.source,
+        external_id: z.external_id,
+        created_at: z.created_at,
+        updated_at: z.updated_at,
+        deleted_at: z.deleted_at,
+    }
+}
+
+fn room_obj(r: RoomView) -> RoomObject {
+    RoomObject {
+        id: r.id,
+        home_id: r.home_id,
+        name: r.name,
+        icon: r.icon,
+        sort_order: r.sort_order,
+        source: r.source,
+        external_id: r.external_id,
+        created_at: r.created_at,
+        updated_at: r.updated_at,
+        deleted_at: r.deleted_at,
+        zones_total: r.zones_total,
+        zones_cleaned_count: r.zones_cleaned_count,
+        last_cleaned_at: r.last_cleaned_at,
+        zones: r.zones.map(|zones| zones.into_iter().map(zone_obj).collect()),
+    }
+}
+
+fn stats_obj(s: StatsOverview) -> StatsObject {
+    StatsObject { rooms_total: s.rooms_total, zones_total: s.zones_total, due_zones: s.due_zones, due_soon: s.due_soon }
+}
+
+async fn live_room(state: &AppState, id: &str) -> GqlResult<Option<Room>> {
+    sqlx::query_as::<_, Room>(&format!("SELECT {ROOM_COLUMNS} FROM rooms WHERE id = ?1 AND deleted_at IS NULL"))
+        .bind(id)
+        .fetch_optional(&state.pool)
+        .await
+        .map_err(gql)
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Rooms of the home in manual order, with stats; zones come in one query for all rooms.
+    async fn rooms(&self, ctx: &Context<'_>) -> GqlResult<Vec<RoomObject>> {
+        let state = state(ctx);
+        let params = ListParams { with_stats: Some(true), ..Default::default() };
+        let mut rooms =
+            rooms::query_rooms(&state, home(ctx), &params, &PageParams::default()).await.map_err(gql)?.items;
+        if wants_zones(ctx) {
+            let ids: Vec<String> = rooms.iter().map(|r| r.id.clone()).collect();
+            let mut zones = rooms::zones_by_room(&state, &ids).await.map_err(gql)?;
+            for r in &mut rooms {
+                r.zones = Some(zones.remove(&r.id).unwrap_or_default());
+            }
+        }
+        Ok(rooms.into_iter().map(room_obj).collect())
+    }
+
+    async fn room(&self, ctx: &Context<'_>, id: String) -> GqlResult<Option<RoomObject>> {
+        let state = state(ctx);
+        match live_room(&state, &id).await? {
+            Some(r) => Ok(Some(room_obj(rooms::room_view(&state, r, wants_zones(ctx)).await.map_err(gql)?))),
+            None => Ok(None),
+        }
+    }
+
+    async fn zone(&self, ctx: &Context<'_>, id: String) -> GqlResult<Option<ZoneObject>> {
+        let state = state(ctx);
+        let zone = sqlx::query_as::<_, Zone>(&format!(
+            "SELECT {ZONE_COLUMNS} FROM zones WHERE id = ?1 AND deleted_at IS NULL"
+        ))
+        .bind(&id)
+        .fetch_optional(&state.pool)
+        .await
+        .map_err(gql)?;
+        let tz = super::settings::timezone(&state.pool).await.map_err(gql)?;
+        Ok(zone.map(|z| zone_obj(ZoneView::localized(z, tz))))
+    }
+
+    /// Zones due now, never-cleaned and longest overdue first; auto zones are left out.
+    async fn due_zones(&self, ctx: &Context<'_>) -> GqlResult<Vec<ZoneObject>> {
+        let zones = stats::due_zones(&state(ctx), home(ctx)).await.map_err(gql)?;
+        Ok(zones.into_iter().map(zone_obj).collect())
+    }
+
+    /// Same as `GET /stats/overview`; `dueWithin` like `3d` or `12h`.
+    async fn stats(&self, ctx: &Context<'_>, due_within: Option<String>) -> GqlResult<StatsObject> {
+        let params = Query(OverviewParams { due_within });
+        let Json(overview) = stats::overview(State(state(ctx)), HomeScope(home(ctx)), params).await.map_err(gql)?;
+        Ok(stats_obj(overview))
+    }
+}
+
+pub struct MutationRoot;
+
+#[Object]
+impl MutationRoot {
+    async fn create_room(&self, ctx: &Context<'_>, name: String, icon: Option<String>) -> GqlResult<RoomObject> {
+        let body = NewRoom { name, icon, home_id: None, source: None, external_id: None };
+        let created = rooms::insert_room(State(state(ctx)), HomeScope(home(ctx)), Json(body)).await;
+        let (_, Json(room)) = created.map_err(gql)?;
+        Ok(room_obj(room))
+    }
+
+    /// `weekdays` (ISO, 1 = Monday) only for the `WEEKDAYS` frequency, `customIntervalDays` for `CUSTOM`.
+    async fn create_zone(
+        &self,
+        ctx: &Context<'_>,
+        room_id: String,
+        name: String,
+        frequency: Frequency,
+        custom_interval_days: Option<u16>,
+        weekdays: Option<Vec<u8>>,
+    ) -> GqlResult<ZoneObject> {
+        let state = state(ctx);
+        let room = live_room(&state, &room_id).await?.ok_or_else(|| gql(AppError::NotFound))?;
+        let body = NewZone {
+            name,
+            icon: None,
+            notes: None,
+            metadata: None,
+            frequency: frequency.into(),
+            custom_interval_days,
+            weekdays,
+            auto: None,
+            estimated_minutes: None,
+            source: None,
+            external_id: None,
+        };
+        let (_, Json(zone)) = zones::new_zone(State(state), LiveRoom(room), Json(body)).await.map_err(gql)?;
+        Ok(zone_obj(zone))
+    }
+
+    async fn clean_zone(
+        &self,
+        ctx: &Context<'_>,
+        id: String,
+        note: Option<String>,
+        duration_minutes: Option<u16>,
+    ) -> GqlResult<ZoneObject> {
+        let body = CleanBody { cleaned_at: None, require_tasks: None, note, cost_cents: None, duration_minutes };
+        let Json(zone) = zones::clean(State(state(ctx)), Path(id), Json(body)).await.map_err(gql)?;
+        Ok(zone_obj(zone))
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/graphql",
+    params(HomeParams),
+    request_body(content = Object, description = r#"`{"query": "...", "variables": {...}}`; `GET /graphql` gives the schema"#),
+    responses((status = 200, description = "GraphQL response; failures are in `errors`, with `extensions.code`", body = Object))
+)]
+pub async fn execute(
+    State(state): State<Arc<AppState>>,
+    HomeScope(home_id): HomeScope,
+    Json(request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    Json(schema().execute(request.data(state).data(Home(home_id))).await)
+}
+
+/// The schema in SDL, for code generators.
+pub async fn sdl() -> String {
+    schema().sdl()
+}
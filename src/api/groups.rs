@@ -0,0 +1,296 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::{
+    undo::{self, Deleted, Undoable},
+    zones::{self, BulkResponse},
+};
+use crate::{
+    error::{AppError, AppResult},
+    models::{AppState, NewZoneGroup, UpdateZoneGroup, Zone, ZoneGroup, ZoneView, ZONE_COLUMNS},
+};
+
+const GROUP_COLUMNS: &str = "id, name, created_at, updated_at, deleted_at";
+
+async fn fetch_group(state: &AppState, id: &str) -> AppResult<ZoneGroup> {
+    sqlx::query_as::<_, ZoneGroup>(&format!(
+        "SELECT {GROUP_COLUMNS} FROM zone_groups WHERE id = ?1 AND deleted_at IS NULL"
+    ))
+    .bind(id)
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or(AppError::NotFound)
+}
+
+fn validate_name(name: &str) -> AppResult<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::Validation("name is required".into()));
+    }
+    Ok(name.to_string())
+}
+
+#[utoipa::path(
+    get,
+    path = "/groups",
+    responses((status = 200, description = "List zone groups", body = [ZoneGroup]))
+)]
+pub async fn list_groups(State(state): State<std::sync::Arc<AppState>>) -> AppResult<Json<Vec<ZoneGroup>>> {
+    let groups = sqlx::query_as::<_, ZoneGroup>(&format!(
+        "SELECT {GROUP_COLUMNS} FROM zone_groups WHERE deleted_at IS NULL ORDER BY name ASC"
+    ))
+    .fetch_all(&state.pool)
+    .await?;
+    Ok(Json(groups))
+}
+
+#[utoipa::path(
+    post,
+    path = "/groups",
+    request_body = NewZoneGroup,
+    responses((status = 201, description = "Zone group created", body = ZoneGroup))
+)]
+pub async fn create_group(
+    State(state): State<std::sync::Arc<AppState>>,
+    Json(body): Json<NewZoneGroup>,
+) -> AppResult<(axum::http::StatusCode, Json<ZoneGroup>)> {
+    let now = Utc::now();
+    let group = ZoneGroup {
+        id: Uuid::new_v4().to_string(),
+        name: validate_name(&body.name)?,
+        created_at: now,
+        updated_at: now,
+        deleted_at: None,
+    };
+    sqlx::query(
+        r#"INSERT INTO zone_groups(id, name, created_at, updated_at, deleted_at)
+           VALUES (?1, ?2, ?3, ?3, NULL)"#,
+    )
+    .bind(&group.id)
+    .bind(&group.name)
+    .bind(now)
+    .execute(&state.writer)
+    .await?;
+    Ok((axum::http::StatusCode::CREATED, Json(group)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/groups/{id}",
+    params(("id" = String, Path, description = "Zone group id")),
+    responses((status = 200, description = "Get zone group", body = ZoneGroup))
+)]
+pub async fn get_group(
+    State(state): State<std::sync::Arc<AppState>>,
+    Path(id): Path<String>,
+) -> AppResult<Json<ZoneGroup>> {
+    Ok(Json(fetch_group(&state, &id).await?))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/groups/{id}",
+    params(("id" = String, Path, description = "Zone group id")),
+    request_body = UpdateZoneGroup,
+    responses((status = 200, description = "Zone group updated", body = ZoneGroup))
+)]
+pub async fn update_group(
+    State(state): State<std::sync::Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(body): Json<UpdateZoneGroup>,
+) -> AppResult<Json<ZoneGroup>> {
+    let mut g = fetch_group(&state, &id).await?;
+    if let Some(name) = body.name {
+        g.name = validate_name(&name)?;
+    }
+    g.updated_at = Utc::now();
+    sqlx::query("UPDATE zone_groups SET name = ?1, updated_at = ?2 WHERE id = ?3")
+        .bind(&g.name)
+        .bind(g.updated_at)
+        .bind(&id)
+        .execute(&state.writer)
+        .await?;
+    Ok(Json(g))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/groups/{id}",
+    params(("id" = String, Path, description = "Zone group id")),
+    responses((status = 204, description = "Zone group deleted; its zones stay", headers(("x-undo-token" = String, description = "For `POST /undo/{token}` within 30 seconds"))))
+)]
+pub async fn delete_group(
+    State(state): State<std::sync::Arc<AppState>>,
+    Path(id): Path<String>,
+) -> AppResult<Deleted> {
+    let now = Utc::now();
+    let mut tx = state.writer.begin().await?;
+    let res = sqlx::query("UPDATE zone_groups SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL")
+        .bind(now)
+        .bind(&id)
+        .execute(&mut *tx)
+        .await?;
+    if res.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    let deleted = undo::issue(&mut tx, Undoable::Group, &id, now).await?;
+    sqlx::query("DELETE FROM zone_group_members WHERE group_id = ?1")
+        .bind(&id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(deleted)
+}
+
+async fn member_zones(state: &AppState, group_id: &str) -> AppResult<Vec<Zone>> {
+    let zones = sqlx::query_as::<_, Zone>(&format!(
+        r#"SELECT {ZONE_COLUMNS} FROM zones
+           WHERE deleted_at IS NULL AND id IN (SELECT zone_id FROM zone_group_members WHERE group_id = ?1)
+           ORDER BY name ASC"#
+    ))
+    .bind(group_id)
+    .fetch_all(&state.pool)
+    .await?;
+    Ok(zones)
+}
+
+#[utoipa::path(
+    get,
+    path = "/groups/{id}/zones",
+    params(("id" = String, Path, description = "Zone group id")),
+    responses((status = 200, description = "Zones in the group, from any room", body = [ZoneView]))
+)]
+pub async fn list_group_zones(
+    State(state): State<std::sync::Arc<AppState>>,
+    Path(id): Path<String>,
+) -> AppResult<Json<Vec<ZoneView>>> {
+    fetch_group(&state, &id).await?;
+    let tz = super::settings::timezone(&state.pool).await?;
+    let zones = member_zones(&state, &id).await?;
+    Ok(Json(zones.into_iter().map(|z| ZoneView::localized(z, tz)).collect()))
+}
+
+#[utoipa::path(
+    put,
+    path = "/groups/{id}/zones/{zone_id}",
+    params(
+        ("id" = String, Path, description = "Zone group id"),
+        ("zone_id" = String, Path, description = "Zone id"),
+    ),
+    responses((status = 204, description = "Zone added to the group"))
+)]
+pub async fn add_zone(
+    State(state): State<std::sync::Arc<AppState>>,
+    Path((group_id, zone_id)): Path<(String, String)>,
+) -> AppResult<axum::http::StatusCode> {
+    let (found,): (i64,) = sqlx::query_as(
+        r#"SELECT (SELECT COUNT(1) FROM zone_groups WHERE id = ?1 AND deleted_at IS NULL)
+                + (SELECT COUNT(1) FROM zones WHERE id = ?2 AND deleted_at IS NULL)"#,
+    )
+    .bind(&group_id)
+    .bind(&zone_id)
+    .fetch_one(&state.pool)
+    .await?;
+    if found < 2 {
+        return Err(AppError::NotFound);
+    }
+    sqlx::query("INSERT OR IGNORE INTO zone_group_members(group_id, zone_id) VALUES (?1, ?2)")
+        .bind(&group_id)
+        .bind(&zone_id)
+        .execute(&state.writer)
+        .await?;
+    Ok(axum::http::StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/groups/{id}/zones/{zone_id}",
+    params(
+        ("id" = String, Path, description = "Zone group id"),
+        ("zone_id" = String, Path, description = "Zone id"),
+    ),
+    responses((status = 204, description = "Zone removed from the group"))
+)]
+pub async fn remove_zone(
+    State(state): State<std::sync::Arc<AppState>>,
+    Path((group_id, zone_id)): Path<(String, String)>,
+) -> AppResult<axum::http::StatusCode> {
+    let res = sqlx::query("DELETE FROM zone_group_members WHERE group_id = ?1 AND zone_id = ?2")
+        .bind(&group_id)
+        .bind(&zone_id)
+        .execute(&state.writer)
+        .await?;
+    if res.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(axum::http::StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct GroupClean {
+    pub cleaned_at: Option<DateTime<Utc>>,
+    /// Only zones that are due; all zones of the group when unset.
+    pub only_due: Option<bool>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/groups/{id}/clean",
+    params(("id" = String, Path, description = "Zone group id")),
+    request_body = GroupClean,
+    responses((status = 200, description = "Zones of the group cleaned", body = BulkResponse))
+)]
+pub async fn clean_group(
+    State(state): State<std::sync::Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(body): Json<GroupClean>,
+) -> AppResult<Json<BulkResponse>> {
+    fetch_group(&state, &id).await?;
+    let tz = super::settings::timezone(&state.pool).await?;
+    let only_due = body.only_due.unwrap_or(false);
+    let ids: Vec<String> = member_zones(&state, &id)
+        .await?
+        .into_iter()
+        .map(|z| ZoneView::localized(z, tz))
+        .filter(|z| !only_due || z.is_due)
+        .map(|z| z.id)
+        .collect();
+
+    let mut tx = state.writer.begin().await?;
+    let results = zones::clean_many(&mut tx, &ids, body.cleaned_at.unwrap_or_else(Utc::now)).await?;
+    tx.commit().await?;
+    Ok(Json(results.into()))
+}
+
+/// How far along a group is: its zones and how many of them are not due.
+#[derive(Serialize, Deserialize, ToSchema, sqlx::FromRow)]
+pub struct GroupProgress {
+    pub id: String,
+    pub name: String,
+    pub zones: i64,
+    pub done: i64,
+}
+
+/// Progress of every group, for the today view.
+pub async fn progress(state: &AppState) -> AppResult<Vec<GroupProgress>> {
+    let groups = sqlx::query_as::<_, GroupProgress>(
+        r#"SELECT g.id, g.name, COUNT(z.id) AS zones,
+                  COALESCE(SUM(z.next_due_at > ?1), 0) AS done
+           FROM zone_groups g
+           LEFT JOIN zone_group_members m ON m.group_id = g.id
+           LEFT JOIN zones z ON z.id = m.zone_id AND z.deleted_at IS NULL
+           WHERE g.deleted_at IS NULL
+           GROUP BY g.id, g.name
+           ORDER BY g.name ASC"#,
+    )
+    .bind(Utc::now())
+    .fetch_all(&state.pool)
+    .await?;
+    Ok(groups)
+}
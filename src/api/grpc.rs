@@ -0,0 +1,251 @@
+//! gRPC server (`proto/cleaner.proto`) for internal integrations, on its own
+//! port when `GRPC_PORT` is set. Like GraphQL, the methods call the REST
+//! queries and write paths, so events, webhooks and cached stats behave the same.
+
+use std::{net::SocketAddr, sync::Arc};
+
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use tonic::{metadata::MetadataMap, transport::server::TcpIncoming, Request, Response, Status};
+
+use super::{
+    extract::LiveRoom,
+    homes::{self, HomeScope, HOME_HEADER},
+    pagination::PageParams,
+    rooms::{self, ListParams},
+    stats,
+    zones::{self, CleanBody},
+};
+use crate::{
+    error::AppError,
+    models::{AppState, Frequency, NewRoom, NewZone, Room, RoomView, ZoneView, ROOM_COLUMNS},
+};
+
+pub mod pb {
+    tonic::include_proto!("cleaner.v1");
+}
+
+use pb::cleaner_server::{Cleaner, CleanerServer};
+
+pub struct CleanerService {
+    state: Arc<AppState>,
+}
+
+impl CleanerService {
+    pub fn new(state: Arc<AppState>) -> Self {
+        Self { state }
+    }
+
+    /// Home from the `x-home-id` metadata, else the default home, as [`HomeScope`] does.
+    async fn home(&self, metadata: &MetadataMap) -> Result<Option<String>, Status> {
+        let id = metadata
+            .get(HOME_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty());
+        match id {
+            Some(id) => {
+                homes::ensure_home(&self.state, &id).await.map_err(status)?;
+                Ok(Some(id))
+            }
+            None => homes::default_home(&self.state).await.map_err(status),
+        }
+    }
+}
+
+/// Serves until `shutdown` resolves; the listener is bound by the caller so a
+/// taken port fails the start.
+pub async fn serve(
+    state: Arc<AppState>,
+    listener: tokio::net::TcpListener,
+    shutdown: impl std::future::Future<Output = ()>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let addr: Option<SocketAddr> = listener.local_addr().ok();
+    let incoming = TcpIncoming::from_listener(listener, true, None)?;
+    tracing::info!(?addr, "gRPC listening");
+    tonic::transport::Server::builder()
+        .add_service(CleanerServer::new(CleanerService::new(state)))
+        .serve_with_incoming_shutdown(incoming, shutdown)
+        .await?;
+    Ok(())
+}
+
+/// The REST error as a gRPC status; same message, closest code.
+fn status(e: impl Into<AppError>) -> Status {
+    let e = e.into();
+    let message = e.to_string();
+    match e {
+        AppError::NotFound => Status::not_found(message),
+        AppError::Validation(_) | AppError::AxumJsonRejection(_) => Status::invalid_argument(message),
+        AppError::Conflict(_) => Status::already_exists(message),
+        AppError::Unauthorized(_) => Status::unauthenticated(message),
+        AppError::Timeout(_) => Status::deadline_exceeded(message),
+        AppError::Outbound(_) => Status::unavailable(message),
+        AppError::Sqlx(_) | AppError::Io(_) | AppError::Other(_) => {
+            tracing::error!(error = %message, "grpc request failed");
+            Status::internal(message)
+        }
+    }
+}
+
+/// `None` for the unset `FREQUENCY_UNSPECIFIED`.
+fn frequency_from(f: pb::Frequency) -> Option<Frequency> {
+    Some(match f {
+        pb::Frequency::Unspecified => return None,
+        pb::Frequency::Daily => Frequency::Daily,
+        pb::Frequency::Weekly => Frequency::Weekly,
+        pb::Frequency::Biweekly => Frequency::Biweekly,
+        pb::Frequency::Monthly => Frequency::Monthly,
+        pb::Frequency::MonthlyWeekday => Frequency::MonthlyWeekday,
+        pb::Frequency::Quarterly => Frequency::Quarterly,
+        pb::Frequency::Yearly => Frequency::Yearly,
+        pb::Frequency::Custom => Frequency::Custom,
+        pb::Frequency::Weekdays => Frequency::Weekdays,
+    })
+}
+
+fn frequency_to(f: Frequency) -> pb::Frequency {
+    match f {
+        Frequency::Daily => pb::Frequency::Daily,
+        Frequency::Weekly => pb::Frequency::Weekly,
+        Frequency::Biweekly => pb::Frequency::Biweekly,
+        Frequency::Monthly => pb::Frequency::Monthly,
+        Frequency::MonthlyWeekday => pb::Frequency::MonthlyWeekday,
+        Frequency::Quarterly => pb::Frequency::Quarterly,
+        Frequency::Yearly => pb::Frequency::Yearly,
+        Frequency::Custom => pb::Frequency::Custom,
+        Frequency::Weekdays => pb::Frequency::Weekdays,
+    }
+}
+
+fn time(t: Option<DateTime<Utc>>) -> Option<String> {
+    t.map(|t| t.to_rfc3339())
+}
+
+fn zone_msg(z: ZoneView) -> pb::Zone {
+    pb::Zone {
+        id: z.id,
+        room_id: z.room_id,
+        name: z.name,
+        icon: z.icon,
+        notes: z.notes,
+        frequency: frequency_to(z.frequency).into(),
+        custom_interval_days: z.custom_interval_days,
+        weekdays: z.weekdays.unwrap_or_default().into_iter().map(u32::from).collect(),
+        auto: z.auto,
+        estimated_minutes: z.estimated_minutes,
+        last_cleaned_at: time(z.last_cleaned_at),
+        next_due_at: time(z.next_due_at),
+        is_due: z.is_due,
+    }
+}
+
+fn room_msg(r: RoomView) -> pb::Room {
+    pb::Room {
+        id: r.id,
+        home_id: r.home_id,
+        name: r.name,
+        icon: r.icon,
+        sort_order: r.sort_order,
+        zones_total: r.zones_total.unwrap_or(0),
+        zones_cleaned_count: r.zones_cleaned_count.unwrap_or(0),
+        last_cleaned_at: time(r.last_cleaned_at),
+        zones: r.zones.unwrap_or_default().into_iter().map(zone_msg).collect(),
+    }
+}
+
+#[tonic::async_trait]
+impl Cleaner for CleanerService {
+    async fn list_rooms(&self, req: Request<pb::ListRoomsRequest>) -> Result<Response<pb::ListRoomsResponse>, Status> {
+        let home = self.home(req.metadata()).await?;
+        let params = ListParams { with_stats: Some(true), ..Default::default() };
+        let mut rooms = rooms::query_rooms(&self.state, home, &params, &PageParams::default()).await.map_err(status)?.items;
+        if req.get_ref().include_zones {
+            let ids: Vec<String> = rooms.iter().map(|r| r.id.clone()).collect();
+            let mut zones = rooms::zones_by_room(&self.state, &ids).await.map_err(status)?;
+            for r in &mut rooms {
+                r.zones = Some(zones.remove(&r.id).unwrap_or_default());
+            }
+        }
+        Ok(Response::new(pb::ListRoomsResponse { rooms: rooms.into_iter().map(room_msg).collect() }))
+    }
+
+    async fn create_room(&self, req: Request<pb::CreateRoomRequest>) -> Result<Response<pb::Room>, Status> {
+        let home = self.home(req.metadata()).await?;
+        let pb::CreateRoomRequest { name, icon } = req.into_inner();
+        let body = NewRoom { name, icon, home_id: None, source: None, external_id: None };
+        let (_, Json(room)) =
+            rooms::insert_room(State(self.state.clone()), HomeScope(home), Json(body)).await.map_err(status)?;
+        Ok(Response::new(room_msg(room)))
+    }
+
+    async fn get_zone(&self, req: Request<pb::GetZoneRequest>) -> Result<Response<pb::Zone>, Status> {
+        let Json(zone) = zones::zone_view(&self.state, &req.get_ref().id).await.map_err(status)?;
+        Ok(Response::new(zone_msg(zone)))
+    }
+
+    async fn create_zone(&self, req: Request<pb::CreateZoneRequest>) -> Result<Response<pb::Zone>, Status> {
+        let frequency = frequency_from(req.get_ref().frequency())
+            .ok_or_else(|| Status::invalid_argument("frequency is required"))?;
+        let pb::CreateZoneRequest { room_id, name, custom_interval_days, weekdays, .. } = req.into_inner();
+        let room = sqlx::query_as::<_, Room>(&format!(
+            "SELECT {ROOM_COLUMNS} FROM rooms WHERE id = ?1 AND deleted_at IS NULL"
+        ))
+        .bind(&room_id)
+        .fetch_optional(&self.state.pool)
+        .await
+        .map_err(status)?
+        .ok_or_else(|| status(AppError::NotFound))?;
+        let too_big = |field: &str| Status::invalid_argument(format!("{field} is out of range"));
+        let custom_interval_days =
+            custom_interval_days.map(u16::try_from).transpose().map_err(|_| too_big("custom_interval_days"))?;
+        let weekdays: Vec<u8> =
+            weekdays.into_iter().map(u8::try_from).collect::<Result<_, _>>().map_err(|_| too_big("weekdays"))?;
+        let body = NewZone {
+            name,
+            icon: None,
+            notes: None,
+            metadata: None,
+            frequency,
+            custom_interval_days,
+            weekdays: (!weekdays.is_empty()).then_some(weekdays),
+            auto: None,
+            estimated_minutes: None,
+            source: None,
+            external_id: None,
+        };
+        let (_, Json(zone)) =
+            zones::new_zone(State(self.state.clone()), LiveRoom(room), Json(body)).await.map_err(status)?;
+        Ok(Response::new(zone_msg(zone)))
+    }
+
+    async fn list_due_zones(
+        &self,
+        req: Request<pb::ListDueZonesRequest>,
+    ) -> Result<Response<pb::ListZonesResponse>, Status> {
+        let home = self.home(req.metadata()).await?;
+        let zones = stats::due_zones(&self.state, home).await.map_err(status)?;
+        Ok(Response::new(pb::ListZonesResponse { zones: zones.into_iter().map(zone_msg).collect() }))
+    }
+
+    async fn clean_zone(&self, req: Request<pb::CleanZoneRequest>) -> Result<Response<pb::Zone>, Status> {
+        let pb::CleanZoneRequest { id, cleaned_at, note, duration_minutes } = req.into_inner();
+        let cleaned_at = cleaned_at
+            .map(|t| DateTime::parse_from_rfc3339(&t).map(|t| t.with_timezone(&Utc)))
+            .transpose()
+            .map_err(|e| Status::invalid_argument(format!("cleaned_at: {e}")))?;
+        let body = CleanBody {
+            cleaned_at,
+            require_tasks: None,
+            note,
+            cost_cents: None,
+            // вне диапазона — пусть отклонит общая проверка
+            duration_minutes: duration_minutes.map(|m| u16::try_from(m).unwrap_or(u16::MAX)),
+        };
+        let Json(zone) = zones::clean(State(self.state.clone()), Path(id), Json(body)).await.map_err(status)?;
+        Ok(Response::new(zone_msg(zone)))
+    }
+}
@@ -0,0 +1,211 @@
+use std::sync::Arc;
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Path, Query, State},
+    http::request::Parts,
+    Json,
+};
+use chrono::Utc;
+use serde::Deserialize;
+use utoipa::IntoParams;
+use uuid::Uuid;
+
+use super::undo::{self, Deleted, Undoable};
+use crate::{
+    error::{AppError, AppResult},
+    models::{AppState, Home, NewHome, UpdateHome, HOME_COLUMNS},
+};
+
+pub const HOME_HEADER: &str = "x-home-id";
+
+#[derive(Deserialize, IntoParams, Default)]
+pub struct HomeParams {
+    /// Home to scope the request to; same as the `X-Home-Id` header.
+    pub home_id: Option<String>,
+}
+
+/// Home a request is scoped to: `X-Home-Id`, then `?home_id=`, then the default home.
+/// `None` only when no home is marked default, and then nothing is filtered.
+pub struct HomeScope(pub Option<String>);
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for HomeScope {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &Arc<AppState>) -> AppResult<Self> {
+        let from_header = parts
+            .headers
+            .get(HOME_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.trim().to_string());
+        let from_query = Query::<HomeParams>::try_from_uri(&parts.uri)
+            .ok()
+            .and_then(|Query(p)| p.home_id);
+        match from_header.or(from_query).filter(|s| !s.is_empty()) {
+            Some(id) => {
+                ensure_home(state, &id).await?;
+                Ok(HomeScope(Some(id)))
+            }
+            None => Ok(HomeScope(default_home(state).await?)),
+        }
+    }
+}
+
+pub async fn default_home(state: &AppState) -> AppResult<Option<String>> {
+    let id: Option<(String,)> =
+        sqlx::query_as("SELECT id FROM homes WHERE is_default = 1 AND deleted_at IS NULL")
+            .fetch_optional(&state.pool)
+            .await?;
+    Ok(id.map(|(id,)| id))
+}
+
+pub async fn ensure_home(state: &AppState, id: &str) -> AppResult<()> {
+    let (found,): (i64,) = sqlx::query_as("SELECT COUNT(1) FROM homes WHERE id = ?1 AND deleted_at IS NULL")
+        .bind(id)
+        .fetch_one(&state.pool)
+        .await?;
+    if found == 0 {
+        return Err(AppError::Validation(format!("unknown home id {id}")));
+    }
+    Ok(())
+}
+
+async fn make_default(state: &AppState, id: &str) -> AppResult<()> {
+    let mut tx = state.writer.begin().await?;
+    sqlx::query("UPDATE homes SET is_default = 0 WHERE is_default = 1 AND id != ?1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("UPDATE homes SET is_default = 1 WHERE id = ?1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+async fn fetch_home(state: &AppState, id: &str) -> AppResult<Home> {
+    sqlx::query_as::<_, Home>(&format!(
+        "SELECT {HOME_COLUMNS} FROM homes WHERE id = ?1 AND deleted_at IS NULL"
+    ))
+    .bind(id)
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or(AppError::NotFound)
+}
+
+#[utoipa::path(
+    get,
+    path = "/homes",
+    responses((status = 200, description = "List homes", body = [Home]))
+)]
+pub async fn list_homes(State(state): State<Arc<AppState>>) -> AppResult<Json<Vec<Home>>> {
+    let homes = sqlx::query_as::<_, Home>(&format!(
+        "SELECT {HOME_COLUMNS} FROM homes WHERE deleted_at IS NULL ORDER BY is_default DESC, name ASC"
+    ))
+    .fetch_all(&state.pool)
+    .await?;
+    Ok(Json(homes))
+}
+
+#[utoipa::path(
+    post,
+    path = "/homes",
+    request_body = NewHome,
+    responses((status = 201, description = "Home created", body = Home))
+)]
+pub async fn create_home(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<NewHome>,
+) -> AppResult<(axum::http::StatusCode, Json<Home>)> {
+    let name = body.name.trim().to_string();
+    if name.is_empty() {
+        return Err(AppError::Validation("name is required".into()));
+    }
+    let now = Utc::now();
+    let id = Uuid::new_v4().to_string();
+    sqlx::query(
+        r#"INSERT INTO homes(id, name, icon, is_default, created_at, updated_at, deleted_at)
+           VALUES (?1, ?2, ?3, 0, ?4, ?4, NULL)"#,
+    )
+    .bind(&id)
+    .bind(&name)
+    .bind(&body.icon)
+    .bind(now)
+    .execute(&state.writer)
+    .await?;
+    // первый дом всегда становится домом по умолчанию
+    if body.is_default.unwrap_or(false) || default_home(&state).await?.is_none() {
+        make_default(&state, &id).await?;
+    }
+    Ok((axum::http::StatusCode::CREATED, Json(fetch_home(&state, &id).await?)))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/homes/{id}",
+    params(("id" = String, Path, description = "Home id")),
+    request_body = UpdateHome,
+    responses((status = 200, description = "Home updated", body = Home))
+)]
+pub async fn update_home(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(body): Json<UpdateHome>,
+) -> AppResult<Json<Home>> {
+    let h = fetch_home(&state, &id).await?;
+    if body.is_default == Some(false) && h.is_default {
+        return Err(AppError::Validation("make another home the default instead".into()));
+    }
+    let name = match body.name {
+        Some(n) if n.trim().is_empty() => return Err(AppError::Validation("name is required".into())),
+        Some(n) => n.trim().to_string(),
+        None => h.name,
+    };
+    let icon = body.icon.apply(h.icon);
+    sqlx::query("UPDATE homes SET name = ?1, icon = ?2, updated_at = ?3 WHERE id = ?4")
+        .bind(&name)
+        .bind(&icon)
+        .bind(Utc::now())
+        .bind(&id)
+        .execute(&state.writer)
+        .await?;
+    if body.is_default == Some(true) {
+        make_default(&state, &id).await?;
+    }
+    Ok(Json(fetch_home(&state, &id).await?))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/homes/{id}",
+    params(("id" = String, Path, description = "Home id")),
+    responses((status = 204, description = "Home deleted", headers(("x-undo-token" = String, description = "For `POST /undo/{token}` within 30 seconds"))))
+)]
+pub async fn delete_home(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> AppResult<Deleted> {
+    let h = fetch_home(&state, &id).await?;
+    if h.is_default {
+        return Err(AppError::Validation("the default home cannot be deleted".into()));
+    }
+    let (rooms,): (i64,) = sqlx::query_as("SELECT COUNT(1) FROM rooms WHERE home_id = ?1 AND deleted_at IS NULL")
+        .bind(&id)
+        .fetch_one(&state.pool)
+        .await?;
+    if rooms > 0 {
+        return Err(AppError::Validation(format!("home still has {rooms} room(s)")));
+    }
+    let now = Utc::now();
+    let mut tx = state.writer.begin().await?;
+    sqlx::query("UPDATE homes SET deleted_at = ?1 WHERE id = ?2")
+        .bind(now)
+        .bind(&id)
+        .execute(&mut *tx)
+        .await?;
+    let deleted = undo::issue(&mut tx, Undoable::Home, &id, now).await?;
+    tx.commit().await?;
+    Ok(deleted)
+}
@@ -0,0 +1,188 @@
+use std::{future::Future, sync::Arc};
+
+use axum::{
+    async_trait,
+    body::{to_bytes, Body},
+    extract::{FromRequest, Request},
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    error::{AppError, AppResult},
+    models::{AppState, Db},
+};
+
+pub const KEY_HEADER: &str = "idempotency-key";
+/// Set on responses replayed from a stored key.
+pub const REPLAYED_HEADER: &str = "idempotent-replayed";
+/// How long a key is remembered; a retry after that runs as a new request.
+const KEY_TTL_HOURS: i64 = 24;
+const MAX_KEY_LEN: usize = 255;
+/// Same as axum's default body limit for `Json`.
+const MAX_BODY: usize = 2 * 1024 * 1024;
+
+/// `Idempotency-Key` of a request and a hash of its method, path and body.
+pub struct Idempotency {
+    key: Option<String>,
+    hash: String,
+}
+
+/// Body extractor `T` together with the request's `Idempotency`; the body is
+/// read once for the hash and then handed to `T`.
+pub struct Keyed<T>(pub Idempotency, pub T);
+
+#[async_trait]
+impl<T> FromRequest<Arc<AppState>> for Keyed<T>
+where
+    T: FromRequest<Arc<AppState>>,
+    AppError: From<T::Rejection>,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &Arc<AppState>) -> AppResult<Self> {
+        let key = match req.headers().get(KEY_HEADER) {
+            None => None,
+            Some(v) => {
+                let key = v.to_str().map(str::trim).unwrap_or_default();
+                if key.is_empty() || key.len() > MAX_KEY_LEN {
+                    return Err(AppError::Validation(format!(
+                        "Idempotency-Key must be 1 to {MAX_KEY_LEN} visible ASCII characters"
+                    )));
+                }
+                Some(key.to_string())
+            }
+        };
+        let (parts, body) = req.into_parts();
+        let bytes = to_bytes(body, MAX_BODY)
+            .await
+            .map_err(|e| AppError::Validation(format!("cannot read body: {e}")))?;
+        let mut hasher = Sha256::new();
+        hasher.update(parts.method.as_str());
+        hasher.update(b"\n");
+        hasher.update(parts.uri.path());
+        hasher.update(b"\n");
+        hasher.update(&bytes);
+        let hash = hex::encode(hasher.finalize());
+        let inner = T::from_request(Request::from_parts(parts, Body::from(bytes)), state).await?;
+        Ok(Keyed(Idempotency { key, hash }, inner))
+    }
+}
+
+/// A key claimed as in progress. Dropped while still holding the key (the
+/// handler was cut off with 504, panicked or failed to store its response) it
+/// frees the key, so a retry is not refused until the key expires.
+struct Claim {
+    writer: Db,
+    key: Option<String>,
+}
+
+impl Drop for Claim {
+    fn drop(&mut self) {
+        let Some(key) = self.key.take() else { return };
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else { return };
+        let writer = self.writer.clone();
+        // в drop ждать нельзя, удаление уходит в отдельную задачу
+        runtime.spawn(async move {
+            let freed = sqlx::query("DELETE FROM idempotency_keys WHERE key = ?1 AND status IS NULL")
+                .bind(&key)
+                .execute(&writer)
+                .await;
+            if let Err(e) = freed {
+                tracing::warn!(error = %e, "cannot free an abandoned Idempotency-Key");
+            }
+        });
+    }
+}
+
+impl Idempotency {
+    /// Runs `handler` once per key. A retry with the same key and request gets
+    /// the stored response; the same key on a different request, or while the
+    /// first one still runs, gets 409. Only successful responses are kept, so a
+    /// failed request can be retried with its key.
+    pub async fn run<R, F>(self, state: &AppState, handler: F) -> AppResult<Response>
+    where
+        F: Future<Output = AppResult<R>>,
+        R: IntoResponse,
+    {
+        let Some(key) = &self.key else {
+            return Ok(handler.await.into_response());
+        };
+        if let Some(stored) = self.claim(state, key).await? {
+            return Ok(stored);
+        }
+        let mut claim = Claim { writer: state.writer.clone(), key: Some(key.clone()) };
+
+        let res = handler.await.into_response();
+        if !res.status().is_success() {
+            claim.key = None;
+            sqlx::query("DELETE FROM idempotency_keys WHERE key = ?1")
+                .bind(key)
+                .execute(&state.writer)
+                .await?;
+            return Ok(res);
+        }
+        let (parts, body) = res.into_parts();
+        let bytes = to_bytes(body, usize::MAX)
+            .await
+            .map_err(|e| AppError::Other(anyhow::anyhow!(e)))?;
+        sqlx::query("UPDATE idempotency_keys SET status = ?1, body = ?2 WHERE key = ?3")
+            .bind(i64::from(parts.status.as_u16()))
+            .bind(String::from_utf8_lossy(&bytes).into_owned())
+            .bind(key)
+            .execute(&state.writer)
+            .await?;
+        claim.key = None;
+        Ok(Response::from_parts(parts, Body::from(bytes)))
+    }
+
+    /// Records `key` as in progress, or returns the response stored for it.
+    async fn claim(&self, state: &AppState, key: &str) -> AppResult<Option<Response>> {
+        let now = Utc::now();
+        let mut tx = state.writer.begin().await?;
+        sqlx::query("DELETE FROM idempotency_keys WHERE created_at < ?1")
+            .bind(now - chrono::Duration::hours(KEY_TTL_HOURS))
+            .execute(&mut *tx)
+            .await?;
+        let inserted = sqlx::query(
+            r#"INSERT OR IGNORE INTO idempotency_keys(key, request_hash, status, body, created_at)
+               VALUES (?1, ?2, NULL, NULL, ?3)"#,
+        )
+        .bind(key)
+        .bind(&self.hash)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        let stored: Option<(String, Option<i64>, Option<String>)> = if inserted == 0 {
+            sqlx::query_as("SELECT request_hash, status, body FROM idempotency_keys WHERE key = ?1")
+                .bind(key)
+                .fetch_optional(&mut *tx)
+                .await?
+        } else {
+            None
+        };
+        tx.commit().await?;
+
+        let Some((hash, status, body)) = stored else {
+            return Ok(None);
+        };
+        if hash != self.hash {
+            return Err(AppError::Conflict(
+                "Idempotency-Key was already used for a different request".into(),
+            ));
+        }
+        let Some(status) = status.and_then(|s| StatusCode::from_u16(s as u16).ok()) else {
+            return Err(AppError::Conflict(
+                "a request with this Idempotency-Key is still in progress".into(),
+            ));
+        };
+        let mut res = (status, body.unwrap_or_default()).into_response();
+        res.headers_mut()
+            .insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        res.headers_mut().insert(REPLAYED_HEADER, HeaderValue::from_static("true"));
+        Ok(Some(res))
+    }
+}
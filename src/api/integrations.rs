@@ -0,0 +1,303 @@
+use std::collections::HashSet;
+
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::HeaderMap,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use sqlx::types::Json as SqlJson;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::{
+    undo::{self, Deleted, Undoable},
+    webhooks::validate_secret,
+    zones,
+};
+use crate::{
+    error::{AppError, AppResult},
+    models::{AppState, Integration, IntegrationMapping, NewIntegration, UpdateIntegration, ZoneChange},
+};
+
+const INTEGRATION_COLUMNS: &str = "id, name, mappings, active, created_at, updated_at, deleted_at";
+pub const SIGNATURE_HEADER: &str = "x-signature";
+pub const TIMESTAMP_HEADER: &str = "x-timestamp";
+pub const NONCE_HEADER: &str = "x-nonce";
+/// How far `X-Timestamp` may be from the server clock, either way.
+const REPLAY_WINDOW_SECS: i64 = 300;
+const MAX_NONCE_LEN: usize = 128;
+
+async fn validate_mappings(state: &AppState, mappings: &[IntegrationMapping]) -> AppResult<()> {
+    let mut events = HashSet::new();
+    for m in mappings {
+        if m.event.trim().is_empty() {
+            return Err(AppError::Validation("mapping event must not be empty".into()));
+        }
+        if !events.insert(m.event.as_str()) {
+            return Err(AppError::Validation(format!("event '{}' is mapped twice", m.event)));
+        }
+        let (found,): (i64,) = sqlx::query_as("SELECT COUNT(1) FROM zones WHERE id = ?1 AND deleted_at IS NULL")
+            .bind(&m.zone_id)
+            .fetch_one(&state.pool)
+            .await?;
+        if found == 0 {
+            return Err(AppError::Validation(format!("unknown zone id {}", m.zone_id)));
+        }
+    }
+    Ok(())
+}
+
+async fn fetch_integration(state: &AppState, id: &str) -> AppResult<Integration> {
+    sqlx::query_as::<_, Integration>(&format!(
+        "SELECT {INTEGRATION_COLUMNS} FROM integrations WHERE id = ?1 AND deleted_at IS NULL"
+    ))
+    .bind(id)
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or(AppError::NotFound)
+}
+
+#[utoipa::path(
+    get,
+    path = "/integrations",
+    responses((status = 200, description = "List integrations", body = [Integration]))
+)]
+pub async fn list_integrations(
+    State(state): State<std::sync::Arc<AppState>>,
+) -> AppResult<Json<Vec<Integration>>> {
+    let integrations = sqlx::query_as::<_, Integration>(&format!(
+        "SELECT {INTEGRATION_COLUMNS} FROM integrations WHERE deleted_at IS NULL ORDER BY created_at ASC"
+    ))
+    .fetch_all(&state.pool)
+    .await?;
+    Ok(Json(integrations))
+}
+
+#[utoipa::path(
+    post,
+    path = "/integrations",
+    request_body = NewIntegration,
+    responses((status = 201, description = "Integration registered", body = Integration))
+)]
+pub async fn create_integration(
+    State(state): State<std::sync::Arc<AppState>>,
+    Json(body): Json<NewIntegration>,
+) -> AppResult<(axum::http::StatusCode, Json<Integration>)> {
+    if body.name.trim().is_empty() {
+        return Err(AppError::Validation("name is required".into()));
+    }
+    validate_secret(&body.secret)?;
+    validate_mappings(&state, &body.mappings).await?;
+
+    let now = Utc::now();
+    let integration = Integration {
+        id: Uuid::new_v4().to_string(),
+        name: body.name,
+        mappings: SqlJson(body.mappings),
+        active: true,
+        created_at: now,
+        updated_at: now,
+        deleted_at: None,
+    };
+    sqlx::query(
+        r#"INSERT INTO integrations(id, name, secret, mappings, active, created_at, updated_at, deleted_at)
+           VALUES (?1, ?2, ?3, ?4, 1, ?5, ?5, NULL)"#,
+    )
+    .bind(&integration.id)
+    .bind(&integration.name)
+    .bind(state.cipher.seal(&body.secret))
+    .bind(&integration.mappings)
+    .bind(now)
+    .execute(&state.writer)
+    .await?;
+    Ok((axum::http::StatusCode::CREATED, Json(integration)))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/integrations/{id}",
+    params(("id" = String, Path, description = "Integration id")),
+    request_body = UpdateIntegration,
+    responses((status = 200, description = "Integration updated", body = Integration))
+)]
+pub async fn update_integration(
+    State(state): State<std::sync::Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(body): Json<UpdateIntegration>,
+) -> AppResult<Json<Integration>> {
+    let mut i = fetch_integration(&state, &id).await?;
+    if let Some(name) = body.name {
+        if name.trim().is_empty() {
+            return Err(AppError::Validation("name is required".into()));
+        }
+        i.name = name;
+    }
+    if let Some(mappings) = body.mappings {
+        validate_mappings(&state, &mappings).await?;
+        i.mappings = SqlJson(mappings);
+    }
+    if let Some(secret) = &body.secret {
+        validate_secret(secret)?;
+    }
+    i.active = body.active.unwrap_or(i.active);
+    i.updated_at = Utc::now();
+
+    sqlx::query(
+        r#"UPDATE integrations SET name = ?1, mappings = ?2, active = ?3, secret = COALESCE(?4, secret), updated_at = ?5
+           WHERE id = ?6"#,
+    )
+    .bind(&i.name)
+    .bind(&i.mappings)
+    .bind(i.active)
+    .bind(body.secret.as_deref().map(|s| state.cipher.seal(s)))
+    .bind(i.updated_at)
+    .bind(&id)
+    .execute(&state.writer)
+    .await?;
+    Ok(Json(i))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/integrations/{id}",
+    params(("id" = String, Path, description = "Integration id")),
+    responses((status = 204, description = "Integration deleted", headers(("x-undo-token" = String, description = "For `POST /undo/{token}` within 30 seconds"))))
+)]
+pub async fn delete_integration(
+    State(state): State<std::sync::Arc<AppState>>,
+    Path(id): Path<String>,
+) -> AppResult<Deleted> {
+    let now = Utc::now();
+    let mut tx = state.writer.begin().await?;
+    let res = sqlx::query("UPDATE integrations SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL")
+        .bind(now)
+        .bind(&id)
+        .execute(&mut *tx)
+        .await?;
+    if res.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    let deleted = undo::issue(&mut tx, Undoable::Integration, &id, now).await?;
+    tx.commit().await?;
+    Ok(deleted)
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct InboundEvent {
+    /// Looked up in the integration's mappings.
+    pub event: String,
+    /// When the device finished; now when unset.
+    pub occurred_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct InboundResult {
+    pub event: String,
+    /// Zone the event is mapped to; `None` for events without a mapping.
+    pub zone_id: Option<String>,
+    pub cleaned: bool,
+}
+
+/// Checks `X-Signature: sha256=<hex>`, the HMAC-SHA256 of
+/// `<X-Timestamp>.<X-Nonce>.<body>` under the integration secret.
+fn verify(secret: &str, headers: &HeaderMap, body: &[u8], now: DateTime<Utc>) -> AppResult<String> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).map(str::trim);
+    let timestamp = header(TIMESTAMP_HEADER)
+        .and_then(|t| t.parse::<i64>().ok())
+        .ok_or_else(|| AppError::Unauthorized("X-Timestamp must be unix seconds".into()))?;
+    if (now.timestamp() - timestamp).abs() > REPLAY_WINDOW_SECS {
+        return Err(AppError::Unauthorized(format!(
+            "X-Timestamp is more than {REPLAY_WINDOW_SECS}s away from server time"
+        )));
+    }
+    let nonce = header(NONCE_HEADER)
+        .filter(|n| !n.is_empty() && n.len() <= MAX_NONCE_LEN)
+        .ok_or_else(|| AppError::Unauthorized(format!("X-Nonce must be 1 to {MAX_NONCE_LEN} characters")))?;
+    let signature = header(SIGNATURE_HEADER)
+        .and_then(|s| s.strip_prefix("sha256="))
+        .and_then(|s| hex::decode(s).ok())
+        .ok_or_else(|| AppError::Unauthorized("X-Signature must be sha256=<hex>".into()))?;
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any size");
+    mac.update(format!("{timestamp}.{nonce}.").as_bytes());
+    mac.update(body);
+    // verify_slice сравнивает за постоянное время
+    mac.verify_slice(&signature)
+        .map_err(|_| AppError::Unauthorized("signature does not match".into()))?;
+    Ok(nonce.to_string())
+}
+
+#[utoipa::path(
+    post,
+    path = "/integrations/inbound/{integration_id}",
+    params(
+        ("integration_id" = String, Path, description = "Integration id"),
+        ("X-Timestamp" = i64, Header, description = "Unix seconds when the call was signed"),
+        ("X-Nonce" = String, Header, description = "Unique per call; a repeated nonce is rejected"),
+        ("X-Signature" = String, Header, description = "sha256=<hex HMAC of timestamp.nonce.body>"),
+    ),
+    request_body = InboundEvent,
+    responses(
+        (status = 200, description = "Event accepted", body = InboundResult),
+        (status = 401, description = "Signature, timestamp or nonce missing or wrong"),
+        (status = 409, description = "Nonce already used"),
+    )
+)]
+pub async fn inbound(
+    State(state): State<std::sync::Arc<AppState>>,
+    Path(integration_id): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> AppResult<Json<InboundResult>> {
+    let found: Option<(String, SqlJson<Vec<IntegrationMapping>>)> = sqlx::query_as(
+        "SELECT secret, mappings FROM integrations WHERE id = ?1 AND active = 1 AND deleted_at IS NULL",
+    )
+    .bind(&integration_id)
+    .fetch_optional(&state.pool)
+    .await?;
+    let (secret, SqlJson(mappings)) = found.ok_or(AppError::NotFound)?;
+    let secret = state.cipher.open(&secret)?;
+
+    let now = Utc::now();
+    let nonce = verify(&secret, &headers, &body, now)?;
+    let event: InboundEvent = serde_json::from_slice(&body)
+        .map_err(|e| AppError::Validation(format!("invalid event: {e}")))?;
+
+    let mut tx = state.writer.begin().await?;
+    // nonce хранится, пока его метка времени может пройти проверку
+    sqlx::query("DELETE FROM integration_nonces WHERE seen_at < ?1")
+        .bind(now - chrono::Duration::seconds(2 * REPLAY_WINDOW_SECS))
+        .execute(&mut *tx)
+        .await?;
+    let fresh = sqlx::query(
+        "INSERT OR IGNORE INTO integration_nonces(integration_id, nonce, seen_at) VALUES (?1, ?2, ?3)",
+    )
+    .bind(&integration_id)
+    .bind(&nonce)
+    .bind(now)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    if fresh == 0 {
+        return Err(AppError::Conflict("nonce already used".into()));
+    }
+
+    let zone_id = mappings.into_iter().find(|m| m.event == event.event).map(|m| m.zone_id);
+    let cleaned = match &zone_id {
+        Some(zone_id) => {
+            let change = ZoneChange::Cleaned { note: None, auto: true, cost_cents: None, duration_minutes: None };
+            zones::mark_cleaned(&mut tx, zone_id, event.occurred_at.unwrap_or(now), &change).await?
+        }
+        None => false,
+    };
+    tx.commit().await?;
+    if zone_id.is_none() {
+        tracing::info!(integration_id, event = %event.event, "inbound event without mapping");
+    }
+    Ok(Json(InboundResult { event: event.event, zone_id, cleaned }))
+}
@@ -0,0 +1,21 @@
+use axum::{
+    extract::State,
+    http::header,
+    response::{IntoResponse, Response},
+};
+
+use crate::{error::AppResult, metrics, models::AppState};
+
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    responses((status = 200, description = "Instance-wide gauges in Prometheus text format", body = String, content_type = "text/plain"))
+)]
+pub async fn metrics(State(state): State<std::sync::Arc<AppState>>) -> AppResult<Response> {
+    let snapshot = metrics::latest(&state).await?;
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics::render(&snapshot),
+    )
+        .into_response())
+}
@@ -1,7 +1,293 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use axum::{
+    body::{to_bytes, Body, HttpBody},
+    extract::{Request, State},
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{delete, get, patch, post, put},
+    Router,
+};
+
+use sha2::{Digest, Sha256};
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::{error::AppError, models::AppState};
+
+pub mod homes;
 pub mod rooms;
 pub mod zones;
+pub mod tasks;
+pub mod tags;
+pub mod groups;
+pub mod supplies;
 pub mod stats;
+pub mod settings;
+pub mod webhooks;
+pub mod undo;
+pub mod integrations;
+pub mod google_calendar;
+pub mod voice;
+pub mod notifications;
+pub mod metrics;
+pub mod pagination;
 pub mod docs;
+pub mod export;
+pub mod graphql;
+pub mod grpc;
+pub mod extract;
+pub mod fields;
+pub mod idempotency;
+pub mod operations;
+
+/// Request id header; echoed back, and set on every log line of the request.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+tokio::task_local! {
+    /// Id of the request being handled, for error bodies.
+    pub static REQUEST_ID: String;
+}
+
+/// Takes the caller's `X-Request-Id` when it is short printable ASCII, otherwise
+/// makes a UUID. The request runs inside a `request` span carrying the id, so
+/// logs from handlers and from tasks they spawn can be matched to it, and with
+/// the id in [`REQUEST_ID`], which error bodies repeat.
+pub async fn request_id(req: Request, next: Next) -> Response {
+    let id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| (1..=128).contains(&v.len()) && v.bytes().all(|b| b.is_ascii_graphic()))
+        .map_or_else(|| Uuid::new_v4().to_string(), str::to_string);
+    let span = tracing::info_span!("request", id = %id, method = %req.method(), path = %req.uri().path());
+    let started = Instant::now();
+    let mut res = REQUEST_ID.scope(id.clone(), next.run(req)).instrument(span.clone()).await;
+    span.in_scope(|| {
+        tracing::info!(status = res.status().as_u16(), elapsed_ms = started.elapsed().as_millis() as u64, "done")
+    });
+    let value = HeaderValue::from_str(&id).expect("request ids are printable ASCII");
+    res.headers_mut().insert(REQUEST_ID_HEADER, value);
+    res
+}
+
+/// Time any request may take before it is answered with 504.
+pub const REQUEST_BUDGET: Duration = Duration::from_secs(15);
+/// Stats scan every zone of a home; they are cut off sooner so a slow one
+/// does not pin pool connections.
+pub const STATS_BUDGET: Duration = Duration::from_secs(5);
+
+/// Answers 504 once `budget` runs out. The handler future is dropped, which
+/// cancels its pending queries and hands their connections back to the pool,
+/// the same as when the client disconnects.
+pub async fn enforce_budget(State(budget): State<Duration>, req: Request, next: Next) -> Response {
+    match tokio::time::timeout(budget, next.run(req)).await {
+        Ok(res) => res,
+        Err(_) => AppError::Timeout(budget).into_response(),
+    }
+}
+
+/// Weak ETag (hash of the body) on successful GETs; a matching `If-None-Match`
+/// gets 304 without a body. The handler still runs, so this saves bandwidth,
+/// not queries.
+pub async fn conditional_get(req: Request, next: Next) -> Response {
+    if req.method() != Method::GET {
+        return next.run(req).await;
+    }
+    let if_none_match = req.headers().get(header::IF_NONE_MATCH).cloned();
+    let res = next.run(req).await;
+    // потоковые ответы (выгрузки) не буферизуются ради ETag
+    if res.status() != StatusCode::OK || res.body().size_hint().exact().is_none() {
+        return res;
+    }
+
+    let (mut parts, body) = res.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(b) => b,
+        Err(e) => return AppError::Other(anyhow::anyhow!(e)).into_response(),
+    };
+    let tag = format!("\"{}\"", hex::encode(&Sha256::digest(&bytes)[..16]));
+    // слабое сравнение: префикс W/ не учитывается
+    let matches = if_none_match
+        .as_ref()
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.split(',').map(str::trim).any(|t| t == "*" || t.trim_start_matches("W/") == tag));
+    let etag = HeaderValue::from_str(&format!("W/{tag}")).expect("hex is a valid header value");
+    parts.headers.insert(header::ETAG, etag);
+    if matches {
+        parts.status = StatusCode::NOT_MODIFIED;
+        parts.headers.remove(header::CONTENT_LENGTH);
+        parts.headers.remove(header::CONTENT_TYPE);
+        return Response::from_parts(parts, Body::empty());
+    }
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+pub fn routes() -> Router<Arc<AppState>> {
+    let stats = Router::new()
+        .route("/stats/overview", get(stats::overview))
+        .route("/stats/costs", get(stats::costs))
+        .route("/stats/streaks", get(stats::streaks))
+        .route("/stats/heatmap", get(stats::heatmap))
+        .route("/stats/trends", get(stats::trends))
+        .route("/stats/overdue", get(stats::overdue))
+        .route("/stats/time", get(stats::time_spent))
+        .route("/stats/breakdown", get(stats::breakdown))
+        .route("/zones/due", get(stats::zones_due))
+        .route("/today", get(stats::today))
+        .route("/suggestion", get(stats::suggestion))
+        .route("/badge", get(stats::badge))
+        .route("/focus", get(stats::focus))
+        .route_layer(middleware::from_fn_with_state(STATS_BUDGET, enforce_budget));
+
+    Router::new()
+        // Homes
+        .route("/homes", get(homes::list_homes).post(homes::create_home))
+        .route("/homes/:id", patch(homes::update_home).delete(homes::delete_home))
+        // Rooms
+        .route("/rooms", get(rooms::list_rooms).post(rooms::create_room))
+        .route(
+            "/rooms/:id",
+            get(rooms::get_room)
+                .patch(rooms::update_room)
+                .delete(rooms::delete_room),
+        )
+        .route("/rooms/:id/restore", post(rooms::restore_room))
+        .route("/rooms/reorder", post(rooms::reorder_rooms))
+        // Zones
+        .route(
+            "/rooms/:room_id/zones",
+            get(zones::list_zones).post(zones::create_zone),
+        )
+        .route("/rooms/:room_id/zones/bulk", post(zones::create_zones))
+        .route("/rooms/:room_id/zones/reorder", post(zones::reorder_zones))
+        .route("/rooms/:room_id/zones/:id", put(zones::put_zone))
+        .route(
+            "/zones/:id",
+            get(zones::get_zone)
+                .patch(zones::update_zone)
+                .delete(zones::delete_zone),
+        )
+        .route("/zones/:id/clean", post(zones::clean_zone))
+        .route(
+            "/zones/:id/pause",
+            put(zones::pause_zone).delete(zones::resume_zone),
+        )
+        .route("/zones/:id/events", get(zones::list_events))
+        .route("/zones", get(zones::list_all_zones))
+        .route("/zones/bulk/clean", post(zones::bulk_clean))
+        .route("/zones/bulk/delete", post(zones::bulk_delete))
+        .route("/zones/bulk/update", post(zones::bulk_update))
+        .route("/zones/auto/clean", post(zones::trigger_auto_clean))
+        // Background bulk operations
+        .route("/operations", post(operations::create_operation))
+        .route("/operations/:id", get(operations::get_operation))
+        // Tasks
+        .route(
+            "/zones/:id/tasks",
+            get(tasks::list_tasks).post(tasks::create_task),
+        )
+        .route(
+            "/zones/:id/tasks/:task_id",
+            patch(tasks::update_task).delete(tasks::delete_task),
+        )
+        .route("/zones/:id/instructions", put(tasks::put_instructions))
+        // Tags
+        .route("/tags", get(tags::list_tags).post(tags::create_tag))
+        .route("/tags/:id", patch(tags::update_tag).delete(tags::delete_tag))
+        .route("/zones/:id/tags", get(tags::list_zone_tags))
+        .route(
+            "/zones/:id/tags/:tag_id",
+            put(tags::attach_tag).delete(tags::detach_tag),
+        )
+        // Zone groups
+        .route("/groups", get(groups::list_groups).post(groups::create_group))
+        .route(
+            "/groups/:id",
+            get(groups::get_group)
+                .patch(groups::update_group)
+                .delete(groups::delete_group),
+        )
+        .route("/groups/:id/zones", get(groups::list_group_zones))
+        .route(
+            "/groups/:id/zones/:zone_id",
+            put(groups::add_zone).delete(groups::remove_zone),
+        )
+        .route("/groups/:id/clean", post(groups::clean_group))
+        // Supplies
+        .route(
+            "/supplies",
+            get(supplies::list_supplies).post(supplies::create_supply),
+        )
+        .route("/supplies/low", get(supplies::low_supplies))
+        .route(
+            "/supplies/:id/purchases",
+            get(supplies::list_purchases).post(supplies::create_purchase),
+        )
+        .route(
+            "/supplies/:id",
+            get(supplies::get_supply)
+                .patch(supplies::update_supply)
+                .delete(supplies::delete_supply),
+        )
+        .route("/zones/:id/supplies", get(supplies::list_zone_supplies))
+        .route(
+            "/zones/:id/supplies/:supply_id",
+            put(supplies::link_supply).delete(supplies::unlink_supply),
+        )
+        // Stats
+        .merge(stats)
+        // Webhooks
+        .route(
+            "/webhooks",
+            get(webhooks::list_webhooks).post(webhooks::create_webhook),
+        )
+        .route(
+            "/webhooks/:id",
+            patch(webhooks::update_webhook).delete(webhooks::delete_webhook),
+        )
+        .route("/webhooks/:id/deliveries", get(webhooks::list_deliveries))
+        // Undo of deletes
+        .route("/undo/:token", post(undo::undo))
+        // Integrations
+        .route(
+            "/integrations",
+            get(integrations::list_integrations).post(integrations::create_integration),
+        )
+        .route(
+            "/integrations/:id",
+            patch(integrations::update_integration).delete(integrations::delete_integration),
+        )
+        .route("/integrations/inbound/:integration_id", post(integrations::inbound))
+        .route("/integrations/voice/intents", post(voice::intents))
+        .route("/integrations/google-calendar", get(google_calendar::list_calendars))
+        .route("/integrations/google-calendar/connect", post(google_calendar::connect))
+        .route("/integrations/google-calendar/callback", get(google_calendar::callback))
+        .route("/integrations/google-calendar/:id", delete(google_calendar::disconnect))
+        // Notifications
+        .route("/notifications", get(notifications::list_notifications))
+        .route("/notifications/unread", get(notifications::unread_count))
+        .route("/notifications/read", post(notifications::mark_all_read))
+        .route("/notifications/:id/read", post(notifications::mark_read))
+        // Settings
+        .route(
+            "/settings",
+            get(settings::get_settings).patch(settings::update_settings),
+        )
+        // Metrics
+        .route("/metrics", get(metrics::metrics))
+        // Export
+        .route("/export/cleanings.csv", get(export::cleanings))
+        .route("/export/zones.csv", get(export::zones))
+        .route("/graphql", get(graphql::sdl).post(graphql::execute))
+        .layer(middleware::from_fn(conditional_get))
+        .layer(middleware::from_fn_with_state(REQUEST_BUDGET, enforce_budget))
+        .layer(middleware::from_fn(request_id))
+}
 
 #[cfg(test)]
 mod tests {
@@ -17,7 +303,10 @@ mod tests {
     use std::sync::Arc;
     use tower::ServiceExt;
 
-    use crate::models::{AppState, RoomView};
+    use crate::{
+        config::Config,
+        models::{AppState, Page, RoomView},
+    };
 
     fn test_app(state: Arc<AppState>) -> Router {
         Router::new()
@@ -33,7 +322,7 @@ mod tests {
             .await
             .unwrap();
         sqlx::migrate!("./migrations").run(&pool).await.unwrap();
-        let state = Arc::new(AppState { pool });
+        let state = Arc::new(AppState::new(pool, &Config::default()).await.unwrap());
 
         let app = test_app(state);
 
@@ -59,8 +348,80 @@ mod tests {
             .unwrap();
         assert_eq!(response.status(), StatusCode::OK);
         let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
-        let rooms: Vec<RoomView> = serde_json::from_slice(&body).unwrap();
-        assert_eq!(rooms.len(), 1);
-        assert_eq!(rooms[0].name, "kitchen");
+        let rooms: Page<RoomView> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(rooms.total, 1);
+        assert_eq!(rooms.items[0].name, "kitchen");
+    }
+
+    #[tokio::test]
+    async fn slow_request_gets_504() {
+        let app = Router::new()
+            .route(
+                "/slow",
+                get(|| async {
+                    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                    "done"
+                }),
+            )
+            .layer(axum::middleware::from_fn_with_state(
+                std::time::Duration::from_millis(20),
+                super::enforce_budget,
+            ));
+
+        let response = app
+            .oneshot(Request::get("/slow").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let err: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(err["code"], "timeout");
+    }
+
+    #[tokio::test]
+    async fn timed_out_request_frees_its_idempotency_key() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        use axum::{extract::State, routing::post, Json};
+
+        use crate::{api::idempotency::Keyed, error::AppError};
+
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+        let state = Arc::new(AppState::new(pool, &Config::default()).await.unwrap());
+        // первый запрос зависает, повтор отвечает сразу
+        static FIRST: AtomicBool = AtomicBool::new(true);
+        let app = Router::new()
+            .route(
+                "/keyed",
+                post(|State(state): State<Arc<AppState>>, Keyed(key, Json(_)): Keyed<Json<serde_json::Value>>| async move {
+                    key.run(&state, async {
+                        if FIRST.swap(false, Ordering::SeqCst) {
+                            tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                        }
+                        Ok::<_, AppError>(StatusCode::CREATED)
+                    })
+                    .await
+                }),
+            )
+            .layer(axum::middleware::from_fn_with_state(
+                std::time::Duration::from_millis(50),
+                super::enforce_budget,
+            ))
+            .with_state(state);
+        let keyed = || {
+            Request::post("/keyed")
+                .header("content-type", "application/json")
+                .header("idempotency-key", "k-1")
+                .body(Body::from("{}"))
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(keyed()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+        // ключ освобождает фоновая задача
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        let response = app.oneshot(keyed()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
     }
 }
@@ -0,0 +1,95 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    error::{AppError, AppResult},
+    models::{AppState, Notification},
+};
+
+const DEFAULT_LIMIT: u32 = 50;
+
+#[derive(Deserialize, IntoParams)]
+pub struct ListNotifications {
+    /// Only notifications not read yet.
+    pub unread: Option<bool>,
+    /// At most this many, newest first (default 50).
+    pub limit: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct UnreadCount {
+    pub unread: i64,
+}
+
+#[utoipa::path(
+    get,
+    path = "/notifications",
+    params(ListNotifications),
+    responses((status = 200, description = "Sent notifications, newest first", body = [Notification]))
+)]
+pub async fn list_notifications(
+    State(state): State<std::sync::Arc<AppState>>,
+    Query(p): Query<ListNotifications>,
+) -> AppResult<Json<Vec<Notification>>> {
+    let notifications = sqlx::query_as::<_, Notification>(
+        r#"SELECT id, event, summary, data, created_at, read_at
+           FROM notifications WHERE (?1 = 0 OR read_at IS NULL)
+           ORDER BY created_at DESC LIMIT ?2"#,
+    )
+    .bind(p.unread.unwrap_or(false))
+    .bind(p.limit.unwrap_or(DEFAULT_LIMIT).max(1))
+    .fetch_all(&state.pool)
+    .await?;
+    Ok(Json(notifications))
+}
+
+#[utoipa::path(
+    get,
+    path = "/notifications/unread",
+    responses((status = 200, description = "Number of unread notifications", body = UnreadCount))
+)]
+pub async fn unread_count(State(state): State<std::sync::Arc<AppState>>) -> AppResult<Json<UnreadCount>> {
+    let (unread,): (i64,) = sqlx::query_as("SELECT COUNT(1) FROM notifications WHERE read_at IS NULL")
+        .fetch_one(&state.pool)
+        .await?;
+    Ok(Json(UnreadCount { unread }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/notifications/{id}/read",
+    params(("id" = String, Path, description = "Notification id")),
+    responses((status = 204, description = "Marked as read"))
+)]
+pub async fn mark_read(
+    State(state): State<std::sync::Arc<AppState>>,
+    Path(id): Path<String>,
+) -> AppResult<axum::http::StatusCode> {
+    let res = sqlx::query("UPDATE notifications SET read_at = COALESCE(read_at, ?1) WHERE id = ?2")
+        .bind(Utc::now())
+        .bind(&id)
+        .execute(&state.writer)
+        .await?;
+    if res.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(axum::http::StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/notifications/read",
+    responses((status = 204, description = "Every notification marked as read"))
+)]
+pub async fn mark_all_read(State(state): State<std::sync::Arc<AppState>>) -> AppResult<axum::http::StatusCode> {
+    sqlx::query("UPDATE notifications SET read_at = ?1 WHERE read_at IS NULL")
+        .bind(Utc::now())
+        .execute(&state.writer)
+        .await?;
+    Ok(axum::http::StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,220 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::Utc;
+use sqlx::types::Json as SqlJson;
+use tracing::Instrument;
+use uuid::Uuid;
+
+use super::zones;
+use crate::{
+    error::{AppError, AppResult},
+    jobs,
+    models::{AppState, BulkItem, BulkOperation, BulkStatus, Operation, OPERATION_COLUMNS},
+};
+
+/// Lease name shared by the request that starts an operation and the scheduler.
+pub const JOB: &str = "operations";
+/// Zones handled per transaction; progress is saved after each chunk.
+const CHUNK: usize = 50;
+pub const MAX_OPERATION_ZONES: usize = 10_000;
+/// Renewed after every chunk, so only a stalled run loses the lease.
+const LEASE_TTL: Duration = Duration::from_secs(120);
+
+async fn fetch_operation(state: &AppState, id: &str) -> AppResult<Operation> {
+    sqlx::query_as::<_, Operation>(&format!("SELECT {OPERATION_COLUMNS} FROM bulk_operations WHERE id = ?1"))
+        .bind(id)
+        .fetch_optional(&state.pool)
+        .await?
+        .ok_or(AppError::NotFound)
+}
+
+#[utoipa::path(
+    post,
+    path = "/operations",
+    request_body = BulkOperation,
+    responses((status = 202, description = "Operation accepted; poll it for progress", body = Operation))
+)]
+pub async fn create_operation(
+    State(state): State<Arc<AppState>>,
+    Json(mut body): Json<BulkOperation>,
+) -> AppResult<(StatusCode, Json<Operation>)> {
+    let total = body.zone_ids().len();
+    if total == 0 || total > MAX_OPERATION_ZONES {
+        return Err(AppError::Validation(format!(
+            "zone_ids must have 1 to {MAX_OPERATION_ZONES} ids"
+        )));
+    }
+    let now = Utc::now();
+    // время уборки фиксируется сразу, чтобы продолжение после перезапуска не сдвигало его
+    if let BulkOperation::Clean { cleaned_at, .. } = &mut body {
+        cleaned_at.get_or_insert(now);
+    }
+    let op = Operation {
+        id: Uuid::new_v4().to_string(),
+        kind: body.kind().to_string(),
+        status: "pending".into(),
+        total: total as i64,
+        processed: 0,
+        updated: 0,
+        results: SqlJson(Vec::new()),
+        error: None,
+        created_at: now,
+        updated_at: now,
+        finished_at: None,
+    };
+    sqlx::query(
+        r#"INSERT INTO bulk_operations(id, kind, status, request, total, created_at, updated_at)
+           VALUES (?1, ?2, 'pending', ?3, ?4, ?5, ?5)"#,
+    )
+    .bind(&op.id)
+    .bind(&op.kind)
+    .bind(SqlJson(&body))
+    .bind(op.total)
+    .bind(now)
+    .execute(&state.writer)
+    .await?;
+
+    // если аренду держит другой запуск, операцию подберёт он или планировщик
+    let worker = state.clone();
+    tokio::spawn(
+        async move {
+            if let Err(e) = jobs::run_exclusive(&worker.writer, JOB, LEASE_TTL, run_pending(&worker)).await {
+                tracing::warn!(error = %e, "bulk operations failed");
+            }
+        }
+        .in_current_span(),
+    );
+    Ok((StatusCode::ACCEPTED, Json(op)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/operations/{id}",
+    params(("id" = String, Path, description = "Operation id")),
+    responses((status = 200, description = "Operation progress", body = Operation))
+)]
+pub async fn get_operation(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> AppResult<Json<Operation>> {
+    Ok(Json(fetch_operation(&state, &id).await?))
+}
+
+/// Runs every unfinished operation, oldest first, picking up `running` ones
+/// where a crashed run left them. Call under the `operations` lease; runs in
+/// this process wait for each other. Stops early when the lease is lost.
+/// Returns how many operations were run.
+pub async fn run_pending(state: &Arc<AppState>) -> AppResult<u64> {
+    let _running = state.operations.lock().await;
+    let mut n = 0;
+    loop {
+        let next: Option<(String, SqlJson<BulkOperation>, SqlJson<Vec<BulkItem>>)> = sqlx::query_as(
+            r#"SELECT id, request, results FROM bulk_operations
+               WHERE status IN ('pending', 'running')
+               ORDER BY created_at ASC LIMIT 1"#,
+        )
+        .fetch_optional(&state.pool)
+        .await?;
+        let Some((id, SqlJson(request), SqlJson(results))) = next else {
+            return Ok(n);
+        };
+        sqlx::query("UPDATE bulk_operations SET status = 'running', updated_at = ?1 WHERE id = ?2")
+            .bind(Utc::now())
+            .bind(&id)
+            .execute(&state.writer)
+            .await?;
+        match run_operation(state, &id, &request, results).await {
+            Ok(true) => {}
+            // операцию дочистит новый держатель аренды с сохранённого места
+            Ok(false) => {
+                tracing::warn!(operation = id, "lease lost, leaving the operation to its new holder");
+                return Ok(n);
+            }
+            Err(e) => {
+                tracing::warn!(operation = id, error = %e, "bulk operation failed");
+                let now = Utc::now();
+                sqlx::query(
+                    "UPDATE bulk_operations SET status = 'failed', error = ?1, updated_at = ?2, finished_at = ?2 WHERE id = ?3",
+                )
+                .bind(e.to_string())
+                .bind(now)
+                .bind(&id)
+                .execute(&state.writer)
+                .await?;
+            }
+        }
+        n += 1;
+    }
+}
+
+/// Handles the ids after the `results` already saved, one chunk at a time,
+/// renewing the lease after each. `false` when the lease was lost midway.
+async fn run_operation(
+    state: &Arc<AppState>,
+    id: &str,
+    request: &BulkOperation,
+    mut results: Vec<BulkItem>,
+) -> AppResult<bool> {
+    let ids = request.zone_ids();
+    while results.len() < ids.len() {
+        let chunk = &ids[results.len()..ids.len().min(results.len() + CHUNK)];
+        match request {
+            // уборка и удаление пишут порцию и прогресс одной транзакцией
+            BulkOperation::Clean { cleaned_at, .. } => {
+                let mut tx = state.writer.begin().await?;
+                let done = zones::clean_many(&mut tx, chunk, cleaned_at.unwrap_or_else(Utc::now)).await?;
+                results.extend(done);
+                save_progress(&mut tx, id, &results, ids.len()).await?;
+                tx.commit().await?;
+            }
+            BulkOperation::Delete { .. } => {
+                let mut tx = state.writer.begin().await?;
+                results.extend(zones::delete_many(&mut tx, chunk, Utc::now()).await?);
+                save_progress(&mut tx, id, &results, ids.len()).await?;
+                tx.commit().await?;
+            }
+            // каждая зона меняется своей транзакцией; повтор после сбоя даёт тот же результат
+            BulkOperation::Update { changes, .. } => {
+                results.extend(zones::update_many(state, chunk, changes).await?);
+                let mut conn = state.writer.acquire().await?;
+                save_progress(&mut conn, id, &results, ids.len()).await?;
+            }
+        }
+        if results.len() < ids.len() && !jobs::try_acquire(&state.writer, JOB, jobs::instance_id(), LEASE_TTL).await? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+async fn save_progress(
+    conn: &mut sqlx::SqliteConnection,
+    id: &str,
+    results: &[BulkItem],
+    total: usize,
+) -> AppResult<()> {
+    let now = Utc::now();
+    let updated = results.iter().filter(|r| r.status == BulkStatus::Ok).count() as i64;
+    let finished = results.len() >= total;
+    sqlx::query(
+        r#"UPDATE bulk_operations
+           SET processed = ?1, updated = ?2, results = ?3, updated_at = ?4,
+               status = CASE WHEN ?5 THEN 'done' ELSE status END,
+               finished_at = CASE WHEN ?5 THEN ?4 ELSE NULL END
+           WHERE id = ?6"#,
+    )
+    .bind(results.len() as i64)
+    .bind(updated)
+    .bind(SqlJson(results))
+    .bind(now)
+    .bind(finished)
+    .bind(id)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
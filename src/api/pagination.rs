@@ -0,0 +1,59 @@
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::error::{AppError, AppResult};
+
+#[derive(Deserialize, IntoParams, Default)]
+pub struct PageParams {
+    /// Page size; every item when unset.
+    pub limit: Option<u32>,
+    /// `next_cursor` of the previous page.
+    pub cursor: Option<String>,
+}
+
+impl PageParams {
+    /// `LIMIT` for the query: `-1` (no limit) when unset, otherwise one row more
+    /// than the page so `trim` can tell whether another page follows.
+    pub fn fetch_limit(&self) -> i64 {
+        self.limit.map_or(-1, |l| i64::from(l.max(1)) + 1)
+    }
+
+    /// Drops the extra row fetched by `fetch_limit`; `true` when there is a next page.
+    pub fn trim<T>(&self, items: &mut Vec<T>) -> bool {
+        let page = self.limit.map_or(usize::MAX, |l| l.max(1) as usize);
+        if items.len() > page {
+            items.truncate(page);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Rows to skip for lists paged by position; their cursor is the offset.
+    pub fn offset(&self) -> AppResult<i64> {
+        match self.cursor.as_deref() {
+            None => Ok(0),
+            Some(c) => c
+                .parse::<u32>()
+                .map(i64::from)
+                .map_err(|_| AppError::Validation("invalid cursor".into())),
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    pub fn as_sql(self) -> &'static str {
+        match self {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        }
+    }
+}
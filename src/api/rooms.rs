@@ -1,136 +1,287 @@
+use std::collections::HashMap;
+
 use axum::{
     extract::{Path, Query, State},
+    response::Response,
     Json,
 };
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use serde::Deserialize;
-use utoipa::IntoParams;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
-use sqlx::Row;
+use sqlx::{types::Json as SqlJson, FromRow, Row};
 
+use super::{
+    extract::LiveRoom,
+    fields::{Fields, FieldsParams},
+    idempotency::Keyed,
+    homes::{ensure_home, HomeParams, HomeScope},
+    pagination::{PageParams, SortOrder},
+    undo::{self, Deleted, Undoable},
+};
 use crate::{
     error::{AppError, AppResult},
-    models::{AppState, NewRoom, Room, RoomView, UpdateRoom},
+    events, webhooks,
+    models::{
+        validate_external_ref, AppState, NewRoom, Page, Reorder, Room, RoomView, UpdateRoom, Zone,
+        ZoneChange, ZoneView, ROOM_COLUMNS, ZONE_COLUMNS,
+    },
 };
 
-#[derive(Deserialize, IntoParams)]
+#[derive(Deserialize, IntoParams, Default)]
 pub struct ListParams {
     pub with_stats: Option<bool>,
     pub q: Option<String>,
+    /// Manual order (`sort_order`) when unset.
+    pub sort: Option<RoomSort>,
+    pub order: Option<SortOrder>,
+}
+
+#[derive(Deserialize, IntoParams, Default)]
+pub struct IncludeParams {
+    /// `zones` nests each room's zones in the response.
+    pub include: Option<String>,
+}
+
+impl IncludeParams {
+    fn zones(&self) -> AppResult<bool> {
+        match self.include.as_deref().map(str::trim) {
+            None | Some("") => Ok(false),
+            Some("zones") => Ok(true),
+            Some(other) => Err(AppError::Validation(format!("cannot include '{other}'; only 'zones'"))),
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum RoomSort {
+    Name,
+    CreatedAt,
 }
 
+/// Room filter for `list_rooms`: name search `?1`, home `?2`.
+const LIST_FILTER: &str = r#"deleted_at IS NULL AND (?1 IS NULL OR name LIKE '%' || ?1 || '%')
+    AND (?2 IS NULL OR home_id = ?2)"#;
+
 #[utoipa::path(
     get,
     path = "/rooms",
-    params(ListParams),
-    responses((status = 200, description = "List rooms", body = [RoomView]))
+    params(ListParams, PageParams, HomeParams, IncludeParams, FieldsParams),
+    responses((status = 200, description = "List rooms", body = RoomPage))
 )]
 pub async fn list_rooms(
     State(state): State<std::sync::Arc<AppState>>,
+    HomeScope(home_id): HomeScope,
     Query(p): Query<ListParams>,
-) -> AppResult<Json<Vec<RoomView>>> {
-    let mut rooms: Vec<Room> = sqlx::query_as::<_, Room>(
-        r#"SELECT id, name, icon, created_at, updated_at, deleted_at
-           FROM rooms
-           WHERE deleted_at IS NULL AND (?1 IS NULL OR name LIKE '%' || ?1 || '%')
-           ORDER BY created_at DESC"#,
-    )
-    .bind(p.q)
+    Query(page): Query<PageParams>,
+    Query(include): Query<IncludeParams>,
+    fields: Fields,
+) -> AppResult<Json<Page<serde_json::Value>>> {
+    let with_zones = include.zones()?;
+    let mut rooms = query_rooms(&state, home_id, &p, &page).await?;
+    if with_zones {
+        let ids: Vec<String> = rooms.items.iter().map(|r| r.id.clone()).collect();
+        let mut zones = zones_by_room(&state, &ids).await?;
+        for r in &mut rooms.items {
+            r.zones = Some(zones.remove(&r.id).unwrap_or_default());
+        }
+    }
+    Ok(Json(fields.page(rooms)?))
+}
+
+/// Zones of all `room_ids` in one query, grouped by room in list order.
+pub(super) async fn zones_by_room(state: &AppState, room_ids: &[String]) -> AppResult<HashMap<String, Vec<ZoneView>>> {
+    let zones = sqlx::query_as::<_, Zone>(&format!(
+        r#"SELECT {ZONE_COLUMNS} FROM zones
+           WHERE deleted_at IS NULL AND room_id IN (SELECT value FROM json_each(?1))
+           ORDER BY sort_order ASC, created_at DESC, id"#
+    ))
+    .bind(SqlJson(room_ids))
     .fetch_all(&state.pool)
     .await?;
+    let tz = super::settings::timezone(&state.pool).await?;
+    let mut by_room: HashMap<String, Vec<ZoneView>> = HashMap::new();
+    for z in zones {
+        by_room.entry(z.room_id.clone()).or_default().push(ZoneView::localized(z, tz));
+    }
+    Ok(by_room)
+}
 
-    let with_stats = p.with_stats.unwrap_or(false);
-    let mut out: Vec<RoomView> = Vec::with_capacity(rooms.len());
-    for r in rooms.drain(..) {
-        if with_stats {
-            let rec = sqlx::query(
-                r#"SELECT COUNT(*) as zones_total,
-                          MAX(last_cleaned_at) as last_cleaned_at
-                   FROM zones WHERE room_id = ?1 AND deleted_at IS NULL"#,
-            )
-            .bind(&r.id)
-            .fetch_one(&state.pool)
-            .await?;
-            let zones_total: i64 = rec.try_get::<i64, _>("zones_total").unwrap_or(0);
-            let last_cleaned_at: Option<chrono::DateTime<Utc>> = rec.try_get("last_cleaned_at").ok();
-            let rec = sqlx::query(
-                r#"SELECT COUNT(*) as cnt
-                   FROM zones
-                   WHERE room_id = ?1 AND deleted_at IS NULL
-                     AND (last_cleaned_at IS NOT NULL)"#,
-            )
-            .bind(&r.id)
-            .fetch_one(&state.pool)
-            .await?;
-            let cleaned_count: i64 = rec.try_get::<i64, _>("cnt").unwrap_or(0);
+/// A listed room; the stats columns are NULL unless `with_stats` asked for them.
+#[derive(FromRow)]
+struct RoomRow {
+    #[sqlx(flatten)]
+    room: Room,
+    zones_total: Option<i64>,
+    zones_cleaned_count: Option<i64>,
+    last_cleaned_at: Option<DateTime<Utc>>,
+}
 
-            out.push(RoomView {
-                id: r.id,
-                name: r.name,
-                icon: r.icon,
-                created_at: r.created_at,
-                updated_at: r.updated_at,
-                deleted_at: r.deleted_at,
-                zones_total: Some(zones_total),
-                zones_cleaned_count: Some(cleaned_count),
-                last_cleaned_at,
-            });
-        } else {
-            out.push(RoomView {
-                id: r.id,
-                name: r.name,
-                icon: r.icon,
-                created_at: r.created_at,
-                updated_at: r.updated_at,
-                deleted_at: r.deleted_at,
-                zones_total: None,
-                zones_cleaned_count: None,
-                last_cleaned_at: None,
-            });
-        }
-    }
-    Ok(Json(out))
+pub(super) async fn query_rooms(
+    state: &AppState,
+    home_id: Option<String>,
+    p: &ListParams,
+    page: &PageParams,
+) -> AppResult<Page<RoomView>> {
+    let offset = page.offset()?;
+    let (total,): (i64,) = sqlx::query_as(&format!("SELECT COUNT(1) FROM rooms WHERE {LIST_FILTER}"))
+        .bind(&p.q)
+        .bind(&home_id)
+        .fetch_one(&state.pool)
+        .await?;
+    let order = p.order.unwrap_or_default().as_sql();
+    let order_by = match p.sort {
+        None => "sort_order ASC, created_at DESC, id".to_string(),
+        Some(RoomSort::Name) => format!("name {order}, id"),
+        Some(RoomSort::CreatedAt) => format!("created_at {order}, id"),
+    };
+    // статистика одним запросом: зоны агрегируются до join, колонки комнат не пересекаются
+    let (stats, stats_join) = if p.with_stats.unwrap_or(false) {
+        (
+            "COALESCE(s.zones_total, 0) AS zones_total, COALESCE(s.zones_cleaned_count, 0) AS zones_cleaned_count, \
+             s.last_cleaned_at",
+            r#"LEFT JOIN (SELECT room_id, COUNT(*) AS zones_total,
+                                COUNT(last_cleaned_at) AS zones_cleaned_count,
+                                MAX(last_cleaned_at) AS last_cleaned_at
+                         FROM zones WHERE deleted_at IS NULL
+                         GROUP BY room_id) s ON s.room_id = rooms.id"#,
+        )
+    } else {
+        ("NULL AS zones_total, NULL AS zones_cleaned_count, NULL AS last_cleaned_at", "")
+    };
+    let mut rooms = sqlx::query_as::<_, RoomRow>(&format!(
+        r#"SELECT {ROOM_COLUMNS}, {stats}
+           FROM rooms {stats_join}
+           WHERE {LIST_FILTER}
+           ORDER BY {order_by}
+           LIMIT ?3 OFFSET ?4"#
+    ))
+    .bind(&p.q)
+    .bind(&home_id)
+    .bind(page.fetch_limit())
+    .bind(offset)
+    .fetch_all(&state.pool)
+    .await?;
+    let next_cursor = page
+        .trim(&mut rooms)
+        .then(|| (offset + rooms.len() as i64).to_string());
+
+    let items = rooms
+        .into_iter()
+        .map(|r| RoomView {
+            zones_total: r.zones_total,
+            zones_cleaned_count: r.zones_cleaned_count,
+            last_cleaned_at: r.last_cleaned_at,
+            ..RoomView::from(r.room)
+        })
+        .collect();
+    Ok(Page { items, total, next_cursor })
+}
+
+/// Live room with this `source`/`external_id`, as its id and home.
+async fn find_external(
+    state: &AppState,
+    source: &Option<String>,
+    external_id: &Option<String>,
+) -> AppResult<Option<(String, Option<String>)>> {
+    let (Some(source), Some(external_id)) = (source, external_id) else {
+        return Ok(None);
+    };
+    Ok(sqlx::query_as("SELECT id, home_id FROM rooms WHERE source = ?1 AND external_id = ?2 AND deleted_at IS NULL")
+        .bind(source)
+        .bind(external_id)
+        .fetch_optional(&state.pool)
+        .await?)
 }
 
 #[utoipa::path(
     post,
     path = "/rooms",
+    params(
+        HomeParams,
+        ("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key get the first response"),
+    ),
     request_body = NewRoom,
-    responses((status = 201, description = "Room created", body = RoomView))
+    responses(
+        (status = 201, description = "Room created", body = RoomView),
+        (status = 200, description = "Room with the same source/external_id updated", body = RoomView),
+        (status = 409, description = "The source/external_id belongs to a room in another home, or the Idempotency-Key was reused for another request or is still in progress"),
+    )
 )]
 pub async fn create_room(
     State(state): State<std::sync::Arc<AppState>>,
+    scope: HomeScope,
+    Keyed(key, body): Keyed<Json<NewRoom>>,
+) -> AppResult<Response> {
+    key.run(&state, insert_room(State(state.clone()), scope, body)).await
+}
+
+pub(super) async fn insert_room(
+    State(state): State<std::sync::Arc<AppState>>,
+    HomeScope(scope): HomeScope,
     Json(body): Json<NewRoom>,
 ) -> AppResult<(axum::http::StatusCode, Json<RoomView>)> {
     if body.name.trim().is_empty() {
         return Err(AppError::Validation("name is required".into()));
     }
+    validate_external_ref(&body.source, &body.external_id)?;
+    if let Some((existing, room_home)) = find_external(&state, &body.source, &body.external_id).await? {
+        // повторный импорт обновляет комнату только в том же доме
+        if room_home != body.home_id.clone().or(scope.clone()) {
+            return Err(AppError::Conflict(format!(
+                "external_id {} belongs to room {existing} in another home",
+                body.external_id.as_deref().unwrap_or_default()
+            )));
+        }
+        let upd = UpdateRoom {
+            name: Some(body.name),
+            icon: body.icon.into(),
+            home_id: body.home_id,
+        };
+        let view = update_room(State(state), Path(existing), Json(upd)).await?;
+        return Ok((axum::http::StatusCode::OK, view));
+    }
+
+    if let Some(home_id) = &body.home_id {
+        ensure_home(&state, home_id).await?;
+    }
+    let home_id = body.home_id.or(scope);
+
     let now = Utc::now();
     let id = Uuid::new_v4().to_string();
     let name = body.name;
     let icon = body.icon;
     sqlx::query(
-        r#"INSERT INTO rooms(id, name, icon, created_at, updated_at, deleted_at)
-           VALUES (?1, ?2, ?3, ?4, ?5, NULL)"#,
+        r#"INSERT INTO rooms(id, home_id, name, icon, source, external_id, created_at, updated_at, deleted_at)
+           VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, NULL)"#,
     )
     .bind(&id)
+    .bind(&home_id)
     .bind(&name)
     .bind(&icon)
+    .bind(&body.source)
+    .bind(&body.external_id)
     .bind(now)
     .bind(now)
-    .execute(&state.pool)
+    .execute(&state.writer)
     .await?;
 
     let view = RoomView {
-        id,
-        name,
-        icon,
-        created_at: now,
-        updated_at: now,
-        deleted_at: None,
         zones_total: Some(0),
         zones_cleaned_count: Some(0),
-        last_cleaned_at: None,
+        ..RoomView::from(Room {
+            id,
+            home_id,
+            name,
+            icon,
+            sort_order: 0,
+            source: body.source,
+            external_id: body.external_id,
+            created_at: now,
+            updated_at: now,
+            deleted_at: None,
+        })
     };
     Ok((axum::http::StatusCode::CREATED, Json(view)))
 }
@@ -138,22 +289,25 @@ pub async fn create_room(
 #[utoipa::path(
     get,
     path = "/rooms/{id}",
-    params(("id" = String, Path, description = "Room id")),
+    params(("id" = String, Path, description = "Room id"), IncludeParams, FieldsParams),
     responses((status = 200, description = "Room details", body = RoomView))
 )]
 pub async fn get_room(
     State(state): State<std::sync::Arc<AppState>>,
-    Path(id): Path<String>,
-) -> AppResult<Json<RoomView>> {
-    let r = sqlx::query_as::<_, Room>(
-        r#"SELECT id, name, icon, created_at, updated_at, deleted_at
-           FROM rooms WHERE id = ?1 AND deleted_at IS NULL"#,
-    )
-    .bind(&id)
-    .fetch_optional(&state.pool)
-    .await?;
-    let r = r.ok_or(AppError::NotFound)?;
+    LiveRoom(r): LiveRoom,
+    Query(include): Query<IncludeParams>,
+    fields: Fields,
+) -> AppResult<Json<serde_json::Value>> {
+    let view = room_view(&state, r, include.zones()?).await?;
+    Ok(Json(fields.one(&view)?))
+}
 
+/// One room with its stats, and its zones when `with_zones`.
+pub(super) async fn room_view(state: &AppState, r: Room, with_zones: bool) -> AppResult<RoomView> {
+    let zones = match with_zones {
+        true => Some(zones_by_room(state, std::slice::from_ref(&r.id)).await?.remove(&r.id).unwrap_or_default()),
+        false => None,
+    };
     let stats = sqlx::query(
         r#"SELECT COUNT(*) as zones_total,
                   MAX(last_cleaned_at) as last_cleaned_at
@@ -177,17 +331,13 @@ pub async fn get_room(
     .await?;
     let zones_cleaned_count: i64 = cleaned.try_get("cnt").unwrap_or(0);
 
-    Ok(Json(RoomView {
-        id: r.id,
-        name: r.name,
-        icon: r.icon,
-        created_at: r.created_at,
-        updated_at: r.updated_at,
-        deleted_at: r.deleted_at,
+    Ok(RoomView {
         zones_total: Some(zones_total),
         zones_cleaned_count: Some(zones_cleaned_count),
         last_cleaned_at,
-    }))
+        zones,
+        ..RoomView::from(r)
+    })
 }
 
 #[utoipa::path(
@@ -203,104 +353,148 @@ pub async fn update_room(
     Json(body): Json<UpdateRoom>,
 ) -> AppResult<Json<RoomView>> {
     let now = Utc::now();
-    let rec = sqlx::query_as::<_, Room>(
-        "SELECT id, name, icon, created_at, updated_at, deleted_at FROM rooms WHERE id = ?1 AND deleted_at IS NULL"
-    ).bind(&id).fetch_optional(&state.pool).await?;
+    let rec = sqlx::query_as::<_, Room>(&format!(
+        "SELECT {ROOM_COLUMNS} FROM rooms WHERE id = ?1 AND deleted_at IS NULL"
+    ))
+    .bind(&id)
+    .fetch_optional(&state.pool)
+    .await?;
     let mut r = rec.ok_or(AppError::NotFound)?;
 
     let name = body.name.unwrap_or(r.name.clone());
-    let icon = body.icon.or(r.icon.clone());
+    let icon = body.icon.apply(r.icon.clone());
+    if let Some(home_id) = &body.home_id {
+        ensure_home(&state, home_id).await?;
+    }
+    let home_id = body.home_id.or(r.home_id.clone());
 
     sqlx::query(
-        "UPDATE rooms SET name = ?1, icon = ?2, updated_at = ?3 WHERE id = ?4",
+        "UPDATE rooms SET name = ?1, icon = ?2, home_id = ?3, updated_at = ?4 WHERE id = ?5",
     )
     .bind(&name)
     .bind(&icon)
+    .bind(&home_id)
     .bind(now)
     .bind(&id)
-    .execute(&state.pool)
+    .execute(&state.writer)
     .await?;
 
     r.name = name.clone();
     r.icon = icon.clone();
+    r.home_id = home_id;
     r.updated_at = now;
-    Ok(Json(RoomView {
-        id: r.id,
-        name: r.name,
-        icon: r.icon,
-        created_at: r.created_at,
-        updated_at: r.updated_at,
-        deleted_at: r.deleted_at,
-        zones_total: None,
-        zones_cleaned_count: None,
-        last_cleaned_at: None,
-    }))
+    Ok(Json(RoomView::from(r)))
 }
 
 #[utoipa::path(
     delete,
     path = "/rooms/{id}",
     params(("id" = String, Path, description = "Room id")),
-    responses((status = 204, description = "Room deleted"))
+    responses((status = 204, description = "Room deleted", headers(("x-undo-token" = String, description = "For `POST /undo/{token}` within 30 seconds"))))
 )]
 pub async fn delete_room(
     State(state): State<std::sync::Arc<AppState>>,
     Path(id): Path<String>,
-) -> AppResult<axum::http::StatusCode> {
+) -> AppResult<Deleted> {
     let now = Utc::now();
+    let mut tx = state.writer.begin().await?;
     let res = sqlx::query(
         "UPDATE rooms SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
     )
     .bind(now)
     .bind(&id)
-    .execute(&state.pool)
+    .execute(&mut *tx)
     .await?;
     if res.rows_affected() == 0 {
         return Err(AppError::NotFound);
     }
     // мягко скрываем зоны
-    sqlx::query(
-        "UPDATE zones SET deleted_at = ?1 WHERE room_id = ?2 AND deleted_at IS NULL",
+    let zone_ids: Vec<(String,)> = sqlx::query_as(
+        "UPDATE zones SET deleted_at = ?1 WHERE room_id = ?2 AND deleted_at IS NULL RETURNING id",
     )
     .bind(now)
     .bind(&id)
-    .execute(&state.pool)
+    .fetch_all(&mut *tx)
     .await?;
-    Ok(axum::http::StatusCode::NO_CONTENT)
+    for (zone_id,) in &zone_ids {
+        events::record(&mut *tx, zone_id, &ZoneChange::Deleted, now).await?;
+    }
+    let data = serde_json::json!({ "room_id": id, "zone_ids": zone_ids.iter().map(|(z,)| z).collect::<Vec<_>>() });
+    webhooks::enqueue(&mut tx, webhooks::ROOM_DELETED, data, now).await?;
+    let deleted = undo::issue(&mut tx, Undoable::Room, &id, now).await?;
+    tx.commit().await?;
+    Ok(deleted)
 }
 
 #[utoipa::path(
     post,
     path = "/rooms/{id}/restore",
     params(("id" = String, Path, description = "Room id")),
-    responses((status = 200, description = "Room restored", body = RoomView))
+    responses((status = 200, description = "Room restored with the zones deleted along with it", body = RoomView))
 )]
 pub async fn restore_room(
     State(state): State<std::sync::Arc<AppState>>,
     Path(id): Path<String>,
 ) -> AppResult<Json<RoomView>> {
-    let res = sqlx::query("UPDATE rooms SET deleted_at = NULL WHERE id = ?1")
-        .bind(&id)
-        .execute(&state.pool)
-        .await?;
-    if res.rows_affected() == 0 {
-        return Err(AppError::NotFound);
-    }
-    let r = sqlx::query_as::<_, Room>(
-        "SELECT id, name, icon, created_at, updated_at, deleted_at FROM rooms WHERE id = ?1",
+    let mut tx = state.writer.begin().await?;
+    let r = restore(&mut tx, &id, Utc::now()).await?;
+    tx.commit().await?;
+    Ok(Json(RoomView::from(r)))
+}
+
+/// Clears the room's `deleted_at` together with that of the zones deleted at
+/// the same moment, which is how `delete_room` hides them.
+pub(super) async fn restore(conn: &mut sqlx::SqliteConnection, id: &str, now: DateTime<Utc>) -> AppResult<Room> {
+    // зоны, удалённые вместе с комнатой, помечены тем же временем; удалённые раньше остаются удалёнными
+    let zone_ids: Vec<(String,)> = sqlx::query_as(
+        r#"UPDATE zones SET deleted_at = NULL, updated_at = ?2
+           WHERE room_id = ?1 AND deleted_at = (SELECT deleted_at FROM rooms WHERE id = ?1)
+           RETURNING id"#,
     )
-    .bind(&id)
-    .fetch_one(&state.pool)
+    .bind(id)
+    .bind(now)
+    .fetch_all(&mut *conn)
     .await?;
-    Ok(Json(RoomView {
-        id: r.id,
-        name: r.name,
-        icon: r.icon,
-        created_at: r.created_at,
-        updated_at: r.updated_at,
-        deleted_at: r.deleted_at,
-        zones_total: None,
-        zones_cleaned_count: None,
-        last_cleaned_at: None,
-    }))
+    let r = sqlx::query_as::<_, Room>(&format!(
+        "UPDATE rooms SET deleted_at = NULL WHERE id = ?1 RETURNING {ROOM_COLUMNS}"
+    ))
+    .bind(id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(AppError::NotFound)?;
+    for (zone_id,) in &zone_ids {
+        events::record(&mut *conn, zone_id, &ZoneChange::Restored, now).await?;
+        // пока зона была удалена, срок не пересчитывался
+        super::zones::sync_next_due(&mut *conn, Some(zone_id)).await?;
+    }
+    Ok(r)
+}
+
+#[utoipa::path(
+    post,
+    path = "/rooms/reorder",
+    params(HomeParams),
+    request_body = Reorder,
+    responses((status = 200, description = "Rooms in the new order", body = [RoomView]))
+)]
+pub async fn reorder_rooms(
+    State(state): State<std::sync::Arc<AppState>>,
+    HomeScope(home_id): HomeScope,
+    Json(body): Json<Reorder>,
+) -> AppResult<Json<Vec<RoomView>>> {
+    body.validate()?;
+    let mut tx = state.writer.begin().await?;
+    for (pos, id) in body.ids.iter().enumerate() {
+        let res = sqlx::query("UPDATE rooms SET sort_order = ?1 WHERE id = ?2 AND deleted_at IS NULL")
+            .bind(pos as i64 + 1)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        if res.rows_affected() == 0 {
+            return Err(AppError::Validation(format!("unknown room id {id}")));
+        }
+    }
+    tx.commit().await?;
+    let page = query_rooms(&state, home_id, &ListParams::default(), &PageParams::default()).await?;
+    Ok(Json(page.items))
 }
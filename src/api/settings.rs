@@ -0,0 +1,86 @@
+use axum::{extract::State, Json};
+use chrono::Utc;
+use chrono_tz::Tz;
+use sqlx::SqliteExecutor;
+
+use crate::{
+    error::{AppError, AppResult},
+    models::{AppState, Db, Settings, UpdateSettings},
+};
+
+pub async fn load(pool: &Db) -> AppResult<Settings> {
+    let s = sqlx::query_as::<_, Settings>(
+        "SELECT max_zones_per_day, timezone, week_starts_on, updated_at FROM settings WHERE id = 1",
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(s)
+}
+
+/// Timezone due dates are evaluated in, if one is configured.
+pub async fn timezone<'e, E: SqliteExecutor<'e>>(exec: E) -> AppResult<Option<Tz>> {
+    let (tz,): (Option<String>,) = sqlx::query_as("SELECT timezone FROM settings WHERE id = 1")
+        .fetch_one(exec)
+        .await?;
+    Ok(tz.and_then(|t| t.parse().ok()))
+}
+
+/// SQLite date modifier moving the configured first day of the week onto Monday,
+/// so `%W` buckets start on that day.
+pub fn week_shift(s: &Settings) -> String {
+    format!("+{} days", (8 - s.week_starts_on) % 7)
+}
+
+#[utoipa::path(
+    get,
+    path = "/settings",
+    responses((status = 200, description = "Instance settings", body = Settings))
+)]
+pub async fn get_settings(State(state): State<std::sync::Arc<AppState>>) -> AppResult<Json<Settings>> {
+    Ok(Json(load(&state.pool).await?))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/settings",
+    request_body = UpdateSettings,
+    responses((status = 200, description = "Settings updated", body = Settings))
+)]
+pub async fn update_settings(
+    State(state): State<std::sync::Arc<AppState>>,
+    Json(body): Json<UpdateSettings>,
+) -> AppResult<Json<Settings>> {
+    let mut s = load(&state.pool).await?;
+    let old_timezone = s.timezone.clone();
+    if let Some(max) = body.max_zones_per_day {
+        s.max_zones_per_day = (max > 0).then_some(max as i64);
+    }
+    if let Some(tz) = body.timezone {
+        let tz = tz.trim();
+        if !tz.is_empty() && tz.parse::<Tz>().is_err() {
+            return Err(AppError::Validation(format!("unknown timezone '{tz}'")));
+        }
+        s.timezone = (!tz.is_empty()).then(|| tz.to_string());
+    }
+    if let Some(day) = body.week_starts_on {
+        if !(1..=7).contains(&day) {
+            return Err(AppError::Validation("week_starts_on must be an ISO weekday 1..=7".into()));
+        }
+        s.week_starts_on = day as i64;
+    }
+    s.updated_at = Utc::now();
+    sqlx::query(
+        "UPDATE settings SET max_zones_per_day = ?1, timezone = ?2, week_starts_on = ?3, updated_at = ?4 WHERE id = 1",
+    )
+    .bind(s.max_zones_per_day)
+    .bind(&s.timezone)
+    .bind(s.week_starts_on)
+    .bind(s.updated_at)
+    .execute(&state.writer)
+    .await?;
+    if s.timezone != old_timezone {
+        let mut conn = state.writer.acquire().await?;
+        super::zones::sync_next_due(&mut conn, None).await?;
+    }
+    Ok(Json(s))
+}
@@ -1,122 +1,1035 @@
-use axum::{extract::Query, Json};
-use chrono::{Duration, Utc};
+use axum::{
+    extract::Query,
+    http::{header, HeaderValue},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Offset, TimeZone, Utc};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::FromRow;
 use utoipa::{IntoParams, ToSchema};
 
+use super::{
+    groups::GroupProgress,
+    homes::{HomeParams, HomeScope},
+    pagination::PageParams,
+};
 use crate::{
-    error::AppResult,
-    models::{compute_is_due, compute_next_due, AppState, Zone, ZoneView},
+    error::{AppError, AppResult},
+    models::{compute_next_due_local, AppState, Frequency, Page, Zone, ZoneView, ZONE_COLUMNS},
 };
 
-#[derive(Serialize, ToSchema)]
+/// Zone filter on the home bound to `?1`; a `NULL` home matches every zone.
+const IN_HOME: &str = "(?1 IS NULL OR room_id IN (SELECT id FROM rooms WHERE home_id = ?1))";
+
+/// Due at `?2` by the stored `next_due_at`, which already has the pause and the
+/// timezone applied; never-cleaned zones are always due.
+const IS_DUE: &str = "(next_due_at IS NULL OR next_due_at <= ?2)";
+
+/// Zones due now, never-cleaned and longest overdue first; auto zones are left
+/// out. Shared by GraphQL and gRPC.
+pub(super) async fn due_zones(state: &AppState, home_id: Option<String>) -> AppResult<Vec<ZoneView>> {
+    let zones: Vec<Zone> = sqlx::query_as(&format!(
+        "SELECT {ZONE_COLUMNS} FROM zones WHERE deleted_at IS NULL AND auto = 0 AND {IN_HOME} AND {IS_DUE}
+         ORDER BY COALESCE(next_due_at, ''), sort_order, id"
+    ))
+    .bind(home_id)
+    .bind(Utc::now())
+    .fetch_all(&state.pool)
+    .await?;
+    let tz = super::settings::timezone(&state.pool).await?;
+    Ok(zones.into_iter().map(|z| ZoneView::localized(z, tz)).collect())
+}
+
+/// Moves on every change to zones, rooms, zone tags or settings (see the
+/// `stats_version` triggers); cache keys carry it, so a cached answer is never
+/// served after the data behind it changed.
+async fn stats_version(pool: &crate::models::Db) -> AppResult<i64> {
+    let (version,): (i64,) = sqlx::query_as("SELECT version FROM stats_version WHERE id = 1")
+        .fetch_one(pool)
+        .await?;
+    Ok(version)
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct StatsOverview {
     pub rooms_total: i64,
     pub zones_total: i64,
     pub due_zones: i64,
+    /// Zones due within `due_within` from now, overdue ones included.
+    pub due_soon: Option<i64>,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct OverviewParams {
+    /// Horizon for `due_soon`, e.g. `3d`, `12h` or `1w` (same grammar as `/zones/due`).
+    pub due_within: Option<String>,
 }
 
 #[utoipa::path(
     get,
     path = "/stats/overview",
+    params(OverviewParams, HomeParams),
     responses((status = 200, description = "Overview stats", body = StatsOverview))
 )]
 pub async fn overview(
     state: axum::extract::State<std::sync::Arc<AppState>>,
+    HomeScope(home_id): HomeScope,
+    Query(p): Query<OverviewParams>,
 ) -> AppResult<Json<StatsOverview>> {
+    let within = match p.due_within.as_deref() {
+        Some(w) => Some(
+            parse_within(Some(w)).ok_or_else(|| AppError::Validation(format!("invalid due_within '{w}'")))?,
+        ),
+        None => None,
+    };
+    let cache_key = format!(
+        "stats:{}:overview:{}:{}",
+        stats_version(&state.pool).await?,
+        home_id.as_deref().unwrap_or("all"),
+        p.due_within.as_deref().unwrap_or("-")
+    );
+    if let Some(cached) = state.cache.get_json::<StatsOverview>(&cache_key).await? {
+        return Ok(Json(cached));
+    }
+
     let (rooms_total,): (i64,) =
-        sqlx::query_as("SELECT COUNT(1) FROM rooms WHERE deleted_at IS NULL")
-            .fetch_one(&state.pool)
-            .await?;
-    let (zones_total,): (i64,) =
-        sqlx::query_as("SELECT COUNT(1) FROM zones WHERE deleted_at IS NULL")
+        sqlx::query_as("SELECT COUNT(1) FROM rooms WHERE deleted_at IS NULL AND (?1 IS NULL OR home_id = ?1)")
+            .bind(&home_id)
             .fetch_one(&state.pool)
             .await?;
 
-    let zones: Vec<Zone> = sqlx::query_as(
-        r#"SELECT id, room_id, name, icon, frequency, custom_interval_days, last_cleaned_at, created_at, updated_at, deleted_at
-           FROM zones WHERE deleted_at IS NULL"#
-    ).fetch_all(&state.pool).await?;
-
-    let mut due_zones = 0i64;
-    for z in &zones {
-        let next_due = compute_next_due(z.last_cleaned_at, &z.frequency, z.custom_interval_days);
-        let is_due = compute_is_due(next_due);
-        if is_due {
-            due_zones += 1;
-        }
-    }
+    let now = Utc::now();
+    let (zones_total, due_zones, due_soon): (i64, i64, i64) = sqlx::query_as(&format!(
+        "SELECT COUNT(1), COALESCE(SUM({IS_DUE}), 0),
+                COALESCE(SUM(next_due_at IS NULL OR next_due_at <= ?3), 0)
+         FROM zones WHERE deleted_at IS NULL AND {IN_HOME}"
+    ))
+    .bind(&home_id)
+    .bind(now)
+    .bind(horizon(now, within.unwrap_or_default(), "due_within")?)
+    .fetch_one(&state.pool)
+    .await?;
+    let due_soon = within.map(|_| due_soon);
 
-    Ok(Json(StatsOverview {
+    let out = StatsOverview {
         rooms_total,
         zones_total,
         due_zones,
-    }))
+        due_soon,
+    };
+    state.cache.set_json(&cache_key, &out, state.stats_cache_ttl).await?;
+    Ok(Json(out))
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct BreakdownParams {
+    /// Grouping: `room` (default), `frequency` or `tag`.
+    pub by: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, FromRow)]
+pub struct BreakdownGroup {
+    /// Room id, frequency or tag id; `None` groups untagged zones.
+    pub key: Option<String>,
+    pub label: Option<String>,
+    pub zones: i64,
+    pub due: i64,
+}
+
+#[utoipa::path(
+    get,
+    path = "/stats/breakdown",
+    params(BreakdownParams, HomeParams),
+    responses((status = 200, description = "Zone and due counts per group, largest first; a zone with several tags counts once per tag", body = [BreakdownGroup]))
+)]
+pub async fn breakdown(
+    state: axum::extract::State<std::sync::Arc<AppState>>,
+    HomeScope(home_id): HomeScope,
+    Query(p): Query<BreakdownParams>,
+) -> AppResult<Json<Vec<BreakdownGroup>>> {
+    // `next_due_at` хранится уже с учётом паузы и часового пояса
+    let zones = format!(
+        "SELECT id, room_id, frequency, {IS_DUE} AS due
+         FROM zones WHERE deleted_at IS NULL AND {IN_HOME}"
+    );
+    let sql = match p.by.as_deref().unwrap_or("room") {
+        "room" => format!(
+            "SELECT z.room_id AS key, r.name AS label, COUNT(1) AS zones, SUM(z.due) AS due
+             FROM ({zones}) z JOIN rooms r ON r.id = z.room_id
+             GROUP BY z.room_id, r.name"
+        ),
+        "frequency" => format!(
+            "SELECT frequency AS key, frequency AS label, COUNT(1) AS zones, SUM(due) AS due
+             FROM ({zones}) GROUP BY frequency"
+        ),
+        "tag" => format!(
+            "SELECT t.id AS key, t.name AS label, COUNT(1) AS zones, SUM(z.due) AS due
+             FROM ({zones}) z
+             LEFT JOIN zone_tags zt ON zt.zone_id = z.id
+             LEFT JOIN tags t ON t.id = zt.tag_id AND t.deleted_at IS NULL
+             GROUP BY t.id, t.name"
+        ),
+        other => return Err(AppError::Validation(format!("unknown breakdown '{other}'"))),
+    };
+    let groups = sqlx::query_as::<_, BreakdownGroup>(&format!("{sql} ORDER BY zones DESC, label"))
+        .bind(&home_id)
+        .bind(Utc::now())
+        .fetch_all(&state.pool)
+        .await?;
+    Ok(Json(groups))
 }
 
 #[derive(Deserialize, IntoParams)]
 pub struct DueParams {
+    /// Horizon, e.g. `3d`, `12h` or `1w`; `7d` when unset.
     pub within: Option<String>,
+    /// Only zones carrying this tag id.
+    pub tag: Option<String>,
+    /// Return only `{"count": n}`, e.g. for a badge.
+    pub count_only: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct DueCount {
+    pub count: i64,
+}
+
+/// Due filter for `/zones/due`: home `?1`, tag `?2`, now `?3`, horizon `?4`.
+/// Never-cleaned zones have no `next_due_at` and are due right away.
+const DUE_WITHIN: &str = r#"deleted_at IS NULL AND auto = 0
+    AND (?1 IS NULL OR room_id IN (SELECT id FROM rooms WHERE home_id = ?1))
+    AND (?2 IS NULL OR id IN (SELECT zone_id FROM zone_tags WHERE tag_id = ?2))
+    AND (paused_until IS NULL OR paused_until <= ?3)
+    AND (next_due_at IS NULL OR next_due_at <= ?4)"#;
+
+/// Cursors are the `(next_due_at, id)` sort key of the last zone on a page.
+fn encode_cursor(key: &str, id: &str) -> String {
+    hex::encode(format!("{key}\n{id}"))
+}
+
+fn decode_cursor(cursor: &str) -> AppResult<(String, String)> {
+    hex::decode(cursor)
+        .ok()
+        .and_then(|b| String::from_utf8(b).ok())
+        .and_then(|s| s.split_once('\n').map(|(k, id)| (k.to_string(), id.to_string())))
+        .ok_or_else(|| AppError::Validation("invalid cursor".into()))
 }
 
 #[utoipa::path(
     get,
     path = "/zones/due",
-    params(DueParams),
-    responses((status = 200, description = "Zones due", body = [ZoneView]))
+    params(DueParams, PageParams, HomeParams),
+    responses(
+        (status = 200, description = "Zones due, most overdue first", body = ZonePage),
+        (status = 200, description = "With `count_only=true`", body = DueCount),
+        (status = 400, description = "`within` or `cursor` cannot be parsed"),
+    )
 )]
 pub async fn zones_due(
     state: axum::extract::State<std::sync::Arc<AppState>>,
+    HomeScope(home_id): HomeScope,
     Query(p): Query<DueParams>,
-) -> AppResult<Json<Vec<ZoneView>>> {
-    let within = parse_within(p.within.as_deref()).unwrap_or(Duration::days(7));
-    let horizon = Utc::now() + within;
+    Query(page): Query<PageParams>,
+) -> AppResult<Response> {
+    let within = match p.within.as_deref() {
+        Some(w) => parse_within(Some(w)).ok_or_else(|| AppError::Validation(format!("invalid within '{w}'")))?,
+        None => Duration::days(7),
+    };
+    let cache_key = format!(
+        "stats:{}:due:{}:{}:{}:{}:{}:{}",
+        stats_version(&state.pool).await?,
+        home_id.as_deref().unwrap_or("all"),
+        p.within.as_deref().unwrap_or("-"),
+        p.tag.as_deref().unwrap_or("-"),
+        p.count_only.unwrap_or(false),
+        page.limit.map_or("-".to_string(), |l| l.to_string()),
+        page.cursor.as_deref().unwrap_or("-"),
+    );
+    if let Some(cached) = state.cache.get_json::<serde_json::Value>(&cache_key).await? {
+        return Ok(Json(cached).into_response());
+    }
 
-    let zones: Vec<Zone> = sqlx::query_as(
-        r#"SELECT id, room_id, name, icon, frequency, custom_interval_days, last_cleaned_at, created_at, updated_at, deleted_at
-           FROM zones WHERE deleted_at IS NULL"#
-    ).fetch_all(&state.pool).await?;
+    let now = Utc::now();
+    let horizon = horizon(now, within, "within")?;
 
-    let mut out = Vec::new();
+    let (total,): (i64,) = sqlx::query_as(&format!("SELECT COUNT(1) FROM zones WHERE {DUE_WITHIN}"))
+        .bind(&home_id)
+        .bind(&p.tag)
+        .bind(now)
+        .bind(horizon)
+        .fetch_one(&state.pool)
+        .await?;
+    if p.count_only.unwrap_or(false) {
+        let out = DueCount { count: total };
+        state.cache.set_json(&cache_key, &out, state.stats_cache_ttl).await?;
+        return Ok(Json(out).into_response());
+    }
+
+    let (after_key, after_id) = match page.cursor.as_deref() {
+        Some(c) => {
+            let (k, id) = decode_cursor(c)?;
+            (Some(k), Some(id))
+        }
+        None => (None, None),
+    };
+    let mut zones: Vec<Zone> = sqlx::query_as(&format!(
+        r#"SELECT {ZONE_COLUMNS} FROM zones
+           WHERE {DUE_WITHIN}
+             AND (?5 IS NULL OR (COALESCE(next_due_at, ''), id) > (?5, ?6))
+           ORDER BY COALESCE(next_due_at, ''), id
+           LIMIT ?7"#
+    ))
+    .bind(&home_id)
+    .bind(&p.tag)
+    .bind(now)
+    .bind(horizon)
+    .bind(&after_key)
+    .bind(&after_id)
+    .bind(page.fetch_limit())
+    .fetch_all(&state.pool)
+    .await?;
+
+    let mut next_cursor = None;
+    if page.trim(&mut zones) {
+        if let Some(last) = zones.last() {
+            let (key,): (String,) = sqlx::query_as("SELECT COALESCE(next_due_at, '') FROM zones WHERE id = ?1")
+                .bind(&last.id)
+                .fetch_one(&state.pool)
+                .await?;
+            next_cursor = Some(encode_cursor(&key, &last.id));
+        }
+    }
+
+    let tz = super::settings::timezone(&state.pool).await?;
+    let items = zones
+        .into_iter()
+        .map(|z| ZoneView {
+            is_due: true,
+            ..ZoneView::localized(z, tz)
+        })
+        .collect();
+    let out = Page { items, total, next_cursor };
+    state.cache.set_json(&cache_key, &out, state.stats_cache_ttl).await?;
+    Ok(Json(out).into_response())
+}
+
+#[derive(Serialize, Deserialize, ToSchema, FromRow)]
+pub struct OverdueZone {
+    pub id: String,
+    pub name: String,
+    pub room_id: String,
+    /// Whole days past due; never-cleaned zones count from their creation.
+    pub days_overdue: i64,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct OverdueBucket {
+    /// `1-3`, `4-7`, `8-30` or `30+` days.
+    pub label: String,
+    pub count: i64,
+    /// Most overdue first.
+    pub zones: Vec<OverdueZone>,
+}
+
+/// Lower bounds in days of the `/stats/overdue` buckets, worst first.
+const OVERDUE_BUCKETS: [(i64, &str); 4] = [(31, "30+"), (8, "8-30"), (4, "4-7"), (1, "1-3")];
+
+#[utoipa::path(
+    get,
+    path = "/stats/overdue",
+    params(HomeParams),
+    responses((status = 200, description = "Zones overdue by a day or more, bucketed by how late they are, worst first; empty buckets included", body = [OverdueBucket]))
+)]
+pub async fn overdue(
+    state: axum::extract::State<std::sync::Arc<AppState>>,
+    HomeScope(home_id): HomeScope,
+) -> AppResult<Json<Vec<OverdueBucket>>> {
+    let now = Utc::now();
+    let zones = sqlx::query_as::<_, OverdueZone>(&format!(
+        r#"SELECT id, name, room_id, days_overdue
+           FROM (
+             SELECT id, name, room_id,
+                    CAST(julianday(?3) - julianday(COALESCE(next_due_at, created_at)) AS INTEGER) AS days_overdue
+             FROM zones WHERE {DUE_WITHIN}
+           )
+           WHERE days_overdue >= 1
+           ORDER BY days_overdue DESC, id"#
+    ))
+    .bind(&home_id)
+    .bind(None::<String>)
+    .bind(now)
+    .bind(now)
+    .fetch_all(&state.pool)
+    .await?;
+
+    let mut buckets: Vec<OverdueBucket> = OVERDUE_BUCKETS
+        .iter()
+        .map(|(_, label)| OverdueBucket { label: label.to_string(), count: 0, zones: Vec::new() })
+        .collect();
     for z in zones {
-        let next_due = compute_next_due(z.last_cleaned_at, &z.frequency, z.custom_interval_days);
-        let is_due = match next_due {
-            Some(dt) => dt <= horizon,
-            None => true,
+        let i = OVERDUE_BUCKETS
+            .iter()
+            .position(|(from, _)| z.days_overdue >= *from)
+            .expect("zones overdue less than a day are filtered out");
+        let b = &mut buckets[i];
+        b.count += 1;
+        b.zones.push(z);
+    }
+    Ok(Json(buckets))
+}
+
+#[derive(Serialize, Deserialize, ToSchema, FromRow)]
+pub struct Badge {
+    /// Zones due now, the overdue ones included.
+    pub due: i64,
+    /// Zones due for more than a day, or never cleaned.
+    pub overdue: i64,
+}
+
+/// Widgets poll every few minutes; a minute-old count is good enough.
+const BADGE_CACHE_CONTROL: &str = "private, max-age=60";
+
+#[utoipa::path(
+    get,
+    path = "/badge",
+    params(HomeParams),
+    responses((status = 200, description = "Due and overdue counts for widgets", body = Badge))
+)]
+pub async fn badge(
+    state: axum::extract::State<std::sync::Arc<AppState>>,
+    HomeScope(home_id): HomeScope,
+) -> AppResult<Response> {
+    let cache_key = format!(
+        "stats:{}:badge:{}",
+        stats_version(&state.pool).await?,
+        home_id.as_deref().unwrap_or("all")
+    );
+    let badge = match state.cache.get_json::<Badge>(&cache_key).await? {
+        Some(b) => b,
+        None => {
+            let b = count_badge(&state, &home_id).await?;
+            state.cache.set_json(&cache_key, &b, state.stats_cache_ttl).await?;
+            b
+        }
+    };
+
+    let mut res = Json(badge).into_response();
+    res.headers_mut()
+        .insert(header::CACHE_CONTROL, HeaderValue::from_static(BADGE_CACHE_CONTROL));
+    Ok(res)
+}
+
+async fn count_badge(state: &AppState, home_id: &Option<String>) -> AppResult<Badge> {
+    let now = Utc::now();
+    let badge = sqlx::query_as::<_, Badge>(&format!(
+        r#"SELECT COUNT(1) AS due,
+                  COALESCE(SUM(next_due_at IS NULL OR next_due_at <= ?5), 0) AS overdue
+           FROM zones WHERE {DUE_WITHIN}"#
+    ))
+    .bind(home_id)
+    .bind(None::<String>)
+    .bind(now)
+    .bind(now)
+    .bind(now - Duration::days(1))
+    .fetch_one(&state.pool)
+    .await?;
+    Ok(badge)
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct Today {
+    /// Due zones, most overdue first, at most `max_zones_per_day` of them.
+    pub zones: Vec<ZoneView>,
+    /// Due zones left for the following days.
+    pub carried_over: i64,
+    pub max_zones_per_day: Option<i64>,
+    /// Every zone group and how much of it is done.
+    pub groups: Vec<GroupProgress>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/today",
+    params(HomeParams),
+    responses((status = 200, description = "Today's share of the due zones", body = Today))
+)]
+pub async fn today(
+    state: axum::extract::State<std::sync::Arc<AppState>>,
+    HomeScope(home_id): HomeScope,
+) -> AppResult<Json<Today>> {
+    let settings = super::settings::load(&state.pool).await?;
+    let zones: Vec<Zone> = sqlx::query_as(&format!(
+        "SELECT {ZONE_COLUMNS} FROM zones WHERE deleted_at IS NULL AND auto = 0 AND {IN_HOME} AND {IS_DUE}"
+    ))
+    .bind(&home_id)
+    .bind(Utc::now())
+    .fetch_all(&state.pool)
+    .await?;
+
+    let tz = super::settings::timezone(&state.pool).await?;
+    let mut due: Vec<ZoneView> = zones.into_iter().map(|z| ZoneView::localized(z, tz)).collect();
+    // никогда не убранные — первыми, дальше по давности просрочки
+    due.sort_by(|a, b| a.next_due_at.cmp(&b.next_due_at).then_with(|| a.sort_order.cmp(&b.sort_order)));
+
+    let cap = settings.max_zones_per_day.map(|m| m as usize).unwrap_or(due.len());
+    let carried_over = due.len().saturating_sub(cap) as i64;
+    due.truncate(cap);
+    Ok(Json(Today {
+        zones: due,
+        carried_over,
+        max_zones_per_day: settings.max_zones_per_day,
+        groups: super::groups::progress(&state).await?,
+    }))
+}
+
+/// Estimate assumed for zones without `estimated_minutes`.
+const DEFAULT_MINUTES: f64 = 30.0;
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct Suggestion {
+    /// Day the pick is for; it stays the same until the day is over.
+    pub day: NaiveDate,
+    /// `None` when nothing is due.
+    pub zone: Option<ZoneView>,
+}
+
+/// Long-overdue and quick zones are the likeliest picks.
+fn suggestion_weight(z: &ZoneView, now: DateTime<Utc>) -> f64 {
+    let due_since = z.next_due_at.unwrap_or(z.created_at);
+    let overdue_days = (now - due_since).num_hours().max(0) as f64 / 24.0;
+    let minutes = z.estimated_minutes.map_or(DEFAULT_MINUTES, |m| (m as f64).max(5.0));
+    (1.0 + overdue_days) * DEFAULT_MINUTES / minutes
+}
+
+#[utoipa::path(
+    get,
+    path = "/suggestion",
+    params(HomeParams),
+    responses((status = 200, description = "One due zone to clean today", body = Suggestion))
+)]
+pub async fn suggestion(
+    state: axum::extract::State<std::sync::Arc<AppState>>,
+    HomeScope(home_id): HomeScope,
+) -> AppResult<Json<Suggestion>> {
+    let now = Utc::now();
+    let zones: Vec<Zone> = sqlx::query_as(&format!(
+        "SELECT {ZONE_COLUMNS} FROM zones WHERE deleted_at IS NULL AND auto = 0 AND {IN_HOME} AND {IS_DUE} ORDER BY id"
+    ))
+    .bind(&home_id)
+    .bind(now)
+    .fetch_all(&state.pool)
+    .await?;
+
+    let tz = super::settings::timezone(&state.pool).await?;
+    let day = tz.map_or(now.date_naive(), |tz| now.with_timezone(&tz).date_naive());
+    let due: Vec<(ZoneView, f64)> = zones
+        .into_iter()
+        .map(|z| ZoneView::localized(z, tz))
+        .map(|z| {
+            let w = suggestion_weight(&z, now);
+            (z, w)
+        })
+        .collect();
+
+    // выбор «случайный», но один и тот же весь день: зерно — дата и дом
+    let digest = Sha256::digest(format!("{day}:{}", home_id.as_deref().unwrap_or("")));
+    let seed = u64::from_be_bytes(digest[..8].try_into().expect("sha256 is 32 bytes"));
+    let total: f64 = due.iter().map(|(_, w)| w).sum();
+    let mut point = seed as f64 / u64::MAX as f64 * total;
+    let mut zone = None;
+    for (z, w) in due {
+        zone = Some(z);
+        point -= w;
+        if point < 0.0 {
+            break;
+        }
+    }
+    Ok(Json(Suggestion { day, zone }))
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct FocusParams {
+    /// Time available, 1 to 1440 minutes (default 30).
+    pub minutes: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct FocusRoom {
+    pub room_id: String,
+    pub room_name: String,
+    pub minutes: u32,
+    pub zones: Vec<ZoneView>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct Focus {
+    pub minutes: u32,
+    /// Estimated time of the picked zones, at most `minutes`.
+    pub planned_minutes: u32,
+    /// Picked zones room by room, in room order.
+    pub rooms: Vec<FocusRoom>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/focus",
+    params(FocusParams, HomeParams),
+    responses((status = 200, description = "Due zones that fit into the given time, grouped by room", body = Focus))
+)]
+pub async fn focus(
+    state: axum::extract::State<std::sync::Arc<AppState>>,
+    HomeScope(home_id): HomeScope,
+    Query(p): Query<FocusParams>,
+) -> AppResult<Json<Focus>> {
+    let minutes = p.minutes.unwrap_or(30);
+    if !(1..=1440).contains(&minutes) {
+        return Err(AppError::Validation("minutes must be between 1 and 1440".into()));
+    }
+    let now = Utc::now();
+    let zones: Vec<Zone> = sqlx::query_as(&format!(
+        "SELECT {ZONE_COLUMNS} FROM zones WHERE deleted_at IS NULL AND auto = 0 AND {IN_HOME} AND {IS_DUE} ORDER BY id"
+    ))
+    .bind(&home_id)
+    .bind(now)
+    .fetch_all(&state.pool)
+    .await?;
+    let rooms: Vec<(String, String)> = sqlx::query_as(
+        "SELECT id, name FROM rooms WHERE deleted_at IS NULL ORDER BY sort_order ASC, created_at DESC",
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    let tz = super::settings::timezone(&state.pool).await?;
+    let mut due: Vec<(ZoneView, f64)> = zones
+        .into_iter()
+        .map(|z| ZoneView::localized(z, tz))
+        .map(|z| {
+            let w = suggestion_weight(&z, now);
+            (z, w)
+        })
+        .collect();
+    // жадно: самые «выгодные» (просрочка на минуту работы) — первыми, пока влезают
+    due.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let mut left = minutes;
+    let mut picked = Vec::new();
+    for (z, _) in due {
+        let cost = z.estimated_minutes.map_or(DEFAULT_MINUTES as u32, |m| m.max(1) as u32);
+        if cost <= left {
+            left -= cost;
+            picked.push((z, cost));
+        }
+    }
+
+    let mut out = Vec::new();
+    for (room_id, room_name) in rooms {
+        let (zones, costs): (Vec<ZoneView>, Vec<u32>) = picked
+            .iter()
+            .filter(|(z, _)| z.room_id == room_id)
+            .map(|(z, c)| (z.clone(), *c))
+            .unzip();
+        if !zones.is_empty() {
+            out.push(FocusRoom { room_id, room_name, minutes: costs.iter().sum(), zones });
+        }
+    }
+    Ok(Json(Focus { minutes, planned_minutes: minutes - left, rooms: out }))
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct CostParams {
+    /// Bucket size: `day`, `week`, `month` (default) or `year`. Weeks start on the
+    /// configured `week_starts_on` day.
+    pub period: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, FromRow)]
+pub struct CostBucket {
+    /// Bucket label, e.g. `2025-09` for months or `2025-W36` for weeks.
+    pub period: String,
+    pub supplies_cents: i64,
+    pub services_cents: i64,
+    pub total_cents: i64,
+}
+
+#[utoipa::path(
+    get,
+    path = "/stats/costs",
+    params(CostParams, HomeParams),
+    responses((status = 200, description = "Cleaning spending per period, newest first; supplies count in the homes whose zones use them, unlinked ones in every home", body = [CostBucket]))
+)]
+pub async fn costs(
+    state: axum::extract::State<std::sync::Arc<AppState>>,
+    HomeScope(home_id): HomeScope,
+    Query(p): Query<CostParams>,
+) -> AppResult<Json<Vec<CostBucket>>> {
+    let format = match p.period.as_deref().unwrap_or("month") {
+        "day" => "%Y-%m-%d",
+        "week" => "%Y-W%W",
+        "month" => "%Y-%m",
+        "year" => "%Y",
+        other => return Err(AppError::Validation(format!("unknown period '{other}'"))),
+    };
+    let shift = if format.contains("%W") {
+        super::settings::week_shift(&super::settings::load(&state.pool).await?)
+    } else {
+        "+0 days".to_string()
+    };
+    let buckets = sqlx::query_as::<_, CostBucket>(
+        r#"SELECT period,
+                  SUM(supplies) AS supplies_cents,
+                  SUM(services) AS services_cents,
+                  SUM(supplies + services) AS total_cents
+           FROM (
+             SELECT strftime(?1, purchased_at, ?2) AS period, price_cents AS supplies, 0 AS services
+             FROM supply_purchases
+             WHERE ?3 IS NULL
+                OR supply_id NOT IN (SELECT supply_id FROM zone_supplies)
+                OR supply_id IN (
+                  SELECT zs.supply_id FROM zone_supplies zs
+                  JOIN zones z ON z.id = zs.zone_id JOIN rooms r ON r.id = z.room_id
+                  WHERE r.home_id = ?3)
+             UNION ALL
+             SELECT strftime(?1, occurred_at, ?2), 0, json_extract(payload, '$.cost_cents')
+             FROM zone_events
+             WHERE kind = 'cleaned' AND json_extract(payload, '$.cost_cents') IS NOT NULL
+               AND (?3 IS NULL OR zone_id IN (
+                 SELECT z.id FROM zones z JOIN rooms r ON r.id = z.room_id WHERE r.home_id = ?3))
+           )
+           GROUP BY period
+           ORDER BY period DESC"#,
+    )
+    .bind(format)
+    .bind(shift)
+    .bind(&home_id)
+    .fetch_all(&state.pool)
+    .await?;
+    Ok(Json(buckets))
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct TimeParams {
+    /// Grouping: `zone` (default) or `room`.
+    pub by: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, FromRow)]
+pub struct TimeSpent {
+    /// Zone or room id.
+    pub key: String,
+    pub label: String,
+    /// Cleanings that recorded `duration_minutes`; the others are left out.
+    pub timed_cleanings: i64,
+    pub total_minutes: i64,
+    pub average_minutes: f64,
+}
+
+#[utoipa::path(
+    get,
+    path = "/stats/time",
+    params(TimeParams, HomeParams),
+    responses((status = 200, description = "Time spent cleaning per zone or room, most first", body = [TimeSpent]))
+)]
+pub async fn time_spent(
+    state: axum::extract::State<std::sync::Arc<AppState>>,
+    HomeScope(home_id): HomeScope,
+    Query(p): Query<TimeParams>,
+) -> AppResult<Json<Vec<TimeSpent>>> {
+    let (key, label) = match p.by.as_deref().unwrap_or("zone") {
+        "zone" => ("z.id", "z.name"),
+        "room" => ("r.id", "r.name"),
+        other => return Err(AppError::Validation(format!("unknown grouping '{other}'"))),
+    };
+    let groups = sqlx::query_as::<_, TimeSpent>(&format!(
+        r#"SELECT {key} AS key, {label} AS label, COUNT(1) AS timed_cleanings,
+                  SUM(t.minutes) AS total_minutes, AVG(t.minutes) AS average_minutes
+           FROM (
+             SELECT zone_id, json_extract(payload, '$.duration_minutes') AS minutes
+             FROM zone_events
+             WHERE kind = 'cleaned' AND json_extract(payload, '$.duration_minutes') IS NOT NULL
+           ) t
+           JOIN zones z ON z.id = t.zone_id
+           JOIN rooms r ON r.id = z.room_id
+           WHERE z.deleted_at IS NULL AND (?1 IS NULL OR r.home_id = ?1)
+           GROUP BY {key}, {label}
+           ORDER BY total_minutes DESC, label"#
+    ))
+    .bind(&home_id)
+    .fetch_all(&state.pool)
+    .await?;
+    Ok(Json(groups))
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct Streaks {
+    /// Consecutive days with a cleaning, up to today; yesterday counts until today ends.
+    pub current: i64,
+    pub longest: i64,
+    pub total_cleanings: i64,
+    /// Local day of the latest cleaning.
+    pub last_cleaned_on: Option<NaiveDate>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/stats/streaks",
+    params(HomeParams),
+    responses((status = 200, description = "Cleaning streaks in local days", body = Streaks))
+)]
+pub async fn streaks(
+    state: axum::extract::State<std::sync::Arc<AppState>>,
+    HomeScope(home_id): HomeScope,
+) -> AppResult<Json<Streaks>> {
+    let cleaned: Vec<(DateTime<Utc>,)> = sqlx::query_as(&format!(
+        r#"SELECT occurred_at FROM zone_events
+           WHERE kind = 'cleaned' AND zone_id IN (SELECT id FROM zones WHERE {IN_HOME})
+           ORDER BY occurred_at ASC"#
+    ))
+    .bind(&home_id)
+    .fetch_all(&state.pool)
+    .await?;
+    let tz = super::settings::timezone(&state.pool).await?;
+    let local_day = |t: DateTime<Utc>| tz.map_or(t.date_naive(), |tz| t.with_timezone(&tz).date_naive());
+    let mut days: Vec<NaiveDate> = cleaned.iter().map(|&(t,)| local_day(t)).collect();
+    days.dedup();
+    let (current, longest) = streak_lengths(&days, local_day(Utc::now()));
+    Ok(Json(Streaks {
+        current,
+        longest,
+        total_cleanings: cleaned.len() as i64,
+        last_cleaned_on: days.last().copied(),
+    }))
+}
+
+/// Longest range `/stats/heatmap` answers for.
+const MAX_HEATMAP_DAYS: i64 = 731;
+
+#[derive(Deserialize, IntoParams)]
+pub struct HeatmapParams {
+    /// First local day, inclusive; a year before `to` when unset.
+    pub from: Option<NaiveDate>,
+    /// Last local day, inclusive; today when unset.
+    pub to: Option<NaiveDate>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, FromRow)]
+pub struct HeatmapDay {
+    pub date: NaiveDate,
+    pub count: i64,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct Heatmap {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// Highest daily count in the range, for scaling colours.
+    pub max: i64,
+    /// Every day of the range in order, days without cleanings included.
+    pub days: Vec<HeatmapDay>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/stats/heatmap",
+    params(HeatmapParams, HomeParams),
+    responses(
+        (status = 200, description = "Cleanings per local day", body = Heatmap),
+        (status = 400, description = "from is after to, the range is too long, or a date is out of range"),
+    )
+)]
+pub async fn heatmap(
+    state: axum::extract::State<std::sync::Arc<AppState>>,
+    HomeScope(home_id): HomeScope,
+    Query(p): Query<HeatmapParams>,
+) -> AppResult<Json<Heatmap>> {
+    let tz = super::settings::timezone(&state.pool).await?;
+    let now = Utc::now();
+    let offset = utc_offset(tz, now);
+    let today = (now + Duration::seconds(offset.into())).date_naive();
+    // крайние даты chrono: арифметика с ними переполняется
+    let out_of_range = || AppError::Validation("from and to must be dates within the supported range".into());
+    let to = p.to.unwrap_or(today);
+    let from = match p.from {
+        Some(from) => from,
+        None => to.checked_sub_signed(Duration::days(364)).ok_or_else(out_of_range)?,
+    };
+    if from > to || (to - from).num_days() >= MAX_HEATMAP_DAYS {
+        return Err(AppError::Validation(format!(
+            "from must not be after to, and the range at most {MAX_HEATMAP_DAYS} days"
+        )));
+    }
+    let start = from
+        .and_time(NaiveTime::MIN)
+        .and_utc()
+        .checked_sub_signed(Duration::seconds(offset.into()))
+        .ok_or_else(out_of_range)?;
+    let end = start
+        .checked_add_signed(Duration::days((to - from).num_days() + 1))
+        .ok_or_else(out_of_range)?;
+    let counts = sqlx::query_as::<_, HeatmapDay>(&format!(
+        r#"SELECT date(occurred_at, ?2) AS date, COUNT(1) AS count
+           FROM zone_events
+           WHERE kind = 'cleaned' AND occurred_at >= ?3 AND occurred_at < ?4
+             AND zone_id IN (SELECT id FROM zones WHERE {IN_HOME})
+           GROUP BY 1"#
+    ))
+    .bind(&home_id)
+    .bind(format!("{offset:+} seconds"))
+    .bind(start)
+    .bind(end)
+    .fetch_all(&state.pool)
+    .await?;
+    let counts: std::collections::HashMap<NaiveDate, i64> = counts.into_iter().map(|d| (d.date, d.count)).collect();
+    let days: Vec<HeatmapDay> = from
+        .iter_days()
+        .take_while(|d| *d <= to)
+        .map(|date| HeatmapDay { date, count: counts.get(&date).copied().unwrap_or(0) })
+        .collect();
+    let max = days.iter().map(|d| d.count).max().unwrap_or(0);
+    Ok(Json(Heatmap { from, to, max, days }))
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct TrendParams {
+    /// Bucket size: `week` (default) or `month`. Weeks start on the configured
+    /// `week_starts_on` day.
+    pub granularity: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct TrendBucket {
+    /// Bucket label, e.g. `2025-W36` or `2025-09`.
+    pub period: String,
+    /// Cleanings done on or before the local day the zone fell due.
+    pub on_time: i64,
+    pub late: i64,
+    /// `on_time / (on_time + late)`.
+    pub on_time_rate: f64,
+}
+
+#[derive(FromRow)]
+struct TrendClean {
+    period: String,
+    occurred_at: DateTime<Utc>,
+    previous_at: Option<DateTime<Utc>>,
+    frequency: Frequency,
+    custom_interval_days: Option<i64>,
+    weekday_mask: Option<i64>,
+    auto: bool,
+}
+
+#[utoipa::path(
+    get,
+    path = "/stats/trends",
+    params(TrendParams, HomeParams),
+    responses((status = 200, description = "On-time and late cleanings per period, newest first; a zone's first cleaning and auto-cleanings are not counted", body = [TrendBucket]))
+)]
+pub async fn trends(
+    state: axum::extract::State<std::sync::Arc<AppState>>,
+    HomeScope(home_id): HomeScope,
+    Query(p): Query<TrendParams>,
+) -> AppResult<Json<Vec<TrendBucket>>> {
+    let format = match p.granularity.as_deref().unwrap_or("week") {
+        "week" => "%Y-W%W",
+        "month" => "%Y-%m",
+        other => return Err(AppError::Validation(format!("unknown granularity '{other}'"))),
+    };
+    let shift = if format.contains("%W") {
+        super::settings::week_shift(&super::settings::load(&state.pool).await?)
+    } else {
+        "+0 days".to_string()
+    };
+    let tz = super::settings::timezone(&state.pool).await?;
+    let offset = utc_offset(tz, Utc::now());
+    // срок считается по текущему расписанию зоны: смена частоты задним числом не пересчитывается
+    let cleans = sqlx::query_as::<_, TrendClean>(&format!(
+        r#"SELECT strftime(?2, e.occurred_at, ?3, ?4) AS period, e.occurred_at,
+                  LAG(e.occurred_at) OVER (PARTITION BY e.zone_id ORDER BY e.occurred_at, e.id) AS previous_at,
+                  z.frequency, z.custom_interval_days, z.weekday_mask,
+                  COALESCE(json_extract(e.payload, '$.auto'), 0) AS auto
+           FROM zone_events e JOIN zones z ON z.id = e.zone_id
+           WHERE e.kind = 'cleaned' AND e.zone_id IN (SELECT id FROM zones WHERE {IN_HOME})"#
+    ))
+    .bind(&home_id)
+    .bind(format)
+    .bind(format!("{offset:+} seconds"))
+    .bind(shift)
+    .fetch_all(&state.pool)
+    .await?;
+
+    let local_day = |t: DateTime<Utc>| tz.map_or(t.date_naive(), |tz| t.with_timezone(&tz).date_naive());
+    let mut buckets: std::collections::BTreeMap<String, (i64, i64)> = Default::default();
+    for c in cleans.into_iter().filter(|c| !c.auto) {
+        let Some(due) =
+            compute_next_due_local(c.previous_at, c.frequency, c.custom_interval_days, c.weekday_mask, tz)
+        else {
+            continue;
         };
-        if is_due {
-            out.push(ZoneView {
-                id: z.id,
-                room_id: z.room_id,
-                name: z.name,
-                icon: z.icon,
-                frequency: z.frequency,
-                custom_interval_days: z.custom_interval_days,
-                last_cleaned_at: z.last_cleaned_at,
-                next_due_at: next_due,
-                is_due: true,
-                created_at: z.created_at,
-                updated_at: z.updated_at,
-                deleted_at: z.deleted_at,
-            });
+        let bucket = buckets.entry(c.period).or_default();
+        if local_day(c.occurred_at) <= local_day(due) {
+            bucket.0 += 1;
+        } else {
+            bucket.1 += 1;
         }
     }
+    Ok(Json(
+        buckets
+            .into_iter()
+            .rev()
+            .map(|(period, (on_time, late))| TrendBucket {
+                period,
+                on_time,
+                late,
+                on_time_rate: on_time as f64 / (on_time + late) as f64,
+            })
+            .collect(),
+    ))
+}
 
-    Ok(Json(out))
+/// Seconds `tz` is ahead of UTC at `now`; zero without a timezone.
+fn utc_offset(tz: Option<chrono_tz::Tz>, now: DateTime<Utc>) -> i32 {
+    // сдвиг берётся на текущий момент; дни у перехода на летнее время могут съехать на час
+    tz.map_or(0, |tz| tz.offset_from_utc_datetime(&now.naive_utc()).fix().local_minus_utc())
+}
+
+/// Current and longest runs of consecutive days in sorted, distinct `days`.
+fn streak_lengths(days: &[NaiveDate], today: NaiveDate) -> (i64, i64) {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut prev: Option<NaiveDate> = None;
+    for &d in days {
+        run = match prev {
+            Some(p) if d - p == Duration::days(1) => run + 1,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        prev = Some(d);
+    }
+    // серия ещё жива, если последний день — сегодня или вчера
+    let current = match prev {
+        Some(p) if today - p <= Duration::days(1) => run,
+        _ => 0,
+    };
+    (current, longest)
 }
 
 fn parse_within(s: Option<&str>) -> Option<Duration> {
     let s = s?;
     let s = s.trim();
-    if s.ends_with("d") {
-        s[..s.len() - 1].parse::<i64>().ok().map(Duration::days)
-    } else if s.ends_with("h") {
-        s[..s.len() - 1].parse::<i64>().ok().map(Duration::hours)
-    } else if s.ends_with("w") {
-        s[..s.len() - 1]
-            .parse::<i64>()
-            .ok()
-            .map(|w| Duration::days(w * 7))
+    // огромные числа — не паника, а `None`
+    if let Some(n) = s.strip_suffix('d') {
+        Duration::try_days(n.parse().ok()?)
+    } else if let Some(n) = s.strip_suffix('h') {
+        Duration::try_hours(n.parse().ok()?)
+    } else if let Some(n) = s.strip_suffix('w') {
+        Duration::try_days(n.parse::<i64>().ok()?.checked_mul(7)?)
     } else {
         None
     }
 }
+
+/// `now + within`; a horizon past the last representable date is a bad `param`.
+fn horizon(now: DateTime<Utc>, within: Duration, param: &str) -> AppResult<DateTime<Utc>> {
+    now.checked_add_signed(within)
+        .ok_or_else(|| AppError::Validation(format!("{param} reaches too far")))
+}
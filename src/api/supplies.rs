@@ -0,0 +1,373 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use chrono::Utc;
+use sqlx::SqliteExecutor;
+use uuid::Uuid;
+
+use super::undo::{self, Deleted, Undoable};
+use crate::{
+    error::{AppError, AppResult},
+    models::{
+        AppState, LinkSupply, NewSupply, NewSupplyPurchase, Supply, SupplyPurchase, UpdateSupply, ZoneSupply,
+    },
+};
+
+const SUPPLY_COLUMNS: &str = "id, name, unit, quantity, low_threshold, created_at, updated_at, deleted_at";
+
+fn validate_amount(field: &str, value: f64) -> AppResult<()> {
+    if !value.is_finite() || value < 0.0 {
+        return Err(AppError::Validation(format!("{field} must be a non-negative number")));
+    }
+    Ok(())
+}
+
+async fn fetch_supply(state: &AppState, id: &str) -> AppResult<Supply> {
+    sqlx::query_as::<_, Supply>(&format!(
+        "SELECT {SUPPLY_COLUMNS} FROM supplies WHERE id = ?1 AND deleted_at IS NULL"
+    ))
+    .bind(id)
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or(AppError::NotFound)
+}
+
+/// Takes the linked supplies of a zone off the stock; stock never goes below zero.
+/// Run it on the transaction that marks the zone cleaned.
+pub async fn consume_for_clean<'e, E: SqliteExecutor<'e>>(exec: E, zone_id: &str) -> AppResult<()> {
+    sqlx::query(
+        r#"UPDATE supplies
+           SET quantity = MAX(0, quantity - (SELECT zs.usage FROM zone_supplies zs
+                                             WHERE zs.supply_id = supplies.id AND zs.zone_id = ?1)),
+               updated_at = ?2
+           WHERE deleted_at IS NULL
+             AND id IN (SELECT supply_id FROM zone_supplies WHERE zone_id = ?1)"#,
+    )
+    .bind(zone_id)
+    .bind(Utc::now())
+    .execute(exec)
+    .await?;
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/supplies",
+    responses((status = 200, description = "List supplies", body = [Supply]))
+)]
+pub async fn list_supplies(State(state): State<std::sync::Arc<AppState>>) -> AppResult<Json<Vec<Supply>>> {
+    let supplies = sqlx::query_as::<_, Supply>(&format!(
+        "SELECT {SUPPLY_COLUMNS} FROM supplies WHERE deleted_at IS NULL ORDER BY name ASC"
+    ))
+    .fetch_all(&state.pool)
+    .await?;
+    Ok(Json(supplies))
+}
+
+#[utoipa::path(
+    get,
+    path = "/supplies/low",
+    responses((status = 200, description = "Supplies at or below their low threshold", body = [Supply]))
+)]
+pub async fn low_supplies(State(state): State<std::sync::Arc<AppState>>) -> AppResult<Json<Vec<Supply>>> {
+    let supplies = sqlx::query_as::<_, Supply>(&format!(
+        r#"SELECT {SUPPLY_COLUMNS} FROM supplies
+           WHERE deleted_at IS NULL AND quantity <= low_threshold
+           ORDER BY quantity - low_threshold ASC, name ASC"#
+    ))
+    .fetch_all(&state.pool)
+    .await?;
+    Ok(Json(supplies))
+}
+
+#[utoipa::path(
+    post,
+    path = "/supplies",
+    request_body = NewSupply,
+    responses((status = 201, description = "Supply created", body = Supply))
+)]
+pub async fn create_supply(
+    State(state): State<std::sync::Arc<AppState>>,
+    Json(body): Json<NewSupply>,
+) -> AppResult<(axum::http::StatusCode, Json<Supply>)> {
+    let name = body.name.trim().to_string();
+    if name.is_empty() {
+        return Err(AppError::Validation("name is required".into()));
+    }
+    let low_threshold = body.low_threshold.unwrap_or(0.0);
+    validate_amount("quantity", body.quantity)?;
+    validate_amount("low_threshold", low_threshold)?;
+
+    let now = Utc::now();
+    let supply = Supply {
+        id: Uuid::new_v4().to_string(),
+        name,
+        unit: body.unit,
+        quantity: body.quantity,
+        low_threshold,
+        created_at: now,
+        updated_at: now,
+        deleted_at: None,
+    };
+    sqlx::query(
+        r#"INSERT INTO supplies(id, name, unit, quantity, low_threshold, created_at, updated_at, deleted_at)
+           VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6, NULL)"#,
+    )
+    .bind(&supply.id)
+    .bind(&supply.name)
+    .bind(&supply.unit)
+    .bind(supply.quantity)
+    .bind(supply.low_threshold)
+    .bind(now)
+    .execute(&state.writer)
+    .await?;
+    Ok((axum::http::StatusCode::CREATED, Json(supply)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/supplies/{id}",
+    params(("id" = String, Path, description = "Supply id")),
+    responses((status = 200, description = "Supply", body = Supply))
+)]
+pub async fn get_supply(
+    State(state): State<std::sync::Arc<AppState>>,
+    Path(id): Path<String>,
+) -> AppResult<Json<Supply>> {
+    Ok(Json(fetch_supply(&state, &id).await?))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/supplies/{id}",
+    params(("id" = String, Path, description = "Supply id")),
+    request_body = UpdateSupply,
+    responses((status = 200, description = "Supply updated", body = Supply))
+)]
+pub async fn update_supply(
+    State(state): State<std::sync::Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(body): Json<UpdateSupply>,
+) -> AppResult<Json<Supply>> {
+    let mut s = fetch_supply(&state, &id).await?;
+
+    if let Some(name) = body.name {
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            return Err(AppError::Validation("name is required".into()));
+        }
+        s.name = name;
+    }
+    if let Some(quantity) = body.quantity {
+        validate_amount("quantity", quantity)?;
+        s.quantity = quantity;
+    }
+    if let Some(low_threshold) = body.low_threshold {
+        validate_amount("low_threshold", low_threshold)?;
+        s.low_threshold = low_threshold;
+    }
+    s.unit = body.unit.apply(s.unit);
+    s.updated_at = Utc::now();
+
+    sqlx::query(
+        "UPDATE supplies SET name = ?1, unit = ?2, quantity = ?3, low_threshold = ?4, updated_at = ?5 WHERE id = ?6",
+    )
+    .bind(&s.name)
+    .bind(&s.unit)
+    .bind(s.quantity)
+    .bind(s.low_threshold)
+    .bind(s.updated_at)
+    .bind(&id)
+    .execute(&state.writer)
+    .await?;
+    Ok(Json(s))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/supplies/{id}",
+    params(("id" = String, Path, description = "Supply id")),
+    responses((status = 204, description = "Supply deleted", headers(("x-undo-token" = String, description = "For `POST /undo/{token}` within 30 seconds"))))
+)]
+pub async fn delete_supply(
+    State(state): State<std::sync::Arc<AppState>>,
+    Path(id): Path<String>,
+) -> AppResult<Deleted> {
+    let now = Utc::now();
+    let mut tx = state.writer.begin().await?;
+    let res = sqlx::query("UPDATE supplies SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL")
+        .bind(now)
+        .bind(&id)
+        .execute(&mut *tx)
+        .await?;
+    if res.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    let deleted = undo::issue(&mut tx, Undoable::Supply, &id, now).await?;
+    sqlx::query("DELETE FROM zone_supplies WHERE supply_id = ?1")
+        .bind(&id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(deleted)
+}
+
+#[utoipa::path(
+    get,
+    path = "/supplies/{id}/purchases",
+    params(("id" = String, Path, description = "Supply id")),
+    responses((status = 200, description = "Purchases of the supply, newest first", body = [SupplyPurchase]))
+)]
+pub async fn list_purchases(
+    State(state): State<std::sync::Arc<AppState>>,
+    Path(id): Path<String>,
+) -> AppResult<Json<Vec<SupplyPurchase>>> {
+    fetch_supply(&state, &id).await?;
+    let purchases = sqlx::query_as::<_, SupplyPurchase>(
+        r#"SELECT id, supply_id, quantity, price_cents, vendor, purchased_at, created_at
+           FROM supply_purchases WHERE supply_id = ?1
+           ORDER BY purchased_at DESC"#,
+    )
+    .bind(&id)
+    .fetch_all(&state.pool)
+    .await?;
+    Ok(Json(purchases))
+}
+
+#[utoipa::path(
+    post,
+    path = "/supplies/{id}/purchases",
+    params(("id" = String, Path, description = "Supply id")),
+    request_body = NewSupplyPurchase,
+    responses((status = 201, description = "Purchase recorded and added to stock", body = SupplyPurchase))
+)]
+pub async fn create_purchase(
+    State(state): State<std::sync::Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(body): Json<NewSupplyPurchase>,
+) -> AppResult<(axum::http::StatusCode, Json<SupplyPurchase>)> {
+    fetch_supply(&state, &id).await?;
+    validate_amount("quantity", body.quantity)?;
+    if body.price_cents < 0 {
+        return Err(AppError::Validation("price_cents must not be negative".into()));
+    }
+    let now = Utc::now();
+    let purchase = SupplyPurchase {
+        id: Uuid::new_v4().to_string(),
+        supply_id: id,
+        quantity: body.quantity,
+        price_cents: body.price_cents,
+        vendor: body.vendor,
+        purchased_at: body.purchased_at.unwrap_or(now),
+        created_at: now,
+    };
+    let mut tx = state.writer.begin().await?;
+    sqlx::query(
+        r#"INSERT INTO supply_purchases(id, supply_id, quantity, price_cents, vendor, purchased_at, created_at)
+           VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)"#,
+    )
+    .bind(&purchase.id)
+    .bind(&purchase.supply_id)
+    .bind(purchase.quantity)
+    .bind(purchase.price_cents)
+    .bind(&purchase.vendor)
+    .bind(purchase.purchased_at)
+    .bind(now)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("UPDATE supplies SET quantity = quantity + ?1, updated_at = ?2 WHERE id = ?3")
+        .bind(purchase.quantity)
+        .bind(now)
+        .bind(&purchase.supply_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok((axum::http::StatusCode::CREATED, Json(purchase)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/zones/{id}/supplies",
+    params(("id" = String, Path, description = "Zone id")),
+    responses((status = 200, description = "Supplies used by the zone", body = [ZoneSupply]))
+)]
+pub async fn list_zone_supplies(
+    State(state): State<std::sync::Arc<AppState>>,
+    Path(zone_id): Path<String>,
+) -> AppResult<Json<Vec<ZoneSupply>>> {
+    let links = sqlx::query_as::<_, ZoneSupply>(
+        r#"SELECT zs.zone_id, zs.supply_id, zs.usage
+           FROM zone_supplies zs JOIN supplies s ON s.id = zs.supply_id
+           WHERE zs.zone_id = ?1 AND s.deleted_at IS NULL
+           ORDER BY s.name ASC"#,
+    )
+    .bind(&zone_id)
+    .fetch_all(&state.pool)
+    .await?;
+    Ok(Json(links))
+}
+
+#[utoipa::path(
+    put,
+    path = "/zones/{id}/supplies/{supply_id}",
+    params(
+        ("id" = String, Path, description = "Zone id"),
+        ("supply_id" = String, Path, description = "Supply id"),
+    ),
+    request_body = LinkSupply,
+    responses((status = 200, description = "Supply linked", body = ZoneSupply))
+)]
+pub async fn link_supply(
+    State(state): State<std::sync::Arc<AppState>>,
+    Path((zone_id, supply_id)): Path<(String, String)>,
+    Json(body): Json<LinkSupply>,
+) -> AppResult<Json<ZoneSupply>> {
+    let usage = body.usage.unwrap_or(1.0);
+    validate_amount("usage", usage)?;
+    let (found,): (i64,) = sqlx::query_as(
+        r#"SELECT (SELECT COUNT(1) FROM zones WHERE id = ?1 AND deleted_at IS NULL)
+                + (SELECT COUNT(1) FROM supplies WHERE id = ?2 AND deleted_at IS NULL)"#,
+    )
+    .bind(&zone_id)
+    .bind(&supply_id)
+    .fetch_one(&state.pool)
+    .await?;
+    if found < 2 {
+        return Err(AppError::NotFound);
+    }
+    sqlx::query(
+        r#"INSERT INTO zone_supplies(zone_id, supply_id, usage) VALUES (?1, ?2, ?3)
+           ON CONFLICT(zone_id, supply_id) DO UPDATE SET usage = excluded.usage"#,
+    )
+    .bind(&zone_id)
+    .bind(&supply_id)
+    .bind(usage)
+    .execute(&state.writer)
+    .await?;
+    Ok(Json(ZoneSupply { zone_id, supply_id, usage }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/zones/{id}/supplies/{supply_id}",
+    params(
+        ("id" = String, Path, description = "Zone id"),
+        ("supply_id" = String, Path, description = "Supply id"),
+    ),
+    responses((status = 204, description = "Supply unlinked"))
+)]
+pub async fn unlink_supply(
+    State(state): State<std::sync::Arc<AppState>>,
+    Path((zone_id, supply_id)): Path<(String, String)>,
+) -> AppResult<axum::http::StatusCode> {
+    let res = sqlx::query("DELETE FROM zone_supplies WHERE zone_id = ?1 AND supply_id = ?2")
+        .bind(&zone_id)
+        .bind(&supply_id)
+        .execute(&state.writer)
+        .await?;
+    if res.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(axum::http::StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,227 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use chrono::Utc;
+use uuid::Uuid;
+
+use super::undo::{self, Deleted, Undoable};
+use crate::{
+    error::{AppError, AppResult},
+    models::{AppState, NewTag, Tag, UpdateTag},
+};
+
+async fn ensure_unique_name(state: &AppState, name: &str, except_id: Option<&str>) -> AppResult<()> {
+    let (taken,): (i64,) = sqlx::query_as(
+        "SELECT COUNT(1) FROM tags WHERE name = ?1 AND deleted_at IS NULL AND (?2 IS NULL OR id != ?2)",
+    )
+    .bind(name)
+    .bind(except_id)
+    .fetch_one(&state.pool)
+    .await?;
+    if taken > 0 {
+        return Err(AppError::Validation(format!("tag '{name}' already exists")));
+    }
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/tags",
+    responses((status = 200, description = "List tags", body = [Tag]))
+)]
+pub async fn list_tags(State(state): State<std::sync::Arc<AppState>>) -> AppResult<Json<Vec<Tag>>> {
+    let tags = sqlx::query_as::<_, Tag>(
+        r#"SELECT id, name, color, created_at, updated_at, deleted_at
+           FROM tags WHERE deleted_at IS NULL ORDER BY name ASC"#,
+    )
+    .fetch_all(&state.pool)
+    .await?;
+    Ok(Json(tags))
+}
+
+#[utoipa::path(
+    post,
+    path = "/tags",
+    request_body = NewTag,
+    responses((status = 201, description = "Tag created", body = Tag))
+)]
+pub async fn create_tag(
+    State(state): State<std::sync::Arc<AppState>>,
+    Json(body): Json<NewTag>,
+) -> AppResult<(axum::http::StatusCode, Json<Tag>)> {
+    let name = body.name.trim().to_string();
+    if name.is_empty() {
+        return Err(AppError::Validation("name is required".into()));
+    }
+    ensure_unique_name(&state, &name, None).await?;
+
+    let now = Utc::now();
+    let tag = Tag {
+        id: Uuid::new_v4().to_string(),
+        name,
+        color: body.color,
+        created_at: now,
+        updated_at: now,
+        deleted_at: None,
+    };
+    sqlx::query(
+        r#"INSERT INTO tags(id, name, color, created_at, updated_at, deleted_at)
+           VALUES (?1, ?2, ?3, ?4, ?4, NULL)"#,
+    )
+    .bind(&tag.id)
+    .bind(&tag.name)
+    .bind(&tag.color)
+    .bind(now)
+    .execute(&state.writer)
+    .await?;
+    Ok((axum::http::StatusCode::CREATED, Json(tag)))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/tags/{id}",
+    params(("id" = String, Path, description = "Tag id")),
+    request_body = UpdateTag,
+    responses((status = 200, description = "Tag updated", body = Tag))
+)]
+pub async fn update_tag(
+    State(state): State<std::sync::Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(body): Json<UpdateTag>,
+) -> AppResult<Json<Tag>> {
+    let t = sqlx::query_as::<_, Tag>(
+        "SELECT id, name, color, created_at, updated_at, deleted_at FROM tags WHERE id = ?1 AND deleted_at IS NULL",
+    )
+    .bind(&id)
+    .fetch_optional(&state.pool)
+    .await?;
+    let mut t = t.ok_or(AppError::NotFound)?;
+
+    if let Some(name) = body.name {
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            return Err(AppError::Validation("name is required".into()));
+        }
+        ensure_unique_name(&state, &name, Some(&id)).await?;
+        t.name = name;
+    }
+    t.color = body.color.apply(t.color);
+    t.updated_at = Utc::now();
+
+    sqlx::query("UPDATE tags SET name = ?1, color = ?2, updated_at = ?3 WHERE id = ?4")
+        .bind(&t.name)
+        .bind(&t.color)
+        .bind(t.updated_at)
+        .bind(&id)
+        .execute(&state.writer)
+        .await?;
+    Ok(Json(t))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/tags/{id}",
+    params(("id" = String, Path, description = "Tag id")),
+    responses((status = 204, description = "Tag deleted", headers(("x-undo-token" = String, description = "For `POST /undo/{token}` within 30 seconds"))))
+)]
+pub async fn delete_tag(
+    State(state): State<std::sync::Arc<AppState>>,
+    Path(id): Path<String>,
+) -> AppResult<Deleted> {
+    let now = Utc::now();
+    let mut tx = state.writer.begin().await?;
+    let res = sqlx::query("UPDATE tags SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL")
+        .bind(now)
+        .bind(&id)
+        .execute(&mut *tx)
+        .await?;
+    if res.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    let deleted = undo::issue(&mut tx, Undoable::Tag, &id, now).await?;
+    sqlx::query("DELETE FROM zone_tags WHERE tag_id = ?1")
+        .bind(&id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(deleted)
+}
+
+#[utoipa::path(
+    get,
+    path = "/zones/{id}/tags",
+    params(("id" = String, Path, description = "Zone id")),
+    responses((status = 200, description = "Tags of the zone", body = [Tag]))
+)]
+pub async fn list_zone_tags(
+    State(state): State<std::sync::Arc<AppState>>,
+    Path(zone_id): Path<String>,
+) -> AppResult<Json<Vec<Tag>>> {
+    let tags = sqlx::query_as::<_, Tag>(
+        r#"SELECT t.id, t.name, t.color, t.created_at, t.updated_at, t.deleted_at
+           FROM tags t JOIN zone_tags zt ON zt.tag_id = t.id
+           WHERE zt.zone_id = ?1 AND t.deleted_at IS NULL
+           ORDER BY t.name ASC"#,
+    )
+    .bind(&zone_id)
+    .fetch_all(&state.pool)
+    .await?;
+    Ok(Json(tags))
+}
+
+#[utoipa::path(
+    put,
+    path = "/zones/{id}/tags/{tag_id}",
+    params(
+        ("id" = String, Path, description = "Zone id"),
+        ("tag_id" = String, Path, description = "Tag id"),
+    ),
+    responses((status = 204, description = "Tag attached"))
+)]
+pub async fn attach_tag(
+    State(state): State<std::sync::Arc<AppState>>,
+    Path((zone_id, tag_id)): Path<(String, String)>,
+) -> AppResult<axum::http::StatusCode> {
+    let (found,): (i64,) = sqlx::query_as(
+        r#"SELECT (SELECT COUNT(1) FROM zones WHERE id = ?1 AND deleted_at IS NULL)
+                + (SELECT COUNT(1) FROM tags WHERE id = ?2 AND deleted_at IS NULL)"#,
+    )
+    .bind(&zone_id)
+    .bind(&tag_id)
+    .fetch_one(&state.pool)
+    .await?;
+    if found < 2 {
+        return Err(AppError::NotFound);
+    }
+    sqlx::query("INSERT OR IGNORE INTO zone_tags(zone_id, tag_id) VALUES (?1, ?2)")
+        .bind(&zone_id)
+        .bind(&tag_id)
+        .execute(&state.writer)
+        .await?;
+    Ok(axum::http::StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/zones/{id}/tags/{tag_id}",
+    params(
+        ("id" = String, Path, description = "Zone id"),
+        ("tag_id" = String, Path, description = "Tag id"),
+    ),
+    responses((status = 204, description = "Tag detached"))
+)]
+pub async fn detach_tag(
+    State(state): State<std::sync::Arc<AppState>>,
+    Path((zone_id, tag_id)): Path<(String, String)>,
+) -> AppResult<axum::http::StatusCode> {
+    let res = sqlx::query("DELETE FROM zone_tags WHERE zone_id = ?1 AND tag_id = ?2")
+        .bind(&zone_id)
+        .bind(&tag_id)
+        .execute(&state.writer)
+        .await?;
+    if res.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(axum::http::StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,200 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use chrono::Utc;
+use uuid::Uuid;
+
+use super::{
+    extract::LiveZone,
+    undo::{self, Deleted, Undoable},
+};
+use crate::{
+    error::{AppError, AppResult},
+    models::{AppState, Checklist, NewZoneTask, UpdateZoneTask, ZoneInstructions, ZoneTask},
+};
+
+#[utoipa::path(
+    get,
+    path = "/zones/{id}/tasks",
+    params(("id" = String, Path, description = "Zone id")),
+    responses((status = 200, description = "The zone's tasks with its instructions", body = Checklist))
+)]
+pub async fn list_tasks(
+    State(state): State<std::sync::Arc<AppState>>,
+    LiveZone(zone): LiveZone,
+) -> AppResult<Json<Checklist>> {
+    let tasks = sqlx::query_as::<_, ZoneTask>(
+        r#"SELECT id, zone_id, title, required, checked_at, created_at, updated_at, deleted_at
+           FROM zone_tasks WHERE zone_id = ?1 AND deleted_at IS NULL
+           ORDER BY created_at ASC"#,
+    )
+    .bind(&zone.id)
+    .fetch_all(&state.pool)
+    .await?;
+    let instructions: Option<String> = sqlx::query_scalar("SELECT instructions FROM zones WHERE id = ?1")
+        .bind(&zone.id)
+        .fetch_one(&state.pool)
+        .await?;
+    Ok(Json(Checklist { instructions, tasks }))
+}
+
+/// Longest instructions accepted, in bytes.
+const MAX_INSTRUCTIONS_BYTES: usize = 16 * 1024;
+
+#[utoipa::path(
+    put,
+    path = "/zones/{id}/instructions",
+    params(("id" = String, Path, description = "Zone id")),
+    request_body = ZoneInstructions,
+    responses(
+        (status = 200, description = "Instructions replaced", body = ZoneInstructions),
+        (status = 400, description = "Instructions are too long"),
+    )
+)]
+pub async fn put_instructions(
+    State(state): State<std::sync::Arc<AppState>>,
+    LiveZone(zone): LiveZone,
+    Json(body): Json<ZoneInstructions>,
+) -> AppResult<Json<ZoneInstructions>> {
+    let instructions = body.instructions.filter(|i| !i.trim().is_empty());
+    if instructions.as_ref().is_some_and(|i| i.len() > MAX_INSTRUCTIONS_BYTES) {
+        return Err(AppError::Validation(format!(
+            "instructions must be at most {MAX_INSTRUCTIONS_BYTES} bytes"
+        )));
+    }
+    sqlx::query("UPDATE zones SET instructions = ?1, updated_at = ?2 WHERE id = ?3")
+        .bind(&instructions)
+        .bind(Utc::now())
+        .bind(&zone.id)
+        .execute(&state.writer)
+        .await?;
+    Ok(Json(ZoneInstructions { instructions }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/zones/{id}/tasks",
+    params(("id" = String, Path, description = "Zone id")),
+    request_body = NewZoneTask,
+    responses((status = 201, description = "Task created", body = ZoneTask))
+)]
+pub async fn create_task(
+    State(state): State<std::sync::Arc<AppState>>,
+    LiveZone(zone): LiveZone,
+    Json(body): Json<NewZoneTask>,
+) -> AppResult<(axum::http::StatusCode, Json<ZoneTask>)> {
+    if body.title.trim().is_empty() {
+        return Err(AppError::Validation("title is required".into()));
+    }
+
+    let now = Utc::now();
+    let task = ZoneTask {
+        id: Uuid::new_v4().to_string(),
+        zone_id: zone.id,
+        title: body.title,
+        required: body.required.unwrap_or(true),
+        checked_at: None,
+        created_at: now,
+        updated_at: now,
+        deleted_at: None,
+    };
+    sqlx::query(
+        r#"INSERT INTO zone_tasks(id, zone_id, title, required, checked_at, created_at, updated_at, deleted_at)
+           VALUES (?1, ?2, ?3, ?4, NULL, ?5, ?5, NULL)"#,
+    )
+    .bind(&task.id)
+    .bind(&task.zone_id)
+    .bind(&task.title)
+    .bind(task.required)
+    .bind(now)
+    .execute(&state.writer)
+    .await?;
+    Ok((axum::http::StatusCode::CREATED, Json(task)))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/zones/{id}/tasks/{task_id}",
+    params(
+        ("id" = String, Path, description = "Zone id"),
+        ("task_id" = String, Path, description = "Task id"),
+    ),
+    request_body = UpdateZoneTask,
+    responses((status = 200, description = "Task updated", body = ZoneTask))
+)]
+pub async fn update_task(
+    State(state): State<std::sync::Arc<AppState>>,
+    Path((zone_id, task_id)): Path<(String, String)>,
+    Json(body): Json<UpdateZoneTask>,
+) -> AppResult<Json<ZoneTask>> {
+    let t = sqlx::query_as::<_, ZoneTask>(
+        r#"SELECT id, zone_id, title, required, checked_at, created_at, updated_at, deleted_at
+           FROM zone_tasks WHERE id = ?1 AND zone_id = ?2 AND deleted_at IS NULL"#,
+    )
+    .bind(&task_id)
+    .bind(&zone_id)
+    .fetch_optional(&state.pool)
+    .await?;
+    let mut t = t.ok_or(AppError::NotFound)?;
+
+    if let Some(title) = body.title {
+        if title.trim().is_empty() {
+            return Err(AppError::Validation("title is required".into()));
+        }
+        t.title = title;
+    }
+    if let Some(required) = body.required {
+        t.required = required;
+    }
+    let now = Utc::now();
+    match body.checked {
+        Some(true) if t.checked_at.is_none() => t.checked_at = Some(now),
+        Some(false) => t.checked_at = None,
+        _ => {}
+    }
+    t.updated_at = now;
+
+    sqlx::query(
+        "UPDATE zone_tasks SET title = ?1, required = ?2, checked_at = ?3, updated_at = ?4 WHERE id = ?5",
+    )
+    .bind(&t.title)
+    .bind(t.required)
+    .bind(t.checked_at)
+    .bind(now)
+    .bind(&task_id)
+    .execute(&state.writer)
+    .await?;
+    Ok(Json(t))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/zones/{id}/tasks/{task_id}",
+    params(
+        ("id" = String, Path, description = "Zone id"),
+        ("task_id" = String, Path, description = "Task id"),
+    ),
+    responses((status = 204, description = "Task deleted", headers(("x-undo-token" = String, description = "For `POST /undo/{token}` within 30 seconds"))))
+)]
+pub async fn delete_task(
+    State(state): State<std::sync::Arc<AppState>>,
+    Path((zone_id, task_id)): Path<(String, String)>,
+) -> AppResult<Deleted> {
+    let now = Utc::now();
+    let mut tx = state.writer.begin().await?;
+    let res = sqlx::query(
+        "UPDATE zone_tasks SET deleted_at = ?1 WHERE id = ?2 AND zone_id = ?3 AND deleted_at IS NULL",
+    )
+    .bind(now)
+    .bind(&task_id)
+    .bind(&zone_id)
+    .execute(&mut *tx)
+    .await?;
+    if res.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    let deleted = undo::issue(&mut tx, Undoable::Task, &task_id, now).await?;
+    tx.commit().await?;
+    Ok(deleted)
+}
@@ -0,0 +1,232 @@
+//! `/undo/{token}`: deletes answer with an `X-Undo-Token` that brings the
+//! deleted item back for a short while. The token remembers the moment of the
+//! delete, so an item deleted again or restored in the meantime is left alone.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::SqliteConnection;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, AppResult},
+    events,
+    models::{AppState, ZoneChange},
+};
+
+pub const UNDO_HEADER: &str = "x-undo-token";
+/// How long after a delete its token is good for.
+pub const UNDO_WINDOW_SECS: i64 = 30;
+
+#[derive(Clone, Copy, Serialize, Deserialize, ToSchema, sqlx::Type, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum Undoable {
+    Home,
+    Room,
+    Zone,
+    Task,
+    Tag,
+    Group,
+    Supply,
+    Webhook,
+    Integration,
+}
+
+impl Undoable {
+    fn table(self) -> &'static str {
+        match self {
+            Undoable::Home => "homes",
+            Undoable::Room => "rooms",
+            Undoable::Zone => "zones",
+            Undoable::Task => "zone_tasks",
+            Undoable::Tag => "tags",
+            Undoable::Group => "zone_groups",
+            Undoable::Supply => "supplies",
+            Undoable::Webhook => "webhooks",
+            Undoable::Integration => "integrations",
+        }
+    }
+
+    /// Links the delete removes outright: how to save them as JSON before it
+    /// does, and how to put them back (`?1` the id, `?2` the saved JSON). Links
+    /// to zones deleted in the meantime are not put back.
+    fn links(self) -> Option<(&'static str, &'static str)> {
+        match self {
+            Undoable::Tag => Some((
+                "SELECT json_group_array(zone_id) FROM zone_tags WHERE tag_id = ?1",
+                r#"INSERT OR IGNORE INTO zone_tags(zone_id, tag_id) SELECT value, ?1 FROM json_each(?2)
+                   WHERE value IN (SELECT id FROM zones WHERE deleted_at IS NULL)"#,
+            )),
+            Undoable::Group => Some((
+                "SELECT json_group_array(zone_id) FROM zone_group_members WHERE group_id = ?1",
+                r#"INSERT OR IGNORE INTO zone_group_members(group_id, zone_id) SELECT ?1, value FROM json_each(?2)
+                   WHERE value IN (SELECT id FROM zones WHERE deleted_at IS NULL)"#,
+            )),
+            Undoable::Supply => Some((
+                r#"SELECT json_group_array(json_object('zone_id', zone_id, 'usage', usage))
+                   FROM zone_supplies WHERE supply_id = ?1"#,
+                r#"INSERT OR IGNORE INTO zone_supplies(zone_id, supply_id, usage)
+                   SELECT json_extract(value, '$.zone_id'), ?1, json_extract(value, '$.usage') FROM json_each(?2)
+                   WHERE json_extract(value, '$.zone_id') IN (SELECT id FROM zones WHERE deleted_at IS NULL)"#,
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// 204 for a delete, with the token that undoes it.
+pub struct Deleted(pub String);
+
+impl IntoResponse for Deleted {
+    fn into_response(self) -> Response {
+        (StatusCode::NO_CONTENT, [(UNDO_HEADER, self.0)]).into_response()
+    }
+}
+
+/// Issues the token for an item just soft-deleted at `deleted_at`. Runs in the
+/// delete's transaction, before links the delete removes are gone.
+pub(super) async fn issue(
+    conn: &mut SqliteConnection,
+    kind: Undoable,
+    id: &str,
+    deleted_at: DateTime<Utc>,
+) -> AppResult<Deleted> {
+    sqlx::query("DELETE FROM undo_tokens WHERE expires_at <= ?1")
+        .bind(deleted_at)
+        .execute(&mut *conn)
+        .await?;
+    let links: Option<String> = match kind.links() {
+        Some((save, _)) => Some(sqlx::query_scalar(save).bind(id).fetch_one(&mut *conn).await?),
+        None => None,
+    };
+    let token = Uuid::new_v4().simple().to_string();
+    sqlx::query(
+        r#"INSERT INTO undo_tokens(token, kind, target_id, deleted_at, links, expires_at)
+           VALUES (?1, ?2, ?3, ?4, ?5, ?6)"#,
+    )
+    .bind(&token)
+    .bind(kind)
+    .bind(id)
+    .bind(deleted_at)
+    .bind(links)
+    .bind(deleted_at + Duration::seconds(UNDO_WINDOW_SECS))
+    .execute(&mut *conn)
+    .await?;
+    Ok(Deleted(token))
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct Undone {
+    pub kind: Undoable,
+    /// Id of the item that is back.
+    pub id: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/undo/{token}",
+    params(("token" = String, Path, description = "`X-Undo-Token` of a delete")),
+    responses(
+        (status = 200, description = "The delete is reversed", body = Undone),
+        (status = 404, description = "Token unknown, used or expired"),
+        (status = 409, description = "The item was restored or deleted again since, or what it belongs to is deleted"),
+    )
+)]
+pub async fn undo(
+    State(state): State<std::sync::Arc<AppState>>,
+    Path(token): Path<String>,
+) -> AppResult<Json<Undone>> {
+    let now = Utc::now();
+    let mut tx = state.writer.begin().await?;
+    let (kind, id, deleted_at, links): (Undoable, String, DateTime<Utc>, Option<String>) = sqlx::query_as(
+        "DELETE FROM undo_tokens WHERE token = ?1 AND expires_at > ?2 RETURNING kind, target_id, deleted_at, links",
+    )
+    .bind(&token)
+    .bind(now)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    let (still,): (i64,) =
+        sqlx::query_as(&format!("SELECT COUNT(1) FROM {} WHERE id = ?1 AND deleted_at = ?2", kind.table()))
+            .bind(&id)
+            .bind(deleted_at)
+            .fetch_one(&mut *tx)
+            .await?;
+    if still == 0 {
+        return Err(AppError::Conflict("the item was restored or deleted again since".into()));
+    }
+    match kind {
+        Undoable::Room => {
+            super::rooms::restore(&mut tx, &id, now).await?;
+        }
+        Undoable::Zone => {
+            let (room_live,): (bool,) = sqlx::query_as(
+                r#"SELECT EXISTS(SELECT 1 FROM rooms r JOIN zones z ON z.room_id = r.id
+                   WHERE z.id = ?1 AND r.deleted_at IS NULL)"#,
+            )
+            .bind(&id)
+            .fetch_one(&mut *tx)
+            .await?;
+            if !room_live {
+                return Err(AppError::Conflict("the zone's room is deleted; restore the room first".into()));
+            }
+            sqlx::query("UPDATE zones SET deleted_at = NULL, updated_at = ?2 WHERE id = ?1")
+                .bind(&id)
+                .bind(now)
+                .execute(&mut *tx)
+                .await?;
+            events::record(&mut *tx, &id, &ZoneChange::Restored, now).await?;
+            super::zones::sync_next_due(&mut tx, Some(&id)).await?;
+        }
+        Undoable::Task => {
+            let (zone_live,): (bool,) = sqlx::query_as(
+                r#"SELECT EXISTS(SELECT 1 FROM zones z JOIN zone_tasks t ON t.zone_id = z.id
+                   WHERE t.id = ?1 AND z.deleted_at IS NULL)"#,
+            )
+            .bind(&id)
+            .fetch_one(&mut *tx)
+            .await?;
+            if !zone_live {
+                return Err(AppError::Conflict("the task's zone is deleted; restore the zone first".into()));
+            }
+            restore_linked(&mut tx, kind, &id, links).await?;
+        }
+        Undoable::Tag => {
+            let (taken,): (bool,) = sqlx::query_as(
+                r#"SELECT EXISTS(SELECT 1 FROM tags t JOIN tags d ON d.id = ?1
+                   WHERE t.name = d.name AND t.deleted_at IS NULL)"#,
+            )
+            .bind(&id)
+            .fetch_one(&mut *tx)
+            .await?;
+            // имя меток уникально среди живых
+            if taken {
+                return Err(AppError::Conflict("a tag with the same name was created since".into()));
+            }
+            restore_linked(&mut tx, kind, &id, links).await?;
+        }
+        _ => restore_linked(&mut tx, kind, &id, links).await?,
+    }
+    tx.commit().await?;
+    Ok(Json(Undone { kind, id }))
+}
+
+/// Clears `deleted_at` and puts back the links saved by [`issue`].
+async fn restore_linked(conn: &mut SqliteConnection, kind: Undoable, id: &str, links: Option<String>) -> AppResult<()> {
+    sqlx::query(&format!("UPDATE {} SET deleted_at = NULL WHERE id = ?1", kind.table()))
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
+    if let (Some((_, put_back)), Some(links)) = (kind.links(), links) {
+        sqlx::query(put_back).bind(id).bind(links).execute(&mut *conn).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,214 @@
+//! `/integrations/voice/intents`: intents an Alexa skill or a Google Assistant
+//! action has already recognised, answered with a sentence to read out. Zones
+//! are named the way people say them ("bathroom sink"), so they are matched
+//! fuzzily against zone and room names.
+
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::{
+    homes::{HomeParams, HomeScope},
+    stats,
+    zones::{self, CleanBody},
+};
+use crate::{
+    error::AppResult,
+    models::{AppState, ZoneView},
+};
+
+/// Below this a zone is not taken for the one asked about.
+const MIN_SCORE: f64 = 0.8;
+/// Zones scoring this close to the best one make the request ambiguous.
+const TIE_MARGIN: f64 = 0.05;
+/// Due zones read out by name; the rest are only counted.
+const MAX_LISTED: usize = 5;
+/// Words that say nothing about which zone is meant.
+const FILLER: [&str; 6] = ["the", "a", "my", "in", "of", "room"];
+
+#[derive(Deserialize, ToSchema)]
+#[serde(tag = "intent", rename_all = "snake_case")]
+pub enum VoiceIntent {
+    /// "What's due today?"
+    DueToday,
+    /// "Mark bathroom sink cleaned."
+    MarkCleaned { zone: String },
+    /// "When is the kitchen floor due?"
+    WhenDue { zone: String },
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct VoiceReply {
+    /// Plain sentence for the assistant to say.
+    pub speech: String,
+    /// Zone the intent was about, once it was matched.
+    pub zone_id: Option<String>,
+    /// Zones to ask "which one?" about when the name fit several.
+    pub choices: Vec<String>,
+}
+
+impl VoiceReply {
+    fn say(speech: String) -> Self {
+        Self { speech, zone_id: None, choices: Vec::new() }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/integrations/voice/intents",
+    params(HomeParams),
+    request_body = VoiceIntent,
+    responses((status = 200, description = "What to say back, also when no zone fits", body = VoiceReply))
+)]
+pub async fn intents(
+    State(state): State<std::sync::Arc<AppState>>,
+    HomeScope(home_id): HomeScope,
+    Json(intent): Json<VoiceIntent>,
+) -> AppResult<Json<VoiceReply>> {
+    let now = Utc::now();
+    let reply = match intent {
+        VoiceIntent::DueToday => {
+            let due = stats::due_zones(&state, home_id).await?;
+            VoiceReply::say(due_speech(&due))
+        }
+        VoiceIntent::MarkCleaned { zone } => match find_zone(&state, home_id, &zone).await? {
+            Found::One(id, name) => {
+                let body = CleanBody {
+                    cleaned_at: None,
+                    require_tasks: None,
+                    note: None,
+                    cost_cents: None,
+                    duration_minutes: None,
+                };
+                let Json(z) = zones::clean(State(state.clone()), Path(id.clone()), Json(body)).await?;
+                let next = match z.next_due_at {
+                    Some(t) => format!(" Next time {}.", until(t, now)),
+                    None => String::new(),
+                };
+                let speech = format!("Marked {name} as cleaned.{next}");
+                VoiceReply { speech, zone_id: Some(id), choices: Vec::new() }
+            }
+            other => other.into_reply(&zone),
+        },
+        VoiceIntent::WhenDue { zone } => match find_zone(&state, home_id, &zone).await? {
+            Found::One(id, name) => {
+                let Json(z) = zones::zone_view(&state, &id).await?;
+                let when = match z.next_due_at {
+                    None => "has never been cleaned, so it is due now".to_string(),
+                    Some(t) if t <= now => match (now - t).num_days() {
+                        0 => "is due now".to_string(),
+                        1 => "is a day overdue".to_string(),
+                        n => format!("is {n} days overdue"),
+                    },
+                    Some(t) => format!("is due {}", until(t, now)),
+                };
+                VoiceReply { speech: format!("{name} {when}."), zone_id: Some(id), choices: Vec::new() }
+            }
+            other => other.into_reply(&zone),
+        },
+    };
+    Ok(Json(reply))
+}
+
+fn due_speech(due: &[ZoneView]) -> String {
+    if due.is_empty() {
+        return "Nothing is due. Nice work!".into();
+    }
+    let mut names: Vec<String> = due.iter().take(MAX_LISTED).map(|z| z.name.clone()).collect();
+    if due.len() > MAX_LISTED {
+        names.push(format!("{} more", due.len() - MAX_LISTED));
+    }
+    match due.len() {
+        1 => format!("One zone is due: {}.", names[0]),
+        n => format!("{n} zones are due: {}.", spoken_list(&names)),
+    }
+}
+
+/// "a", "a and b", "a, b and c".
+fn spoken_list(items: &[String]) -> String {
+    match items {
+        [] => String::new(),
+        [one] => one.clone(),
+        [rest @ .., last] => format!("{} and {last}", rest.join(", ")),
+    }
+}
+
+/// "in 3 days"; anything under a day is "within a day".
+fn until(t: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    match ((t - now).num_hours() + 23) / 24 {
+        ..=1 => "within a day".to_string(),
+        days => format!("in {days} days"),
+    }
+}
+
+enum Found {
+    /// Zone id and its spoken name, "Sink in Bathroom".
+    One(String, String),
+    Several(Vec<String>),
+    None,
+}
+
+impl Found {
+    fn into_reply(self, asked: &str) -> VoiceReply {
+        match self {
+            Found::Several(names) => VoiceReply {
+                speech: format!("Which one: {}?", spoken_list(&names).replacen(" and ", " or ", 1)),
+                zone_id: None,
+                choices: names,
+            },
+            _ => VoiceReply::say(format!("I couldn't find a zone called {asked}.")),
+        }
+    }
+}
+
+/// The zone of the home whose name, together with its room's, fits `asked`
+/// best; [`Found::Several`] when others fit about as well.
+async fn find_zone(state: &AppState, home_id: Option<String>, asked: &str) -> AppResult<Found> {
+    let zones: Vec<(String, String, String)> = sqlx::query_as(
+        r#"SELECT z.id, z.name, r.name FROM zones z JOIN rooms r ON r.id = z.room_id
+           WHERE z.deleted_at IS NULL AND (?1 IS NULL OR r.home_id = ?1)
+           ORDER BY r.sort_order, r.created_at DESC, r.id, z.sort_order, z.id"#,
+    )
+    .bind(home_id)
+    .fetch_all(&state.pool)
+    .await?;
+    let asked = words(asked);
+    let mut scored: Vec<(f64, String, String)> = zones
+        .into_iter()
+        .map(|(id, zone, room)| (score(&asked, &words(&zone), &words(&room)), id, format!("{zone} in {room}")))
+        .filter(|(s, ..)| *s >= MIN_SCORE)
+        .collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    Ok(match scored.as_slice() {
+        [] => Found::None,
+        [(best, ..), rest @ ..] if rest.first().is_some_and(|(s, ..)| best - s < TIE_MARGIN) => Found::Several(
+            scored.iter().take_while(|(s, ..)| best - s < TIE_MARGIN).map(|(.., name)| name.clone()).collect(),
+        ),
+        [(_, id, name), ..] => Found::One(id.clone(), name.clone()),
+    })
+}
+
+/// Lower-case words without filler.
+fn words(s: &str) -> Vec<String> {
+    s.split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|w| !w.is_empty() && !FILLER.contains(&w.as_str()))
+        .collect()
+}
+
+/// How well the spoken words fit a zone, 0 to 1: every spoken word should be
+/// in the zone or room name, and every word of the zone name should be spoken.
+/// Words are compared with Jaro-Winkler, so "sinc" still finds "sink".
+fn score(asked: &[String], zone: &[String], room: &[String]) -> f64 {
+    let best = |w: &str, among: &mut dyn Iterator<Item = &String>| {
+        among.map(|o| strsim::jaro_winkler(w, o)).fold(0.0, f64::max)
+    };
+    let mean = |xs: Vec<f64>| if xs.is_empty() { 0.0 } else { xs.iter().sum::<f64>() / xs.len() as f64 };
+    let said = mean(asked.iter().map(|w| best(w, &mut zone.iter().chain(room))).collect());
+    let named = mean(zone.iter().map(|w| best(w, &mut asked.iter())).collect());
+    (said + named) / 2.0
+}
@@ -0,0 +1,197 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use chrono::Utc;
+use sqlx::types::Json as SqlJson;
+use uuid::Uuid;
+
+use super::undo::{self, Deleted, Undoable};
+use crate::{
+    error::{AppError, AppResult},
+    models::{AppState, NewWebhook, UpdateWebhook, Webhook, WebhookDelivery},
+    webhooks::EVENTS,
+};
+
+const WEBHOOK_COLUMNS: &str = "id, url, events, active, created_at, updated_at, deleted_at";
+pub const MIN_SECRET_LEN: usize = 16;
+
+fn validate_url(url: &str) -> AppResult<()> {
+    match reqwest::Url::parse(url) {
+        Ok(u) if matches!(u.scheme(), "http" | "https") => Ok(()),
+        _ => Err(AppError::Validation(format!("'{url}' is not an http(s) URL"))),
+    }
+}
+
+fn validate_events(events: &[String]) -> AppResult<()> {
+    if events.is_empty() {
+        return Err(AppError::Validation("events must not be empty".into()));
+    }
+    if let Some(bad) = events.iter().find(|e| !EVENTS.contains(&e.as_str())) {
+        return Err(AppError::Validation(format!(
+            "unknown event '{bad}', expected one of {}",
+            EVENTS.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+pub(crate) fn validate_secret(secret: &str) -> AppResult<()> {
+    if secret.len() < MIN_SECRET_LEN {
+        return Err(AppError::Validation(format!(
+            "secret must be at least {MIN_SECRET_LEN} characters"
+        )));
+    }
+    Ok(())
+}
+
+async fn fetch_webhook(state: &AppState, id: &str) -> AppResult<Webhook> {
+    sqlx::query_as::<_, Webhook>(&format!(
+        "SELECT {WEBHOOK_COLUMNS} FROM webhooks WHERE id = ?1 AND deleted_at IS NULL"
+    ))
+    .bind(id)
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or(AppError::NotFound)
+}
+
+#[utoipa::path(
+    get,
+    path = "/webhooks",
+    responses((status = 200, description = "List webhooks", body = [Webhook]))
+)]
+pub async fn list_webhooks(State(state): State<std::sync::Arc<AppState>>) -> AppResult<Json<Vec<Webhook>>> {
+    let hooks = sqlx::query_as::<_, Webhook>(&format!(
+        "SELECT {WEBHOOK_COLUMNS} FROM webhooks WHERE deleted_at IS NULL ORDER BY created_at ASC"
+    ))
+    .fetch_all(&state.pool)
+    .await?;
+    Ok(Json(hooks))
+}
+
+#[utoipa::path(
+    post,
+    path = "/webhooks",
+    request_body = NewWebhook,
+    responses((status = 201, description = "Webhook registered", body = Webhook))
+)]
+pub async fn create_webhook(
+    State(state): State<std::sync::Arc<AppState>>,
+    Json(body): Json<NewWebhook>,
+) -> AppResult<(axum::http::StatusCode, Json<Webhook>)> {
+    validate_url(&body.url)?;
+    validate_events(&body.events)?;
+    validate_secret(&body.secret)?;
+
+    let now = Utc::now();
+    let hook = Webhook {
+        id: Uuid::new_v4().to_string(),
+        url: body.url,
+        events: SqlJson(body.events),
+        active: true,
+        created_at: now,
+        updated_at: now,
+        deleted_at: None,
+    };
+    sqlx::query(
+        r#"INSERT INTO webhooks(id, url, secret, events, active, created_at, updated_at, deleted_at)
+           VALUES (?1, ?2, ?3, ?4, 1, ?5, ?5, NULL)"#,
+    )
+    .bind(&hook.id)
+    .bind(&hook.url)
+    .bind(state.cipher.seal(&body.secret))
+    .bind(&hook.events)
+    .bind(now)
+    .execute(&state.writer)
+    .await?;
+    Ok((axum::http::StatusCode::CREATED, Json(hook)))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/webhooks/{id}",
+    params(("id" = String, Path, description = "Webhook id")),
+    request_body = UpdateWebhook,
+    responses((status = 200, description = "Webhook updated", body = Webhook))
+)]
+pub async fn update_webhook(
+    State(state): State<std::sync::Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(body): Json<UpdateWebhook>,
+) -> AppResult<Json<Webhook>> {
+    let mut h = fetch_webhook(&state, &id).await?;
+    if let Some(url) = body.url {
+        validate_url(&url)?;
+        h.url = url;
+    }
+    if let Some(events) = body.events {
+        validate_events(&events)?;
+        h.events = SqlJson(events);
+    }
+    if let Some(secret) = &body.secret {
+        validate_secret(secret)?;
+    }
+    h.active = body.active.unwrap_or(h.active);
+    h.updated_at = Utc::now();
+
+    sqlx::query(
+        r#"UPDATE webhooks SET url = ?1, events = ?2, active = ?3, secret = COALESCE(?4, secret), updated_at = ?5
+           WHERE id = ?6"#,
+    )
+    .bind(&h.url)
+    .bind(&h.events)
+    .bind(h.active)
+    .bind(body.secret.as_deref().map(|s| state.cipher.seal(s)))
+    .bind(h.updated_at)
+    .bind(&id)
+    .execute(&state.writer)
+    .await?;
+    Ok(Json(h))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/webhooks/{id}",
+    params(("id" = String, Path, description = "Webhook id")),
+    responses((status = 204, description = "Webhook deleted", headers(("x-undo-token" = String, description = "For `POST /undo/{token}` within 30 seconds"))))
+)]
+pub async fn delete_webhook(
+    State(state): State<std::sync::Arc<AppState>>,
+    Path(id): Path<String>,
+) -> AppResult<Deleted> {
+    let now = Utc::now();
+    let mut tx = state.writer.begin().await?;
+    let res = sqlx::query("UPDATE webhooks SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL")
+        .bind(now)
+        .bind(&id)
+        .execute(&mut *tx)
+        .await?;
+    if res.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    let deleted = undo::issue(&mut tx, Undoable::Webhook, &id, now).await?;
+    tx.commit().await?;
+    Ok(deleted)
+}
+
+#[utoipa::path(
+    get,
+    path = "/webhooks/{id}/deliveries",
+    params(("id" = String, Path, description = "Webhook id")),
+    responses((status = 200, description = "Latest deliveries, newest first", body = [WebhookDelivery]))
+)]
+pub async fn list_deliveries(
+    State(state): State<std::sync::Arc<AppState>>,
+    Path(id): Path<String>,
+) -> AppResult<Json<Vec<WebhookDelivery>>> {
+    fetch_webhook(&state, &id).await?;
+    let deliveries = sqlx::query_as::<_, WebhookDelivery>(
+        r#"SELECT id, webhook_id, event, attempts, next_attempt_at, delivered_at, last_error, created_at
+           FROM webhook_deliveries WHERE webhook_id = ?1
+           ORDER BY created_at DESC LIMIT 100"#,
+    )
+    .bind(&id)
+    .fetch_all(&state.pool)
+    .await?;
+    Ok(Json(deliveries))
+}
@@ -1,166 +1,489 @@
 use axum::{
     extract::{Path, Query, State},
+    response::Response,
     Json,
 };
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
+use sqlx::types::Json as SqlJson;
 use uuid::Uuid;
 use utoipa::{IntoParams, ToSchema};
 
+use super::{
+    extract::{LiveRoom, LiveZone},
+    fields::{Fields, FieldsParams},
+    idempotency::Keyed,
+    homes::{HomeParams, HomeScope},
+    pagination::{PageParams, SortOrder},
+    supplies,
+    undo::{self, Deleted, Undoable},
+};
 use crate::{
     error::{AppError, AppResult},
-    models::{compute_is_due, compute_next_due, AppState, NewZone, UpdateZone, Zone, ZoneView},
+    events, webhooks,
+    models::{
+        mask_to_weekdays, validate_external_ref, MaybeAbsent, weekdays_to_mask, AppState, BulkItem, BulkStatus, Frequency,
+        NewZone, Page, PauseZone, Reorder, Reschedule, UpdateZone, Zone, ZoneChange, ZoneEvent,
+        ZoneView, ZONE_COLUMNS,
+    },
 };
 
-#[derive(Deserialize, IntoParams)]
+/// Upper bound for the serialized `metadata` object.
+const METADATA_MAX_BYTES: usize = 4096;
+
+fn validate_metadata(metadata: Option<&serde_json::Value>) -> AppResult<()> {
+    let Some(m) = metadata else { return Ok(()) };
+    if !m.is_object() {
+        return Err(AppError::Validation("metadata must be a JSON object".into()));
+    }
+    if m.to_string().len() > METADATA_MAX_BYTES {
+        return Err(AppError::Validation(format!(
+            "metadata must not exceed {METADATA_MAX_BYTES} bytes"
+        )));
+    }
+    Ok(())
+}
+
+/// Weekday mask for a `weekdays` zone; other frequencies carry none.
+fn resolve_weekdays(frequency: Frequency, weekdays: Option<&[u8]>) -> AppResult<Option<i64>> {
+    if frequency != Frequency::Weekdays {
+        return Ok(None);
+    }
+    match weekdays {
+        Some(days) if !days.is_empty() && days.iter().all(|d| (1..=7).contains(d)) => {
+            Ok(Some(weekdays_to_mask(days)))
+        }
+        _ => Err(AppError::Validation(
+            "weekdays must list ISO weekdays 1..=7 for weekdays frequency".into(),
+        )),
+    }
+}
+
+#[derive(Deserialize, IntoParams, Default)]
 pub struct ListZones {
+    /// Same as `is_due=true`.
     pub only_due: Option<bool>,
+    /// `true` for due zones only, `false` for the others.
+    pub is_due: Option<bool>,
+    /// Only zones carrying this tag id.
+    pub tag: Option<String>,
+    pub frequency: Option<Frequency>,
+    /// Only zones of this room; ignored under `/rooms/{room_id}/zones`.
+    pub room_id: Option<String>,
+    /// Room order (`sort_order`) when unset.
+    pub sort: Option<ZoneSort>,
+    pub order: Option<SortOrder>,
+}
+
+#[derive(Deserialize, ToSchema, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum ZoneSort {
+    Name,
+    CreatedAt,
+    /// Never-cleaned zones count as due the earliest.
+    NextDueAt,
+}
+
+/// Zone filter for listings: room `?1`, home `?2`, tag `?3`, frequency `?4`,
+/// due `?5` as of `?6`.
+const LIST_FILTER: &str = r#"deleted_at IS NULL
+    AND (?1 IS NULL OR room_id = ?1)
+    AND (?2 IS NULL OR room_id IN (SELECT id FROM rooms WHERE home_id = ?2))
+    AND (?3 IS NULL OR id IN (SELECT zone_id FROM zone_tags WHERE tag_id = ?3))
+    AND (?4 IS NULL OR frequency = ?4)
+    AND (?5 IS NULL OR (next_due_at IS NULL OR next_due_at <= ?6) = ?5)"#;
+
+async fn query_zones(
+    state: &AppState,
+    home_id: Option<&str>,
+    p: &ListZones,
+    page: &PageParams,
+) -> AppResult<Page<ZoneView>> {
+    let offset = page.offset()?;
+    let is_due = p.is_due.or(p.only_due.filter(|&d| d));
+    let frequency = p.frequency.map(|f| f.as_str());
+    let now = Utc::now();
+    let order = p.order.unwrap_or_default().as_sql();
+    // столбцы сортировки — только из перечисления, не из запроса
+    let order_by = match p.sort {
+        None => "sort_order ASC, created_at DESC, id".to_string(),
+        Some(ZoneSort::Name) => format!("name {order}, id"),
+        Some(ZoneSort::CreatedAt) => format!("created_at {order}, id"),
+        Some(ZoneSort::NextDueAt) => format!("COALESCE(next_due_at, '') {order}, id"),
+    };
+
+    let (total,): (i64,) = sqlx::query_as(&format!("SELECT COUNT(1) FROM zones WHERE {LIST_FILTER}"))
+        .bind(&p.room_id)
+        .bind(home_id)
+        .bind(&p.tag)
+        .bind(frequency)
+        .bind(is_due)
+        .bind(now)
+        .fetch_one(&state.pool)
+        .await?;
+    let mut zones: Vec<Zone> = sqlx::query_as::<_, Zone>(&format!(
+        r#"SELECT {ZONE_COLUMNS}
+           FROM zones WHERE {LIST_FILTER}
+           ORDER BY {order_by}
+           LIMIT ?7 OFFSET ?8"#
+    ))
+    .bind(&p.room_id)
+    .bind(home_id)
+    .bind(&p.tag)
+    .bind(frequency)
+    .bind(is_due)
+    .bind(now)
+    .bind(page.fetch_limit())
+    .bind(offset)
+    .fetch_all(&state.pool)
+    .await?;
+    let next_cursor = page
+        .trim(&mut zones)
+        .then(|| (offset + zones.len() as i64).to_string());
+
+    let tz = super::settings::timezone(&state.pool).await?;
+    let items = zones.into_iter().map(|z| ZoneView::localized(z, tz)).collect();
+    Ok(Page { items, total, next_cursor })
 }
 
 #[utoipa::path(
     get,
     path = "/rooms/{room_id}/zones",
-    params(("room_id" = String, Path, description = "Room id"), ListZones),
-    responses((status = 200, description = "List zones", body = [ZoneView]))
+    params(("room_id" = String, Path, description = "Room id"), ListZones, PageParams, FieldsParams),
+    responses((status = 200, description = "List zones", body = ZonePage))
 )]
 pub async fn list_zones(
     State(state): State<std::sync::Arc<AppState>>,
     Path(room_id): Path<String>,
     Query(p): Query<ListZones>,
-) -> AppResult<Json<Vec<ZoneView>>> {
-    let mut zones: Vec<Zone> = sqlx::query_as::<_, Zone>(
-        r#"SELECT id, room_id, name, icon, frequency, custom_interval_days, last_cleaned_at, created_at, updated_at, deleted_at
-           FROM zones WHERE room_id = ?1 AND deleted_at IS NULL
-           ORDER BY created_at DESC"#
-    ).bind(&room_id).fetch_all(&state.pool).await?;
-
-    let mut out = Vec::with_capacity(zones.len());
-    for z in zones.drain(..) {
-        let next_due = compute_next_due(z.last_cleaned_at, &z.frequency, z.custom_interval_days);
-        let is_due = compute_is_due(next_due);
-        if p.only_due.unwrap_or(false) && !is_due {
-            continue;
-        }
-        out.push(ZoneView {
-            id: z.id,
-            room_id: z.room_id,
-            name: z.name,
-            icon: z.icon,
-            frequency: z.frequency,
-            custom_interval_days: z.custom_interval_days,
-            last_cleaned_at: z.last_cleaned_at,
-            next_due_at: next_due,
-            is_due,
-            created_at: z.created_at,
-            updated_at: z.updated_at,
-            deleted_at: z.deleted_at,
-        });
-    }
-    Ok(Json(out))
+    Query(page): Query<PageParams>,
+    fields: Fields,
+) -> AppResult<Json<Page<serde_json::Value>>> {
+    let p = ListZones { room_id: Some(room_id), ..p };
+    Ok(Json(fields.page(query_zones(&state, None, &p, &page).await?)?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/zones",
+    params(ListZones, PageParams, HomeParams, FieldsParams),
+    responses((status = 200, description = "Zones across rooms", body = ZonePage))
+)]
+pub async fn list_all_zones(
+    State(state): State<std::sync::Arc<AppState>>,
+    HomeScope(home_id): HomeScope,
+    Query(p): Query<ListZones>,
+    Query(page): Query<PageParams>,
+    fields: Fields,
+) -> AppResult<Json<Page<serde_json::Value>>> {
+    Ok(Json(fields.page(query_zones(&state, home_id.as_deref(), &p, &page).await?)?))
 }
 
 #[utoipa::path(
     post,
     path = "/rooms/{room_id}/zones",
-    params(("room_id" = String, Path, description = "Room id")),
+    params(
+        ("room_id" = String, Path, description = "Room id"),
+        ("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key get the first response"),
+    ),
     request_body = NewZone,
-    responses((status = 201, description = "Zone created", body = ZoneView))
+    responses(
+        (status = 201, description = "Zone created", body = ZoneView),
+        (status = 200, description = "Zone with the same source/external_id updated", body = ZoneView),
+        (status = 409, description = "The source/external_id belongs to a zone in another room, or the Idempotency-Key was reused for another request or is still in progress"),
+    )
 )]
 pub async fn create_zone(
     State(state): State<std::sync::Arc<AppState>>,
-    Path(room_id): Path<String>,
+    room: LiveRoom,
+    Keyed(key, body): Keyed<Json<NewZone>>,
+) -> AppResult<Response> {
+    key.run(&state, new_zone(State(state.clone()), room, body)).await
+}
+
+pub(super) async fn new_zone(
+    State(state): State<std::sync::Arc<AppState>>,
+    LiveRoom(room): LiveRoom,
     Json(body): Json<NewZone>,
 ) -> AppResult<(axum::http::StatusCode, Json<ZoneView>)> {
+    let weekday_mask = validate_new_zone(&body)?;
+    if let (Some(source), Some(external_id)) = (&body.source, &body.external_id) {
+        let existing: Option<(String, String)> = sqlx::query_as(
+            "SELECT id, room_id FROM zones WHERE source = ?1 AND external_id = ?2 AND deleted_at IS NULL",
+        )
+        .bind(source)
+        .bind(external_id)
+        .fetch_optional(&state.pool)
+        .await?;
+        if let Some((existing, zone_room)) = existing {
+            // повторный импорт обновляет зону только в той же комнате
+            if zone_room != room.id {
+                return Err(AppError::Conflict(format!(
+                    "external_id {external_id} belongs to zone {existing} in another room"
+                )));
+            }
+            let view = update_zone(State(state), Path(existing), Json(body.into())).await?;
+            return Ok((axum::http::StatusCode::OK, view));
+        }
+    }
+
+    let id = Uuid::new_v4().to_string();
+    let mut tx = state.writer.begin().await?;
+    let view = insert_zone(&mut tx, id, room.id, body, weekday_mask).await?;
+    tx.commit().await?;
+    Ok((axum::http::StatusCode::CREATED, Json(view)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/rooms/{room_id}/zones/{id}",
+    params(
+        ("room_id" = String, Path, description = "Room id"),
+        ("id" = String, Path, description = "Client-generated UUID of the zone"),
+    ),
+    request_body = NewZone,
+    responses(
+        (status = 201, description = "Zone created under the given id", body = ZoneView),
+        (status = 200, description = "Existing zone replaced; fields left out are cleared", body = ZoneView),
+        (status = 404, description = "Room not found, or the zone was deleted"),
+        (status = 409, description = "The zone id is in another room, or the source/external_id belongs to another zone"),
+    )
+)]
+pub async fn put_zone(
+    State(state): State<std::sync::Arc<AppState>>,
+    LiveRoom(room): LiveRoom,
+    Path((_, id)): Path<(String, String)>,
+    Json(body): Json<NewZone>,
+) -> AppResult<(axum::http::StatusCode, Json<ZoneView>)> {
+    if Uuid::parse_str(&id).is_err() {
+        return Err(AppError::Validation("zone id must be a UUID".into()));
+    }
+    let weekday_mask = validate_new_zone(&body)?;
+    // проверки и запись в одной транзакции: зона заменяется целиком или никак
+    let mut tx = state.writer.begin().await?;
+    let existing: Option<(String, Option<chrono::DateTime<Utc>>)> =
+        sqlx::query_as("SELECT room_id, deleted_at FROM zones WHERE id = ?1")
+            .bind(&id)
+            .fetch_optional(&mut *tx)
+            .await?;
+    match &existing {
+        // удалённую зону повторная синхронизация не воскрешает
+        Some((_, Some(_))) => return Err(AppError::NotFound),
+        Some((zone_room, None)) if *zone_room != room.id => {
+            return Err(AppError::Conflict(format!("zone {id} belongs to another room")));
+        }
+        _ => {}
+    }
+    if let (Some(source), Some(external_id)) = (&body.source, &body.external_id) {
+        let (taken,): (i64,) = sqlx::query_as(
+            "SELECT COUNT(1) FROM zones WHERE source = ?1 AND external_id = ?2 AND id != ?3 AND deleted_at IS NULL",
+        )
+        .bind(source)
+        .bind(external_id)
+        .bind(&id)
+        .fetch_one(&mut *tx)
+        .await?;
+        if taken > 0 {
+            return Err(AppError::Conflict(format!("a zone with external_id {external_id} already exists")));
+        }
+    }
+    if existing.is_none() {
+        let view = insert_zone(&mut tx, id, room.id, body, weekday_mask).await?;
+        tx.commit().await?;
+        return Ok((axum::http::StatusCode::CREATED, Json(view)));
+    }
+    let external = (body.source.clone(), body.external_id.clone());
+    let z = apply_update(&mut tx, &id, body.into_replacement(), Some(external)).await?;
+    tx.commit().await?;
+    let tz = super::settings::timezone(&state.pool).await?;
+    Ok((axum::http::StatusCode::OK, Json(ZoneView::localized(z, tz))))
+}
+
+/// Most zones one bulk request may create.
+const MAX_BULK_ZONES: usize = 100;
+
+#[utoipa::path(
+    post,
+    path = "/rooms/{room_id}/zones/bulk",
+    params(("room_id" = String, Path, description = "Room id")),
+    request_body = [NewZone],
+    responses(
+        (status = 201, description = "Zones created, in request order", body = [ZoneView]),
+        (status = 400, description = "Some zone is invalid; none were created"),
+    )
+)]
+pub async fn create_zones(
+    State(state): State<std::sync::Arc<AppState>>,
+    LiveRoom(room): LiveRoom,
+    Json(body): Json<Vec<NewZone>>,
+) -> AppResult<(axum::http::StatusCode, Json<Vec<ZoneView>>)> {
+    if body.is_empty() || body.len() > MAX_BULK_ZONES {
+        return Err(AppError::Validation(format!("expected 1 to {MAX_BULK_ZONES} zones")));
+    }
+    let mut masks = Vec::with_capacity(body.len());
+    let mut external = std::collections::HashSet::new();
+    for (i, zone) in body.iter().enumerate() {
+        let mask = validate_new_zone(zone).map_err(|e| match e {
+            AppError::Validation(m) => AppError::Validation(format!("zones[{i}]: {m}")),
+            e => e,
+        })?;
+        if let Some(external_id) = &zone.external_id {
+            if !external.insert((zone.source.clone(), external_id.clone())) {
+                return Err(AppError::Validation(format!(
+                    "zones[{i}]: external_id {external_id} appears twice"
+                )));
+            }
+        }
+        masks.push(mask);
+    }
+
+    // всё или ничего: одна транзакция на весь список
+    let mut tx = state.writer.begin().await?;
+    let mut views = Vec::with_capacity(body.len());
+    for (i, (zone, mask)) in body.into_iter().zip(masks).enumerate() {
+        if let (Some(source), Some(external_id)) = (&zone.source, &zone.external_id) {
+            let (taken,): (i64,) = sqlx::query_as(
+                "SELECT COUNT(1) FROM zones WHERE source = ?1 AND external_id = ?2 AND deleted_at IS NULL",
+            )
+            .bind(source)
+            .bind(external_id)
+            .fetch_one(&mut *tx)
+            .await?;
+            if taken > 0 {
+                return Err(AppError::Validation(format!(
+                    "zones[{i}]: a zone with external_id {external_id} already exists"
+                )));
+            }
+        }
+        let id = Uuid::new_v4().to_string();
+        views.push(insert_zone(&mut tx, id, room.id.clone(), zone, mask).await?);
+    }
+    tx.commit().await?;
+    Ok((axum::http::StatusCode::CREATED, Json(views)))
+}
+
+/// Checks a new zone and returns its weekday mask.
+fn validate_new_zone(body: &NewZone) -> AppResult<Option<i64>> {
     if body.name.trim().is_empty() {
         return Err(AppError::Validation("name is required".into()));
     }
-    if matches!(body.frequency, crate::models::Frequency::Custom)
+    if matches!(body.frequency, Frequency::Custom)
         && body.custom_interval_days.unwrap_or(0) == 0
     {
         return Err(AppError::Validation(
             "custom_interval_days must be >= 1 for custom frequency".into(),
         ));
     }
-    // проверим, что комната существует и не удалена
-    let exists: (i64,) =
-        sqlx::query_as("SELECT COUNT(1) FROM rooms WHERE id = ?1 AND deleted_at IS NULL")
-            .bind(&room_id)
-            .fetch_one(&state.pool)
-            .await?;
-    if exists.0 == 0 {
-        return Err(AppError::NotFound);
-    }
+    let weekday_mask = resolve_weekdays(body.frequency, body.weekdays.as_deref())?;
+    validate_metadata(body.metadata.as_ref())?;
+    validate_external_ref(&body.source, &body.external_id)?;
+    Ok(weekday_mask)
+}
 
+async fn insert_zone(
+    conn: &mut sqlx::SqliteConnection,
+    id: String,
+    room_id: String,
+    body: NewZone,
+    weekday_mask: Option<i64>,
+) -> AppResult<ZoneView> {
     let now = Utc::now();
-    let id = Uuid::new_v4().to_string();
     let name = body.name;
     let icon = body.icon;
-    let frequency = body.frequency.as_str().to_string();
+    let notes = body.notes;
+    let metadata = body.metadata;
+    let frequency = body.frequency;
     let custom_interval_days = body.custom_interval_days.map(|v| v as i64);
+    let auto = body.auto.unwrap_or(false);
+    let estimated_minutes = body.estimated_minutes.filter(|&m| m > 0).map(i64::from);
     sqlx::query(
-        r#"INSERT INTO zones(id, room_id, name, icon, frequency, custom_interval_days, last_cleaned_at, created_at, updated_at, deleted_at)
-           VALUES (?1, ?2, ?3, ?4, ?5, ?6, NULL, ?7, ?7, NULL)"#,
+        r#"INSERT INTO zones(id, room_id, name, icon, notes, metadata, frequency, custom_interval_days, weekday_mask, auto, estimated_minutes, source, external_id, last_cleaned_at, created_at, updated_at, deleted_at)
+           VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, NULL, ?14, ?14, NULL)"#,
     )
     .bind(&id)
     .bind(&room_id)
     .bind(&name)
     .bind(&icon)
-    .bind(&frequency)
+    .bind(&notes)
+    .bind(metadata.as_ref().map(SqlJson))
+    .bind(frequency)
     .bind(custom_interval_days)
+    .bind(weekday_mask)
+    .bind(auto)
+    .bind(estimated_minutes)
+    .bind(&body.source)
+    .bind(&body.external_id)
     .bind(now)
-    .execute(&state.pool)
+    .execute(&mut *conn)
     .await?;
+    let created = ZoneChange::Created {
+        room_id: room_id.clone(),
+        name: name.clone(),
+        icon: icon.clone(),
+        notes: notes.clone(),
+        metadata: metadata.clone(),
+        frequency,
+        custom_interval_days,
+        weekdays: weekday_mask.map(mask_to_weekdays),
+        auto,
+        estimated_minutes,
+        source: body.source.clone(),
+        external_id: body.external_id.clone(),
+    };
+    events::record(&mut *conn, &id, &created, now).await?;
 
-    let next_due = None;
-    let is_due = true; // ещё не убиралось
-    let view = ZoneView {
+    // ещё не убиралось — сразу к уборке
+    let view = ZoneView::from(Zone {
         id,
         room_id,
         name,
         icon,
+        notes,
+        metadata: metadata.map(SqlJson),
         frequency,
         custom_interval_days,
+        weekday_mask,
+        auto,
+        estimated_minutes,
+        paused_until: None,
+        schedule_anchor_at: None,
         last_cleaned_at: None,
-        next_due_at: next_due,
-        is_due,
+        sort_order: 0,
+        source: body.source,
+        external_id: body.external_id,
         created_at: now,
         updated_at: now,
         deleted_at: None,
-    };
-    Ok((axum::http::StatusCode::CREATED, Json(view)))
+    });
+    Ok(view)
 }
 
 #[utoipa::path(
     get,
     path = "/zones/{id}",
-    params(("id" = String, Path, description = "Zone id")),
+    params(("id" = String, Path, description = "Zone id"), FieldsParams),
     responses((status = 200, description = "Zone details", body = ZoneView))
 )]
 pub async fn get_zone(
     State(state): State<std::sync::Arc<AppState>>,
-    Path(id): Path<String>,
-) -> AppResult<Json<ZoneView>> {
-    let z = sqlx::query_as::<_, Zone>(
-        r#"SELECT id, room_id, name, icon, frequency, custom_interval_days, last_cleaned_at, created_at, updated_at, deleted_at
-           FROM zones WHERE id = ?1 AND deleted_at IS NULL"#
-    ).bind(&id).fetch_optional(&state.pool).await?;
-    let z = z.ok_or(AppError::NotFound)?;
-    let next_due = compute_next_due(z.last_cleaned_at, &z.frequency, z.custom_interval_days);
-    let is_due = compute_is_due(next_due);
-    Ok(Json(ZoneView {
-        id: z.id,
-        room_id: z.room_id,
-        name: z.name,
-        icon: z.icon,
-        frequency: z.frequency,
-        custom_interval_days: z.custom_interval_days,
-        last_cleaned_at: z.last_cleaned_at,
-        next_due_at: next_due,
-        is_due,
-        created_at: z.created_at,
-        updated_at: z.updated_at,
-        deleted_at: z.deleted_at,
-    }))
+    LiveZone(z): LiveZone,
+    fields: Fields,
+) -> AppResult<Json<serde_json::Value>> {
+    let tz = super::settings::timezone(&state.pool).await?;
+    Ok(Json(fields.one(&ZoneView::localized(z, tz))?))
+}
+
+/// Re-reads a zone after a write, for handlers answering with its view.
+pub(super) async fn zone_view(state: &AppState, id: &str) -> AppResult<Json<ZoneView>> {
+    let z = sqlx::query_as::<_, Zone>(&format!(
+        "SELECT {ZONE_COLUMNS} FROM zones WHERE id = ?1 AND deleted_at IS NULL"
+    ))
+    .bind(id)
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+    let tz = super::settings::timezone(&state.pool).await?;
+    Ok(Json(ZoneView::localized(z, tz)))
 }
 
 #[utoipa::path(
@@ -175,147 +498,625 @@ pub async fn update_zone(
     Path(id): Path<String>,
     Json(body): Json<UpdateZone>,
 ) -> AppResult<Json<ZoneView>> {
-    let z = sqlx::query_as::<_, Zone>(
-        "SELECT id, room_id, name, icon, frequency, custom_interval_days, last_cleaned_at, created_at, updated_at, deleted_at FROM zones WHERE id = ?1 AND deleted_at IS NULL"
-    ).bind(&id).fetch_optional(&state.pool).await?;
+    let mut tx = state.writer.begin().await?;
+    let z = apply_update(&mut tx, &id, body, None).await?;
+    tx.commit().await?;
+    let tz = super::settings::timezone(&state.pool).await?;
+    Ok(Json(ZoneView::localized(z, tz)))
+}
+
+/// Applies `body` to the live zone `id` and records the changes as events.
+/// `external`, when given, also replaces the zone's `source`/`external_id`.
+async fn apply_update(
+    conn: &mut sqlx::SqliteConnection,
+    id: &str,
+    body: UpdateZone,
+    external: Option<(Option<String>, Option<String>)>,
+) -> AppResult<Zone> {
+    let z = sqlx::query_as::<_, Zone>(&format!(
+        "SELECT {ZONE_COLUMNS} FROM zones WHERE id = ?1 AND deleted_at IS NULL"
+    ))
+    .bind(id)
+    .fetch_optional(&mut *conn)
+    .await?;
     let mut z = z.ok_or(AppError::NotFound)?;
 
     let now = Utc::now();
     let name = body.name.unwrap_or(z.name.clone());
-    let icon = body.icon.or(z.icon.clone());
-    let frequency = body
-        .frequency
-        .map(|f| f.as_str().to_string())
-        .unwrap_or(z.frequency.clone());
+    let icon = body.icon.apply(z.icon.clone());
+    let notes = body.notes.apply(z.notes.clone());
+    if let MaybeAbsent::Value(metadata) = &body.metadata {
+        validate_metadata(Some(metadata))?;
+    }
+    let metadata = body.metadata.apply(z.metadata.clone().map(|m| m.0));
+    let frequency = body.frequency.unwrap_or(z.frequency);
     let custom_interval_days = body
         .custom_interval_days
-        .map(|v| v as i64)
-        .or(z.custom_interval_days);
+        .map(i64::from)
+        .apply(z.custom_interval_days);
+    let auto = body.auto.unwrap_or(z.auto);
+    let estimated_minutes = body
+        .estimated_minutes
+        .map(i64::from)
+        .apply(z.estimated_minutes)
+        .filter(|&m| m > 0);
 
-    if frequency == "custom" && custom_interval_days.unwrap_or(0) <= 0 {
+    if frequency == Frequency::Custom && custom_interval_days.unwrap_or(0) <= 0 {
         return Err(AppError::Validation(
             "custom_interval_days must be >= 1".into(),
         ));
     }
+    let weekdays = body.weekdays.or(z.weekday_mask.map(mask_to_weekdays));
+    let weekday_mask = resolve_weekdays(frequency, weekdays.as_deref())?;
+
+    let schedule_changed = frequency != z.frequency
+        || custom_interval_days != z.custom_interval_days
+        || weekday_mask != z.weekday_mask;
+    let schedule_anchor_at = match (schedule_changed, body.reschedule.unwrap_or_default()) {
+        (false, _) => z.schedule_anchor_at,
+        (true, Reschedule::LastClean) => None,
+        (true, Reschedule::Now) => Some(now),
+    };
+
+    let mut changes = Vec::new();
+    if name != z.name {
+        changes.push(ZoneChange::Renamed { name: name.clone() });
+    }
+    if icon != z.icon {
+        changes.push(ZoneChange::IconChanged { icon: icon.clone() });
+    }
+    if notes != z.notes {
+        changes.push(ZoneChange::NotesChanged { notes: notes.clone() });
+    }
+    if metadata != z.metadata.as_ref().map(|m| m.0.clone()) {
+        changes.push(ZoneChange::MetadataChanged { metadata: metadata.clone() });
+    }
+    if schedule_changed {
+        changes.push(ZoneChange::FrequencyChanged {
+            frequency,
+            custom_interval_days,
+            weekdays: weekday_mask.map(mask_to_weekdays),
+            previous_frequency: Some(z.frequency),
+            previous_custom_interval_days: z.custom_interval_days,
+            previous_weekdays: z.weekday_mask.map(mask_to_weekdays),
+            anchor_at: schedule_anchor_at,
+        });
+    }
+    if auto != z.auto {
+        changes.push(ZoneChange::AutoChanged { auto });
+    }
+    if estimated_minutes != z.estimated_minutes {
+        changes.push(ZoneChange::EstimateChanged { estimated_minutes });
+    }
+    let (source, external_id) = external.unwrap_or((z.source.clone(), z.external_id.clone()));
+    if (&source, &external_id) != (&z.source, &z.external_id) {
+        changes.push(ZoneChange::ExternalRefChanged { source: source.clone(), external_id: external_id.clone() });
+    }
 
     sqlx::query(
-        "UPDATE zones SET name = ?1, icon = ?2, frequency = ?3, custom_interval_days = ?4, updated_at = ?5 WHERE id = ?6",
+        "UPDATE zones SET name = ?1, icon = ?2, notes = ?3, metadata = ?4, frequency = ?5, custom_interval_days = ?6, weekday_mask = ?7, auto = ?8, estimated_minutes = ?9, schedule_anchor_at = ?10, updated_at = ?11, source = ?13, external_id = ?14 WHERE id = ?12",
     )
     .bind(&name)
     .bind(&icon)
-    .bind(&frequency)
+    .bind(&notes)
+    .bind(metadata.as_ref().map(SqlJson))
+    .bind(frequency)
     .bind(custom_interval_days)
+    .bind(weekday_mask)
+    .bind(auto)
+    .bind(estimated_minutes)
+    .bind(schedule_anchor_at)
     .bind(now)
-    .bind(&id)
-    .execute(&state.pool)
+    .bind(id)
+    .bind(&source)
+    .bind(&external_id)
+    .execute(&mut *conn)
     .await?;
+    for change in &changes {
+        events::record(&mut *conn, id, change, now).await?;
+    }
+    sync_next_due(&mut *conn, Some(id)).await?;
 
     z.name = name.clone();
     z.icon = icon.clone();
-    z.frequency = frequency.clone();
+    z.notes = notes.clone();
+    z.metadata = metadata.map(SqlJson);
+    z.frequency = frequency;
     z.custom_interval_days = custom_interval_days;
+    z.weekday_mask = weekday_mask;
+    z.auto = auto;
+    z.estimated_minutes = estimated_minutes;
+    z.schedule_anchor_at = schedule_anchor_at;
+    z.source = source;
+    z.external_id = external_id;
     z.updated_at = now;
-    let next_due = compute_next_due(z.last_cleaned_at, &z.frequency, z.custom_interval_days);
-    let is_due = compute_is_due(next_due);
-    Ok(Json(ZoneView {
-        id: z.id,
-        room_id: z.room_id,
-        name: z.name,
-        icon: z.icon,
-        frequency: z.frequency,
-        custom_interval_days: z.custom_interval_days,
-        last_cleaned_at: z.last_cleaned_at,
-        next_due_at: next_due,
-        is_due,
-        created_at: z.created_at,
-        updated_at: z.updated_at,
-        deleted_at: z.deleted_at,
-    }))
+    Ok(z)
 }
 
 #[utoipa::path(
     delete,
     path = "/zones/{id}",
     params(("id" = String, Path, description = "Zone id")),
-    responses((status = 204, description = "Zone deleted"))
+    responses((status = 204, description = "Zone deleted", headers(("x-undo-token" = String, description = "For `POST /undo/{token}` within 30 seconds"))))
 )]
 pub async fn delete_zone(
     State(state): State<std::sync::Arc<AppState>>,
     Path(id): Path<String>,
-) -> AppResult<axum::http::StatusCode> {
+) -> AppResult<Deleted> {
     let now = Utc::now();
+    let mut tx = state.writer.begin().await?;
     let res = sqlx::query(
         "UPDATE zones SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
     )
     .bind(now)
     .bind(&id)
-    .execute(&state.pool)
+    .execute(&mut *tx)
     .await?;
     if res.rows_affected() == 0 {
         return Err(AppError::NotFound);
     }
-    Ok(axum::http::StatusCode::NO_CONTENT)
+    events::record(&mut *tx, &id, &ZoneChange::Deleted, now).await?;
+    let deleted = undo::issue(&mut tx, Undoable::Zone, &id, now).await?;
+    tx.commit().await?;
+    Ok(deleted)
+}
+
+#[utoipa::path(
+    put,
+    path = "/zones/{id}/pause",
+    params(("id" = String, Path, description = "Zone id")),
+    request_body = PauseZone,
+    responses((status = 200, description = "Zone paused", body = ZoneView))
+)]
+pub async fn pause_zone(
+    State(state): State<std::sync::Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(body): Json<PauseZone>,
+) -> AppResult<Json<ZoneView>> {
+    if body.until <= Utc::now() {
+        return Err(AppError::Validation("until must be in the future".into()));
+    }
+    set_paused_until(&state, &id, Some(body.until)).await?;
+    zone_view(&state, &id).await
+}
+
+#[utoipa::path(
+    delete,
+    path = "/zones/{id}/pause",
+    params(("id" = String, Path, description = "Zone id")),
+    responses((status = 200, description = "Zone resumed", body = ZoneView))
+)]
+pub async fn resume_zone(
+    State(state): State<std::sync::Arc<AppState>>,
+    Path(id): Path<String>,
+) -> AppResult<Json<ZoneView>> {
+    set_paused_until(&state, &id, None).await?;
+    zone_view(&state, &id).await
+}
+
+async fn set_paused_until(
+    state: &AppState,
+    id: &str,
+    until: Option<chrono::DateTime<chrono::Utc>>,
+) -> AppResult<()> {
+    let now = Utc::now();
+    let mut tx = state.writer.begin().await?;
+    let res = sqlx::query("UPDATE zones SET paused_until = ?1, updated_at = ?2 WHERE id = ?3 AND deleted_at IS NULL")
+        .bind(until)
+        .bind(now)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    if res.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    events::record(&mut *tx, id, &ZoneChange::Paused { until }, now).await?;
+    sync_next_due(&mut tx, Some(id)).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Recomputes the stored `next_due_at`, which SQL filters and sorts by, from the
+/// schedule, pause and configured timezone. `None` refreshes every zone.
+pub async fn sync_next_due(conn: &mut sqlx::SqliteConnection, id: Option<&str>) -> AppResult<()> {
+    let tz = super::settings::timezone(&mut *conn).await?;
+    let zones = sqlx::query_as::<_, Zone>(&format!(
+        "SELECT {ZONE_COLUMNS} FROM zones WHERE (?1 IS NULL OR id = ?1) AND deleted_at IS NULL"
+    ))
+    .bind(id)
+    .fetch_all(&mut *conn)
+    .await?;
+    for z in zones {
+        let id = z.id.clone();
+        let next_due = ZoneView::localized(z, tz).next_due_at;
+        sqlx::query("UPDATE zones SET next_due_at = ?1 WHERE id = ?2")
+            .bind(next_due)
+            .bind(&id)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Backdated cleans are kept in history but never move `last_cleaned_at` backwards,
+/// matching how `events::fold` merges them.
+const MARK_CLEANED_SQL: &str = r#"UPDATE zones
+    SET last_cleaned_at = CASE WHEN last_cleaned_at IS NULL OR last_cleaned_at < ?1 THEN ?1 ELSE last_cleaned_at END,
+        updated_at = ?1,
+        due_since = NULL
+    WHERE id = ?2 AND deleted_at IS NULL"#;
+
+/// Marks the zone cleaned, records `cleaned` (a `ZoneChange::Cleaned`), resets
+/// its checklist, uses up its linked supplies and queues `zone.cleaned` webhooks.
+/// Returns `false` when the zone does not exist.
+pub(crate) async fn mark_cleaned(
+    conn: &mut sqlx::SqliteConnection,
+    id: &str,
+    cleaned_at: chrono::DateTime<chrono::Utc>,
+    cleaned: &ZoneChange,
+) -> AppResult<bool> {
+    let res = sqlx::query(MARK_CLEANED_SQL)
+        .bind(cleaned_at)
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
+    if res.rows_affected() == 0 {
+        return Ok(false);
+    }
+    events::record(&mut *conn, id, cleaned, cleaned_at).await?;
+    sync_next_due(&mut *conn, Some(id)).await?;
+    // чек-лист начинается заново со следующей уборки
+    sqlx::query("UPDATE zone_tasks SET checked_at = NULL WHERE zone_id = ?1 AND checked_at IS NOT NULL")
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
+    supplies::consume_for_clean(&mut *conn, id).await?;
+    let data = serde_json::json!({ "zone_id": id, "change": cleaned });
+    webhooks::enqueue(&mut *conn, webhooks::ZONE_CLEANED, data, cleaned_at).await?;
+    Ok(true)
 }
 
 #[derive(Deserialize, ToSchema)]
 pub struct CleanBody {
     pub cleaned_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Reject the clean while any required checklist task is unchecked.
+    pub require_tasks: Option<bool>,
+    /// Stored with this cleaning in the zone history.
+    pub note: Option<String>,
+    /// What the cleaning cost, e.g. hired help, in cents.
+    pub cost_cents: Option<i64>,
+    /// Time the cleaning took, 1 to 1440 minutes.
+    pub duration_minutes: Option<u16>,
 }
 
 #[utoipa::path(
     post,
     path = "/zones/{id}/clean",
-    params(("id" = String, Path, description = "Zone id")),
+    params(
+        ("id" = String, Path, description = "Zone id"),
+        ("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key get the first response"),
+    ),
     request_body = CleanBody,
-    responses((status = 200, description = "Zone cleaned", body = ZoneView))
+    responses(
+        (status = 200, description = "Zone cleaned", body = ZoneView),
+        (status = 409, description = "Idempotency-Key reused for another request or still in progress"),
+    )
 )]
 pub async fn clean_zone(
+    State(state): State<std::sync::Arc<AppState>>,
+    id: Path<String>,
+    Keyed(key, body): Keyed<Json<CleanBody>>,
+) -> AppResult<Response> {
+    key.run(&state, clean(State(state.clone()), id, body)).await
+}
+
+pub(super) async fn clean(
     State(state): State<std::sync::Arc<AppState>>,
     Path(id): Path<String>,
     Json(body): Json<CleanBody>,
 ) -> AppResult<Json<ZoneView>> {
     let cleaned_at = body.cleaned_at.unwrap_or_else(chrono::Utc::now);
-    let res = sqlx::query("UPDATE zones SET last_cleaned_at = ?1, updated_at = ?1 WHERE id = ?2 AND deleted_at IS NULL")
-        .bind(cleaned_at)
+    if body.cost_cents.is_some_and(|c| c < 0) {
+        return Err(AppError::Validation("cost_cents must not be negative".into()));
+    }
+    if body.duration_minutes.is_some_and(|m| !(1..=1440).contains(&m)) {
+        return Err(AppError::Validation("duration_minutes must be between 1 and 1440".into()));
+    }
+    let mut tx = state.writer.begin().await?;
+    if body.require_tasks.unwrap_or(false) {
+        let (pending,): (i64,) = sqlx::query_as(
+            r#"SELECT COUNT(1) FROM zone_tasks
+               WHERE zone_id = ?1 AND deleted_at IS NULL AND required = 1 AND checked_at IS NULL"#,
+        )
         .bind(&id)
-        .execute(&state.pool)
+        .fetch_one(&mut *tx)
         .await?;
-    if res.rows_affected() == 0 {
+        if pending > 0 {
+            return Err(AppError::Validation(format!(
+                "{pending} required task(s) not checked"
+            )));
+        }
+    }
+    let cleaned = ZoneChange::Cleaned {
+        note: body.note,
+        auto: false,
+        cost_cents: body.cost_cents,
+        duration_minutes: body.duration_minutes.map(i64::from),
+    };
+    if !mark_cleaned(&mut tx, &id, cleaned_at, &cleaned).await? {
         return Err(AppError::NotFound);
     }
-    get_zone(State(state), Path(id)).await
+    tx.commit().await?;
+    zone_view(&state, &id).await
 }
 
-#[derive(Deserialize, ToSchema)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct BulkClean {
     pub zone_ids: Vec<String>,
     pub cleaned_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
+/// Outcome of a bulk request on zones.
 #[derive(Serialize, ToSchema)]
-pub struct BulkCleanResponse {
+pub struct BulkResponse {
+    /// Zones the request changed.
     pub updated: u64,
+    /// One entry per requested id, in request order.
+    pub results: Vec<BulkItem>,
+}
+
+impl From<Vec<BulkItem>> for BulkResponse {
+    fn from(results: Vec<BulkItem>) -> Self {
+        let updated = results.iter().filter(|r| r.status == BulkStatus::Ok).count() as u64;
+        BulkResponse { updated, results }
+    }
+}
+
+/// Cleans each of `ids` through `conn`; one result per id, in order.
+pub(crate) async fn clean_many(
+    conn: &mut sqlx::SqliteConnection,
+    ids: &[String],
+    cleaned_at: chrono::DateTime<chrono::Utc>,
+) -> AppResult<Vec<BulkItem>> {
+    let cleaned = ZoneChange::Cleaned { note: None, auto: false, cost_cents: None, duration_minutes: None };
+    let mut results = Vec::with_capacity(ids.len());
+    for id in ids {
+        if mark_cleaned(&mut *conn, id, cleaned_at, &cleaned).await? {
+            results.push(BulkItem::ok(id));
+        } else {
+            results.push(BulkItem::failed(id, &AppError::NotFound));
+        }
+    }
+    Ok(results)
 }
 
 #[utoipa::path(
     post,
     path = "/zones/bulk/clean",
     request_body = BulkClean,
-    responses((status = 200, description = "Bulk clean result", body = BulkCleanResponse))
+    responses((status = 200, description = "Bulk clean result", body = BulkResponse))
 )]
 pub async fn bulk_clean(
     State(state): State<std::sync::Arc<AppState>>,
     Json(body): Json<BulkClean>,
-) -> AppResult<Json<BulkCleanResponse>> {
+) -> AppResult<Json<BulkResponse>> {
     let cleaned_at = body.cleaned_at.unwrap_or_else(chrono::Utc::now);
-    let mut updated = 0u64;
-    for id in body.zone_ids.iter() {
-        let res = sqlx::query("UPDATE zones SET last_cleaned_at = ?1, updated_at = ?1 WHERE id = ?2 AND deleted_at IS NULL")
-            .bind(cleaned_at)
-            .bind(id)
-            .execute(&state.pool)
-            .await?;
-        updated += res.rows_affected();
+    let mut tx = state.writer.begin().await?;
+    let results = clean_many(&mut tx, &body.zone_ids, cleaned_at).await?;
+    tx.commit().await?;
+    Ok(Json(results.into()))
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct BulkDelete {
+    pub zone_ids: Vec<String>,
+}
+
+/// Soft-deletes the live zones among `ids` through `conn`.
+pub(crate) async fn delete_many(
+    conn: &mut sqlx::SqliteConnection,
+    ids: &[String],
+    now: chrono::DateTime<chrono::Utc>,
+) -> AppResult<Vec<BulkItem>> {
+    // проверка и удаление одним запросом: RETURNING отдаёт только живые зоны из списка
+    let deleted: std::collections::HashSet<String> = sqlx::query_as::<_, (String,)>(
+        r#"UPDATE zones SET deleted_at = ?1
+           WHERE id IN (SELECT value FROM json_each(?2)) AND deleted_at IS NULL
+           RETURNING id"#,
+    )
+    .bind(now)
+    .bind(SqlJson(ids))
+    .fetch_all(&mut *conn)
+    .await?
+    .into_iter()
+    .map(|(id,)| id)
+    .collect();
+    for id in &deleted {
+        events::record(&mut *conn, id, &ZoneChange::Deleted, now).await?;
+    }
+    Ok(ids
+        .iter()
+        .map(|id| match deleted.contains(id) {
+            true => BulkItem::ok(id),
+            false => BulkItem::failed(id, &AppError::NotFound),
+        })
+        .collect())
+}
+
+#[utoipa::path(
+    post,
+    path = "/zones/bulk/delete",
+    request_body = BulkDelete,
+    responses((status = 200, description = "Bulk delete result", body = BulkResponse))
+)]
+pub async fn bulk_delete(
+    State(state): State<std::sync::Arc<AppState>>,
+    Json(body): Json<BulkDelete>,
+) -> AppResult<Json<BulkResponse>> {
+    let mut tx = state.writer.begin().await?;
+    let results = delete_many(&mut tx, &body.zone_ids, Utc::now()).await?;
+    tx.commit().await?;
+    Ok(Json(results.into()))
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct BulkUpdate {
+    pub zone_ids: Vec<String>,
+    /// Applied to every zone, as with `PATCH /zones/{id}`.
+    pub changes: UpdateZone,
+}
+
+/// Applies `changes` to each of `ids`, every zone in its own transaction, so
+/// one invalid zone (e.g. custom without an interval) does not undo the rest.
+pub(crate) async fn update_many(
+    state: &std::sync::Arc<AppState>,
+    ids: &[String],
+    changes: &UpdateZone,
+) -> AppResult<Vec<BulkItem>> {
+    let live: std::collections::HashSet<String> = sqlx::query_as::<_, (String,)>(
+        r#"SELECT value FROM json_each(?1)
+           WHERE value IN (SELECT id FROM zones WHERE deleted_at IS NULL)"#,
+    )
+    .bind(SqlJson(ids))
+    .fetch_all(&state.pool)
+    .await?
+    .into_iter()
+    .map(|(id,)| id)
+    .collect();
+
+    let mut results = Vec::with_capacity(ids.len());
+    for id in ids {
+        if !live.contains(id) {
+            results.push(BulkItem::failed(id, &AppError::NotFound));
+            continue;
+        }
+        match update_zone(State(state.clone()), Path(id.clone()), Json(changes.clone())).await {
+            Ok(_) => results.push(BulkItem::ok(id)),
+            Err(e @ (AppError::NotFound | AppError::Validation(_))) => results.push(BulkItem::failed(id, &e)),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(results)
+}
+
+#[utoipa::path(
+    post,
+    path = "/zones/bulk/update",
+    request_body = BulkUpdate,
+    responses((status = 200, description = "Bulk update result", body = BulkResponse))
+)]
+pub async fn bulk_update(
+    State(state): State<std::sync::Arc<AppState>>,
+    Json(body): Json<BulkUpdate>,
+) -> AppResult<Json<BulkResponse>> {
+    Ok(Json(update_many(&state, &body.zone_ids, &body.changes).await?.into()))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct AutoCleanTrigger {
+    /// Integration and its id for the zone, as given on create.
+    pub source: String,
+    pub external_id: String,
+    pub cleaned_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/zones/auto/clean",
+    request_body = AutoCleanTrigger,
+    responses((status = 200, description = "Auto zone cleaned", body = ZoneView))
+)]
+pub async fn trigger_auto_clean(
+    State(state): State<std::sync::Arc<AppState>>,
+    Json(body): Json<AutoCleanTrigger>,
+) -> AppResult<Json<ZoneView>> {
+    let z = sqlx::query_as::<_, Zone>(&format!(
+        "SELECT {ZONE_COLUMNS} FROM zones WHERE source = ?1 AND external_id = ?2 AND deleted_at IS NULL"
+    ))
+    .bind(&body.source)
+    .bind(&body.external_id)
+    .fetch_optional(&state.pool)
+    .await?;
+    let z = z.ok_or(AppError::NotFound)?;
+    if !z.auto {
+        return Err(AppError::Validation(format!("zone {} is not an auto zone", z.id)));
+    }
+    let cleaned_at = body.cleaned_at.unwrap_or_else(chrono::Utc::now);
+    let mut tx = state.writer.begin().await?;
+    let cleaned = ZoneChange::Cleaned { note: None, auto: true, cost_cents: None, duration_minutes: None };
+    mark_cleaned(&mut tx, &z.id, cleaned_at, &cleaned).await?;
+    tx.commit().await?;
+    zone_view(&state, &z.id).await
+}
+
+/// Marks every auto zone whose schedule has come round as cleaned now.
+/// Returns how many zones were cleaned.
+pub async fn run_auto_clean(state: &AppState) -> AppResult<u64> {
+    let zones = sqlx::query_as::<_, Zone>(&format!(
+        "SELECT {ZONE_COLUMNS} FROM zones WHERE auto = 1 AND deleted_at IS NULL"
+    ))
+    .fetch_all(&state.pool)
+    .await?;
+    let tz = super::settings::timezone(&state.pool).await?;
+    let now = chrono::Utc::now();
+    let change = ZoneChange::Cleaned { note: None, auto: true, cost_cents: None, duration_minutes: None };
+    let mut cleaned = 0u64;
+    let mut tx = state.writer.begin().await?;
+    for z in zones.into_iter().filter(|z| ZoneView::localized(z.clone(), tz).is_due) {
+        if mark_cleaned(&mut tx, &z.id, now, &change).await? {
+            cleaned += 1;
+        }
+    }
+    tx.commit().await?;
+    Ok(cleaned)
+}
+
+#[utoipa::path(
+    get,
+    path = "/zones/{id}/events",
+    params(("id" = String, Path, description = "Zone id")),
+    responses((status = 200, description = "Zone history in replay order", body = [ZoneEvent]))
+)]
+pub async fn list_events(
+    State(state): State<std::sync::Arc<AppState>>,
+    Path(id): Path<String>,
+) -> AppResult<Json<Vec<ZoneEvent>>> {
+    let out = events::list(&state.pool, &id).await?;
+    if out.is_empty() {
+        return Err(AppError::NotFound);
+    }
+    Ok(Json(out))
+}
+
+#[utoipa::path(
+    post,
+    path = "/rooms/{room_id}/zones/reorder",
+    params(("room_id" = String, Path, description = "Room id")),
+    request_body = Reorder,
+    responses((status = 200, description = "Zones in the new order", body = [ZoneView]))
+)]
+pub async fn reorder_zones(
+    State(state): State<std::sync::Arc<AppState>>,
+    Path(room_id): Path<String>,
+    Json(body): Json<Reorder>,
+) -> AppResult<Json<Vec<ZoneView>>> {
+    body.validate()?;
+    let mut tx = state.writer.begin().await?;
+    for (pos, id) in body.ids.iter().enumerate() {
+        let res = sqlx::query(
+            "UPDATE zones SET sort_order = ?1 WHERE id = ?2 AND room_id = ?3 AND deleted_at IS NULL",
+        )
+        .bind(pos as i64 + 1)
+        .bind(id)
+        .bind(&room_id)
+        .execute(&mut *tx)
+        .await?;
+        if res.rows_affected() == 0 {
+            return Err(AppError::Validation(format!("unknown zone id {id}")));
+        }
     }
-    Ok(Json(BulkCleanResponse { updated }))
+    tx.commit().await?;
+    let p = ListZones { room_id: Some(room_id), ..ListZones::default() };
+    let page = query_zones(&state, None, &p, &PageParams::default()).await?;
+    Ok(Json(page.items))
 }
@@ -0,0 +1,196 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::error::{AppError, AppResult};
+
+/// Key/value cache shared by stats, rate limiting and token lookups.
+/// In-memory by default; Redis when `CACHE_URL` is set and the `redis` feature is enabled,
+/// so several instances see the same entries.
+pub enum Cache {
+    Memory(MemoryCache),
+    #[cfg(feature = "redis")]
+    Redis(RedisCache),
+}
+
+impl Cache {
+    pub async fn connect(url: Option<&str>) -> AppResult<Self> {
+        match url {
+            None => Ok(Cache::Memory(MemoryCache::default())),
+            #[cfg(feature = "redis")]
+            Some(url) => Ok(Cache::Redis(RedisCache::connect(url).await?)),
+            #[cfg(not(feature = "redis"))]
+            Some(_) => Err(AppError::Other(anyhow::anyhow!(
+                "CACHE_URL is set but cleaner-api was built without the `redis` feature"
+            ))),
+        }
+    }
+
+    pub async fn get(&self, key: &str) -> AppResult<Option<String>> {
+        match self {
+            Cache::Memory(c) => Ok(c.get(key)),
+            #[cfg(feature = "redis")]
+            Cache::Redis(c) => c.get(key).await,
+        }
+    }
+
+    pub async fn set(&self, key: &str, value: String, ttl: Duration) -> AppResult<()> {
+        match self {
+            Cache::Memory(c) => {
+                c.set(key, value, ttl);
+                Ok(())
+            }
+            #[cfg(feature = "redis")]
+            Cache::Redis(c) => c.set(key, value, ttl).await,
+        }
+    }
+
+    pub async fn delete(&self, key: &str) -> AppResult<()> {
+        match self {
+            Cache::Memory(c) => {
+                c.delete(key);
+                Ok(())
+            }
+            #[cfg(feature = "redis")]
+            Cache::Redis(c) => c.delete(key).await,
+        }
+    }
+
+    /// Increments a counter; the TTL starts when the counter is created (fixed window).
+    pub async fn incr(&self, key: &str, ttl: Duration) -> AppResult<i64> {
+        match self {
+            Cache::Memory(c) => Ok(c.incr(key, ttl)),
+            #[cfg(feature = "redis")]
+            Cache::Redis(c) => c.incr(key, ttl).await,
+        }
+    }
+
+    pub async fn get_json<T: DeserializeOwned>(&self, key: &str) -> AppResult<Option<T>> {
+        Ok(self
+            .get(key)
+            .await?
+            .and_then(|raw| serde_json::from_str(&raw).ok()))
+    }
+
+    pub async fn set_json<T: Serialize>(&self, key: &str, value: &T, ttl: Duration) -> AppResult<()> {
+        let raw = serde_json::to_string(value).map_err(|e| AppError::Other(e.into()))?;
+        self.set(key, raw, ttl).await
+    }
+}
+
+#[derive(Default)]
+pub struct MemoryCache {
+    entries: Mutex<HashMap<String, (String, Instant)>>,
+}
+
+impl MemoryCache {
+    const PURGE_THRESHOLD: usize = 10_000;
+
+    fn get(&self, key: &str) -> Option<String> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(key) {
+            Some((value, expires)) if *expires > Instant::now() => Some(value.clone()),
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn set(&self, key: &str, value: String, ttl: Duration) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= Self::PURGE_THRESHOLD {
+            let now = Instant::now();
+            entries.retain(|_, (_, expires)| *expires > now);
+        }
+        entries.insert(key.to_string(), (value, Instant::now() + ttl));
+    }
+
+    fn delete(&self, key: &str) {
+        self.entries.lock().unwrap().remove(key);
+    }
+
+    fn incr(&self, key: &str, ttl: Duration) -> i64 {
+        let mut entries = self.entries.lock().unwrap();
+        let now = Instant::now();
+        let entry = entries
+            .entry(key.to_string())
+            .and_modify(|e| {
+                if e.1 <= now {
+                    *e = ("0".into(), now + ttl);
+                }
+            })
+            .or_insert_with(|| ("0".into(), now + ttl));
+        let next = entry.0.parse::<i64>().unwrap_or(0) + 1;
+        entry.0 = next.to_string();
+        next
+    }
+}
+
+#[cfg(feature = "redis")]
+pub struct RedisCache {
+    conn: redis::aio::ConnectionManager,
+}
+
+#[cfg(feature = "redis")]
+impl RedisCache {
+    async fn connect(url: &str) -> AppResult<Self> {
+        let client = redis::Client::open(url).map_err(|e| AppError::Other(e.into()))?;
+        let conn = redis::aio::ConnectionManager::new(client)
+            .await
+            .map_err(|e| AppError::Other(e.into()))?;
+        Ok(Self { conn })
+    }
+
+    async fn get(&self, key: &str) -> AppResult<Option<String>> {
+        let mut conn = self.conn.clone();
+        redis::cmd("GET")
+            .arg(key)
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| AppError::Other(e.into()))
+    }
+
+    async fn set(&self, key: &str, value: String, ttl: Duration) -> AppResult<()> {
+        let mut conn = self.conn.clone();
+        redis::cmd("SET")
+            .arg(key)
+            .arg(value)
+            .arg("PX")
+            .arg(ttl.as_millis() as u64)
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| AppError::Other(e.into()))
+    }
+
+    async fn delete(&self, key: &str) -> AppResult<()> {
+        let mut conn = self.conn.clone();
+        redis::cmd("DEL")
+            .arg(key)
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| AppError::Other(e.into()))
+    }
+
+    async fn incr(&self, key: &str, ttl: Duration) -> AppResult<i64> {
+        let mut conn = self.conn.clone();
+        let (count,): (i64,) = redis::pipe()
+            .atomic()
+            .cmd("INCR")
+            .arg(key)
+            .cmd("PEXPIRE")
+            .arg(key)
+            .arg(ttl.as_millis() as u64)
+            .arg("NX")
+            .ignore()
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| AppError::Other(e.into()))?;
+        Ok(count)
+    }
+}
@@ -0,0 +1,359 @@
+use std::{env, net::IpAddr, path::Path, str::FromStr, time::Duration};
+
+use axum::http::HeaderValue;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{crypto::Key, google_calendar::GoogleConfig, outbound::OutboundConfig};
+
+/// Read when `CONFIG_FILE` is unset and the file exists.
+pub const DEFAULT_FILE: &str = "cleaner.toml";
+
+/// `APP_ENV`: production refuses to start on anything `doctor` would flag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Environment {
+    #[default]
+    Development,
+    Production,
+}
+
+impl Environment {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Environment::Development => "development",
+            Environment::Production => "production",
+        }
+    }
+}
+
+impl FromStr for Environment {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s.to_ascii_lowercase().as_str() {
+            "development" | "dev" => Ok(Environment::Development),
+            "production" | "prod" => Ok(Environment::Production),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub env: Environment,
+    /// Address to listen on; `0.0.0.0` inside containers.
+    pub host: IpAddr,
+    pub port: u16,
+    /// Second port for the gRPC server; no gRPC when unset.
+    pub grpc_port: Option<u16>,
+    pub database_url: String,
+    /// Read connections; writes always go through a single extra connection.
+    pub db_max_connections: u32,
+    /// `redis://` URL of a shared cache; in-process memory when unset.
+    pub cache_url: Option<String>,
+    pub stats_cache_ttl: Duration,
+    /// How often due auto zones are marked cleaned.
+    pub auto_clean_interval: Duration,
+    /// How often zones are checked for becoming due.
+    pub due_scan_interval: Duration,
+    /// How often queued webhook deliveries are sent.
+    pub webhook_interval: Duration,
+    /// How often the `/metrics` gauges are recomputed.
+    pub metrics_interval: Duration,
+    /// How often unfinished bulk operations are resumed.
+    pub operations_interval: Duration,
+    /// Browser origins allowed to call the API, e.g. `https://app.example.com`;
+    /// no CORS headers at all when empty.
+    pub cors_allowed_origins: Vec<String>,
+    /// Seals secrets stored in the database; stored as given when unset.
+    pub encryption_key: Option<Key>,
+    /// Earlier keys, still accepted for values sealed before a rotation.
+    pub encryption_old_keys: Vec<Key>,
+    pub outbound: OutboundConfig,
+    pub google: GoogleConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            env: Environment::Development,
+            host: IpAddr::from([127, 0, 0, 1]),
+            port: 8080,
+            grpc_port: None,
+            // по умолчанию локальный файл
+            database_url: "sqlite://./cleaner.db".to_string(),
+            db_max_connections: 5,
+            cache_url: None,
+            stats_cache_ttl: Duration::from_secs(5),
+            auto_clean_interval: Duration::from_secs(300),
+            due_scan_interval: Duration::from_secs(60),
+            webhook_interval: Duration::from_secs(10),
+            metrics_interval: Duration::from_secs(60),
+            operations_interval: Duration::from_secs(30),
+            cors_allowed_origins: Vec::new(),
+            encryption_key: None,
+            encryption_old_keys: Vec::new(),
+            outbound: OutboundConfig::default(),
+            google: GoogleConfig::default(),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("cannot read {path}: {source}")]
+    Read { path: String, source: std::io::Error },
+    #[error("{path}: {source}")]
+    Parse { path: String, source: toml::de::Error },
+    #[error("{key}: cannot parse {value:?}")]
+    Value { key: String, value: String },
+    #[error("{0}")]
+    Invalid(String),
+}
+
+/// Contents of the TOML config file. Keys are the environment variable names in
+/// lower case, `APP_` dropped; outbound settings go under `[outbound]`, Google
+/// Calendar ones under `[google]`.
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct FileConfig {
+    env: Option<String>,
+    host: Option<String>,
+    port: Option<u16>,
+    grpc_port: Option<u16>,
+    database_url: Option<String>,
+    db_max_connections: Option<u32>,
+    cache_url: Option<String>,
+    stats_cache_ttl_secs: Option<u64>,
+    auto_clean_interval_secs: Option<u64>,
+    due_scan_interval_secs: Option<u64>,
+    webhook_interval_secs: Option<u64>,
+    metrics_interval_secs: Option<u64>,
+    operations_interval_secs: Option<u64>,
+    cors_allowed_origins: Option<Vec<String>>,
+    encryption_key: Option<String>,
+    encryption_old_keys: Option<Vec<String>>,
+    #[serde(default)]
+    outbound: FileOutbound,
+    #[serde(default)]
+    google: FileGoogle,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct FileOutbound {
+    proxy: Option<String>,
+    connect_timeout_secs: Option<u64>,
+    read_timeout_secs: Option<u64>,
+    breaker_threshold: Option<u32>,
+    breaker_cooldown_secs: Option<u64>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct FileGoogle {
+    client_id: Option<String>,
+    client_secret: Option<String>,
+    redirect_url: Option<String>,
+    sync_interval_secs: Option<u64>,
+}
+
+impl FileConfig {
+    pub fn read(path: &str) -> Result<Self, ConfigError> {
+        let text =
+            std::fs::read_to_string(path).map_err(|source| ConfigError::Read { path: path.into(), source })?;
+        Self::parse(path, &text)
+    }
+
+    /// `path` only names the file in errors.
+    pub fn parse(path: &str, text: &str) -> Result<Self, ConfigError> {
+        toml::from_str(text).map_err(|source| ConfigError::Parse { path: path.into(), source })
+    }
+}
+
+impl Config {
+    /// Defaults, overridden by the TOML file named in `CONFIG_FILE` (or
+    /// [`DEFAULT_FILE`] if present), overridden by environment variables.
+    pub fn load() -> Result<Self, ConfigError> {
+        let file = match env::var("CONFIG_FILE") {
+            Ok(path) => Some(FileConfig::read(&path)?),
+            Err(_) if Path::new(DEFAULT_FILE).exists() => Some(FileConfig::read(DEFAULT_FILE)?),
+            Err(_) => None,
+        };
+        Self::layered(file.unwrap_or_default(), |key| env::var(key).ok())
+    }
+
+    /// `file` over the defaults, then the variables `env` returns over both;
+    /// the result is validated.
+    pub fn layered(file: FileConfig, env: impl Fn(&str) -> Option<String>) -> Result<Self, ConfigError> {
+        let mut c = Config::default();
+
+        if let Some(env) = file.env {
+            c.env = parse("env", &env)?;
+        }
+        if let Some(host) = file.host {
+            c.host = parse("host", &host)?;
+        }
+        set(&mut c.port, file.port);
+        c.grpc_port = file.grpc_port.or(c.grpc_port);
+        set(&mut c.database_url, file.database_url);
+        set(&mut c.db_max_connections, file.db_max_connections);
+        c.cache_url = file.cache_url.or(c.cache_url);
+        set_secs(&mut c.stats_cache_ttl, file.stats_cache_ttl_secs);
+        set_secs(&mut c.auto_clean_interval, file.auto_clean_interval_secs);
+        set_secs(&mut c.due_scan_interval, file.due_scan_interval_secs);
+        set_secs(&mut c.webhook_interval, file.webhook_interval_secs);
+        set_secs(&mut c.metrics_interval, file.metrics_interval_secs);
+        set_secs(&mut c.operations_interval, file.operations_interval_secs);
+        set(&mut c.cors_allowed_origins, file.cors_allowed_origins);
+        c.encryption_key = file.encryption_key.as_deref().map(|k| key("ENCRYPTION_KEY", k)).transpose()?.or(c.encryption_key);
+        if let Some(keys) = file.encryption_old_keys {
+            c.encryption_old_keys = keys.iter().map(|k| key("ENCRYPTION_OLD_KEYS", k)).collect::<Result<_, _>>()?;
+        }
+        c.outbound.proxy = file.outbound.proxy.or(c.outbound.proxy);
+        set_secs(&mut c.outbound.connect_timeout, file.outbound.connect_timeout_secs);
+        set_secs(&mut c.outbound.read_timeout, file.outbound.read_timeout_secs);
+        set(&mut c.outbound.breaker_threshold, file.outbound.breaker_threshold);
+        set_secs(&mut c.outbound.breaker_cooldown, file.outbound.breaker_cooldown_secs);
+        c.google.client_id = file.google.client_id.or(c.google.client_id);
+        c.google.client_secret = file.google.client_secret.or(c.google.client_secret);
+        c.google.redirect_url = file.google.redirect_url.or(c.google.redirect_url);
+        set_secs(&mut c.google.sync_interval, file.google.sync_interval_secs);
+
+        // пустая переменная — как незаданная
+        let var = |key: &str| env(key).map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        set(&mut c.env, typed(&var, "APP_ENV")?);
+        set(&mut c.host, typed(&var, "APP_HOST")?);
+        set(&mut c.port, typed(&var, "APP_PORT")?);
+        c.grpc_port = typed(&var, "GRPC_PORT")?.or(c.grpc_port);
+        set(&mut c.database_url, var("DATABASE_URL"));
+        set(&mut c.db_max_connections, typed(&var, "DB_MAX_CONNECTIONS")?);
+        c.cache_url = var("CACHE_URL").or(c.cache_url);
+        set(&mut c.stats_cache_ttl, secs(&var, "STATS_CACHE_TTL_SECS")?);
+        set(&mut c.auto_clean_interval, secs(&var, "AUTO_CLEAN_INTERVAL_SECS")?);
+        set(&mut c.due_scan_interval, secs(&var, "DUE_SCAN_INTERVAL_SECS")?);
+        set(&mut c.webhook_interval, secs(&var, "WEBHOOK_INTERVAL_SECS")?);
+        set(&mut c.metrics_interval, secs(&var, "METRICS_INTERVAL_SECS")?);
+        set(&mut c.operations_interval, secs(&var, "OPERATIONS_INTERVAL_SECS")?);
+        if let Some(origins) = var("CORS_ALLOWED_ORIGINS") {
+            c.cors_allowed_origins =
+                origins.split(',').map(|o| o.trim().to_string()).filter(|o| !o.is_empty()).collect();
+        }
+        c.encryption_key = var("ENCRYPTION_KEY").map(|k| key("ENCRYPTION_KEY", &k)).transpose()?.or(c.encryption_key);
+        if let Some(keys) = var("ENCRYPTION_OLD_KEYS") {
+            c.encryption_old_keys = keys
+                .split(',')
+                .map(str::trim)
+                .filter(|k| !k.is_empty())
+                .map(|k| key("ENCRYPTION_OLD_KEYS", k))
+                .collect::<Result<_, _>>()?;
+        }
+        c.outbound.proxy = var("OUTBOUND_PROXY").or(c.outbound.proxy);
+        set(&mut c.outbound.connect_timeout, secs(&var, "OUTBOUND_CONNECT_TIMEOUT_SECS")?);
+        set(&mut c.outbound.read_timeout, secs(&var, "OUTBOUND_READ_TIMEOUT_SECS")?);
+        set(&mut c.outbound.breaker_threshold, typed(&var, "OUTBOUND_BREAKER_THRESHOLD")?);
+        set(&mut c.outbound.breaker_cooldown, secs(&var, "OUTBOUND_BREAKER_COOLDOWN_SECS")?);
+        c.google.client_id = var("GOOGLE_CLIENT_ID").or(c.google.client_id);
+        c.google.client_secret = var("GOOGLE_CLIENT_SECRET").or(c.google.client_secret);
+        c.google.redirect_url = var("GOOGLE_REDIRECT_URL").or(c.google.redirect_url);
+        set(&mut c.google.sync_interval, secs(&var, "GOOGLE_SYNC_INTERVAL_SECS")?);
+
+        c.validate()?;
+        Ok(c)
+    }
+
+    /// Settings the server cannot start with; names the variable to fix.
+    /// `cors_allowed_origins` as header values. [`Config::validate`] rejects
+    /// origins that are not valid header values, so none are left out here.
+    pub fn cors_origins(&self) -> Vec<HeaderValue> {
+        self.cors_allowed_origins.iter().filter_map(|o| HeaderValue::from_str(o).ok()).collect()
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let intervals = [
+            ("AUTO_CLEAN_INTERVAL_SECS", self.auto_clean_interval),
+            ("DUE_SCAN_INTERVAL_SECS", self.due_scan_interval),
+            ("WEBHOOK_INTERVAL_SECS", self.webhook_interval),
+            ("METRICS_INTERVAL_SECS", self.metrics_interval),
+            ("OPERATIONS_INTERVAL_SECS", self.operations_interval),
+            ("GOOGLE_SYNC_INTERVAL_SECS", self.google.sync_interval),
+        ];
+        let invalid = |msg: String| Err(ConfigError::Invalid(msg));
+        if self.port == 0 {
+            return invalid("APP_PORT must not be 0".into());
+        }
+        if self.grpc_port.is_some_and(|p| p == 0 || p == self.port) {
+            return invalid("GRPC_PORT must not be 0 or the same as APP_PORT".into());
+        }
+        if !self.database_url.starts_with("sqlite:") {
+            return invalid("DATABASE_URL must look like sqlite://path/to/cleaner.db".into());
+        }
+        if self.db_max_connections == 0 {
+            return invalid("DB_MAX_CONNECTIONS must be at least 1".into());
+        }
+        if let Some((var, _)) = intervals.iter().find(|(_, d)| d.is_zero()) {
+            return invalid(format!("{var} must be at least 1"));
+        }
+        if let Some(o) = self
+            .cors_allowed_origins
+            .iter()
+            .find(|o| {
+                !(o.starts_with("http://") || o.starts_with("https://"))
+                    || o.ends_with('/')
+                    // браузер шлёт Origin в ASCII (punycode); прочее не совпадёт никогда
+                    || !o.is_ascii()
+                    || HeaderValue::from_str(o).is_err()
+            })
+        {
+            return invalid(format!(
+                "CORS_ALLOWED_ORIGINS: {o:?} is not an origin like https://app.example.com"
+            ));
+        }
+        let google = [&self.google.client_id, &self.google.client_secret, &self.google.redirect_url];
+        if google.iter().any(|v| v.is_some()) && !google.iter().all(|v| v.is_some()) {
+            return invalid(
+                "GOOGLE_CLIENT_ID, GOOGLE_CLIENT_SECRET and GOOGLE_REDIRECT_URL are needed together".into(),
+            );
+        }
+        let redirect = self.google.redirect_url.as_deref();
+        if let Some(url) = redirect.filter(|u| !(u.starts_with("https://") || u.starts_with("http://"))) {
+            return invalid(format!("GOOGLE_REDIRECT_URL: {url:?} is not an http(s) URL"));
+        }
+        if self.env == Environment::Production && self.database_url.contains(":memory:") {
+            return invalid("DATABASE_URL: an in-memory database loses everything on restart; not in production".into());
+        }
+        Ok(())
+    }
+}
+
+fn set<T>(target: &mut T, value: Option<T>) {
+    if let Some(v) = value {
+        *target = v;
+    }
+}
+
+fn set_secs(target: &mut Duration, secs: Option<u64>) {
+    set(target, secs.map(Duration::from_secs));
+}
+
+fn typed<T: FromStr>(var: &impl Fn(&str) -> Option<String>, key: &str) -> Result<Option<T>, ConfigError> {
+    var(key).map(|v| parse(key, &v)).transpose()
+}
+
+fn secs(var: &impl Fn(&str) -> Option<String>, key: &str) -> Result<Option<Duration>, ConfigError> {
+    Ok(typed(var, key)?.map(Duration::from_secs))
+}
+
+/// Unlike [`parse`], the error leaves the value out: it is a secret.
+fn key(name: &str, value: &str) -> Result<Key, ConfigError> {
+    value
+        .parse()
+        .map_err(|_| ConfigError::Invalid(format!("{name} must be 64 hex characters (a 32-byte key)")))
+}
+
+fn parse<T: FromStr>(key: &str, value: &str) -> Result<T, ConfigError> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| ConfigError::Value { key: key.into(), value: value.into() })
+}
@@ -0,0 +1,146 @@
+//! Secrets kept in the database (webhook and integration secrets, Google
+//! Calendar tokens) are sealed with AES-256-GCM under `ENCRYPTION_KEY` before
+//! they are written and opened where they are used. Keys in
+//! `ENCRYPTION_OLD_KEYS` still open older values; `cleaner-api reencrypt` moves
+//! every sealed column to the current key.
+
+use std::{fmt, str::FromStr};
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, OsRng},
+    Aes256Gcm, KeyInit, Nonce,
+};
+use anyhow::anyhow;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    error::{AppError, AppResult},
+    models::Db,
+};
+
+/// Start of a sealed value: `enc:v1:<key id>:<hex nonce and ciphertext>`.
+const PREFIX: &str = "enc:v1:";
+const NONCE_LEN: usize = 12;
+
+/// Sealed columns as `(table, column)`; rows are addressed by `id`.
+pub const SEALED_COLUMNS: &[(&str, &str)] = &[
+    ("webhooks", "secret"),
+    ("integrations", "secret"),
+    ("google_calendars", "refresh_token"),
+    ("google_calendars", "access_token"),
+];
+
+/// A 256-bit key, written as 64 hex characters.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Key([u8; 32]);
+
+impl Key {
+    /// Short fingerprint stored with each value, so the right key is picked on open.
+    pub fn id(&self) -> String {
+        hex::encode(&Sha256::digest(self.0)[..4])
+    }
+}
+
+impl FromStr for Key {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        let bytes = hex::decode(s.trim()).map_err(|_| ())?;
+        Ok(Key(bytes.try_into().map_err(|_| ())?))
+    }
+}
+
+// ключ не должен попасть в лог вместе с конфигурацией
+impl fmt::Debug for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Key({})", self.id())
+    }
+}
+
+/// Seals with the current key, opens with any known one. Without a current key
+/// values are stored as given, which is what a development setup gets.
+#[derive(Clone, Debug, Default)]
+pub struct Cipher {
+    current: Option<Key>,
+    old: Vec<Key>,
+}
+
+impl Cipher {
+    pub fn new(current: Option<Key>, old: Vec<Key>) -> Self {
+        Self { current, old }
+    }
+
+    pub fn current_key_id(&self) -> Option<String> {
+        self.current.as_ref().map(Key::id)
+    }
+
+    pub fn seal(&self, plain: &str) -> String {
+        let Some(key) = &self.current else {
+            return plain.to_string();
+        };
+        let aead = Aes256Gcm::new(&key.0.into());
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let sealed = aead.encrypt(&nonce, plain.as_bytes()).expect("AES-GCM seals any length we store");
+        let mut out = nonce.to_vec();
+        out.extend(sealed);
+        format!("{PREFIX}{}:{}", key.id(), hex::encode(out))
+    }
+
+    /// Values written before a key was configured are returned as they are.
+    pub fn open(&self, stored: &str) -> AppResult<String> {
+        let Some(rest) = stored.strip_prefix(PREFIX) else {
+            return Ok(stored.to_string());
+        };
+        let (id, data) = rest.split_once(':').ok_or_else(|| anyhow!("malformed sealed value"))?;
+        let key = self
+            .current
+            .iter()
+            .chain(&self.old)
+            .find(|k| k.id() == id)
+            .ok_or_else(|| anyhow!("value sealed with key {id}, which is not in ENCRYPTION_KEY or ENCRYPTION_OLD_KEYS"))?;
+        let data = hex::decode(data).map_err(|_| anyhow!("malformed sealed value"))?;
+        if data.len() < NONCE_LEN {
+            return Err(anyhow!("malformed sealed value").into());
+        }
+        let (nonce, sealed) = data.split_at(NONCE_LEN);
+        let plain = Aes256Gcm::new(&key.0.into())
+            .decrypt(Nonce::from_slice(nonce), sealed)
+            .map_err(|_| anyhow!("sealed value does not open with key {id}"))?;
+        String::from_utf8(plain).map_err(|_| anyhow!("sealed value is not text").into())
+    }
+
+    /// Whether `stored` is already sealed with the current key.
+    fn is_current(&self, stored: &str) -> bool {
+        match &self.current {
+            Some(key) => stored.strip_prefix(PREFIX).is_some_and(|rest| rest.starts_with(&format!("{}:", key.id()))),
+            None => !stored.starts_with(PREFIX),
+        }
+    }
+}
+
+/// Reseals every value of [`SEALED_COLUMNS`] not sealed with the current key,
+/// plain ones included, in one transaction. Returns how many were rewritten.
+pub async fn reencrypt(pool: &Db, cipher: &Cipher) -> AppResult<u64> {
+    if cipher.current.is_none() {
+        return Err(AppError::Validation("set ENCRYPTION_KEY to the key to re-encrypt with".into()));
+    }
+    let mut tx = pool.begin().await?;
+    let mut rewritten = 0u64;
+    for (table, column) in SEALED_COLUMNS {
+        let rows: Vec<(String, String)> =
+            sqlx::query_as(&format!("SELECT id, {column} FROM {table} WHERE {column} IS NOT NULL AND {column} != ''"))
+                .fetch_all(&mut *tx)
+                .await?;
+        for (id, stored) in rows.into_iter().filter(|(_, s)| !cipher.is_current(s)) {
+            let sealed = cipher.seal(&cipher.open(&stored)?);
+            sqlx::query(&format!("UPDATE {table} SET {column} = ?1 WHERE id = ?2"))
+                .bind(sealed)
+                .bind(&id)
+                .execute(&mut *tx)
+                .await?;
+            rewritten += 1;
+        }
+    }
+    tx.commit().await?;
+    Ok(rewritten)
+}
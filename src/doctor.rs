@@ -0,0 +1,246 @@
+use std::{collections::HashSet, str::FromStr, time::Duration};
+
+use chrono::{DateTime, Utc};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+
+use crate::{
+    api::webhooks::MIN_SECRET_LEN,
+    cache::Cache,
+    config::{Config, Environment},
+    crypto::Cipher,
+    models::Db,
+    outbound::OutboundClient,
+};
+
+/// Outcome of one `doctor` check; `Err` carries what to fix.
+pub struct Check {
+    pub name: &'static str,
+    pub result: Result<String, String>,
+}
+
+impl Check {
+    fn new(name: &'static str, result: Result<String, String>) -> Self {
+        Self { name, result }
+    }
+
+    pub fn ok(&self) -> bool {
+        self.result.is_ok()
+    }
+}
+
+/// `cleaner-api doctor`: checks the configuration and the database without
+/// migrating or serving anything, so a broken deployment says what is wrong
+/// instead of failing on its first request.
+pub async fn run(config: &Config) -> Vec<Check> {
+    let mut checks = check_services(config).await;
+    let pool = match SqliteConnectOptions::from_str(&config.database_url) {
+        Ok(options) => SqlitePoolOptions::new()
+            .max_connections(1)
+            .acquire_timeout(Duration::from_secs(5))
+            .connect_with(options)
+            .await
+            .map_err(|e| format!("{e}; check DATABASE_URL and that the file exists")),
+        Err(e) => Err(format!("{e}; DATABASE_URL must look like sqlite://path/to/cleaner.db")),
+    };
+    match pool {
+        Ok(pool) => {
+            checks.push(Check::new("database", Ok(config.database_url.clone())));
+            checks.extend(check_database(&pool, &cipher(config)).await);
+        }
+        Err(e) => checks.push(Check::new("database", Err(e))),
+    }
+    checks
+}
+
+/// The same checks against the server's own, already migrated database; run
+/// before serving, fatal in production.
+pub async fn startup(config: &Config, pool: &Db) -> Vec<Check> {
+    let mut checks = check_services(config).await;
+    checks.extend(check_database(pool, &cipher(config)).await);
+    checks
+}
+
+fn cipher(config: &Config) -> Cipher {
+    Cipher::new(config.encryption_key.clone(), config.encryption_old_keys.clone())
+}
+
+async fn check_services(config: &Config) -> Vec<Check> {
+    vec![
+        check_config(config),
+        Check::new(
+            "outbound client",
+            OutboundClient::new(&config.outbound)
+                .map(|_| "configured".to_string())
+                .map_err(|e| format!("{e}; check OUTBOUND_PROXY")),
+        ),
+        check_cache(config).await,
+        check_encryption(config),
+    ]
+}
+
+/// Checks that only need an open database: writes, migrations, clock and
+/// webhook secrets, opened with `cipher`.
+pub async fn check_database(pool: &Db, cipher: &Cipher) -> Vec<Check> {
+    let mut checks = vec![check_writable(pool).await, check_migrations(pool).await];
+    // без схемы остальные проверки только повторят ту же ошибку
+    if checks.iter().all(Check::ok) {
+        checks.push(check_clock(pool).await);
+        checks.push(check_webhook_secrets(pool, cipher).await);
+    }
+    checks
+}
+
+fn check_config(config: &Config) -> Check {
+    let result = config
+        .validate()
+        .map(|_| format!("{}, listening on {}:{}", config.env.as_str(), config.host, config.port))
+        .map_err(|e| e.to_string());
+    Check::new("config", result)
+}
+
+async fn check_cache(config: &Config) -> Check {
+    const KEY: &str = "doctor:probe";
+    let result = async {
+        let cache = Cache::connect(config.cache_url.as_deref()).await?;
+        cache.set(KEY, "ok".into(), Duration::from_secs(10)).await?;
+        let read = cache.get(KEY).await?;
+        cache.delete(KEY).await?;
+        Ok::<_, crate::error::AppError>(read)
+    }
+    .await;
+    let result = match result {
+        Ok(Some(_)) if config.cache_url.is_some() => Ok("shared cache reachable".to_string()),
+        Ok(Some(_)) => Ok("in-process memory".to_string()),
+        Ok(None) => Err("cache accepted a value but did not return it".to_string()),
+        Err(e) => Err(format!("{e}; check CACHE_URL")),
+    };
+    Check::new("cache", result)
+}
+
+fn check_encryption(config: &Config) -> Check {
+    let result = match &config.encryption_key {
+        Some(key) => Ok(format!("secrets sealed with key {}", key.id())),
+        None if config.env == Environment::Production => {
+            Err("ENCRYPTION_KEY unset; secrets would be stored in plain text".to_string())
+        }
+        None => Ok("off; secrets stored in plain text".to_string()),
+    };
+    Check::new("encryption", result)
+}
+
+async fn check_writable(pool: &Db) -> Check {
+    let result = async {
+        let mut tx = pool.begin().await?;
+        // пробная запись в основную базу, откатывается
+        sqlx::query("CREATE TABLE doctor_probe(x INTEGER)").execute(&mut *tx).await?;
+        tx.rollback().await
+    }
+    .await;
+    Check::new(
+        "database writable",
+        result
+            .map(|_| "yes".to_string())
+            .map_err(|e| format!("{e}; the file and its directory must be writable")),
+    )
+}
+
+async fn check_migrations(pool: &Db) -> Check {
+    let applied: Result<Vec<(i64, Vec<u8>, bool)>, _> =
+        sqlx::query_as("SELECT version, checksum, success FROM _sqlx_migrations")
+            .fetch_all(pool)
+            .await;
+    let applied = match applied {
+        Ok(rows) => rows,
+        Err(_) => {
+            return Check::new(
+                "migrations",
+                Err("no migrations applied; start the server once to create the schema".into()),
+            )
+        }
+    };
+    let known = sqlx::migrate!("./migrations");
+    let known: Vec<_> = known.iter().filter(|m| m.migration_type.is_up_migration()).collect();
+
+    if let Some((v, ..)) = applied.iter().find(|(_, _, success)| !success) {
+        return Check::new("migrations", Err(format!("migration {v} failed halfway; restore a backup")));
+    }
+    let versions: HashSet<i64> = known.iter().map(|m| m.version).collect();
+    if let Some((v, ..)) = applied.iter().find(|(v, ..)| !versions.contains(v)) {
+        return Check::new(
+            "migrations",
+            Err(format!("database has migration {v} this build does not know; deploy a newer build")),
+        );
+    }
+    if let Some(m) = known.iter().find(|m| {
+        applied.iter().any(|(v, checksum, _)| *v == m.version && checksum.as_slice() != &*m.checksum)
+    }) {
+        return Check::new(
+            "migrations",
+            Err(format!("migration {} was edited after it was applied", m.version)),
+        );
+    }
+    let pending = known.len().saturating_sub(applied.len());
+    let result = if pending == 0 {
+        Ok(format!("{} applied", applied.len()))
+    } else {
+        Ok(format!("{pending} pending, applied on next start"))
+    };
+    Check::new("migrations", result)
+}
+
+async fn check_clock(pool: &Db) -> Check {
+    let now = Utc::now();
+    let newest: Result<(Option<DateTime<Utc>>,), _> =
+        sqlx::query_as("SELECT MAX(recorded_at) FROM zone_events").fetch_one(pool).await;
+    let result = match newest {
+        Err(e) => Err(e.to_string()),
+        Ok((Some(newest),)) if newest > now + chrono::Duration::minutes(5) => Err(format!(
+            "system clock {now} is behind the newest recorded event {newest}; due dates will be wrong until NTP is fixed"
+        )),
+        Ok(_) => Ok(now.to_rfc3339()),
+    };
+    Check::new("clock", result)
+}
+
+async fn check_webhook_secrets(pool: &Db, cipher: &Cipher) -> Check {
+    let secrets: Result<Vec<(String, String)>, _> =
+        sqlx::query_as("SELECT id, secret FROM webhooks WHERE active = 1 AND deleted_at IS NULL")
+            .fetch_all(pool)
+            .await;
+    let result = match secrets {
+        Err(e) => Err(e.to_string()),
+        Ok(secrets) => {
+            let (mut weak, mut unopened) = (Vec::new(), Vec::new());
+            for (id, stored) in secrets {
+                match cipher.open(&stored) {
+                    // длина без разнообразия символов («aaaa…») подпись не защищает
+                    Ok(s) if s.len() < MIN_SECRET_LEN || s.chars().collect::<HashSet<_>>().len() < 8 => weak.push(id),
+                    Ok(_) => {}
+                    Err(_) => unopened.push(id),
+                }
+            }
+            if !unopened.is_empty() {
+                Err(format!(
+                    "secrets of webhooks {} do not open with ENCRYPTION_KEY or ENCRYPTION_OLD_KEYS",
+                    unopened.join(", ")
+                ))
+            } else if weak.is_empty() {
+                Ok("all active webhooks signed with strong secrets".to_string())
+            } else {
+                Err(format!("weak secrets on webhooks {}; rotate them with PATCH /webhooks/{{id}}", weak.join(", ")))
+            }
+        }
+    };
+    Check::new("webhook secrets", result)
+}
+
+/// One line per check, for the terminal.
+pub fn report(checks: &[Check]) -> String {
+    checks
+        .iter()
+        .map(|c| match &c.result {
+            Ok(detail) => format!("ok    {}: {detail}\n", c.name),
+            Err(problem) => format!("FAIL  {}: {problem}\n", c.name),
+        })
+        .collect()
+}
@@ -19,25 +19,52 @@ pub enum AppError {
     Other(#[from] anyhow::Error),
     #[error(transparent)]
     Io(#[from] io::Error),
+    #[error(transparent)]
+    Outbound(#[from] crate::outbound::OutboundError),
+    #[error("{0}")]
+    Unauthorized(String),
+    #[error("{0}")]
+    Conflict(String),
+    #[error("request took longer than {0:?}")]
+    Timeout(std::time::Duration),
 }
 
 #[derive(Serialize)]
 struct ErrorBody {
     code: &'static str,
     message: String,
+    /// Same as the `X-Request-Id` header; absent outside an HTTP request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
 }
 
-impl IntoResponse for AppError {
-    fn into_response(self) -> Response {
-        let (status, code) = match self {
+impl AppError {
+    /// HTTP status and the `code` of the error body.
+    pub fn status_code(&self) -> (StatusCode, &'static str) {
+        match self {
             AppError::NotFound => (StatusCode::NOT_FOUND, "not_found"),
             AppError::Validation(_) => (StatusCode::BAD_REQUEST, "validation_error"),
             AppError::Sqlx(_) => (StatusCode::INTERNAL_SERVER_ERROR, "db_error"),
             AppError::AxumJsonRejection(_) => (StatusCode::BAD_REQUEST, "invalid_json"),
             AppError::Other(_) => (StatusCode::INTERNAL_SERVER_ERROR, "internal_error"),
             AppError::Io(_) => (StatusCode::INTERNAL_SERVER_ERROR, "io_error"),
-        };
+            AppError::Outbound(_) => (StatusCode::BAD_GATEWAY, "upstream_error"),
+            AppError::Unauthorized(_) => (StatusCode::UNAUTHORIZED, "unauthorized"),
+            AppError::Conflict(_) => (StatusCode::CONFLICT, "conflict"),
+            AppError::Timeout(_) => (StatusCode::GATEWAY_TIMEOUT, "timeout"),
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let (status, code) = self.status_code();
         let message = self.to_string();
-        (status, Json(ErrorBody{ code, message })).into_response()
+        // внутри span запроса — строка лога несёт его id
+        if status.is_server_error() {
+            tracing::error!(code, error = %message, "request failed");
+        }
+        let request_id = crate::api::REQUEST_ID.try_with(String::clone).ok();
+        (status, Json(ErrorBody{ code, message, request_id })).into_response()
     }
 }
@@ -0,0 +1,149 @@
+use chrono::{DateTime, Utc};
+use sqlx::{types::Json, FromRow, SqliteExecutor};
+use uuid::Uuid;
+
+use crate::{
+    error::AppResult,
+    models::{weekdays_to_mask, Zone, ZoneChange, ZoneEvent},
+};
+
+#[derive(FromRow)]
+struct ZoneEventRow {
+    id: String,
+    zone_id: String,
+    payload: Json<ZoneChange>,
+    occurred_at: DateTime<Utc>,
+    recorded_at: DateTime<Utc>,
+}
+
+impl From<ZoneEventRow> for ZoneEvent {
+    fn from(r: ZoneEventRow) -> Self {
+        ZoneEvent {
+            id: r.id,
+            zone_id: r.zone_id,
+            change: r.payload.0,
+            occurred_at: r.occurred_at,
+            recorded_at: r.recorded_at,
+        }
+    }
+}
+
+/// Appends a change to the zone's stream. Call it on the same transaction
+/// as the projection update in `zones` so both stay in step.
+pub async fn record<'e, E: SqliteExecutor<'e>>(
+    exec: E,
+    zone_id: &str,
+    change: &ZoneChange,
+    occurred_at: DateTime<Utc>,
+) -> AppResult<()> {
+    sqlx::query(
+        r#"INSERT INTO zone_events(id, zone_id, kind, payload, occurred_at, recorded_at)
+           VALUES (?1, ?2, ?3, ?4, ?5, ?6)"#,
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(zone_id)
+    .bind(change.kind())
+    .bind(Json(change))
+    .bind(occurred_at)
+    .bind(Utc::now())
+    .execute(exec)
+    .await?;
+    Ok(())
+}
+
+/// Events of a zone in replay order.
+pub async fn list<'e, E: SqliteExecutor<'e>>(exec: E, zone_id: &str) -> AppResult<Vec<ZoneEvent>> {
+    let rows = sqlx::query_as::<_, ZoneEventRow>(
+        r#"SELECT id, zone_id, payload, occurred_at, recorded_at
+           FROM zone_events WHERE zone_id = ?1
+           ORDER BY occurred_at ASC, id ASC"#,
+    )
+    .bind(zone_id)
+    .fetch_all(exec)
+    .await?;
+    Ok(rows.into_iter().map(ZoneEvent::from).collect())
+}
+
+/// Derives zone state from its events. Events are sorted by `(occurred_at, id)` first,
+/// so concurrent offline edits merge to the same result regardless of arrival order:
+/// the latest edit of each field wins and the latest clean sets `last_cleaned_at`.
+pub fn fold(events: &[ZoneEvent]) -> Option<Zone> {
+    let mut events: Vec<&ZoneEvent> = events.iter().collect();
+    events.sort_by(|a, b| (a.occurred_at, &a.id).cmp(&(b.occurred_at, &b.id)));
+
+    let mut zone: Option<Zone> = None;
+    for e in events {
+        if let ZoneChange::Created {
+            room_id,
+            name,
+            icon,
+            notes,
+            metadata,
+            frequency,
+            custom_interval_days,
+            weekdays,
+            auto,
+            estimated_minutes,
+            source,
+            external_id,
+        } = &e.change
+        {
+            zone = Some(Zone {
+                id: e.zone_id.clone(),
+                room_id: room_id.clone(),
+                name: name.clone(),
+                icon: icon.clone(),
+                notes: notes.clone(),
+                metadata: metadata.clone().map(Json),
+                frequency: *frequency,
+                custom_interval_days: *custom_interval_days,
+                weekday_mask: weekdays.as_deref().map(weekdays_to_mask),
+                auto: *auto,
+                estimated_minutes: *estimated_minutes,
+                paused_until: None,
+                schedule_anchor_at: None,
+                last_cleaned_at: None,
+                sort_order: 0,
+                source: source.clone(),
+                external_id: external_id.clone(),
+                created_at: e.occurred_at,
+                updated_at: e.occurred_at,
+                deleted_at: None,
+            });
+            continue;
+        }
+        // правки до события создания игнорируются
+        let Some(z) = zone.as_mut() else { continue };
+        match &e.change {
+            ZoneChange::Created { .. } => unreachable!(),
+            ZoneChange::Renamed { name } => z.name = name.clone(),
+            ZoneChange::IconChanged { icon } => z.icon = icon.clone(),
+            ZoneChange::NotesChanged { notes } => z.notes = notes.clone(),
+            ZoneChange::MetadataChanged { metadata } => z.metadata = metadata.clone().map(Json),
+            ZoneChange::FrequencyChanged {
+                frequency,
+                custom_interval_days,
+                weekdays,
+                anchor_at,
+                ..
+            } => {
+                z.frequency = *frequency;
+                z.custom_interval_days = *custom_interval_days;
+                z.weekday_mask = weekdays.as_deref().map(weekdays_to_mask);
+                z.schedule_anchor_at = *anchor_at;
+            }
+            ZoneChange::AutoChanged { auto } => z.auto = *auto,
+            ZoneChange::EstimateChanged { estimated_minutes } => z.estimated_minutes = *estimated_minutes,
+            ZoneChange::ExternalRefChanged { source, external_id } => {
+                z.source = source.clone();
+                z.external_id = external_id.clone();
+            }
+            ZoneChange::Paused { until } => z.paused_until = *until,
+            ZoneChange::Cleaned { .. } => z.last_cleaned_at = Some(e.occurred_at),
+            ZoneChange::Deleted => z.deleted_at = Some(e.occurred_at),
+            ZoneChange::Restored => z.deleted_at = None,
+        }
+        z.updated_at = e.occurred_at;
+    }
+    zone
+}
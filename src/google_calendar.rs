@@ -0,0 +1,469 @@
+//! Two-way sync with Google Calendar. Every due zone of a connected home gets an
+//! event at its `next_due_at`, moved when the zone is rescheduled here; ticking
+//! the event off (a title starting with `✓` or `[x]`) marks the zone cleaned.
+//! The endpoints that connect a calendar are in `api::google_calendar`.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use reqwest::{RequestBuilder, Response, StatusCode, Url};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::json;
+use sqlx::FromRow;
+
+use crate::{
+    api::zones,
+    error::{AppError, AppResult},
+    models::{AppState, ZoneChange},
+    outbound::OutboundError,
+};
+
+/// Access asked for: events only, not the rest of the calendar settings.
+pub const SCOPE: &str = "https://www.googleapis.com/auth/calendar.events";
+/// Title prefixes that mark an event done.
+const TICKS: [&str; 2] = ["✓", "[x]"];
+/// Length of an event for a zone without `estimated_minutes`.
+const DEFAULT_MINUTES: i64 = 30;
+
+#[derive(Debug, Clone)]
+pub struct GoogleConfig {
+    /// OAuth client from the Google Cloud console; the sync is off without it.
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    /// `…/api/v1/integrations/google-calendar/callback`, as registered with the client.
+    pub redirect_url: Option<String>,
+    pub sync_interval: Duration,
+    /// Google's endpoints; only tests point them elsewhere.
+    pub auth_url: String,
+    pub token_url: String,
+    pub api_url: String,
+}
+
+impl Default for GoogleConfig {
+    fn default() -> Self {
+        Self {
+            client_id: None,
+            client_secret: None,
+            redirect_url: None,
+            sync_interval: Duration::from_secs(300),
+            auth_url: "https://accounts.google.com/o/oauth2/v2/auth".into(),
+            token_url: "https://oauth2.googleapis.com/token".into(),
+            api_url: "https://www.googleapis.com/calendar/v3".into(),
+        }
+    }
+}
+
+impl GoogleConfig {
+    /// Client id, secret and redirect URL, when all are set.
+    pub fn client(&self) -> AppResult<(&str, &str, &str)> {
+        match (&self.client_id, &self.client_secret, &self.redirect_url) {
+            (Some(id), Some(secret), Some(redirect)) => Ok((id, secret, redirect)),
+            _ => Err(AppError::Validation(
+                "Google Calendar is not configured; set GOOGLE_CLIENT_ID, GOOGLE_CLIENT_SECRET and GOOGLE_REDIRECT_URL"
+                    .into(),
+            )),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct Tokens {
+    pub access_token: String,
+    pub expires_in: i64,
+    /// Only on the first exchange of a consent, or when Google rotates it.
+    pub refresh_token: Option<String>,
+}
+
+impl Tokens {
+    pub fn expires_at(&self) -> DateTime<Utc> {
+        Utc::now() + chrono::Duration::seconds(self.expires_in)
+    }
+}
+
+/// Trades an authorization code or a refresh token (`grant`) for tokens. A
+/// grant Google refuses, e.g. access revoked in the Google account, is
+/// `Unauthorized`.
+pub async fn request_tokens(state: &AppState, grant: &[(&str, &str)]) -> AppResult<Tokens> {
+    let (client_id, client_secret, _) = state.google.client()?;
+    let mut form = vec![("client_id", client_id), ("client_secret", client_secret)];
+    form.extend_from_slice(grant);
+    let req = state.http.client().post(&state.google.token_url).form(&form);
+    let res = state.http.execute(req.build().map_err(OutboundError::from)?).await?;
+    if res.status() == StatusCode::BAD_REQUEST || res.status() == StatusCode::UNAUTHORIZED {
+        let body = res.text().await.unwrap_or_default();
+        return Err(AppError::Unauthorized(format!("Google refused the grant: {}", excerpt(&body))));
+    }
+    json_of(ok_or_fail(res, "token request").await?).await
+}
+
+#[derive(FromRow)]
+struct Connection {
+    id: String,
+    home_id: Option<String>,
+    calendar_id: String,
+    refresh_token: String,
+    access_token: Option<String>,
+    access_expires_at: Option<DateTime<Utc>>,
+    last_synced_at: Option<DateTime<Utc>>,
+}
+
+/// Syncs every connected calendar once. A calendar that fails keeps its error
+/// in `last_error` and is retried on the next run with the same window of
+/// changes. Returns how many events and zones were changed.
+pub async fn sync(state: &AppState) -> AppResult<u64> {
+    if state.google.client().is_err() {
+        return Ok(0);
+    }
+    let connections = sqlx::query_as::<_, Connection>(
+        r#"SELECT id, home_id, calendar_id, refresh_token, access_token, access_expires_at, last_synced_at
+           FROM google_calendars ORDER BY created_at"#,
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    let mut changed = 0;
+    for c in connections {
+        let started = Utc::now();
+        let error = match sync_calendar(state, &c).await {
+            Ok(n) => {
+                changed += n;
+                None
+            }
+            Err(e) => {
+                tracing::warn!(calendar = %c.id, error = %e, "Google Calendar sync failed");
+                Some(e.to_string())
+            }
+        };
+        // после ошибки окно изменений не сдвигаем
+        sqlx::query(
+            r#"UPDATE google_calendars
+               SET last_synced_at = CASE WHEN ?1 IS NULL THEN ?2 ELSE last_synced_at END, last_error = ?1
+               WHERE id = ?3"#,
+        )
+        .bind(error)
+        .bind(started)
+        .bind(&c.id)
+        .execute(&state.writer)
+        .await?;
+    }
+    Ok(changed)
+}
+
+async fn sync_calendar(state: &AppState, c: &Connection) -> AppResult<u64> {
+    let token = access_token(state, c).await?;
+    let calendar = Calendar::new(state, token, &c.calendar_id)?;
+    Ok(pull(state, c, &calendar).await? + push(state, c, &calendar).await?)
+}
+
+/// The stored access token, or a new one when it is about to expire.
+async fn access_token(state: &AppState, c: &Connection) -> AppResult<String> {
+    if let (Some(token), Some(expires_at)) = (&c.access_token, c.access_expires_at) {
+        if expires_at > Utc::now() + chrono::Duration::minutes(1) {
+            return state.cipher.open(token);
+        }
+    }
+    let refresh_token = state.cipher.open(&c.refresh_token)?;
+    let tokens = request_tokens(state, &[("grant_type", "refresh_token"), ("refresh_token", &refresh_token)]).await?;
+    sqlx::query(
+        r#"UPDATE google_calendars
+           SET access_token = ?1, access_expires_at = ?2, refresh_token = COALESCE(?3, refresh_token), updated_at = ?4
+           WHERE id = ?5"#,
+    )
+    .bind(state.cipher.seal(&tokens.access_token))
+    .bind(tokens.expires_at())
+    .bind(tokens.refresh_token.as_deref().map(|t| state.cipher.seal(t)))
+    .bind(Utc::now())
+    .bind(&c.id)
+    .execute(&state.writer)
+    .await?;
+    Ok(tokens.access_token)
+}
+
+/// Events changed in the calendar since the last sync. A ticked-off event
+/// cleans its zone unless the zone was cleaned or rescheduled here meanwhile:
+/// then this side wins and the next event is created for the new date. A
+/// deleted event is not recreated until the zone's due date moves.
+async fn pull(state: &AppState, c: &Connection, calendar: &Calendar<'_>) -> AppResult<u64> {
+    // запас на расхождение часов с Google
+    let since = c.last_synced_at.map(|t| t - chrono::Duration::minutes(5));
+    let mut cleaned = 0;
+    for event in calendar.changed_since(since).await? {
+        let mapped: Option<(String, DateTime<Utc>)> =
+            sqlx::query_as("SELECT zone_id, due_at FROM google_calendar_events WHERE calendar_id = ?1 AND event_id = ?2")
+                .bind(&c.id)
+                .bind(&event.id)
+                .fetch_optional(&state.pool)
+                .await?;
+        let Some((zone_id, due_at)) = mapped else { continue };
+
+        if event.status.as_deref() == Some("cancelled") {
+            sqlx::query(
+                r#"UPDATE google_calendar_events SET event_id = NULL, etag = NULL, synced_at = ?1
+                   WHERE calendar_id = ?2 AND zone_id = ?3"#,
+            )
+            .bind(Utc::now())
+            .bind(&c.id)
+            .bind(&zone_id)
+            .execute(&state.writer)
+            .await?;
+            continue;
+        }
+        if !event.ticked() {
+            continue;
+        }
+
+        let now = Utc::now();
+        let mut tx = state.writer.begin().await?;
+        let next_due: Option<(Option<DateTime<Utc>>,)> =
+            sqlx::query_as("SELECT next_due_at FROM zones WHERE id = ?1 AND deleted_at IS NULL")
+                .bind(&zone_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+        if next_due == Some((Some(due_at),)) {
+            let change = ZoneChange::Cleaned {
+                note: Some("Google Calendar".into()),
+                auto: false,
+                cost_cents: None,
+                duration_minutes: None,
+            };
+            let cleaned_at = event.updated.map_or(now, |t| t.min(now));
+            if zones::mark_cleaned(&mut tx, &zone_id, cleaned_at, &change).await? {
+                cleaned += 1;
+            }
+        }
+        // отмеченное событие остаётся в календаре историей; следующей уборке — новое
+        sqlx::query("DELETE FROM google_calendar_events WHERE calendar_id = ?1 AND zone_id = ?2")
+            .bind(&c.id)
+            .bind(&zone_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+    }
+    Ok(cleaned)
+}
+
+#[derive(FromRow)]
+struct Scheduled {
+    zone_id: String,
+    name: String,
+    room_name: String,
+    estimated_minutes: Option<i64>,
+    /// `None` once the zone should have no event: deleted, auto, moved to
+    /// another home or never cleaned.
+    next_due_at: Option<DateTime<Utc>>,
+    mapped: bool,
+    event_id: Option<String>,
+    etag: Option<String>,
+    due_at: Option<DateTime<Utc>>,
+}
+
+/// Puts the home's due dates into the calendar: creates, moves and removes events.
+async fn push(state: &AppState, c: &Connection, calendar: &Calendar<'_>) -> AppResult<u64> {
+    let rows = sqlx::query_as::<_, Scheduled>(
+        r#"SELECT z.id AS zone_id, z.name, r.name AS room_name, z.estimated_minutes,
+                  CASE WHEN z.deleted_at IS NULL AND z.auto = 0 AND (?2 IS NULL OR r.home_id = ?2)
+                       THEN z.next_due_at END AS next_due_at,
+                  m.zone_id IS NOT NULL AS mapped, m.event_id, m.etag, m.due_at
+           FROM zones z
+           JOIN rooms r ON r.id = z.room_id
+           LEFT JOIN google_calendar_events m ON m.zone_id = z.id AND m.calendar_id = ?1
+           WHERE m.zone_id IS NOT NULL
+              OR (z.deleted_at IS NULL AND z.auto = 0 AND z.next_due_at IS NOT NULL AND (?2 IS NULL OR r.home_id = ?2))"#,
+    )
+    .bind(&c.id)
+    .bind(&c.home_id)
+    .fetch_all(&state.pool)
+    .await?;
+
+    let mut changed = 0;
+    for row in rows {
+        let Some(due) = row.next_due_at else {
+            if let Some(event_id) = &row.event_id {
+                calendar.delete(event_id).await?;
+            }
+            sqlx::query("DELETE FROM google_calendar_events WHERE calendar_id = ?1 AND zone_id = ?2")
+                .bind(&c.id)
+                .bind(&row.zone_id)
+                .execute(&state.writer)
+                .await?;
+            changed += 1;
+            continue;
+        };
+        if row.mapped && row.due_at == Some(due) {
+            continue;
+        }
+        let body = event_body(&row, due);
+        let event = match &row.event_id {
+            Some(event_id) => match calendar.patch(event_id, row.etag.as_deref(), &body).await? {
+                Patched::Done(event) => event,
+                Patched::Gone => calendar.insert(&body).await?,
+                Patched::Conflict => {
+                    // событие правят в календаре; следующий запуск сначала заберёт правку
+                    tracing::debug!(calendar = %c.id, zone = %row.zone_id, "event changed in Google meanwhile");
+                    continue;
+                }
+            },
+            None => calendar.insert(&body).await?,
+        };
+        sqlx::query(
+            r#"INSERT INTO google_calendar_events(calendar_id, zone_id, event_id, etag, due_at, synced_at)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+               ON CONFLICT(calendar_id, zone_id)
+               DO UPDATE SET event_id = ?3, etag = ?4, due_at = ?5, synced_at = ?6"#,
+        )
+        .bind(&c.id)
+        .bind(&row.zone_id)
+        .bind(&event.id)
+        .bind(&event.etag)
+        .bind(due)
+        .bind(Utc::now())
+        .execute(&state.writer)
+        .await?;
+        changed += 1;
+    }
+    Ok(changed)
+}
+
+fn event_body(row: &Scheduled, due: DateTime<Utc>) -> serde_json::Value {
+    let minutes = row.estimated_minutes.unwrap_or(DEFAULT_MINUTES).clamp(5, 24 * 60);
+    json!({
+        "summary": format!("{} · {}", row.name, row.room_name),
+        "description": "Due in the cleaning checklist. Start the title with ✓ once it is done to mark the zone cleaned.",
+        "start": { "dateTime": due.to_rfc3339() },
+        "end": { "dateTime": (due + chrono::Duration::minutes(minutes)).to_rfc3339() },
+        "extendedProperties": { "private": { "cleaner": "1", "zone_id": row.zone_id } },
+    })
+}
+
+#[derive(Deserialize)]
+struct Event {
+    id: String,
+    etag: Option<String>,
+    status: Option<String>,
+    summary: Option<String>,
+    updated: Option<DateTime<Utc>>,
+}
+
+impl Event {
+    fn ticked(&self) -> bool {
+        let summary = self.summary.as_deref().unwrap_or_default().trim_start().to_lowercase();
+        TICKS.iter().any(|t| summary.starts_with(t))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EventList {
+    #[serde(default)]
+    items: Vec<Event>,
+    next_page_token: Option<String>,
+}
+
+enum Patched {
+    Done(Event),
+    /// Deleted in the calendar for good.
+    Gone,
+    /// Edited in the calendar since our last write.
+    Conflict,
+}
+
+/// Events of one calendar, called with the connection's access token.
+struct Calendar<'a> {
+    state: &'a AppState,
+    token: String,
+    events: Url,
+}
+
+impl<'a> Calendar<'a> {
+    fn new(state: &'a AppState, token: String, calendar_id: &str) -> AppResult<Self> {
+        let mut events =
+            Url::parse(&state.google.api_url).map_err(|e| AppError::Other(anyhow::anyhow!("Google API URL: {e}")))?;
+        events
+            .path_segments_mut()
+            .map_err(|_| AppError::Other(anyhow::anyhow!("Google API URL cannot have a path")))?
+            .extend(["calendars", calendar_id, "events"]);
+        Ok(Self { state, token, events })
+    }
+
+    fn event_url(&self, id: &str) -> Url {
+        let mut url = self.events.clone();
+        url.path_segments_mut().expect("checked in new").push(id);
+        url
+    }
+
+    async fn send(&self, req: RequestBuilder) -> AppResult<Response> {
+        let req = req.bearer_auth(&self.token).build().map_err(OutboundError::from)?;
+        Ok(self.state.http.execute(req).await?)
+    }
+
+    /// Our events (deleted ones too) changed after `since`; all of them without it.
+    async fn changed_since(&self, since: Option<DateTime<Utc>>) -> AppResult<Vec<Event>> {
+        let mut events = Vec::new();
+        let mut page: Option<String> = None;
+        loop {
+            let mut query = vec![
+                ("privateExtendedProperty", "cleaner=1".to_string()),
+                ("showDeleted", "true".to_string()),
+                ("maxResults", "250".to_string()),
+            ];
+            query.extend(since.map(|t| ("updatedMin", t.to_rfc3339())));
+            query.extend(page.take().map(|p| ("pageToken", p)));
+            let res = self.send(self.state.http.client().get(self.events.clone()).query(&query)).await?;
+            let list: EventList = json_of(ok_or_fail(res, "listing events").await?).await?;
+            events.extend(list.items);
+            match list.next_page_token {
+                Some(next) => page = Some(next),
+                None => return Ok(events),
+            }
+        }
+    }
+
+    async fn insert(&self, body: &serde_json::Value) -> AppResult<Event> {
+        let res = self.send(self.state.http.client().post(self.events.clone()).json(body)).await?;
+        json_of(ok_or_fail(res, "creating an event").await?).await
+    }
+
+    /// Writes over the event only if it still has `etag`.
+    async fn patch(&self, id: &str, etag: Option<&str>, body: &serde_json::Value) -> AppResult<Patched> {
+        let mut req = self.state.http.client().patch(self.event_url(id)).json(body);
+        if let Some(etag) = etag {
+            req = req.header(reqwest::header::IF_MATCH, etag);
+        }
+        let res = self.send(req).await?;
+        match res.status() {
+            StatusCode::NOT_FOUND | StatusCode::GONE => Ok(Patched::Gone),
+            StatusCode::PRECONDITION_FAILED => Ok(Patched::Conflict),
+            _ => Ok(Patched::Done(json_of(ok_or_fail(res, "moving an event").await?).await?)),
+        }
+    }
+
+    /// Already deleted counts as deleted.
+    async fn delete(&self, id: &str) -> AppResult<()> {
+        let res = self.send(self.state.http.client().delete(self.event_url(id))).await?;
+        if matches!(res.status(), StatusCode::NOT_FOUND | StatusCode::GONE) {
+            return Ok(());
+        }
+        ok_or_fail(res, "deleting an event").await.map(|_| ())
+    }
+}
+
+async fn ok_or_fail(res: Response, what: &str) -> AppResult<Response> {
+    if res.status().is_success() {
+        return Ok(res);
+    }
+    let status = res.status();
+    let body = res.text().await.unwrap_or_default();
+    Err(AppError::Other(anyhow::anyhow!("Google {what}: {status} {}", excerpt(&body))))
+}
+
+async fn json_of<T: DeserializeOwned>(res: Response) -> AppResult<T> {
+    Ok(res.json().await.map_err(OutboundError::from)?)
+}
+
+/// Start of an error body, enough for a log line.
+fn excerpt(body: &str) -> &str {
+    match body.char_indices().nth(200) {
+        Some((i, _)) => &body[..i],
+        None => body,
+    }
+}
@@ -0,0 +1,78 @@
+use std::{future::Future, sync::OnceLock, time::Duration};
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{error::AppResult, models::Db};
+
+/// Identifies this process as a lease holder.
+pub fn instance_id() -> &'static str {
+    static ID: OnceLock<String> = OnceLock::new();
+    ID.get_or_init(|| Uuid::new_v4().to_string())
+}
+
+/// Takes (or renews) the lease on `job` for `ttl`. Succeeds when the job is free,
+/// already held by `holder`, or the previous holder's lease has expired.
+pub async fn try_acquire(pool: &Db, job: &str, holder: &str, ttl: Duration) -> AppResult<bool> {
+    let now = Utc::now();
+    let expires_at = now + chrono::Duration::from_std(ttl).unwrap_or(chrono::Duration::zero());
+    let res = sqlx::query(
+        r#"INSERT INTO job_leases(job, holder, acquired_at, expires_at)
+           VALUES (?1, ?2, ?3, ?4)
+           ON CONFLICT(job) DO UPDATE SET
+             acquired_at = CASE WHEN job_leases.holder = excluded.holder
+                                THEN job_leases.acquired_at ELSE excluded.acquired_at END,
+             holder = excluded.holder,
+             expires_at = excluded.expires_at
+           WHERE job_leases.holder = excluded.holder OR job_leases.expires_at <= ?3"#,
+    )
+    .bind(job)
+    .bind(holder)
+    .bind(now)
+    .bind(expires_at)
+    .execute(pool)
+    .await?;
+    Ok(res.rows_affected() == 1)
+}
+
+/// Gives up `holder`'s lease on `job` early, e.g. on shutdown.
+pub async fn release(pool: &Db, job: &str, holder: &str) -> AppResult<()> {
+    sqlx::query("DELETE FROM job_leases WHERE job = ?1 AND holder = ?2")
+        .bind(job)
+        .bind(holder)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Runs `task` only if this instance wins the lease; returns `None` when another
+/// instance holds it. The lease is kept after the run and lapses `ttl` later, so
+/// another instance whose tick comes a moment after ours does not repeat the
+/// run; the holder's own next tick renews it. Runs longer than `ttl` renew the
+/// lease every `ttl / 2` while they go.
+pub async fn run_exclusive<F, T>(pool: &Db, job: &str, ttl: Duration, task: F) -> AppResult<Option<T>>
+where
+    F: Future<Output = AppResult<T>>,
+{
+    let holder = instance_id();
+    if !try_acquire(pool, job, holder, ttl).await? {
+        tracing::debug!(job, "lease held by another instance, skipping");
+        return Ok(None);
+    }
+    let mut task = std::pin::pin!(task);
+    let every = (ttl / 2).max(Duration::from_millis(10));
+    let mut renew = tokio::time::interval_at(tokio::time::Instant::now() + every, every);
+    loop {
+        tokio::select! {
+            out = &mut task => return out.map(Some),
+            _ = renew.tick() => {
+                // запуск не прерываем: потерянная аренда хуже, чем повтор, только в логе
+                match try_acquire(pool, job, holder, ttl).await {
+                    Ok(true) => {}
+                    Ok(false) => tracing::warn!(job, "lease taken over during a long run"),
+                    Err(e) => tracing::warn!(job, error = %e, "lease renewal failed"),
+                }
+            }
+        }
+    }
+}
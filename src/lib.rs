@@ -1,3 +1,15 @@
+pub mod anonymize;
 pub mod api;
+pub mod cache;
+pub mod config;
+pub mod crypto;
+pub mod doctor;
 pub mod error;
+pub mod events;
+pub mod google_calendar;
+pub mod jobs;
+pub mod metrics;
 pub mod models;
+pub mod outbound;
+pub mod scheduler;
+pub mod webhooks;
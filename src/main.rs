@@ -1,19 +1,23 @@
-use std::{env, net::SocketAddr, sync::Arc};
+use std::{env, net::SocketAddr, str::FromStr, sync::Arc};
 
 use axum::{
-    routing::{get, post},
+    http::HeaderName,
     Router,
 };
+use tower_http::cors::{Any, CorsLayer};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use sqlx::sqlite::SqlitePoolOptions;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions};
 
-mod api;
-mod error;
-mod models;
-
-use api::{docs, rooms, stats, zones};
-use error::{AppError, AppResult};
+use cleaner_api::{
+    anonymize,
+    api::{self, docs},
+    config::{Config, Environment},
+    crypto::{self, Cipher},
+    doctor,
+    error::{AppError, AppResult},
+    models, scheduler,
+};
 
 
 #[tokio::main]
@@ -27,64 +31,165 @@ async fn main() -> AppResult<()> {
         .with(tracing_subscriber::fmt::layer())
         .init();
 
-    let port: u16 = env::var("APP_PORT")
-        .ok()
-        .and_then(|s| s.parse().ok())
-        .unwrap_or(8080);
-
-    let db_url = env::var("DATABASE_URL").unwrap_or_else(|_| {
-        // по умолчанию локальный файл
-        "sqlite://./cleaner.db".to_string()
-    });
-
-    let pool = SqlitePoolOptions::new()
-        .max_connections(5)
-        .connect(&db_url)
-        .await?;
+    let config = match Config::load() {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("configuration error: {e}");
+            std::process::exit(2);
+        }
+    };
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    // `cleaner-api doctor`: check the deployment without touching it, exit 1 on problems
+    if args.first().is_some_and(|c| c == "doctor") {
+        let checks = doctor::run(&config).await;
+        print!("{}", doctor::report(&checks));
+        if !checks.iter().all(doctor::Check::ok) {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // WAL: читатели не ждут писателя и наоборот
+    let options = SqliteConnectOptions::from_str(&config.database_url)?
+        .journal_mode(SqliteJournalMode::Wal)
+        .busy_timeout(std::time::Duration::from_secs(5));
+    let pools = async {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(config.db_max_connections)
+            .connect_with(options.clone())
+            .await?;
+        let writer = SqlitePoolOptions::new().max_connections(1).connect_with(options).await?;
+        Ok::<_, sqlx::Error>((pool, writer))
+    };
+    let (pool, writer) = match pools.await {
+        Ok(pools) => pools,
+        Err(e) => {
+            eprintln!("cannot open {}: {e}; check DATABASE_URL and that the file exists", config.database_url);
+            std::process::exit(2);
+        }
+    };
 
     // Миграции (каталог migrations)
     sqlx::migrate!("./migrations")
-        .run(&pool)
+        .run(&writer)
         .await
         .map_err(|e| AppError::Other(e.into()))?;
-
-    let state = Arc::new(models::AppState { pool });
-
-    let api_routes = Router::new()
-        // Rooms
-        .route("/rooms", get(rooms::list_rooms).post(rooms::create_room))
-        .route(
-            "/rooms/:id",
-            get(rooms::get_room)
-                .patch(rooms::update_room)
-                .delete(rooms::delete_room),
-        )
-        .route("/rooms/:id/restore", post(rooms::restore_room))
-        // Zones
-        .route(
-            "/rooms/:room_id/zones",
-            get(zones::list_zones).post(zones::create_zone),
-        )
-        .route(
-            "/zones/:id",
-            get(zones::get_zone)
-                .patch(zones::update_zone)
-                .delete(zones::delete_zone),
-        )
-        .route("/zones/:id/clean", post(zones::clean_zone))
-        .route("/zones/bulk/clean", post(zones::bulk_clean))
-        // Stats
-        .route("/stats/overview", get(stats::overview))
-        .route("/zones/due", get(stats::zones_due));
-
-    let app = Router::new()
-        .nest("/api/v1", api_routes)
+    // сроки в базе считаются приложением: заполняем после миграций и правок расписания
+    api::zones::sync_next_due(&mut *writer.acquire().await?, None).await?;
+
+    // `cleaner-api anonymize <dest.db>`: scrubbed copy for staging, then exit
+    if let [cmd, dest] = args.as_slice() {
+        if cmd == "anonymize" {
+            anonymize::anonymize(&pool, dest).await?;
+            tracing::info!(%dest, "anonymized copy written");
+            return Ok(());
+        }
+    }
+    // `cleaner-api reencrypt`: reseal stored secrets with ENCRYPTION_KEY after a key rotation
+    if args.first().is_some_and(|c| c == "reencrypt") {
+        let cipher = Cipher::new(config.encryption_key.clone(), config.encryption_old_keys.clone());
+        let rewritten = crypto::reencrypt(&writer, &cipher).await?;
+        tracing::info!(rewritten, "secrets resealed");
+        return Ok(());
+    }
+
+    // в production сервер не стартует с тем, что `doctor` считает ошибкой
+    let checks = doctor::startup(&config, &writer).await;
+    if config.env == Environment::Production && !checks.iter().all(doctor::Check::ok) {
+        eprint!("{}", doctor::report(&checks));
+        eprintln!("refusing to start in production; fix the checks above or set APP_ENV=development");
+        std::process::exit(2);
+    }
+    for c in &checks {
+        if let Err(problem) = &c.result {
+            tracing::warn!(check = c.name, %problem, "startup check failed");
+        }
+    }
+
+    let state = Arc::new(models::AppState::new(pool, &config).await?.with_writer(writer));
+
+    let jobs = scheduler::spawn(state.clone(), &config);
+
+    let mut app = Router::new()
+        .nest("/api/v1", api::routes())
         .merge(docs::swagger_ui())
-        .with_state(state);
-
-    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+        .with_state(state.clone());
+    if !config.cors_allowed_origins.is_empty() {
+        app = app.layer(
+            CorsLayer::new()
+                .allow_origin(config.cors_origins())
+                .allow_methods(Any)
+                .allow_headers(Any)
+                .expose_headers([
+                    HeaderName::from_static(api::REQUEST_ID_HEADER),
+                    HeaderName::from_static(api::undo::UNDO_HEADER),
+                ]),
+        );
+    }
+
+    let addr = SocketAddr::new(config.host, config.port);
     tracing::info!(%addr, "🚀 cleaner-api запущен");
 
-    axum::serve(tokio::net::TcpListener::bind(addr).await?, app).await?;
+    let grpc = match config.grpc_port {
+        Some(port) => {
+            let listener = tokio::net::TcpListener::bind(SocketAddr::new(config.host, port)).await?;
+            Some(tokio::spawn(api::grpc::serve(state.clone(), listener, shutdown_signal())))
+        }
+        None => None,
+    };
+
+    axum::serve(tokio::net::TcpListener::bind(addr).await?, app)
+        .with_graceful_shutdown(shutdown_signal())
+        .await?;
+    // сигнал получили оба сервера; ждём, пока gRPC дослужит свои вызовы
+    if let Some(grpc) = grpc {
+        match grpc.await {
+            Ok(Err(e)) => tracing::error!(error = %e, "gRPC server failed"),
+            Err(e) => tracing::error!(error = %e, "gRPC server panicked"),
+            Ok(Ok(())) => {}
+        }
+    }
+
+    // запросы дослужены; даём фоновым задачам закончить текущий запуск
+    tracing::info!("shutting down");
+    if tokio::time::timeout(SHUTDOWN_GRACE, jobs.stop()).await.is_err() {
+        tracing::warn!(grace = ?SHUTDOWN_GRACE, "background jobs still running, leaving them");
+    }
+    state.writer.close().await;
+    state.pool.close().await;
     Ok(())
 }
+
+/// Time background jobs get to finish their current run once the server stopped.
+const SHUTDOWN_GRACE: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Resolves on Ctrl+C or, on Unix, SIGTERM; the server then stops accepting
+/// connections and waits for requests in flight.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::warn!(error = %e, "cannot listen for Ctrl+C");
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut s) => {
+                s.recv().await;
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "cannot listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
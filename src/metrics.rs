@@ -0,0 +1,80 @@
+use std::{fmt::Write, time::Duration};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{error::AppResult, models::AppState};
+
+/// Cache key of the latest snapshot; the cache is shared, so every instance
+/// serves what the one holding the `metrics` lease collected.
+const SNAPSHOT_KEY: &str = "metrics:domain";
+
+/// Instance-wide product gauges, collected by the `metrics` job.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub rooms: i64,
+    pub zones: i64,
+    /// Manual zones due now.
+    pub zones_due: i64,
+    pub cleans_total: i64,
+    pub cleans_last_hour: i64,
+    pub collected_at: DateTime<Utc>,
+}
+
+pub async fn collect(state: &AppState) -> AppResult<Snapshot> {
+    let now = Utc::now();
+    let (rooms, zones, zones_due): (i64, i64, i64) = sqlx::query_as(
+        r#"SELECT (SELECT COUNT(1) FROM rooms WHERE deleted_at IS NULL),
+                  COUNT(1),
+                  COALESCE(SUM(auto = 0 AND (next_due_at IS NULL OR next_due_at <= ?1)), 0)
+           FROM zones WHERE deleted_at IS NULL"#,
+    )
+    .bind(now)
+    .fetch_one(&state.pool)
+    .await?;
+    let (cleans_total, cleans_last_hour): (i64, i64) = sqlx::query_as(
+        "SELECT COUNT(1), COALESCE(SUM(recorded_at > ?1), 0) FROM zone_events WHERE kind = 'cleaned'",
+    )
+    .bind(now - chrono::Duration::hours(1))
+    .fetch_one(&state.pool)
+    .await?;
+    Ok(Snapshot { rooms, zones, zones_due, cleans_total, cleans_last_hour, collected_at: now })
+}
+
+/// Collects a snapshot and keeps it for `ttl`. Runs as the `metrics` job.
+pub async fn refresh(state: &AppState, ttl: Duration) -> AppResult<u64> {
+    let snapshot = collect(state).await?;
+    state.cache.set_json(SNAPSHOT_KEY, &snapshot, ttl).await?;
+    Ok(0)
+}
+
+/// The stored snapshot, or a fresh one before the job first ran.
+pub async fn latest(state: &AppState) -> AppResult<Snapshot> {
+    match state.cache.get_json::<Snapshot>(SNAPSHOT_KEY).await? {
+        Some(s) => Ok(s),
+        None => collect(state).await,
+    }
+}
+
+/// Prometheus text exposition format.
+pub fn render(s: &Snapshot) -> String {
+    let metrics: [(&str, &str, &str, i64); 6] = [
+        ("cleaner_rooms", "gauge", "Rooms not deleted.", s.rooms),
+        ("cleaner_zones", "gauge", "Zones not deleted.", s.zones),
+        ("cleaner_zones_due", "gauge", "Manual zones due now.", s.zones_due),
+        ("cleaner_cleans_total", "counter", "Cleans recorded.", s.cleans_total),
+        ("cleaner_cleans_last_hour", "gauge", "Cleans recorded in the last hour.", s.cleans_last_hour),
+        (
+            "cleaner_metrics_collected_timestamp_seconds",
+            "gauge",
+            "When these values were collected.",
+            s.collected_at.timestamp(),
+        ),
+    ];
+    let mut out = String::new();
+    for (name, kind, help, value) in metrics {
+        // запись в String не падает
+        let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} {kind}\n{name} {value}");
+    }
+    out
+}
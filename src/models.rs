@@ -1,22 +1,83 @@
-use chrono::{DateTime, Utc};
+use std::{str::FromStr, sync::Arc};
+
+use chrono::{DateTime, Datelike, Months, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
-use sqlx::{FromRow, SqlitePool};
+use sqlx::{types::Json, FromRow, SqlitePool};
 use utoipa::ToSchema;
 
+use crate::{
+    cache::Cache,
+    config::Config,
+    crypto::Cipher,
+    error::{AppError, AppResult},
+    google_calendar::GoogleConfig,
+    outbound::OutboundClient,
+};
+
 pub type Db = SqlitePool;
 
 #[derive(Clone)]
 pub struct AppState {
+    /// Reads. With a file database, several connections read in parallel.
     pub pool: Db,
+    /// Writes and write transactions. SQLite lets one writer in at a time, so this
+    /// is a single-connection pool: writers queue here instead of on `SQLITE_BUSY`,
+    /// and reads never wait behind them.
+    pub writer: Db,
+    pub http: Arc<OutboundClient>,
+    pub cache: Arc<Cache>,
+    pub stats_cache_ttl: std::time::Duration,
+    pub google: GoogleConfig,
+    /// Seals and opens the secrets stored in the database.
+    pub cipher: Cipher,
+    /// Held while bulk operations run. The job lease keeps other instances
+    /// out, but this one's request worker and scheduler share the holder id.
+    pub operations: Arc<tokio::sync::Mutex<()>>,
 }
 
-#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, Copy, PartialEq, Eq)]
+impl AppState {
+    pub async fn new(pool: Db, config: &Config) -> AppResult<Self> {
+        let http = Arc::new(OutboundClient::new(&config.outbound)?);
+        let cache = Arc::new(Cache::connect(config.cache_url.as_deref()).await?);
+        Ok(Self {
+            writer: pool.clone(),
+            pool,
+            http,
+            cache,
+            stats_cache_ttl: config.stats_cache_ttl,
+            google: config.google.clone(),
+            cipher: Cipher::new(config.encryption_key.clone(), config.encryption_old_keys.clone()),
+            operations: Arc::default(),
+        })
+    }
+
+    /// Sends writes to `writer` instead of the read pool. Both must open the same database.
+    pub fn with_writer(mut self, writer: Db) -> Self {
+        self.writer = writer;
+        self
+    }
+}
+
+/// Stored as its snake_case name; the `zones_frequency_*` triggers reject others.
+#[derive(Debug, Serialize, Deserialize, ToSchema, sqlx::Type, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "snake_case")]
 pub enum Frequency {
     Daily,
     Weekly,
+    Biweekly,
+    /// Same day of the month as the last clean, clamped to shorter months.
     Monthly,
+    /// Same weekday and week of the month as the last clean, e.g. the second
+    /// Tuesday; a clean in the fifth week means the last such weekday.
+    #[serde(rename = "monthly_weekday")]
+    MonthlyWeekday,
+    Quarterly,
+    Yearly,
     Custom,
+    /// On the days listed in `weekdays`.
+    Weekdays,
 }
 
 impl Frequency {
@@ -24,26 +85,48 @@ impl Frequency {
         match self {
             Frequency::Daily => "daily",
             Frequency::Weekly => "weekly",
+            Frequency::Biweekly => "biweekly",
             Frequency::Monthly => "monthly",
+            Frequency::MonthlyWeekday => "monthly_weekday",
+            Frequency::Quarterly => "quarterly",
+            Frequency::Yearly => "yearly",
             Frequency::Custom => "custom",
+            Frequency::Weekdays => "weekdays",
         }
     }
-    pub fn from_str(s: &str) -> Option<Self> {
+}
+
+impl FromStr for Frequency {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "daily" => Some(Frequency::Daily),
-            "weekly" => Some(Frequency::Weekly),
-            "monthly" => Some(Frequency::Monthly),
-            "custom" => Some(Frequency::Custom),
-            _ => None,
+            "daily" => Ok(Frequency::Daily),
+            "weekly" => Ok(Frequency::Weekly),
+            "biweekly" => Ok(Frequency::Biweekly),
+            "monthly" => Ok(Frequency::Monthly),
+            "monthly_weekday" => Ok(Frequency::MonthlyWeekday),
+            "quarterly" => Ok(Frequency::Quarterly),
+            "yearly" => Ok(Frequency::Yearly),
+            "custom" => Ok(Frequency::Custom),
+            "weekdays" => Ok(Frequency::Weekdays),
+            _ => Err(()),
         }
     }
 }
 
+/// Column list for every `SELECT` that maps into [`Room`].
+pub const ROOM_COLUMNS: &str = "id, home_id, name, icon, sort_order, source, external_id, created_at, updated_at, deleted_at";
+
 #[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone)]
 pub struct Room {
     pub id: String,
+    pub home_id: Option<String>,
     pub name: String,
     pub icon: Option<String>,
+    pub sort_order: i64,
+    pub source: Option<String>,
+    pub external_id: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub deleted_at: Option<DateTime<Utc>>,
@@ -53,21 +136,155 @@ pub struct Room {
 pub struct RoomView {
     #[schema(example = "b0f7462c-6ca0-4a2a-9b77-1a64f1d76b2c")]
     pub id: String,
+    pub home_id: Option<String>,
     pub name: String,
     pub icon: Option<String>,
+    pub sort_order: i64,
+    pub source: Option<String>,
+    pub external_id: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub deleted_at: Option<DateTime<Utc>>,
     pub zones_total: Option<i64>,
     pub zones_cleaned_count: Option<i64>,
     pub last_cleaned_at: Option<DateTime<Utc>>,
+    /// The room's zones, with `include=zones`.
+    pub zones: Option<Vec<ZoneView>>,
+}
+
+impl From<Room> for RoomView {
+    fn from(r: Room) -> Self {
+        RoomView {
+            id: r.id,
+            home_id: r.home_id,
+            name: r.name,
+            icon: r.icon,
+            sort_order: r.sort_order,
+            source: r.source,
+            external_id: r.external_id,
+            created_at: r.created_at,
+            updated_at: r.updated_at,
+            deleted_at: r.deleted_at,
+            zones_total: None,
+            zones_cleaned_count: None,
+            last_cleaned_at: None,
+            zones: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct NewRoom {
+    pub name: String,
+    pub icon: Option<String>,
+    /// Home the room belongs to; the requested or default home when omitted.
+    pub home_id: Option<String>,
+    /// System the room was imported from; with `external_id` makes create an upsert.
+    pub source: Option<String>,
+    pub external_id: Option<String>,
+}
+
+/// PATCH field that tells "leave as is" (absent) from "clear" (`null`).
+/// Use with `#[serde(default)]` so a missing field stays `Absent`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum MaybeAbsent<T> {
+    #[default]
+    Absent,
+    Null,
+    Value(T),
+}
+
+impl<T> MaybeAbsent<T> {
+    pub fn is_absent(&self) -> bool {
+        matches!(self, MaybeAbsent::Absent)
+    }
+
+    /// The field after the patch.
+    pub fn apply(self, current: Option<T>) -> Option<T> {
+        match self {
+            MaybeAbsent::Absent => current,
+            MaybeAbsent::Null => None,
+            MaybeAbsent::Value(v) => Some(v),
+        }
+    }
+
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> MaybeAbsent<U> {
+        match self {
+            MaybeAbsent::Absent => MaybeAbsent::Absent,
+            MaybeAbsent::Null => MaybeAbsent::Null,
+            MaybeAbsent::Value(v) => MaybeAbsent::Value(f(v)),
+        }
+    }
+}
+
+/// Full-object writes (create, upsert) leave unset fields untouched.
+impl<T> From<Option<T>> for MaybeAbsent<T> {
+    fn from(v: Option<T>) -> Self {
+        v.map_or(MaybeAbsent::Absent, MaybeAbsent::Value)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for MaybeAbsent<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        // отсутствующее поле сюда не доходит: его даёт #[serde(default)]
+        Option::<T>::deserialize(d).map(|v| v.map_or(MaybeAbsent::Null, MaybeAbsent::Value))
+    }
+}
+
+impl<T: Serialize> Serialize for MaybeAbsent<T> {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        match self {
+            MaybeAbsent::Value(v) => s.serialize_some(v),
+            _ => s.serialize_none(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateRoom {
+    pub name: Option<String>,
+    /// `null` removes the icon.
+    #[serde(default, skip_serializing_if = "MaybeAbsent::is_absent")]
+    #[schema(value_type = Option<String>)]
+    pub icon: MaybeAbsent<String>,
+    /// Moves the room to another home.
+    pub home_id: Option<String>,
+}
+
+pub const HOME_COLUMNS: &str = "id, name, icon, is_default, created_at, updated_at, deleted_at";
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone)]
+pub struct Home {
+    pub id: String,
+    pub name: String,
+    pub icon: Option<String>,
+    /// Used when a request names no home.
+    pub is_default: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
-pub struct NewRoom { pub name: String, pub icon: Option<String> }
+pub struct NewHome {
+    pub name: String,
+    pub icon: Option<String>,
+    pub is_default: Option<bool>,
+}
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
-pub struct UpdateRoom { pub name: Option<String>, pub icon: Option<String> }
+pub struct UpdateHome {
+    pub name: Option<String>,
+    /// `null` removes the icon.
+    #[serde(default, skip_serializing_if = "MaybeAbsent::is_absent")]
+    #[schema(value_type = Option<String>)]
+    pub icon: MaybeAbsent<String>,
+    /// `true` makes this the default home; the previous default loses the flag.
+    pub is_default: Option<bool>,
+}
+
+/// Column list for every `SELECT` that maps into [`Zone`].
+pub const ZONE_COLUMNS: &str = "id, room_id, name, icon, notes, metadata, frequency, custom_interval_days, weekday_mask, auto, estimated_minutes, paused_until, schedule_anchor_at, last_cleaned_at, sort_order, source, external_id, created_at, updated_at, deleted_at";
 
 #[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone)]
 pub struct Zone {
@@ -75,9 +292,22 @@ pub struct Zone {
     pub room_id: String,
     pub name: String,
     pub icon: Option<String>,
-    pub frequency: String,
+    pub notes: Option<String>,
+    #[schema(value_type = Option<Object>)]
+    pub metadata: Option<Json<serde_json::Value>>,
+    pub frequency: Frequency,
     pub custom_interval_days: Option<i64>,
+    /// Days of a `weekdays` zone, see [`weekdays_to_mask`].
+    pub weekday_mask: Option<i64>,
+    pub auto: bool,
+    pub estimated_minutes: Option<i64>,
+    pub paused_until: Option<DateTime<Utc>>,
+    /// Set by a schedule change with `reschedule: now`; counts like a clean at that time.
+    pub schedule_anchor_at: Option<DateTime<Utc>>,
     pub last_cleaned_at: Option<DateTime<Utc>>,
+    pub sort_order: i64,
+    pub source: Option<String>,
+    pub external_id: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub deleted_at: Option<DateTime<Utc>>,
@@ -89,43 +319,767 @@ pub struct ZoneView {
     pub room_id: String,
     pub name: String,
     pub icon: Option<String>,
-    pub frequency: String,
+    pub notes: Option<String>,
+    /// Free-form integrator data, e.g. `{"ha_entity": "vacuum.kitchen"}`.
+    #[schema(value_type = Option<Object>)]
+    pub metadata: Option<serde_json::Value>,
+    pub frequency: Frequency,
     pub custom_interval_days: Option<i64>,
+    /// ISO weekdays (1 = Monday … 7 = Sunday) of a `weekdays` zone.
+    pub weekdays: Option<Vec<u8>>,
+    /// Cleaned by a machine: auto-cleaned on schedule and left out of `/zones/due`.
+    pub auto: bool,
+    /// Rough time a cleaning takes.
+    pub estimated_minutes: Option<i64>,
+    /// Snoozed until then: not due before it, whatever the schedule says.
+    pub paused_until: Option<DateTime<Utc>>,
+    /// The schedule counts from here instead of the last clean, if later.
+    pub schedule_anchor_at: Option<DateTime<Utc>>,
     pub last_cleaned_at: Option<DateTime<Utc>>,
     pub next_due_at: Option<DateTime<Utc>>,
     pub is_due: bool,
+    pub sort_order: i64,
+    pub source: Option<String>,
+    pub external_id: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub deleted_at: Option<DateTime<Utc>>,
 }
 
+impl From<Zone> for ZoneView {
+    fn from(z: Zone) -> Self {
+        ZoneView::localized(z, None)
+    }
+}
+
+impl ZoneView {
+    /// With a timezone, zones fall due at local midnight; see [`compute_next_due_local`].
+    pub fn localized(z: Zone, tz: Option<Tz>) -> Self {
+        let mut next_due = compute_next_due_local(
+            z.last_cleaned_at.max(z.schedule_anchor_at),
+            z.frequency,
+            z.custom_interval_days,
+            z.weekday_mask,
+            tz,
+        );
+        // пауза только откладывает срок; после неё расписание идёт как обычно
+        if let Some(until) = z.paused_until.filter(|&p| p > Utc::now()) {
+            next_due = Some(next_due.map_or(until, |d| d.max(until)));
+        }
+        ZoneView {
+            id: z.id,
+            room_id: z.room_id,
+            name: z.name,
+            icon: z.icon,
+            notes: z.notes,
+            metadata: z.metadata.map(|m| m.0),
+            frequency: z.frequency,
+            custom_interval_days: z.custom_interval_days,
+            weekdays: z.weekday_mask.map(mask_to_weekdays),
+            auto: z.auto,
+            estimated_minutes: z.estimated_minutes,
+            paused_until: z.paused_until,
+            schedule_anchor_at: z.schedule_anchor_at,
+            last_cleaned_at: z.last_cleaned_at,
+            next_due_at: next_due,
+            is_due: compute_is_due(next_due),
+            sort_order: z.sort_order,
+            source: z.source,
+            external_id: z.external_id,
+            created_at: z.created_at,
+            updated_at: z.updated_at,
+            deleted_at: z.deleted_at,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct NewZone {
     pub name: String,
     pub icon: Option<String>,
+    pub notes: Option<String>,
+    #[schema(value_type = Option<Object>)]
+    pub metadata: Option<serde_json::Value>,
     pub frequency: Frequency,
     pub custom_interval_days: Option<u16>,
+    /// ISO weekdays (1 = Monday … 7 = Sunday); required for `weekdays`.
+    pub weekdays: Option<Vec<u8>>,
+    pub auto: Option<bool>,
+    pub estimated_minutes: Option<u16>,
+    /// System the zone was imported from; with `external_id` makes create an upsert.
+    pub source: Option<String>,
+    pub external_id: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
 pub struct UpdateZone {
     pub name: Option<String>,
-    pub icon: Option<String>,
+    /// `null` removes the icon; the same for `notes`, `metadata` and the other nullable fields.
+    #[serde(default, skip_serializing_if = "MaybeAbsent::is_absent")]
+    #[schema(value_type = Option<String>)]
+    pub icon: MaybeAbsent<String>,
+    #[serde(default, skip_serializing_if = "MaybeAbsent::is_absent")]
+    #[schema(value_type = Option<String>)]
+    pub notes: MaybeAbsent<String>,
+    #[serde(default, skip_serializing_if = "MaybeAbsent::is_absent")]
+    #[schema(value_type = Option<Object>)]
+    pub metadata: MaybeAbsent<serde_json::Value>,
     pub frequency: Option<Frequency>,
-    pub custom_interval_days: Option<u16>,
+    #[serde(default, skip_serializing_if = "MaybeAbsent::is_absent")]
+    #[schema(value_type = Option<u16>)]
+    pub custom_interval_days: MaybeAbsent<u16>,
+    pub weekdays: Option<Vec<u8>>,
+    /// Where the next due date counts from when the schedule changes.
+    pub reschedule: Option<Reschedule>,
+    pub auto: Option<bool>,
+    /// `null` or `0` clears the estimate.
+    #[serde(default, skip_serializing_if = "MaybeAbsent::is_absent")]
+    #[schema(value_type = Option<u16>)]
+    pub estimated_minutes: MaybeAbsent<u16>,
+}
+
+/// Upserts apply a full zone as an update of the existing one.
+impl From<NewZone> for UpdateZone {
+    fn from(z: NewZone) -> Self {
+        UpdateZone {
+            name: Some(z.name),
+            icon: z.icon.into(),
+            notes: z.notes.into(),
+            metadata: z.metadata.into(),
+            frequency: Some(z.frequency),
+            custom_interval_days: z.custom_interval_days.into(),
+            weekdays: z.weekdays,
+            reschedule: None,
+            auto: z.auto,
+            estimated_minutes: z.estimated_minutes.into(),
+        }
+    }
+}
+
+impl NewZone {
+    /// The update a PUT makes: fields the body leaves out are cleared or reset
+    /// to their defaults, as they would be on create.
+    pub fn into_replacement(self) -> UpdateZone {
+        fn or_null<T>(v: Option<T>) -> MaybeAbsent<T> {
+            v.map_or(MaybeAbsent::Null, MaybeAbsent::Value)
+        }
+        UpdateZone {
+            name: Some(self.name),
+            icon: or_null(self.icon),
+            notes: or_null(self.notes),
+            metadata: or_null(self.metadata),
+            frequency: Some(self.frequency),
+            custom_interval_days: or_null(self.custom_interval_days),
+            weekdays: self.weekdays,
+            reschedule: None,
+            auto: Some(self.auto.unwrap_or(false)),
+            estimated_minutes: or_null(self.estimated_minutes),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Reschedule {
+    /// From the last clean, as if the new schedule had always applied.
+    #[default]
+    LastClean,
+    /// From the moment of the change.
+    Now,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone)]
+pub struct Tag {
+    pub id: String,
+    pub name: String,
+    pub color: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct NewTag { pub name: String, pub color: Option<String> }
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateTag {
+    pub name: Option<String>,
+    /// `null` removes the color.
+    #[serde(default, skip_serializing_if = "MaybeAbsent::is_absent")]
+    #[schema(value_type = Option<String>)]
+    pub color: MaybeAbsent<String>,
+}
+
+/// Workflow grouping of zones from any rooms, e.g. "Weekend reset".
+#[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone)]
+pub struct ZoneGroup {
+    pub id: String,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct NewZoneGroup { pub name: String }
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateZoneGroup { pub name: Option<String> }
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone)]
+pub struct Supply {
+    pub id: String,
+    pub name: String,
+    /// Free-form unit of `quantity`, e.g. "ml" or "pcs".
+    pub unit: Option<String>,
+    pub quantity: f64,
+    /// The supply is listed in `/supplies/low` once `quantity` drops to this value.
+    pub low_threshold: f64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct NewSupply {
+    pub name: String,
+    pub unit: Option<String>,
+    pub quantity: f64,
+    pub low_threshold: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateSupply {
+    pub name: Option<String>,
+    /// `null` removes the unit.
+    #[serde(default, skip_serializing_if = "MaybeAbsent::is_absent")]
+    #[schema(value_type = Option<String>)]
+    pub unit: MaybeAbsent<String>,
+    pub quantity: Option<f64>,
+    pub low_threshold: Option<f64>,
+}
+
+/// A supply used by a zone; `usage` is taken off the stock on every clean of the zone.
+#[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone)]
+pub struct ZoneSupply {
+    pub zone_id: String,
+    pub supply_id: String,
+    pub usage: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone)]
+pub struct SupplyPurchase {
+    pub id: String,
+    pub supply_id: String,
+    /// Added to the supply's stock.
+    pub quantity: f64,
+    pub price_cents: i64,
+    pub vendor: Option<String>,
+    pub purchased_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct NewSupplyPurchase {
+    pub quantity: f64,
+    pub price_cents: i64,
+    pub vendor: Option<String>,
+    pub purchased_at: Option<DateTime<Utc>>,
 }
 
-pub fn compute_next_due(last: Option<DateTime<Utc>>, freq: &str, custom: Option<i64>) -> Option<DateTime<Utc>> {
+#[derive(Debug, Serialize, Deserialize, ToSchema, Default)]
+pub struct LinkSupply {
+    /// Amount used per clean, 1 by default.
+    pub usage: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone)]
+pub struct Webhook {
+    pub id: String,
+    pub url: String,
+    /// Event names this hook receives, e.g. `zone.cleaned`.
+    #[schema(value_type = Vec<String>)]
+    pub events: Json<Vec<String>>,
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct NewWebhook {
+    pub url: String,
+    pub events: Vec<String>,
+    /// Key for the `X-Webhook-Signature` HMAC; never returned.
+    pub secret: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateWebhook {
+    pub url: Option<String>,
+    pub events: Option<Vec<String>>,
+    pub secret: Option<String>,
+    pub active: Option<bool>,
+}
+
+/// Inbound event of an integration and the zone it cleans.
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, PartialEq, Eq)]
+pub struct IntegrationMapping {
+    /// Event name the device sends, e.g. `vacuum.finished.kitchen`.
+    pub event: String,
+    pub zone_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone)]
+pub struct Integration {
+    pub id: String,
+    pub name: String,
+    #[schema(value_type = Vec<IntegrationMapping>)]
+    pub mappings: Json<Vec<IntegrationMapping>>,
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct NewIntegration {
+    pub name: String,
+    /// Key the device signs its calls with; never returned.
+    pub secret: String,
+    #[serde(default)]
+    pub mappings: Vec<IntegrationMapping>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateIntegration {
+    pub name: Option<String>,
+    pub secret: Option<String>,
+    pub mappings: Option<Vec<IntegrationMapping>>,
+    pub active: Option<bool>,
+}
+
+/// A connected Google calendar; its tokens are never returned.
+#[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone)]
+pub struct GoogleCalendar {
+    pub id: String,
+    pub home_id: Option<String>,
+    pub calendar_id: String,
+    pub last_synced_at: Option<DateTime<Utc>>,
+    /// Why the last sync failed; cleared by the next one that succeeds.
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+pub const GOOGLE_CALENDAR_COLUMNS: &str =
+    "id, home_id, calendar_id, last_synced_at, last_error, created_at, updated_at";
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone)]
+pub struct WebhookDelivery {
+    pub id: String,
+    pub webhook_id: String,
+    pub event: String,
+    pub attempts: i64,
+    pub next_attempt_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Something announced to the webhooks, kept for the in-app feed.
+#[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone)]
+pub struct Notification {
+    pub id: String,
+    pub event: String,
+    /// One-line text for display, e.g. `Fridge is due`.
+    pub summary: String,
+    #[schema(value_type = Object)]
+    pub data: Json<serde_json::Value>,
+    pub created_at: DateTime<Utc>,
+    pub read_at: Option<DateTime<Utc>>,
+}
+
+/// Instance-wide preferences; a single row.
+#[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone)]
+pub struct Settings {
+    /// Cap on zones shown by `/today`; the rest carry over. `None` means no cap.
+    pub max_zones_per_day: Option<i64>,
+    /// IANA timezone, e.g. `Europe/Moscow`. When set, zones fall due at local midnight.
+    pub timezone: Option<String>,
+    /// ISO weekday weeks start on in weekly stats: 1 = Monday … 7 = Sunday.
+    pub week_starts_on: i64,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateSettings {
+    /// `0` removes the cap.
+    pub max_zones_per_day: Option<u16>,
+    /// Empty string goes back to exact UTC times.
+    pub timezone: Option<String>,
+    pub week_starts_on: Option<u8>,
+}
+
+/// A single mutation in a zone's append-only history.
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ZoneChange {
+    Created {
+        room_id: String,
+        name: String,
+        icon: Option<String>,
+        notes: Option<String>,
+        #[schema(value_type = Option<Object>)]
+        metadata: Option<serde_json::Value>,
+        frequency: Frequency,
+        custom_interval_days: Option<i64>,
+        weekdays: Option<Vec<u8>>,
+        #[serde(default)]
+        auto: bool,
+        #[serde(default)]
+        estimated_minutes: Option<i64>,
+        source: Option<String>,
+        external_id: Option<String>,
+    },
+    Renamed {
+        name: String,
+    },
+    IconChanged {
+        icon: Option<String>,
+    },
+    NotesChanged {
+        notes: Option<String>,
+    },
+    MetadataChanged {
+        #[schema(value_type = Option<Object>)]
+        metadata: Option<serde_json::Value>,
+    },
+    FrequencyChanged {
+        frequency: Frequency,
+        custom_interval_days: Option<i64>,
+        weekdays: Option<Vec<u8>>,
+        #[serde(default)]
+        previous_frequency: Option<Frequency>,
+        #[serde(default)]
+        previous_custom_interval_days: Option<i64>,
+        #[serde(default)]
+        previous_weekdays: Option<Vec<u8>>,
+        /// Set when the schedule was restarted from the time of the change.
+        #[serde(default)]
+        anchor_at: Option<DateTime<Utc>>,
+    },
+    AutoChanged {
+        auto: bool,
+    },
+    EstimateChanged {
+        estimated_minutes: Option<i64>,
+    },
+    /// The record in another system the zone mirrors, replaced by a PUT.
+    ExternalRefChanged {
+        source: Option<String>,
+        external_id: Option<String>,
+    },
+    /// `until: None` resumes the zone.
+    Paused {
+        until: Option<DateTime<Utc>>,
+    },
+    Cleaned {
+        /// Per-cleaning remark, e.g. "ran out of descaler".
+        note: Option<String>,
+        /// Recorded by the auto-clean rule rather than a person.
+        #[serde(default)]
+        auto: bool,
+        /// Spending on this cleaning (hired help), in cents.
+        cost_cents: Option<i64>,
+        /// Time the cleaning took.
+        duration_minutes: Option<i64>,
+    },
+    Deleted,
+    /// Brought back after being deleted.
+    Restored,
+}
+
+impl ZoneChange {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ZoneChange::Created { .. } => "created",
+            ZoneChange::Renamed { .. } => "renamed",
+            ZoneChange::IconChanged { .. } => "icon_changed",
+            ZoneChange::NotesChanged { .. } => "notes_changed",
+            ZoneChange::MetadataChanged { .. } => "metadata_changed",
+            ZoneChange::FrequencyChanged { .. } => "frequency_changed",
+            ZoneChange::AutoChanged { .. } => "auto_changed",
+            ZoneChange::EstimateChanged { .. } => "estimate_changed",
+            ZoneChange::ExternalRefChanged { .. } => "external_ref_changed",
+            ZoneChange::Paused { .. } => "paused",
+            ZoneChange::Cleaned { .. } => "cleaned",
+            ZoneChange::Deleted => "deleted",
+            ZoneChange::Restored => "restored",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
+pub struct ZoneEvent {
+    pub id: String,
+    pub zone_id: String,
+    pub change: ZoneChange,
+    /// When the change happened on the client; drives replay order.
+    pub occurred_at: DateTime<Utc>,
+    pub recorded_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone)]
+pub struct ZoneTask {
+    pub id: String,
+    pub zone_id: String,
+    pub title: String,
+    /// Must be checked before the zone can be cleaned with `require_tasks`.
+    pub required: bool,
+    pub checked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct NewZoneTask {
+    pub title: String,
+    pub required: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateZoneTask {
+    pub title: Option<String>,
+    pub required: Option<bool>,
+    pub checked: Option<bool>,
+}
+
+/// A zone's checklist: its tasks and the instructions shown next to them.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct Checklist {
+    /// The user's own how-to for the zone, in markdown.
+    pub instructions: Option<String>,
+    pub tasks: Vec<ZoneTask>,
+}
+
+/// Body of `PUT /zones/{id}/instructions`; `null` or blank removes them.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ZoneInstructions {
+    pub instructions: Option<String>,
+}
+
+/// One page of a list; `next_cursor` is `None` on the last one.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[aliases(RoomPage = Page<RoomView>, ZonePage = Page<ZoneView>)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Matching items across all pages.
+    pub total: i64,
+    pub next_cursor: Option<String>,
+}
+
+/// Outcome of one item of a bulk request; the request itself succeeds regardless.
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkStatus {
+    Ok,
+    NotFound,
+    ValidationError,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
+pub struct BulkItem {
+    pub id: String,
+    pub status: BulkStatus,
+    /// What was wrong, for `validation_error`.
+    pub message: Option<String>,
+}
+
+impl BulkItem {
+    pub fn ok(id: impl Into<String>) -> Self {
+        BulkItem { id: id.into(), status: BulkStatus::Ok, message: None }
+    }
+
+    pub fn failed(id: impl Into<String>, err: &AppError) -> Self {
+        let status = match err {
+            AppError::NotFound => BulkStatus::NotFound,
+            _ => BulkStatus::ValidationError,
+        };
+        BulkItem { id: id.into(), status, message: Some(err.to_string()) }
+    }
+}
+
+/// Body of `POST /operations`: a bulk request on zones to run in the background.
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BulkOperation {
+    Clean {
+        zone_ids: Vec<String>,
+        /// Now when unset; fixed when the operation is accepted.
+        cleaned_at: Option<DateTime<Utc>>,
+    },
+    Delete {
+        zone_ids: Vec<String>,
+    },
+    Update {
+        zone_ids: Vec<String>,
+        changes: UpdateZone,
+    },
+}
+
+impl BulkOperation {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            BulkOperation::Clean { .. } => "clean",
+            BulkOperation::Delete { .. } => "delete",
+            BulkOperation::Update { .. } => "update",
+        }
+    }
+
+    pub fn zone_ids(&self) -> &[String] {
+        match self {
+            BulkOperation::Clean { zone_ids, .. }
+            | BulkOperation::Delete { zone_ids }
+            | BulkOperation::Update { zone_ids, .. } => zone_ids,
+        }
+    }
+}
+
+/// Column list for every `SELECT` that maps into [`Operation`].
+pub const OPERATION_COLUMNS: &str =
+    "id, kind, status, total, processed, updated, results, error, created_at, updated_at, finished_at";
+
+/// Progress of a background bulk operation.
+#[derive(Debug, Serialize, Deserialize, ToSchema, FromRow, Clone)]
+pub struct Operation {
+    pub id: String,
+    /// `clean`, `delete` or `update`.
+    pub kind: String,
+    /// `pending`, `running`, `done` or `failed`.
+    pub status: String,
+    /// Zones in the request.
+    pub total: i64,
+    /// Zones handled so far; `results` has one entry for each.
+    pub processed: i64,
+    /// Zones the operation changed.
+    pub updated: i64,
+    #[schema(value_type = Vec<BulkItem>)]
+    pub results: Json<Vec<BulkItem>>,
+    /// Why the operation stopped, for `failed`.
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PauseZone {
+    /// Must be in the future.
+    pub until: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct Reorder {
+    /// Ids in the desired display order.
+    pub ids: Vec<String>,
+}
+
+impl Reorder {
+    /// Rejects an id listed twice; its later position would silently win.
+    pub fn validate(&self) -> AppResult<()> {
+        let mut seen = std::collections::HashSet::new();
+        match self.ids.iter().find(|id| !seen.insert(id.as_str())) {
+            Some(id) => Err(AppError::Validation(format!("id {id} is listed more than once"))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// `source` and `external_id` identify a record in another system and only make sense together.
+pub fn validate_external_ref(source: &Option<String>, external_id: &Option<String>) -> AppResult<()> {
+    match (source, external_id) {
+        (Some(s), Some(e)) if !s.trim().is_empty() && !e.trim().is_empty() => Ok(()),
+        (None, None) => Ok(()),
+        _ => Err(AppError::Validation(
+            "source and external_id must be given together".into(),
+        )),
+    }
+}
+
+/// Bit 0 is Monday, bit 6 is Sunday; `days` are ISO weekdays (1..=7).
+pub fn weekdays_to_mask(days: &[u8]) -> i64 {
+    days.iter().fold(0, |mask, d| mask | 1 << (d - 1))
+}
+
+pub fn mask_to_weekdays(mask: i64) -> Vec<u8> {
+    (1..=7u8).filter(|d| mask & (1 << (d - 1)) != 0).collect()
+}
+
+pub fn compute_next_due(
+    last: Option<DateTime<Utc>>,
+    freq: Frequency,
+    custom: Option<i64>,
+    weekday_mask: Option<i64>,
+) -> Option<DateTime<Utc>> {
     let last = last?;
-    match Frequency::from_str(freq) {
-        Some(Frequency::Daily) => Some(last + chrono::Duration::days(1)),
-        Some(Frequency::Weekly) => Some(last + chrono::Duration::weeks(1)),
-        Some(Frequency::Monthly) => Some(last + chrono::Duration::days(30)), // упрощённо
-        Some(Frequency::Custom) => Some(last + chrono::Duration::days(custom.unwrap_or(1))),
-        None => None,
+    match freq {
+        Frequency::Daily => Some(last + chrono::Duration::days(1)),
+        Frequency::Weekly => Some(last + chrono::Duration::weeks(1)),
+        Frequency::Biweekly => Some(last + chrono::Duration::weeks(2)),
+        // 31 января → 28/29 февраля; дальше снова от фактической даты уборки
+        Frequency::Monthly => last.checked_add_months(Months::new(1)),
+        Frequency::MonthlyWeekday => {
+            let date = last.date_naive();
+            let next = date.with_day(1)?.checked_add_months(Months::new(1))?;
+            let nth = date.day0() / 7 + 1;
+            let on = |n| NaiveDate::from_weekday_of_month_opt(next.year(), next.month(), date.weekday(), n);
+            // пятая неделя — «последний» такой день, которого в следующем месяце может не быть
+            let day = if nth < 5 { on(nth as u8) } else { on(5).or_else(|| on(4)) }?;
+            Some(day.and_time(last.time()).and_utc())
+        }
+        Frequency::Quarterly => last.checked_add_months(Months::new(3)),
+        Frequency::Yearly => last.checked_add_months(Months::new(12)),
+        Frequency::Custom => Some(last + chrono::Duration::days(custom.unwrap_or(1))),
+        Frequency::Weekdays => {
+            // ближайший отмеченный день после дня уборки, с полуночи
+            let mask = weekday_mask.filter(|m| m & 0x7f != 0)?;
+            let mut day = last.date_naive();
+            loop {
+                day = day.succ_opt()?;
+                if mask & (1 << day.weekday().num_days_from_monday()) != 0 {
+                    return Some(day.and_hms_opt(0, 0, 0)?.and_utc());
+                }
+            }
+        }
     }
 }
 
+/// Like [`compute_next_due`], but with `tz` the schedule runs on local dates and
+/// the zone falls due at the start of the local due day, so "due today" flips at
+/// local midnight rather than at the time of day of the last clean.
+pub fn compute_next_due_local(
+    last: Option<DateTime<Utc>>,
+    freq: Frequency,
+    custom: Option<i64>,
+    weekday_mask: Option<i64>,
+    tz: Option<Tz>,
+) -> Option<DateTime<Utc>> {
+    let Some(tz) = tz else {
+        return compute_next_due(last, freq, custom, weekday_mask);
+    };
+    // считаем на локальных «настенных» часах, потом возвращаемся в UTC
+    let wall = last?.with_timezone(&tz).naive_local().and_utc();
+    let next = compute_next_due(Some(wall), freq, custom, weekday_mask)?;
+    let midnight = next.date_naive().and_hms_opt(0, 0, 0)?;
+    tz.from_local_datetime(&midnight)
+        .earliest()
+        // полночь выпала на переход на летнее время
+        .or_else(|| tz.from_local_datetime(&(midnight + chrono::Duration::hours(1))).earliest())
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
 pub fn compute_is_due(next_due: Option<DateTime<Utc>>) -> bool {
     match next_due {
         Some(dt) => chrono::Utc::now() >= dt,
@@ -0,0 +1,124 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use thiserror::Error;
+
+#[derive(Debug, Clone)]
+pub struct OutboundConfig {
+    /// Proxy for all outbound traffic; when unset the standard `HTTP(S)_PROXY` env vars apply.
+    pub proxy: Option<String>,
+    pub connect_timeout: Duration,
+    pub read_timeout: Duration,
+    /// Consecutive failures after which a destination host is short-circuited.
+    pub breaker_threshold: u32,
+    pub breaker_cooldown: Duration,
+}
+
+impl Default for OutboundConfig {
+    fn default() -> Self {
+        Self {
+            proxy: None,
+            connect_timeout: Duration::from_secs(5),
+            read_timeout: Duration::from_secs(15),
+            breaker_threshold: 5,
+            breaker_cooldown: Duration::from_secs(60),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum OutboundError {
+    #[error("circuit open for {0}")]
+    CircuitOpen(String),
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+}
+
+#[derive(Default)]
+struct Breaker {
+    failures: u32,
+    open_until: Option<Instant>,
+    /// Start of the one request let through once the cooldown is over.
+    probe_since: Option<Instant>,
+}
+
+/// Shared HTTP client for webhooks, push, email and other outbound integrations.
+pub struct OutboundClient {
+    client: reqwest::Client,
+    threshold: u32,
+    cooldown: Duration,
+    breakers: Mutex<HashMap<String, Breaker>>,
+}
+
+impl OutboundClient {
+    pub fn new(cfg: &OutboundConfig) -> Result<Self, OutboundError> {
+        let mut builder = reqwest::Client::builder()
+            .connect_timeout(cfg.connect_timeout)
+            .read_timeout(cfg.read_timeout)
+            .user_agent(concat!("cleaner-api/", env!("CARGO_PKG_VERSION")));
+        if let Some(proxy) = &cfg.proxy {
+            builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+        }
+        Ok(Self {
+            client: builder.build()?,
+            threshold: cfg.breaker_threshold.max(1),
+            cooldown: cfg.breaker_cooldown,
+            breakers: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn client(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    /// Sends the request unless the destination's breaker is open.
+    /// Transport errors and 5xx responses count as failures for the host.
+    pub async fn execute(&self, req: reqwest::Request) -> Result<reqwest::Response, OutboundError> {
+        let host = req.url().host_str().unwrap_or_default().to_string();
+        if !self.admit(&host) {
+            return Err(OutboundError::CircuitOpen(host));
+        }
+        let res = self.client.execute(req).await;
+        let ok = matches!(&res, Ok(r) if !r.status().is_server_error());
+        self.record(&host, ok);
+        Ok(res?)
+    }
+
+    /// Closed lets everything through; open nothing; half-open (cooldown
+    /// over) a single probe at a time.
+    fn admit(&self, host: &str) -> bool {
+        let mut breakers = self.breakers.lock().unwrap();
+        let Some(b) = breakers.get_mut(host) else { return true };
+        let Some(until) = b.open_until else { return true };
+        let now = Instant::now();
+        if now < until {
+            return false;
+        }
+        // проба, чей запрос бросили без ответа, не держит цепь вечно
+        match b.probe_since {
+            Some(since) if now < since + self.cooldown.max(Duration::from_secs(1)) => false,
+            _ => {
+                b.probe_since = Some(now);
+                true
+            }
+        }
+    }
+
+    fn record(&self, host: &str, ok: bool) {
+        let mut breakers = self.breakers.lock().unwrap();
+        if ok {
+            breakers.remove(host);
+            return;
+        }
+        let b = breakers.entry(host.to_string()).or_default();
+        b.failures += 1;
+        b.probe_since = None;
+        // после кулдауна пропускаем одну пробную попытку; её неудача снова открывает цепь
+        if b.failures >= self.threshold {
+            b.open_until = Some(Instant::now() + self.cooldown);
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use std::{collections::HashSet, future::Future, sync::Arc, time::Duration};
+
+use chrono::Utc;
+use tokio::{sync::watch, task::JoinHandle};
+
+use crate::{
+    api,
+    config::Config,
+    error::AppResult,
+    google_calendar, jobs, metrics,
+    models::{AppState, Zone, ZoneView, ZONE_COLUMNS},
+    webhooks,
+};
+
+/// The running background jobs, see [`spawn`].
+pub struct Scheduler {
+    stop: watch::Sender<bool>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl Scheduler {
+    /// Lets runs in progress finish, then ends every job loop. Bulk operations
+    /// keep their progress in the database, so one cut short by a timeout
+    /// resumes on the next start.
+    pub async fn stop(self) {
+        let _ = self.stop.send(true);
+        for task in self.tasks {
+            let _ = task.await;
+        }
+    }
+}
+
+/// Starts the periodic background jobs. Each job holds a lease while this
+/// instance keeps running it, so with several instances on one database only one
+/// of them does the work; stopping hands the leases back.
+pub fn spawn(state: Arc<AppState>, config: &Config) -> Scheduler {
+    let (stop, stopped) = watch::channel(false);
+    // снимок живёт два интервала: пропущенный запуск не обнуляет метрики
+    let metrics_ttl = config.metrics_interval * 2;
+    let tasks = vec![
+        spawn_job(state.clone(), stopped.clone(), "auto_clean", config.auto_clean_interval, |s| async move {
+            api::zones::run_auto_clean(&s).await
+        }),
+        spawn_job(state.clone(), stopped.clone(), "due_scan", config.due_scan_interval, |s| async move {
+            scan_due(&s).await
+        }),
+        spawn_job(state.clone(), stopped.clone(), "webhooks", config.webhook_interval, |s| async move {
+            webhooks::deliver_pending(&s).await
+        }),
+        spawn_job(
+            state.clone(),
+            stopped.clone(),
+            api::operations::JOB,
+            config.operations_interval,
+            |s| async move { api::operations::run_pending(&s).await },
+        ),
+        spawn_job(
+            state.clone(),
+            stopped.clone(),
+            "google_calendar",
+            config.google.sync_interval,
+            |s| async move { google_calendar::sync(&s).await },
+        ),
+        spawn_job(state, stopped, "metrics", config.metrics_interval, move |s| async move {
+            metrics::refresh(&s, metrics_ttl).await
+        }),
+    ];
+    Scheduler { stop, tasks }
+}
+
+fn spawn_job<F, Fut>(
+    state: Arc<AppState>,
+    mut stopped: watch::Receiver<bool>,
+    job: &'static str,
+    every: Duration,
+    task: F,
+) -> JoinHandle<()>
+where
+    F: Fn(Arc<AppState>) -> Fut + Send + 'static,
+    Fut: Future<Output = AppResult<u64>> + Send,
+{
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(every);
+        let ttl = every.max(Duration::from_secs(60));
+        loop {
+            // остановка важнее очередного запуска; начатый запуск не прерывается
+            tokio::select! {
+                biased;
+                _ = stopped.changed() => break,
+                _ = tick.tick() => {}
+            }
+            match jobs::run_exclusive(&state.writer, job, ttl, task(state.clone())).await {
+                Ok(Some(n)) if n > 0 => tracing::info!(job, n, "scheduled job done"),
+                Ok(_) => {}
+                Err(e) => tracing::warn!(job, error = %e, "scheduled job failed"),
+            }
+        }
+        // аренда переживает запуск; при остановке отдаём её сразу другим экземплярам
+        if let Err(e) = jobs::release(&state.writer, job, jobs::instance_id()).await {
+            tracing::warn!(job, error = %e, "lease release failed");
+        }
+        tracing::debug!(job, "scheduled job stopped");
+    })
+}
+
+/// Finds zones that have become due since the last scan, stamps `due_since` and
+/// queues `zone.due` webhooks for them. Zones that stopped being due without a
+/// clean (e.g. after a frequency change) are re-armed. Auto zones are skipped.
+/// Returns how many zones became due.
+pub async fn scan_due(state: &AppState) -> AppResult<u64> {
+    let zones = sqlx::query_as::<_, Zone>(&format!(
+        "SELECT {ZONE_COLUMNS} FROM zones WHERE auto = 0 AND deleted_at IS NULL"
+    ))
+    .fetch_all(&state.pool)
+    .await?;
+    let notified: HashSet<String> = sqlx::query_as::<_, (String,)>(
+        "SELECT id FROM zones WHERE due_since IS NOT NULL AND deleted_at IS NULL",
+    )
+    .fetch_all(&state.pool)
+    .await?
+    .into_iter()
+    .map(|(id,)| id)
+    .collect();
+    let tz = api::settings::timezone(&state.pool).await?;
+
+    let now = Utc::now();
+    let mut became_due = 0u64;
+    let mut tx = state.writer.begin().await?;
+    for z in zones {
+        let view = ZoneView::localized(z, tz);
+        match (view.is_due, notified.contains(&view.id)) {
+            (true, false) => {
+                sqlx::query("UPDATE zones SET due_since = ?1 WHERE id = ?2")
+                    .bind(now)
+                    .bind(&view.id)
+                    .execute(&mut *tx)
+                    .await?;
+                let data = serde_json::json!({
+                    "zone_id": view.id,
+                    "room_id": view.room_id,
+                    "name": view.name,
+                    "next_due_at": view.next_due_at,
+                });
+                webhooks::enqueue(&mut tx, webhooks::ZONE_DUE, data, now).await?;
+                became_due += 1;
+            }
+            (false, true) => {
+                sqlx::query("UPDATE zones SET due_since = NULL WHERE id = ?1")
+                    .bind(&view.id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            _ => {}
+        }
+    }
+    tx.commit().await?;
+    Ok(became_due)
+}
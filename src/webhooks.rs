@@ -0,0 +1,173 @@
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use sqlx::{types::Json, FromRow, SqliteConnection};
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, AppResult},
+    models::AppState,
+};
+
+pub const ZONE_CLEANED: &str = "zone.cleaned";
+pub const ROOM_DELETED: &str = "room.deleted";
+pub const ZONE_DUE: &str = "zone.due";
+/// Event names a webhook may subscribe to.
+pub const EVENTS: [&str; 3] = [ZONE_CLEANED, ROOM_DELETED, ZONE_DUE];
+
+/// Deliveries are dropped after this many failed attempts.
+pub const MAX_ATTEMPTS: i64 = 8;
+const BATCH: i64 = 50;
+
+#[derive(Serialize)]
+struct Envelope<'a, T: Serialize> {
+    event: &'a str,
+    occurred_at: DateTime<Utc>,
+    data: T,
+}
+
+/// Queues `event` for every active webhook subscribed to it and, when any is,
+/// keeps it in `notifications`. Call it on the transaction that makes the
+/// change so nothing is announced that was rolled back.
+pub async fn enqueue<T: Serialize>(
+    conn: &mut SqliteConnection,
+    event: &str,
+    data: T,
+    occurred_at: DateTime<Utc>,
+) -> AppResult<()> {
+    let data = serde_json::to_value(data).map_err(|e| AppError::Other(e.into()))?;
+    let payload = serde_json::to_string(&Envelope { event, occurred_at, data: &data })
+        .map_err(|e| AppError::Other(e.into()))?;
+    let now = Utc::now();
+    let queued = sqlx::query(
+        r#"INSERT INTO webhook_deliveries(id, webhook_id, event, payload, attempts, next_attempt_at, created_at)
+           SELECT lower(hex(randomblob(16))), w.id, ?1, ?2, 0, ?3, ?3
+           FROM webhooks w
+           WHERE w.active = 1 AND w.deleted_at IS NULL
+             AND EXISTS (SELECT 1 FROM json_each(w.events) WHERE value = ?1)"#,
+    )
+    .bind(event)
+    .bind(payload)
+    .bind(now)
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+    if queued == 0 {
+        return Ok(());
+    }
+
+    let summary = summarize(&mut *conn, event, &data).await?;
+    sqlx::query("INSERT INTO notifications(id, event, summary, data, created_at) VALUES (?1, ?2, ?3, ?4, ?5)")
+        .bind(Uuid::new_v4().to_string())
+        .bind(event)
+        .bind(summary)
+        .bind(Json(&data))
+        .bind(now)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// One-line text of a notification, named after the zone or room it is about.
+async fn summarize(conn: &mut SqliteConnection, event: &str, data: &serde_json::Value) -> AppResult<String> {
+    let (table, key) = match event {
+        ROOM_DELETED => ("rooms", "room_id"),
+        _ => ("zones", "zone_id"),
+    };
+    let name: Option<(String,)> = sqlx::query_as(&format!("SELECT name FROM {table} WHERE id = ?1"))
+        .bind(data[key].as_str())
+        .fetch_optional(&mut *conn)
+        .await?;
+    let name = name.map_or_else(|| "Unknown".to_string(), |(n,)| n);
+    Ok(match event {
+        ZONE_CLEANED => format!("{name} cleaned"),
+        ZONE_DUE => format!("{name} is due"),
+        ROOM_DELETED => format!("Room {name} deleted"),
+        other => format!("{name}: {other}"),
+    })
+}
+
+/// Hex HMAC-SHA256 of `body`, sent as `X-Webhook-Signature: sha256=<hex>`.
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any size");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+#[derive(FromRow)]
+struct Pending {
+    id: String,
+    event: String,
+    payload: String,
+    attempts: i64,
+    url: String,
+    secret: String,
+}
+
+/// Sends due deliveries once. Failures are retried with exponential backoff
+/// until `MAX_ATTEMPTS`. Returns how many were delivered.
+pub async fn deliver_pending(state: &AppState) -> AppResult<u64> {
+    let now = Utc::now();
+    let pending = sqlx::query_as::<_, Pending>(
+        r#"SELECT d.id, d.event, d.payload, d.attempts, w.url, w.secret
+           FROM webhook_deliveries d JOIN webhooks w ON w.id = d.webhook_id
+           WHERE d.delivered_at IS NULL AND d.attempts < ?1 AND d.next_attempt_at <= ?2
+             AND w.active = 1 AND w.deleted_at IS NULL
+           ORDER BY d.next_attempt_at ASC
+           LIMIT ?3"#,
+    )
+    .bind(MAX_ATTEMPTS)
+    .bind(now)
+    .bind(BATCH)
+    .fetch_all(&state.pool)
+    .await?;
+
+    let mut delivered = 0u64;
+    for d in pending {
+        match send(state, &d).await {
+            Ok(()) => {
+                sqlx::query("UPDATE webhook_deliveries SET attempts = attempts + 1, delivered_at = ?1, last_error = NULL WHERE id = ?2")
+                    .bind(Utc::now())
+                    .bind(&d.id)
+                    .execute(&state.writer)
+                    .await?;
+                delivered += 1;
+            }
+            Err(err) => {
+                // 30s, 1m, 2m, ... но не реже раза в 6 часов
+                let backoff = chrono::Duration::seconds((30i64 << d.attempts.min(10)).min(6 * 3600));
+                tracing::warn!(delivery = %d.id, error = %err, "webhook delivery failed");
+                sqlx::query(
+                    "UPDATE webhook_deliveries SET attempts = attempts + 1, next_attempt_at = ?1, last_error = ?2 WHERE id = ?3",
+                )
+                .bind(Utc::now() + backoff)
+                .bind(err)
+                .bind(&d.id)
+                .execute(&state.writer)
+                .await?;
+            }
+        }
+    }
+    Ok(delivered)
+}
+
+async fn send(state: &AppState, d: &Pending) -> Result<(), String> {
+    let secret = state.cipher.open(&d.secret).map_err(|e| e.to_string())?;
+    let req = state
+        .http
+        .client()
+        .post(&d.url)
+        .header("content-type", "application/json")
+        .header("x-webhook-event", &d.event)
+        .header("x-webhook-delivery", &d.id)
+        .header("x-webhook-signature", format!("sha256={}", sign(&secret, d.payload.as_bytes())))
+        .body(d.payload.clone())
+        .build()
+        .map_err(|e| e.to_string())?;
+    let res = state.http.execute(req).await.map_err(|e| e.to_string())?;
+    if !res.status().is_success() {
+        return Err(format!("status {}", res.status()));
+    }
+    Ok(())
+}
@@ -0,0 +1,73 @@
+use cleaner_api::anonymize;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+
+#[tokio::test]
+async fn copy_keeps_rows_but_drops_text_and_secrets() {
+    // VACUUM INTO needs a file-backed source, not `sqlite::memory:`
+    let dir = std::env::temp_dir();
+    let src = dir.join(format!("cleaner-{}.db", uuid::Uuid::new_v4()));
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(SqliteConnectOptions::new().filename(&src).create_if_missing(true))
+        .await
+        .unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+    sqlx::query(
+        r#"INSERT INTO rooms(id, home_id, name, sort_order, created_at, updated_at)
+           SELECT 'r1', id, 'Kitchen', 0, '2025-09-01T00:00:00Z', '2025-09-01T00:00:00Z' FROM homes"#,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        r#"INSERT INTO zones(id, room_id, name, notes, frequency, created_at, updated_at)
+           VALUES ('z1', 'r1', 'Fridge', 'spare key under the mat', 'weekly', '2025-09-01T00:00:00Z', '2025-09-01T00:00:00Z')"#,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        r#"INSERT INTO zone_events(id, zone_id, kind, payload, occurred_at, recorded_at)
+           VALUES ('e1', 'z1', 'cleaned', '{"kind":"cleaned","note":"call Anna","auto":false,"cost_cents":null}',
+                   '2025-09-02T00:00:00Z', '2025-09-02T00:00:00Z')"#,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        r#"INSERT INTO webhooks(id, url, secret, events, active, created_at, updated_at)
+           VALUES ('w1', 'https://hooks.example.com/x', 'supersecretvalue', '["zone.due"]', 1, '2025-09-01T00:00:00Z', '2025-09-01T00:00:00Z')"#,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let dest = dir.join(format!("anonymized-{}.db", uuid::Uuid::new_v4()));
+    let dest = dest.to_str().unwrap().to_string();
+    anonymize::anonymize(&pool, &dest).await.unwrap();
+
+    let copy = SqlitePoolOptions::new()
+        .connect_with(SqliteConnectOptions::new().filename(&dest))
+        .await
+        .unwrap();
+    let (name, notes): (String, String) = sqlx::query_as("SELECT name, notes FROM zones WHERE id = 'z1'")
+        .fetch_one(&copy)
+        .await
+        .unwrap();
+    assert_eq!(name, "Fridge");
+    assert!(!notes.contains("key"));
+    let (payload,): (String,) = sqlx::query_as("SELECT payload FROM zone_events WHERE id = 'e1'")
+        .fetch_one(&copy)
+        .await
+        .unwrap();
+    assert!(!payload.contains("Anna"));
+    let (secret,): (String,) = sqlx::query_as("SELECT secret FROM webhooks")
+        .fetch_one(&copy)
+        .await
+        .unwrap();
+    assert_eq!(secret, "");
+    copy.close().await;
+    pool.close().await;
+    std::fs::remove_file(&dest).unwrap();
+    std::fs::remove_file(&src).unwrap();
+}
@@ -0,0 +1,18 @@
+use std::time::Duration;
+
+use cleaner_api::cache::Cache;
+
+#[tokio::test]
+async fn memory_cache_expires_entries_and_counters() {
+    let cache = Cache::connect(None).await.unwrap();
+
+    cache.set("k", "v".into(), Duration::from_millis(50)).await.unwrap();
+    assert_eq!(cache.get("k").await.unwrap().as_deref(), Some("v"));
+
+    assert_eq!(cache.incr("hits", Duration::from_millis(50)).await.unwrap(), 1);
+    assert_eq!(cache.incr("hits", Duration::from_millis(50)).await.unwrap(), 2);
+
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    assert_eq!(cache.get("k").await.unwrap(), None);
+    assert_eq!(cache.incr("hits", Duration::from_millis(50)).await.unwrap(), 1);
+}
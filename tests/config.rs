@@ -0,0 +1,73 @@
+use std::{collections::HashMap, time::Duration};
+
+use cleaner_api::config::{Config, ConfigError, Environment, FileConfig};
+
+#[test]
+fn env_overrides_file_overrides_defaults() {
+    let file = FileConfig::parse(
+        "cleaner.toml",
+        r#"
+        host = "0.0.0.0"
+        port = 9000
+        db_max_connections = 8
+        cors_allowed_origins = ["https://app.example.com"]
+
+        [outbound]
+        read_timeout_secs = 30
+        "#,
+    )
+    .unwrap();
+    let env: HashMap<&str, &str> = [("APP_PORT", "9100"), ("DUE_SCAN_INTERVAL_SECS", "15"), ("CACHE_URL", " ")].into();
+    let config = Config::layered(file, |k| env.get(k).map(|v| v.to_string())).unwrap();
+
+    assert_eq!(config.host.to_string(), "0.0.0.0");
+    assert_eq!(config.port, 9100);
+    assert_eq!(config.db_max_connections, 8);
+    assert_eq!(config.due_scan_interval, Duration::from_secs(15));
+    assert_eq!(config.cors_allowed_origins, ["https://app.example.com"]);
+    assert_eq!(config.outbound.read_timeout, Duration::from_secs(30));
+    // unset and blank values keep the default
+    assert_eq!(config.cache_url, None);
+    assert_eq!(config.webhook_interval, Config::default().webhook_interval);
+}
+
+#[test]
+fn bad_settings_name_what_to_fix() {
+    let err = FileConfig::parse("cleaner.toml", "prot = 80").err().unwrap();
+    assert!(matches!(err, ConfigError::Parse { .. }));
+    assert!(err.to_string().contains("prot"), "{err}");
+
+    let layered = |key: &'static str, value: &'static str| {
+        Config::layered(FileConfig::default(), |k| (k == key).then(|| value.to_string())).unwrap_err().to_string()
+    };
+    assert_eq!(layered("APP_PORT", "eighty"), "APP_PORT: cannot parse \"eighty\"");
+    assert_eq!(layered("APP_HOST", "localhost"), "APP_HOST: cannot parse \"localhost\"");
+    assert_eq!(layered("APP_PORT", "0"), "APP_PORT must not be 0");
+    assert!(layered("GRPC_PORT", "8080").starts_with("GRPC_PORT"));
+    assert!(layered("GOOGLE_CLIENT_ID", "id.apps.googleusercontent.com").starts_with("GOOGLE_CLIENT_ID"));
+    assert!(layered("CORS_ALLOWED_ORIGINS", "app.example.com").starts_with("CORS_ALLOWED_ORIGINS"));
+    assert!(layered("CORS_ALLOWED_ORIGINS", "https://app.example.com\u{7f}").starts_with("CORS_ALLOWED_ORIGINS"));
+    assert!(layered("CORS_ALLOWED_ORIGINS", "https://приложение.example").starts_with("CORS_ALLOWED_ORIGINS"));
+    assert!(layered("DATABASE_URL", "postgres://db").starts_with("DATABASE_URL"));
+    // the key itself stays out of the message
+    assert_eq!(layered("ENCRYPTION_KEY", "hunter2"), "ENCRYPTION_KEY must be 64 hex characters (a 32-byte key)");
+}
+
+#[test]
+fn production_refuses_an_in_memory_database() {
+    let env = |pairs: &'static [(&'static str, &'static str)]| {
+        move |k: &str| pairs.iter().find(|(key, _)| *key == k).map(|(_, v)| v.to_string())
+    };
+    let dev = Config::layered(FileConfig::default(), env(&[("DATABASE_URL", "sqlite::memory:")])).unwrap();
+    assert_eq!(dev.env, Environment::Development);
+
+    let err = Config::layered(
+        FileConfig::default(),
+        env(&[("APP_ENV", "Production"), ("DATABASE_URL", "sqlite::memory:")]),
+    )
+    .unwrap_err();
+    assert!(err.to_string().starts_with("DATABASE_URL"), "{err}");
+
+    let err = Config::layered(FileConfig::default(), env(&[("APP_ENV", "staging")])).unwrap_err();
+    assert_eq!(err.to_string(), "APP_ENV: cannot parse \"staging\"");
+}
@@ -0,0 +1,97 @@
+use std::sync::{Arc, Mutex};
+
+use axum::{
+    body::{Body, Bytes},
+    http::{HeaderMap, Request, StatusCode},
+    routing::post,
+    Router,
+};
+use cleaner_api::{
+    api,
+    config::Config,
+    crypto::{self, Cipher, Key},
+    models::AppState,
+    webhooks,
+};
+use serde_json::json;
+use sqlx::sqlite::SqlitePoolOptions;
+use tower::ServiceExt; // for oneshot
+
+fn key(byte: &str) -> Key {
+    byte.repeat(32).parse().unwrap()
+}
+
+#[test]
+fn seal_opens_with_current_and_old_keys_only() {
+    let old = Cipher::new(Some(key("0a")), Vec::new());
+    let sealed = old.seal("0123456789abcdef");
+    assert!(sealed.starts_with("enc:v1:"));
+    assert!(!sealed.contains("0123456789abcdef"));
+    assert_ne!(sealed, old.seal("0123456789abcdef"), "nonces differ");
+
+    let rotated = Cipher::new(Some(key("0b")), vec![key("0a")]);
+    assert_eq!(rotated.open(&sealed).unwrap(), "0123456789abcdef");
+    assert!(Cipher::new(Some(key("0b")), Vec::new()).open(&sealed).is_err());
+    // значения до включения шифрования читаются как есть
+    assert_eq!(rotated.open("plain-secret").unwrap(), "plain-secret");
+    assert_eq!(Cipher::default().seal("plain-secret"), "plain-secret");
+}
+
+#[tokio::test]
+async fn webhook_secrets_are_sealed_and_resealed_on_rotation() {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+    let config = Config { encryption_key: Some(key("0a")), ..Config::default() };
+    let state = Arc::new(AppState::new(pool.clone(), &config).await.unwrap());
+    let app = Router::new().nest("/api/v1", api::routes()).with_state(state.clone());
+
+    let received: Arc<Mutex<Vec<(HeaderMap, Bytes)>>> = Arc::default();
+    let sink = received.clone();
+    let hook = Router::new().route(
+        "/hook",
+        post(move |headers: HeaderMap, body: Bytes| async move {
+            sink.lock().unwrap().push((headers, body));
+            StatusCode::NO_CONTENT
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, hook).await.unwrap() });
+
+    let secret = "0123456789abcdef";
+    let body = json!({"url": url, "events": ["zone.cleaned"], "secret": secret});
+    let res = app
+        .clone()
+        .oneshot(
+            Request::post("/api/v1/webhooks")
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let (stored,): (String,) = sqlx::query_as("SELECT secret FROM webhooks").fetch_one(&pool).await.unwrap();
+    assert!(stored.starts_with("enc:v1:") && !stored.contains(secret), "{stored}");
+
+    // подпись считается по открытому секрету
+    webhooks::enqueue(&mut pool.acquire().await.unwrap(), webhooks::ZONE_CLEANED, json!({}), chrono::Utc::now())
+        .await
+        .unwrap();
+    assert_eq!(webhooks::deliver_pending(&state).await.unwrap(), 1);
+    let (headers, body) = received.lock().unwrap()[0].clone();
+    assert_eq!(
+        headers["x-webhook-signature"].to_str().unwrap(),
+        format!("sha256={}", webhooks::sign(secret, &body))
+    );
+
+    let rotated = Cipher::new(Some(key("0b")), vec![key("0a")]);
+    assert_eq!(crypto::reencrypt(&pool, &rotated).await.unwrap(), 1);
+    assert_eq!(crypto::reencrypt(&pool, &rotated).await.unwrap(), 0);
+    let (stored,): (String,) = sqlx::query_as("SELECT secret FROM webhooks").fetch_one(&pool).await.unwrap();
+    assert_eq!(Cipher::new(Some(key("0b")), Vec::new()).open(&stored).unwrap(), secret);
+}
@@ -0,0 +1,32 @@
+use chrono::Utc;
+use cleaner_api::{crypto::Cipher, doctor};
+use sqlx::sqlite::SqlitePoolOptions;
+
+#[tokio::test]
+async fn doctor_reports_schema_and_weak_secrets() {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+
+    let checks = doctor::check_database(&pool, &Cipher::default()).await;
+    let failed: Vec<&str> = checks.iter().filter(|c| !c.ok()).map(|c| c.name).collect();
+    assert_eq!(failed, ["migrations"]);
+
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+    let checks = doctor::check_database(&pool, &Cipher::default()).await;
+    assert!(checks.iter().all(doctor::Check::ok), "{}", doctor::report(&checks));
+
+    sqlx::query(
+        r#"INSERT INTO webhooks(id, url, secret, events, active, created_at, updated_at)
+           VALUES ('w1', 'https://example.com/hook', 'aaaaaaaaaaaaaaaa', '[]', 1, ?1, ?1)"#,
+    )
+    .bind(Utc::now())
+    .execute(&pool)
+    .await
+    .unwrap();
+    let checks = doctor::check_database(&pool, &Cipher::default()).await;
+    let failed: Vec<&str> = checks.iter().filter(|c| !c.ok()).map(|c| c.name).collect();
+    assert_eq!(failed, ["webhook secrets"]);
+}
@@ -0,0 +1,259 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Path, State},
+    http::{HeaderMap, Request, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, patch, post},
+    Form, Json, Router,
+};
+use chrono::{DateTime, Duration, Utc};
+use cleaner_api::{api, config::Config, google_calendar, models::AppState};
+use serde_json::{json, Value};
+use sqlx::sqlite::SqlitePoolOptions;
+use tower::ServiceExt;
+
+/// Google's token endpoint and one calendar, in memory.
+#[derive(Default)]
+struct Google {
+    events: BTreeMap<String, Value>,
+    created: u32,
+}
+
+type Mock = Arc<Mutex<Google>>;
+
+async fn token(Form(form): Form<HashMap<String, String>>) -> Response {
+    match (form["grant_type"].as_str(), form.get("code").map(String::as_str)) {
+        ("authorization_code", Some("good")) => {
+            Json(json!({ "access_token": "a1", "expires_in": 3600, "refresh_token": "r1" })).into_response()
+        }
+        ("refresh_token", _) if form["refresh_token"] == "r1" => {
+            Json(json!({ "access_token": "a2", "expires_in": 3600 })).into_response()
+        }
+        _ => (StatusCode::BAD_REQUEST, Json(json!({ "error": "invalid_grant" }))).into_response(),
+    }
+}
+
+async fn list_events(State(google): State<Mock>) -> Json<Value> {
+    let items: Vec<Value> = google.lock().unwrap().events.values().cloned().collect();
+    Json(json!({ "items": items }))
+}
+
+async fn insert_event(State(google): State<Mock>, Json(mut event): Json<Value>) -> Json<Value> {
+    let mut google = google.lock().unwrap();
+    google.created += 1;
+    let id = format!("e{}", google.created);
+    event["id"] = json!(id);
+    event["etag"] = json!("\"1\"");
+    event["status"] = json!("confirmed");
+    event["updated"] = json!(Utc::now());
+    google.events.insert(id, event.clone());
+    Json(event)
+}
+
+async fn patch_event(
+    State(google): State<Mock>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(changes): Json<Value>,
+) -> Response {
+    let mut google = google.lock().unwrap();
+    let Some(event) = google.events.get_mut(&id) else { return StatusCode::NOT_FOUND.into_response() };
+    if headers.get("if-match").and_then(|v| v.to_str().ok()) != event["etag"].as_str() {
+        return StatusCode::PRECONDITION_FAILED.into_response();
+    }
+    for (k, v) in changes.as_object().unwrap() {
+        event[k] = v.clone();
+    }
+    let version: u32 = event["etag"].as_str().unwrap().trim_matches('"').parse().unwrap();
+    event["etag"] = json!(format!("\"{}\"", version + 1));
+    event["updated"] = json!(Utc::now());
+    Json(event.clone()).into_response()
+}
+
+async fn google() -> (Mock, String) {
+    let mock = Mock::default();
+    let app = Router::new()
+        .route("/token", post(token))
+        .route("/calendars/primary/events", get(list_events).post(insert_event))
+        .route("/calendars/primary/events/:id", patch(patch_event))
+        .with_state(mock.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (mock, base)
+}
+
+async fn test_app(base: &str) -> (Arc<AppState>, Router) {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+    let mut config = Config::default();
+    config.google.client_id = Some("client".into());
+    config.google.client_secret = Some("secret".into());
+    config.google.redirect_url = Some("https://cleaner.example.com/callback".into());
+    config.google.auth_url = format!("{base}/auth");
+    config.google.token_url = format!("{base}/token");
+    config.google.api_url = base.to_string();
+    config.encryption_key = Some("0a".repeat(32).parse().unwrap());
+    let state = Arc::new(AppState::new(pool, &config).await.unwrap());
+    (state.clone(), Router::new().nest("/api/v1", api::routes()).with_state(state))
+}
+
+async fn call(app: &Router, method: &str, uri: &str, body: Value) -> (StatusCode, Value) {
+    let res = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = res.status();
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+fn due_of(zone: &Value) -> DateTime<Utc> {
+    zone["next_due_at"].as_str().unwrap().parse().unwrap()
+}
+
+fn start_of(event: &Value) -> DateTime<Utc> {
+    event["start"]["dateTime"].as_str().unwrap().parse().unwrap()
+}
+
+#[tokio::test]
+async fn due_zones_become_events_and_ticked_events_clean_zones() {
+    let (google, base) = google().await;
+    let (state, app) = test_app(&base).await;
+
+    let (_, room) = call(&app, "POST", "/api/v1/rooms", json!({ "name": "Bath" })).await;
+    let zones_uri = format!("/api/v1/rooms/{}/zones", room["id"].as_str().unwrap());
+    let (_, sink) = call(&app, "POST", &zones_uri, json!({ "name": "Sink", "frequency": "weekly" })).await;
+    // никогда не убранная зона без даты — события нет
+    call(&app, "POST", &zones_uri, json!({ "name": "Tub", "frequency": "weekly" })).await;
+    let clean_uri = format!("/api/v1/zones/{}/clean", sink["id"].as_str().unwrap());
+    let (_, sink) = call(&app, "POST", &clean_uri, json!({ "cleaned_at": Utc::now() - Duration::days(10) })).await;
+
+    let (status, connect) = call(&app, "POST", "/api/v1/integrations/google-calendar/connect", json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let url = reqwest::Url::parse(connect["url"].as_str().unwrap()).unwrap();
+    let params: HashMap<_, _> = url.query_pairs().into_owned().collect();
+    assert_eq!(params["client_id"], "client");
+    assert_eq!(params["access_type"], "offline");
+    let callback = format!("/api/v1/integrations/google-calendar/callback?code=good&state={}", params["state"]);
+    let (status, calendar) = call(&app, "GET", &callback, json!({})).await;
+    assert_eq!(status, StatusCode::OK, "{calendar}");
+    assert!(calendar.get("refresh_token").is_none());
+    let (status, _) = call(&app, "GET", &callback, json!({})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "a state is good for one callback");
+
+    assert_eq!(google_calendar::sync(&state).await.unwrap(), 1);
+    {
+        let google = google.lock().unwrap();
+        let event = &google.events["e1"];
+        assert_eq!(event["summary"], "Sink · Bath");
+        assert_eq!(start_of(event), due_of(&sink));
+    }
+    assert_eq!(google_calendar::sync(&state).await.unwrap(), 0, "nothing changed");
+
+    // уборка здесь двигает событие
+    let (_, sink) = call(&app, "POST", &clean_uri, json!({ "cleaned_at": Utc::now() - Duration::days(8) })).await;
+    assert_eq!(google_calendar::sync(&state).await.unwrap(), 1);
+    assert_eq!(start_of(&google.lock().unwrap().events["e1"]), due_of(&sink));
+
+    // отметка в календаре убирает зону, следующей уборке — новое событие
+    google.lock().unwrap().events.get_mut("e1").unwrap()["summary"] = json!("✓ Sink · Bath");
+    assert_eq!(google_calendar::sync(&state).await.unwrap(), 2);
+    let (_, sink) = call(&app, "GET", &format!("/api/v1/zones/{}", sink["id"].as_str().unwrap()), json!({})).await;
+    let (_, history) = call(&app, "GET", &format!("/api/v1/zones/{}/events", sink["id"].as_str().unwrap()), json!({})).await;
+    let notes: Vec<&Value> =
+        history.as_array().unwrap().iter().filter(|e| e["change"]["kind"] == "cleaned").map(|e| &e["change"]["note"]).collect();
+    assert_eq!(notes, [&Value::Null, &Value::Null, &json!("Google Calendar")]);
+    assert_eq!(start_of(&google.lock().unwrap().events["e2"]), due_of(&sink));
+
+    // отметка о том, что здесь уже убрано, второй уборки не даёт
+    call(&app, "POST", &clean_uri, json!({})).await;
+    google.lock().unwrap().events.get_mut("e2").unwrap()["summary"] = json!("[x] Sink");
+    google_calendar::sync(&state).await.unwrap();
+    let (_, history) = call(&app, "GET", &format!("/api/v1/zones/{}/events", sink["id"].as_str().unwrap()), json!({})).await;
+    assert_eq!(history.as_array().unwrap().iter().filter(|e| e["change"]["kind"] == "cleaned").count(), 4);
+
+    let (_, calendars) = call(&app, "GET", "/api/v1/integrations/google-calendar", json!({})).await;
+    assert_eq!(calendars[0]["last_error"], Value::Null);
+    assert!(calendars[0]["last_synced_at"].is_string());
+}
+
+#[tokio::test]
+async fn connecting_needs_configuration_and_a_good_code() {
+    let (_, base) = google().await;
+    let (_, app) = test_app(&base).await;
+
+    let (_, connect) = call(&app, "POST", "/api/v1/integrations/google-calendar/connect", json!({})).await;
+    let url = reqwest::Url::parse(connect["url"].as_str().unwrap()).unwrap();
+    let state = url.query_pairs().find(|(k, _)| k == "state").unwrap().1.into_owned();
+    let callback = format!("/api/v1/integrations/google-calendar/callback?code=bad&state={state}");
+    let (status, body) = call(&app, "GET", &callback, json!({})).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "{body}");
+
+    let (status, _) = call(&app, "GET", "/api/v1/integrations/google-calendar/callback?error=access_denied", json!({})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+    let state = Arc::new(AppState::new(pool, &Config::default()).await.unwrap());
+    let app = Router::new().nest("/api/v1", api::routes()).with_state(state);
+    let (status, body) = call(&app, "POST", "/api/v1/integrations/google-calendar/connect", json!({})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["message"].as_str().unwrap().contains("GOOGLE_CLIENT_ID"));
+}
+
+#[tokio::test]
+async fn tokens_are_stored_sealed() {
+    let (_, base) = google().await;
+    let (state, app) = test_app(&base).await;
+
+    let (_, connect) = call(&app, "POST", "/api/v1/integrations/google-calendar/connect", json!({})).await;
+    let url = reqwest::Url::parse(connect["url"].as_str().unwrap()).unwrap();
+    let oauth_state = url.query_pairs().find(|(k, _)| k == "state").unwrap().1.into_owned();
+    let callback = format!("/api/v1/integrations/google-calendar/callback?code=good&state={oauth_state}");
+    let (status, calendar) = call(&app, "GET", &callback, json!({})).await;
+    assert_eq!(status, StatusCode::OK, "{calendar}");
+
+    let tokens = || async {
+        let row: (String, String) = sqlx::query_as("SELECT refresh_token, access_token FROM google_calendars")
+            .fetch_one(&state.pool)
+            .await
+            .unwrap();
+        row
+    };
+    let (refresh, access) = tokens().await;
+    assert!(refresh.starts_with("enc:v1:") && access.starts_with("enc:v1:"), "{refresh} {access}");
+    assert_eq!(state.cipher.open(&refresh).unwrap(), "r1");
+
+    // просроченный токен обновляется по открытому refresh-токену и снова хранится закрытым
+    sqlx::query("UPDATE google_calendars SET access_expires_at = ?1")
+        .bind(Utc::now() - Duration::hours(1))
+        .execute(&state.pool)
+        .await
+        .unwrap();
+    google_calendar::sync(&state).await.unwrap();
+    let (_, calendars) = call(&app, "GET", "/api/v1/integrations/google-calendar", json!({})).await;
+    assert_eq!(calendars[0]["last_error"], Value::Null);
+    let (_, access) = tokens().await;
+    assert_eq!(state.cipher.open(&access).unwrap(), "a2");
+    for field in ["refresh_token", "access_token"] {
+        assert!(calendars[0].get(field).is_none());
+    }
+}
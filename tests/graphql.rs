@@ -0,0 +1,82 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use cleaner_api::{api, config::Config, models::AppState};
+use serde_json::{json, Value};
+use sqlx::sqlite::SqlitePoolOptions;
+use std::sync::Arc;
+use tower::ServiceExt; // for oneshot
+
+async fn test_app() -> Router {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+    let state = Arc::new(AppState::new(pool, &Config::default()).await.unwrap());
+    Router::new().nest("/api/v1", api::routes()).with_state(state)
+}
+
+async fn graphql(app: &Router, query: &str, variables: Value) -> Value {
+    let res = app
+        .clone()
+        .oneshot(
+            Request::post("/api/v1/graphql")
+                .header("content-type", "application/json")
+                .body(Body::from(json!({ "query": query, "variables": variables }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn rooms_zones_and_stats_in_one_request() {
+    let app = test_app().await;
+
+    let res = graphql(&app, r#"mutation { createRoom(name: "Bath") { id name } }"#, json!({})).await;
+    let room_id = res["data"]["createRoom"]["id"].as_str().unwrap().to_string();
+    let mut zone_ids = Vec::new();
+    for name in ["Tub", "Mirror"] {
+        let res = graphql(
+            &app,
+            "mutation($room: String!, $name: String!) { createZone(roomId: $room, name: $name, frequency: WEEKLY) { id } }",
+            json!({ "room": room_id, "name": name }),
+        )
+        .await;
+        zone_ids.push(res["data"]["createZone"]["id"].as_str().unwrap().to_string());
+    }
+    let res = graphql(
+        &app,
+        "mutation($id: String!) { cleanZone(id: $id, note: \"new sponge\", durationMinutes: 15) { isDue frequency } }",
+        json!({ "id": zone_ids[0] }),
+    )
+    .await;
+    assert_eq!(res["data"]["cleanZone"], json!({ "isDue": false, "frequency": "WEEKLY" }));
+
+    let res = graphql(
+        &app,
+        "{ rooms { name zonesTotal zones { name isDue } } dueZones { name } stats { zonesTotal dueZones } }",
+        json!({}),
+    )
+    .await;
+    assert!(res.get("errors").is_none(), "{res}");
+    let data = &res["data"];
+    assert_eq!(data["rooms"][0]["name"], "Bath");
+    assert_eq!(data["rooms"][0]["zonesTotal"], 2);
+    assert_eq!(data["rooms"][0]["zones"].as_array().unwrap().len(), 2);
+    assert_eq!(data["dueZones"], json!([{ "name": "Mirror" }]));
+    assert_eq!(data["stats"], json!({ "zonesTotal": 2, "dueZones": 1 }));
+
+    // errors carry the REST error code
+    let res = graphql(&app, r#"mutation { cleanZone(id: "missing") { id } }"#, json!({})).await;
+    assert_eq!(res["errors"][0]["extensions"]["code"], "not_found");
+    let res = graphql(&app, r#"{ room(id: "missing") { id } }"#, json!({})).await;
+    assert_eq!(res["data"]["room"], Value::Null);
+}
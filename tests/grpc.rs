@@ -0,0 +1,84 @@
+use std::sync::Arc;
+
+use cleaner_api::{
+    api::grpc::{self, pb, pb::cleaner_client::CleanerClient},
+    config::Config,
+    models::AppState,
+};
+use sqlx::sqlite::SqlitePoolOptions;
+use tonic::Code;
+
+async fn client() -> CleanerClient<tonic::transport::Channel> {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+    let state = Arc::new(AppState::new(pool, &Config::default()).await.unwrap());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(grpc::serve(state, listener, std::future::pending()));
+    CleanerClient::connect(format!("http://{addr}")).await.unwrap()
+}
+
+#[tokio::test]
+async fn rooms_and_zones_over_grpc() {
+    let mut client = client().await;
+
+    let room = client
+        .create_room(pb::CreateRoomRequest { name: "Bath".into(), icon: None })
+        .await
+        .unwrap()
+        .into_inner();
+    let zone = client
+        .create_zone(pb::CreateZoneRequest {
+            room_id: room.id.clone(),
+            name: "Sink".into(),
+            frequency: pb::Frequency::Weekly.into(),
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(zone.frequency(), pb::Frequency::Weekly);
+    assert!(zone.is_due);
+
+    let due = client.list_due_zones(pb::ListDueZonesRequest {}).await.unwrap().into_inner().zones;
+    assert_eq!(due.iter().map(|z| z.id.as_str()).collect::<Vec<_>>(), [zone.id.as_str()]);
+
+    let cleaned = client
+        .clean_zone(pb::CleanZoneRequest { id: zone.id.clone(), note: Some("quick".into()), ..Default::default() })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(!cleaned.is_due);
+    assert!(cleaned.next_due_at.is_some());
+    assert!(client.list_due_zones(pb::ListDueZonesRequest {}).await.unwrap().into_inner().zones.is_empty());
+
+    let rooms = client
+        .list_rooms(pb::ListRoomsRequest { include_zones: true })
+        .await
+        .unwrap()
+        .into_inner()
+        .rooms;
+    assert_eq!(rooms.len(), 1);
+    assert_eq!((rooms[0].zones_total, rooms[0].zones_cleaned_count), (1, 1));
+    assert_eq!(rooms[0].zones[0].name, "Sink");
+}
+
+#[tokio::test]
+async fn errors_map_to_grpc_codes() {
+    let mut client = client().await;
+
+    let err = client.get_zone(pb::GetZoneRequest { id: "missing".into() }).await.unwrap_err();
+    assert_eq!(err.code(), Code::NotFound);
+
+    let err = client.create_room(pb::CreateRoomRequest { name: " ".into(), icon: None }).await.unwrap_err();
+    assert_eq!(err.code(), Code::InvalidArgument);
+    assert_eq!(err.message(), "validation error: name is required");
+
+    let mut req = tonic::Request::new(pb::ListRoomsRequest::default());
+    req.metadata_mut().insert("x-home-id", "nope".parse().unwrap());
+    assert_eq!(client.list_rooms(req).await.unwrap_err().code(), Code::InvalidArgument);
+}
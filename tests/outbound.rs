@@ -0,0 +1,47 @@
+use std::time::Duration;
+
+use cleaner_api::outbound::{OutboundClient, OutboundConfig, OutboundError};
+
+#[tokio::test]
+async fn breaker_opens_after_consecutive_failures() {
+    let client = OutboundClient::new(&OutboundConfig {
+        connect_timeout: Duration::from_millis(200),
+        breaker_threshold: 2,
+        ..OutboundConfig::default()
+    })
+    .unwrap();
+
+    // на порту 1 никто не слушает — соединение отклоняется
+    for _ in 0..2 {
+        let req = client.client().get("http://127.0.0.1:1/").build().unwrap();
+        assert!(matches!(client.execute(req).await, Err(OutboundError::Http(_))));
+    }
+    let req = client.client().get("http://127.0.0.1:1/").build().unwrap();
+    assert!(matches!(client.execute(req).await, Err(OutboundError::CircuitOpen(_))));
+}
+
+#[tokio::test]
+async fn half_open_breaker_lets_one_probe_through() {
+    let client = OutboundClient::new(&OutboundConfig {
+        connect_timeout: Duration::from_millis(200),
+        breaker_threshold: 1,
+        breaker_cooldown: Duration::ZERO,
+        ..OutboundConfig::default()
+    })
+    .unwrap();
+    let req = client.client().get("http://127.0.0.1:1/").build().unwrap();
+    assert!(matches!(client.execute(req).await, Err(OutboundError::Http(_))));
+
+    // принимает соединения, но не отвечает — проба висит
+    let silent = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", silent.local_addr().unwrap());
+    let client = std::sync::Arc::new(client);
+    let probe = {
+        let (client, url) = (client.clone(), url.clone());
+        tokio::spawn(async move { client.execute(client.client().get(url).build().unwrap()).await })
+    };
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let req = client.client().get(&url).build().unwrap();
+    assert!(matches!(client.execute(req).await, Err(OutboundError::CircuitOpen(_))));
+    probe.abort();
+}
@@ -0,0 +1,536 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use cleaner_api::{
+    api,
+    config::Config,
+    models::{AppState, Page, RoomView},
+};
+use serde_json::json;
+use sqlx::sqlite::SqlitePoolOptions;
+use std::sync::Arc;
+use tower::ServiceExt; // for oneshot
+
+async fn test_app() -> Router {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+    let state = Arc::new(AppState::new(pool, &Config::default()).await.unwrap());
+    Router::new().nest("/api/v1", api::routes()).with_state(state)
+}
+
+async fn send_json(
+    app: &Router,
+    method: &str,
+    uri: &str,
+    body: &serde_json::Value,
+) -> axum::response::Response {
+    app.clone()
+        .oneshot(
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+}
+
+async fn read_json<T: serde::de::DeserializeOwned>(res: axum::response::Response) -> T {
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn reorder_rooms_sets_list_order() {
+    let app = test_app().await;
+
+    let mut ids = Vec::new();
+    for name in ["Kitchen", "Bath", "Hall"] {
+        let res = send_json(&app, "POST", "/api/v1/rooms", &json!({ "name": name })).await;
+        let room: RoomView = read_json(res).await;
+        ids.push(room.id);
+    }
+
+    let order = vec![ids[1].clone(), ids[0].clone(), ids[2].clone()];
+    let res = send_json(&app, "POST", "/api/v1/rooms/reorder", &json!({ "ids": order })).await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = app
+        .clone()
+        .oneshot(Request::get("/api/v1/rooms").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let rooms = read_json::<Page<RoomView>>(res).await.items;
+    let names: Vec<&str> = rooms.iter().map(|r| r.name.as_str()).collect();
+    assert_eq!(names, ["Bath", "Kitchen", "Hall"]);
+
+    let res = send_json(&app, "POST", "/api/v1/rooms/reorder", &json!({ "ids": ["nope"] })).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    // a room listed twice is rejected, not silently moved to its last position
+    let res = send_json(&app, "POST", "/api/v1/rooms/reorder", &json!({ "ids": [ids[0], ids[1], ids[0]] })).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn create_with_external_id_upserts() {
+    let app = test_app().await;
+
+    let first = json!({ "name": "Kitchen", "source": "tody", "external_id": "r-1" });
+    let res = send_json(&app, "POST", "/api/v1/rooms", &first).await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let created: RoomView = read_json(res).await;
+
+    let again = json!({ "name": "Kitchen & dining", "source": "tody", "external_id": "r-1" });
+    let res = send_json(&app, "POST", "/api/v1/rooms", &again).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let updated: RoomView = read_json(res).await;
+    assert_eq!(updated.id, created.id);
+    assert_eq!(updated.name, "Kitchen & dining");
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({ "name": "Bath", "external_id": "r-2" })).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    let res = app
+        .clone()
+        .oneshot(Request::get("/api/v1/rooms").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let rooms = read_json::<Page<RoomView>>(res).await.items;
+    assert_eq!(rooms.len(), 1);
+}
+
+#[tokio::test]
+async fn rooms_are_scoped_by_home() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Kitchen"})).await;
+    let kitchen: RoomView = read_json(res).await;
+    assert!(kitchen.home_id.is_some());
+
+    let res = send_json(&app, "POST", "/api/v1/homes", &json!({"name": "Cottage"})).await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let cottage: cleaner_api::models::Home = read_json(res).await;
+    assert!(!cottage.is_default);
+
+    let res = app
+        .clone()
+        .oneshot(
+            Request::post("/api/v1/rooms")
+                .header("content-type", "application/json")
+                .header("x-home-id", &cottage.id)
+                .body(Body::from(json!({"name": "Sauna"}).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let sauna: RoomView = read_json(res).await;
+    assert_eq!(sauna.home_id.as_deref(), Some(cottage.id.as_str()));
+
+    // без указания дома — дом по умолчанию
+    let res = app.clone().oneshot(Request::get("/api/v1/rooms").body(Body::empty()).unwrap()).await.unwrap();
+    let rooms = read_json::<Page<RoomView>>(res).await.items;
+    assert_eq!(rooms.iter().map(|r| r.name.as_str()).collect::<Vec<_>>(), ["Kitchen"]);
+
+    let res = app
+        .clone()
+        .oneshot(Request::get(format!("/api/v1/rooms?home_id={}", cottage.id)).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let rooms = read_json::<Page<RoomView>>(res).await.items;
+    assert_eq!(rooms.iter().map(|r| r.name.as_str()).collect::<Vec<_>>(), ["Sauna"]);
+
+    let res = send_json(&app, "PATCH", &format!("/api/v1/homes/{}", cottage.id), &json!({"is_default": true})).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let res = app.clone().oneshot(Request::get("/api/v1/rooms").body(Body::empty()).unwrap()).await.unwrap();
+    let rooms = read_json::<Page<RoomView>>(res).await.items;
+    assert_eq!(rooms.iter().map(|r| r.name.as_str()).collect::<Vec<_>>(), ["Sauna"]);
+
+    let res = app
+        .clone()
+        .oneshot(Request::get("/api/v1/rooms").header("x-home-id", "nope").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn rooms_page_by_cursor() {
+    let app = test_app().await;
+
+    for name in ["Kitchen", "Bath", "Hall"] {
+        send_json(&app, "POST", "/api/v1/rooms", &json!({ "name": name })).await;
+    }
+
+    let mut uri = "/api/v1/rooms?limit=2".to_string();
+    let mut names = Vec::new();
+    loop {
+        let res = app.clone().oneshot(Request::get(&uri).body(Body::empty()).unwrap()).await.unwrap();
+        let page: Page<RoomView> = read_json(res).await;
+        assert_eq!(page.total, 3);
+        names.extend(page.items.into_iter().map(|r| r.name));
+        match page.next_cursor {
+            Some(c) => uri = format!("/api/v1/rooms?limit=2&cursor={c}"),
+            None => break,
+        }
+    }
+    assert_eq!(names, ["Hall", "Bath", "Kitchen"]);
+
+    let res = app.clone().oneshot(Request::get("/api/v1/rooms?cursor=x").body(Body::empty()).unwrap()).await.unwrap();
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn unchanged_room_answers_304_to_its_etag() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({ "name": "Kitchen" })).await;
+    let room: RoomView = read_json(res).await;
+    let uri = format!("/api/v1/rooms/{}", room.id);
+
+    let res = app.clone().oneshot(Request::get(&uri).body(Body::empty()).unwrap()).await.unwrap();
+    let etag = res.headers()["etag"].clone();
+    assert!(etag.to_str().unwrap().starts_with("W/\""));
+
+    let res = app
+        .clone()
+        .oneshot(Request::get(&uri).header("if-none-match", &etag).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(res.headers()["etag"], etag);
+    assert!(to_bytes(res.into_body(), usize::MAX).await.unwrap().is_empty());
+
+    send_json(&app, "PATCH", &uri, &json!({ "name": "Kitchen & dining" })).await;
+    let res = app
+        .clone()
+        .oneshot(Request::get(&uri).header("if-none-match", &etag).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_ne!(res.headers()["etag"], etag);
+}
+
+#[tokio::test]
+async fn idempotency_key_replays_the_first_response() {
+    let app = test_app().await;
+
+    let post = |uri: &str, key: &str, body: serde_json::Value| {
+        Request::post(uri)
+            .header("content-type", "application/json")
+            .header("idempotency-key", key)
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let res = app.clone().oneshot(post("/api/v1/rooms", "k-1", json!({ "name": "Kitchen" }))).await.unwrap();
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let first: RoomView = read_json(res).await;
+
+    let res = app.clone().oneshot(post("/api/v1/rooms", "k-1", json!({ "name": "Kitchen" }))).await.unwrap();
+    assert_eq!(res.status(), StatusCode::CREATED);
+    assert_eq!(res.headers()["idempotent-replayed"], "true");
+    let again: RoomView = read_json(res).await;
+    assert_eq!(again.id, first.id);
+
+    let res = app.clone().oneshot(post("/api/v1/rooms", "k-1", json!({ "name": "Bath" }))).await.unwrap();
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+
+    // неудачный запрос ключ не занимает
+    let res = app.clone().oneshot(post("/api/v1/rooms", "k-2", json!({ "name": " " }))).await.unwrap();
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    let res = app.clone().oneshot(post("/api/v1/rooms", "k-2", json!({ "name": "Bath" }))).await.unwrap();
+    assert_eq!(res.status(), StatusCode::CREATED);
+
+    let res = app.clone().oneshot(Request::get("/api/v1/rooms").body(Body::empty()).unwrap()).await.unwrap();
+    assert_eq!(read_json::<Page<RoomView>>(res).await.total, 2);
+
+    let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", first.id), &json!({ "name": "Sink", "frequency": "daily" })).await;
+    let zone: serde_json::Value = read_json(res).await;
+    let zone_id = zone["id"].as_str().unwrap();
+    let clean = format!("/api/v1/zones/{zone_id}/clean");
+    for _ in 0..2 {
+        let res = app.clone().oneshot(post(&clean, "c-1", json!({}))).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+    let res = send_json(&app, "GET", &format!("/api/v1/zones/{zone_id}/events"), &json!({})).await;
+    let events: serde_json::Value = read_json(res).await;
+    let cleaned = events.as_array().unwrap().iter().filter(|e| e["change"]["kind"] == "cleaned").count();
+    assert_eq!(cleaned, 1);
+}
+
+#[tokio::test]
+async fn fields_param_trims_rooms_and_zones() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({ "name": "Kitchen", "icon": "pan" })).await;
+    let room: RoomView = read_json(res).await;
+    let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", room.id), &json!({ "name": "Sink", "frequency": "daily" })).await;
+    let zone: serde_json::Value = read_json(res).await;
+
+    let res = send_json(&app, "GET", "/api/v1/rooms?fields=id,name", &json!({})).await;
+    let page: Page<serde_json::Value> = read_json(res).await;
+    assert_eq!(page.total, 1);
+    assert_eq!(page.items[0], json!({ "id": room.id, "name": "Kitchen" }));
+
+    let res = send_json(&app, "GET", &format!("/api/v1/rooms/{}?fields=icon", room.id), &json!({})).await;
+    let one: serde_json::Value = read_json(res).await;
+    assert_eq!(one, json!({ "icon": "pan" }));
+
+    let res = send_json(&app, "GET", &format!("/api/v1/zones/{}?fields=id,%20is_due", zone["id"].as_str().unwrap()), &json!({})).await;
+    let one: serde_json::Value = read_json(res).await;
+    assert_eq!(one, json!({ "id": zone["id"], "is_due": true }));
+
+    let res = send_json(&app, "GET", &format!("/api/v1/rooms/{}/zones?fields=name", room.id), &json!({})).await;
+    let page: Page<serde_json::Value> = read_json(res).await;
+    assert_eq!(page.items, vec![json!({ "name": "Sink" })]);
+
+    let res = send_json(&app, "GET", "/api/v1/zones?fields=id,secret", &json!({})).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn include_zones_nests_them_in_rooms() {
+    let app = test_app().await;
+
+    let mut rooms = Vec::new();
+    for (name, zones) in [("Kitchen", vec!["Sink", "Oven"]), ("Hall", vec![])] {
+        let res = send_json(&app, "POST", "/api/v1/rooms", &json!({ "name": name })).await;
+        let room: RoomView = read_json(res).await;
+        for zone in zones {
+            send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", room.id), &json!({ "name": zone, "frequency": "weekly" })).await;
+        }
+        rooms.push(room);
+    }
+
+    let res = send_json(&app, "GET", "/api/v1/rooms?include=zones&sort=name", &json!({})).await;
+    let page: Page<RoomView> = read_json(res).await;
+    let nested: Vec<(String, usize)> = page.items.iter().map(|r| (r.name.clone(), r.zones.as_ref().unwrap().len())).collect();
+    assert_eq!(nested, [("Hall".to_string(), 0), ("Kitchen".to_string(), 2)]);
+
+    let res = send_json(&app, "GET", &format!("/api/v1/rooms/{}?include=zones", rooms[0].id), &json!({})).await;
+    let room: RoomView = read_json(res).await;
+    let zones = room.zones.unwrap();
+    assert!(zones.iter().all(|z| z.room_id == rooms[0].id));
+    assert_eq!(zones.len(), 2);
+
+    let res = send_json(&app, "GET", "/api/v1/rooms", &json!({})).await;
+    let page: Page<RoomView> = read_json(res).await;
+    assert!(page.items.iter().all(|r| r.zones.is_none()));
+    let res = send_json(&app, "GET", "/api/v1/rooms?include=tasks", &json!({})).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn external_id_upsert_stays_in_its_home() {
+    let app = test_app().await;
+
+    let body = json!({"name": "Kitchen", "source": "sheet", "external_id": "R1"});
+    let res = send_json(&app, "POST", "/api/v1/rooms", &body).await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let kitchen: RoomView = read_json(res).await;
+    let res = send_json(&app, "POST", "/api/v1/homes", &json!({"name": "Cottage"})).await;
+    let cottage: cleaner_api::models::Home = read_json(res).await;
+
+    let post = |home: Option<&str>| {
+        let mut req = Request::post("/api/v1/rooms").header("content-type", "application/json");
+        if let Some(home) = home {
+            req = req.header("x-home-id", home);
+        }
+        req.body(Body::from(json!({"name": "Kitchen 2", "source": "sheet", "external_id": "R1"}).to_string())).unwrap()
+    };
+    // the same ref imported into another home does not touch the first home's room
+    let res = app.clone().oneshot(post(Some(&cottage.id))).await.unwrap();
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+    let res = app.clone().oneshot(post(None)).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let again: RoomView = read_json(res).await;
+    assert_eq!((again.id, again.name.as_str()), (kitchen.id, "Kitchen 2"));
+}
+
+#[tokio::test]
+async fn request_id_is_echoed_or_generated() {
+    let app = test_app().await;
+
+    let req = Request::builder().uri("/api/v1/rooms").header("x-request-id", "abc-123");
+    let res = app.clone().oneshot(req.body(Body::empty()).unwrap()).await.unwrap();
+    assert_eq!(res.headers()["x-request-id"], "abc-123");
+
+    // no id, or one that is not a plain token — a fresh UUID instead
+    for header in [None, Some("two words")] {
+        let mut req = Request::builder().uri("/api/v1/rooms/999");
+        if let Some(h) = header {
+            req = req.header("x-request-id", h);
+        }
+        let res = app.clone().oneshot(req.body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        let id = res.headers()["x-request-id"].to_str().unwrap().to_string();
+        assert!(uuid::Uuid::parse_str(&id).is_ok(), "{id}");
+        // the error body names the same request
+        let err: serde_json::Value = read_json(res).await;
+        assert_eq!(err["request_id"], id.as_str());
+    }
+}
+
+#[tokio::test]
+async fn with_stats_counts_zones_per_room() {
+    let app = test_app().await;
+
+    for (name, zones) in [("Kitchen", vec!["Sink", "Oven", "Floor"]), ("Hall", vec![])] {
+        let res = send_json(&app, "POST", "/api/v1/rooms", &json!({ "name": name })).await;
+        let room: RoomView = read_json(res).await;
+        for (i, zone) in zones.into_iter().enumerate() {
+            let uri = format!("/api/v1/rooms/{}/zones", room.id);
+            let res = send_json(&app, "POST", &uri, &json!({ "name": zone, "frequency": "weekly" })).await;
+            let zone: serde_json::Value = read_json(res).await;
+            if i == 0 {
+                send_json(&app, "POST", &format!("/api/v1/zones/{}/clean", zone["id"].as_str().unwrap()), &json!({})).await;
+            }
+            if i == 2 {
+                let res = send_json(&app, "DELETE", &format!("/api/v1/zones/{}", zone["id"].as_str().unwrap()), &json!({})).await;
+                assert!(res.status().is_success());
+            }
+        }
+    }
+
+    let res = send_json(&app, "GET", "/api/v1/rooms?with_stats=true&sort=name", &json!({})).await;
+    let page: Page<RoomView> = read_json(res).await;
+    let stats: Vec<_> = page
+        .items
+        .iter()
+        .map(|r| (r.name.as_str(), r.zones_total, r.zones_cleaned_count, r.last_cleaned_at.is_some()))
+        .collect();
+    // deleted zones do not count
+    assert_eq!(stats, [("Hall", Some(0), Some(0), false), ("Kitchen", Some(2), Some(1), true)]);
+
+    let res = send_json(&app, "GET", "/api/v1/rooms", &json!({})).await;
+    let page: Page<RoomView> = read_json(res).await;
+    assert!(page.items.iter().all(|r| r.zones_total.is_none() && r.last_cleaned_at.is_none()));
+}
+
+#[tokio::test]
+async fn restoring_a_room_brings_back_the_zones_deleted_with_it() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({ "name": "Garage" })).await;
+    let room: RoomView = read_json(res).await;
+    let zones_uri = format!("/api/v1/rooms/{}/zones", room.id);
+    let mut ids = Vec::new();
+    for name in ["Floor", "Shelves"] {
+        let res = send_json(&app, "POST", &zones_uri, &json!({ "name": name, "frequency": "weekly" })).await;
+        let zone: serde_json::Value = read_json(res).await;
+        ids.push(zone["id"].as_str().unwrap().to_string());
+    }
+    // deleted on its own before the room — stays deleted
+    send_json(&app, "DELETE", &format!("/api/v1/zones/{}", ids[1]), &json!({})).await;
+    tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+    let res = send_json(&app, "DELETE", &format!("/api/v1/rooms/{}", room.id), &json!({})).await;
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+
+    let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/restore", room.id), &json!({})).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let res = send_json(&app, "GET", &zones_uri, &json!({})).await;
+    let page: serde_json::Value = read_json(res).await;
+    let listed: Vec<&str> = page["items"].as_array().unwrap().iter().map(|z| z["id"].as_str().unwrap()).collect();
+    assert_eq!(listed, [ids[0].as_str()]);
+
+    let res = send_json(&app, "GET", &format!("/api/v1/zones/{}/events", ids[0]), &json!({})).await;
+    let history: Vec<cleaner_api::models::ZoneEvent> = read_json(res).await;
+    let kinds: Vec<&str> = history.iter().map(|e| e.change.kind()).collect();
+    assert_eq!(kinds, ["created", "deleted", "restored"]);
+
+    let res = send_json(&app, "POST", "/api/v1/rooms/missing/restore", &json!({})).await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn undo_token_reverses_a_delete_once() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({ "name": "Shed" })).await;
+    let room: RoomView = read_json(res).await;
+    let zones_uri = format!("/api/v1/rooms/{}/zones", room.id);
+    let res = send_json(&app, "POST", &zones_uri, &json!({ "name": "Bench", "frequency": "weekly" })).await;
+    let zone: serde_json::Value = read_json(res).await;
+    let zone_id = zone["id"].as_str().unwrap();
+    let res = send_json(&app, "POST", "/api/v1/tags", &json!({ "name": "outdoors" })).await;
+    let tag: serde_json::Value = read_json(res).await;
+    let tag_uri = format!("/api/v1/zones/{zone_id}/tags/{}", tag["id"].as_str().unwrap());
+    send_json(&app, "PUT", &tag_uri, &json!(null)).await;
+
+    let res = send_json(&app, "DELETE", &format!("/api/v1/rooms/{}", room.id), &json!({})).await;
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+    let token = res.headers()["x-undo-token"].to_str().unwrap().to_string();
+    let res = send_json(&app, "POST", &format!("/api/v1/undo/{token}"), &json!({})).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let undone: serde_json::Value = read_json(res).await;
+    assert_eq!(undone, json!({ "kind": "room", "id": room.id }));
+    let res = send_json(&app, "GET", &format!("/api/v1/zones/{zone_id}"), &json!({})).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let res = send_json(&app, "POST", &format!("/api/v1/undo/{token}"), &json!({})).await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND, "a token undoes once");
+
+    // zone links of a tag are removed for good; undo puts them back
+    let res = send_json(&app, "DELETE", &format!("/api/v1/tags/{}", tag["id"].as_str().unwrap()), &json!({})).await;
+    let token = res.headers()["x-undo-token"].to_str().unwrap().to_string();
+    let res = send_json(&app, "POST", &format!("/api/v1/undo/{token}"), &json!({})).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let res = send_json(&app, "GET", &format!("/api/v1/zones/{zone_id}/tags"), &json!({})).await;
+    let tags: serde_json::Value = read_json(res).await;
+    assert_eq!(tags[0]["id"], tag["id"]);
+
+    // a zone does not come back into a deleted room
+    let res = send_json(&app, "DELETE", &format!("/api/v1/zones/{zone_id}"), &json!({})).await;
+    let token = res.headers()["x-undo-token"].to_str().unwrap().to_string();
+    send_json(&app, "DELETE", &format!("/api/v1/rooms/{}", room.id), &json!({})).await;
+    let res = send_json(&app, "POST", &format!("/api/v1/undo/{token}"), &json!({})).await;
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn undo_leaves_deleted_zones_alone() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({ "name": "Shed" })).await;
+    let room: RoomView = read_json(res).await;
+    let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", room.id), &json!({ "name": "Bench", "frequency": "weekly" })).await;
+    let zone: serde_json::Value = read_json(res).await;
+    let zone_id = zone["id"].as_str().unwrap();
+    let res = send_json(&app, "POST", &format!("/api/v1/zones/{zone_id}/tasks"), &json!({ "title": "Oil the wood" })).await;
+    let task: serde_json::Value = read_json(res).await;
+    let res = send_json(&app, "POST", "/api/v1/tags", &json!({ "name": "outdoors" })).await;
+    let tag: serde_json::Value = read_json(res).await;
+    let tag_id = tag["id"].as_str().unwrap();
+    send_json(&app, "PUT", &format!("/api/v1/zones/{zone_id}/tags/{tag_id}"), &json!(null)).await;
+
+    let mut tokens = Vec::new();
+    for uri in [format!("/api/v1/zones/{zone_id}/tasks/{}", task["id"].as_str().unwrap()), format!("/api/v1/tags/{tag_id}"), format!("/api/v1/zones/{zone_id}")] {
+        let res = send_json(&app, "DELETE", &uri, &json!({})).await;
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+        tokens.push(res.headers()["x-undo-token"].to_str().unwrap().to_string());
+    }
+    let undo = |token: &str| {
+        let (app, uri) = (app.clone(), format!("/api/v1/undo/{token}"));
+        async move { send_json(&app, "POST", &uri, &json!({})).await }
+    };
+
+    // the task waits for its zone; the tag comes back without the link to the deleted zone
+    assert_eq!(undo(&tokens[0]).await.status(), StatusCode::CONFLICT);
+    assert_eq!(undo(&tokens[1]).await.status(), StatusCode::OK);
+    assert_eq!(undo(&tokens[2]).await.status(), StatusCode::OK);
+    let res = send_json(&app, "GET", &format!("/api/v1/zones/{zone_id}/tags"), &json!({})).await;
+    let tags: serde_json::Value = read_json(res).await;
+    assert_eq!(tags, json!([]));
+
+    // a refused undo keeps its token
+    assert_eq!(undo(&tokens[0]).await.status(), StatusCode::OK);
+    let res = send_json(&app, "GET", &format!("/api/v1/zones/{zone_id}/tasks"), &json!({})).await;
+    let checklist: serde_json::Value = read_json(res).await;
+    assert_eq!(checklist["tasks"][0]["id"], task["id"]);
+}
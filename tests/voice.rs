@@ -0,0 +1,79 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::Request,
+    Router,
+};
+use cleaner_api::{api, config::Config, models::AppState};
+use serde_json::{json, Value};
+use sqlx::sqlite::SqlitePoolOptions;
+use std::sync::Arc;
+use tower::ServiceExt;
+
+async fn test_app() -> Router {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+    let state = Arc::new(AppState::new(pool, &Config::default()).await.unwrap());
+    Router::new().nest("/api/v1", api::routes()).with_state(state)
+}
+
+async fn post(app: &Router, uri: &str, body: Value) -> Value {
+    let res = app
+        .clone()
+        .oneshot(
+            Request::post(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert!(res.status().is_success(), "{}", res.status());
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+async fn say(app: &Router, intent: Value) -> Value {
+    post(app, "/api/v1/integrations/voice/intents", intent).await
+}
+
+#[tokio::test]
+async fn voice_intents_match_zones_by_what_people_say() {
+    let app = test_app().await;
+    assert_eq!(say(&app, json!({ "intent": "due_today" })).await["speech"], "Nothing is due. Nice work!");
+
+    let mut sinks = Vec::new();
+    for room in ["Bathroom", "Kitchen"] {
+        let room = post(&app, "/api/v1/rooms", json!({ "name": room })).await;
+        let uri = format!("/api/v1/rooms/{}/zones", room["id"].as_str().unwrap());
+        sinks.push(post(&app, &uri, json!({ "name": "Sink", "frequency": "weekly" })).await);
+        post(&app, &uri, json!({ "name": "Floor", "frequency": "daily" })).await;
+    }
+    let due = say(&app, json!({ "intent": "due_today" })).await;
+    assert!(due["speech"].as_str().unwrap().starts_with("4 zones are due: "), "{due}");
+
+    let reply = say(&app, json!({ "intent": "mark_cleaned", "zone": "the bathroom sinc" })).await;
+    assert_eq!(reply["zone_id"], sinks[0]["id"]);
+    assert_eq!(reply["speech"], "Marked Sink in Bathroom as cleaned. Next time in 7 days.");
+
+    let reply = say(&app, json!({ "intent": "when_due", "zone": "bathroom sink" })).await;
+    assert_eq!(reply["speech"], "Sink in Bathroom is due in 7 days.");
+    let reply = say(&app, json!({ "intent": "when_due", "zone": "kitchen sink" })).await;
+    assert_eq!(reply["speech"], "Sink in Kitchen has never been cleaned, so it is due now.");
+
+    let reply = say(&app, json!({ "intent": "mark_cleaned", "zone": "sink" })).await;
+    assert_eq!(reply["zone_id"], Value::Null);
+    let mut choices: Vec<&str> = reply["choices"].as_array().unwrap().iter().map(|c| c.as_str().unwrap()).collect();
+    choices.sort();
+    assert_eq!(choices, ["Sink in Bathroom", "Sink in Kitchen"]);
+    let speech = reply["speech"].as_str().unwrap();
+    assert!(speech.starts_with("Which one: Sink in ") && speech.contains(" or "), "{speech}");
+
+    let reply = say(&app, json!({ "intent": "mark_cleaned", "zone": "garage door" })).await;
+    assert_eq!(reply["speech"], "I couldn't find a zone called garage door.");
+    let due = say(&app, json!({ "intent": "due_today" })).await;
+    assert!(due["speech"].as_str().unwrap().starts_with("3 zones are due: "), "{due}");
+}
@@ -0,0 +1,239 @@
+use std::sync::{Arc, Mutex};
+
+use axum::{
+    body::{to_bytes, Body, Bytes},
+    http::{HeaderMap, Request, StatusCode},
+    routing::post,
+    Router,
+};
+use cleaner_api::{api, config::Config, models::AppState, scheduler, webhooks};
+use serde_json::json;
+use sqlx::sqlite::SqlitePoolOptions;
+use tower::ServiceExt; // for oneshot
+
+type Received = Arc<Mutex<Vec<(HeaderMap, Bytes)>>>;
+
+/// Local endpoint recording every request it gets.
+async fn receiver() -> (String, Received) {
+    let received: Received = Arc::default();
+    let sink = received.clone();
+    let app = Router::new().route(
+        "/hook",
+        post(move |headers: HeaderMap, body: Bytes| async move {
+            sink.lock().unwrap().push((headers, body));
+            StatusCode::NO_CONTENT
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (format!("http://{addr}/hook"), received)
+}
+
+async fn send_json(app: &Router, method: &str, uri: &str, body: &serde_json::Value) -> axum::response::Response {
+    app.clone()
+        .oneshot(
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn zone_cleaned_is_delivered_signed() {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+    let state = Arc::new(AppState::new(pool, &Config::default()).await.unwrap());
+    let app = Router::new().nest("/api/v1", api::routes()).with_state(state.clone());
+    let (url, received) = receiver().await;
+
+    let secret = "0123456789abcdef";
+    let res = send_json(&app, "POST", "/api/v1/webhooks", &json!({"url": url, "events": ["zone.dusted"], "secret": secret})).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    let res = send_json(&app, "POST", "/api/v1/webhooks", &json!({"url": url, "events": ["zone.cleaned"], "secret": secret})).await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Kitchen"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", room["id"].as_str().unwrap()), &json!({"name": "Sink", "frequency": "daily"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zone: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let zone_id = zone["id"].as_str().unwrap();
+    send_json(&app, "POST", &format!("/api/v1/zones/{zone_id}/clean"), &json!({})).await;
+
+    assert_eq!(webhooks::deliver_pending(&state).await.unwrap(), 1);
+    assert_eq!(webhooks::deliver_pending(&state).await.unwrap(), 0);
+
+    let received = received.lock().unwrap();
+    assert_eq!(received.len(), 1);
+    let (headers, body) = &received[0];
+    assert_eq!(headers["x-webhook-event"], "zone.cleaned");
+    assert_eq!(
+        headers["x-webhook-signature"].to_str().unwrap(),
+        format!("sha256={}", webhooks::sign(secret, body))
+    );
+    let payload: serde_json::Value = serde_json::from_slice(body).unwrap();
+    assert_eq!(payload["data"]["zone_id"], zone_id);
+}
+
+#[tokio::test]
+async fn scheduler_announces_zones_becoming_due_once() {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+    let state = Arc::new(AppState::new(pool, &Config::default()).await.unwrap());
+    let app = Router::new().nest("/api/v1", api::routes()).with_state(state.clone());
+    let (url, received) = receiver().await;
+
+    send_json(&app, "POST", "/api/v1/webhooks", &json!({"url": url, "events": ["zone.due"], "secret": "0123456789abcdef"})).await;
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Bath"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", room["id"].as_str().unwrap()), &json!({"name": "Tub", "frequency": "weekly"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zone: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let zone_id = zone["id"].as_str().unwrap();
+
+    // никогда не убиралась — сразу к уборке, но сообщаем об этом один раз
+    assert_eq!(scheduler::scan_due(&state).await.unwrap(), 1);
+    assert_eq!(scheduler::scan_due(&state).await.unwrap(), 0);
+
+    // уборка сбрасывает отметку; через неделю зона снова станет «к уборке»
+    send_json(&app, "POST", &format!("/api/v1/zones/{zone_id}/clean"), &json!({"cleaned_at": "2025-01-01T10:00:00Z"})).await;
+    assert_eq!(scheduler::scan_due(&state).await.unwrap(), 1);
+    send_json(&app, "POST", &format!("/api/v1/zones/{zone_id}/clean"), &json!({})).await;
+    assert_eq!(scheduler::scan_due(&state).await.unwrap(), 0);
+
+    assert_eq!(webhooks::deliver_pending(&state).await.unwrap(), 2);
+    let received = received.lock().unwrap();
+    let (headers, body) = &received[0];
+    assert_eq!(headers["x-webhook-event"], "zone.due");
+    let payload: serde_json::Value = serde_json::from_slice(body).unwrap();
+    assert_eq!(payload["data"]["zone_id"], zone_id);
+}
+
+#[tokio::test]
+async fn sent_events_land_in_the_notification_feed() {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+    let state = Arc::new(AppState::new(pool, &Config::default()).await.unwrap());
+    let app = Router::new().nest("/api/v1", api::routes()).with_state(state.clone());
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Kitchen"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", room["id"].as_str().unwrap()), &json!({"name": "Oven", "frequency": "weekly"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zone: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let zone_id = zone["id"].as_str().unwrap();
+
+    // без подписчиков ничего не отправлено — и в ленте пусто
+    send_json(&app, "POST", &format!("/api/v1/zones/{zone_id}/clean"), &json!({})).await;
+    send_json(&app, "POST", "/api/v1/webhooks", &json!({"url": "http://127.0.0.1:9/hook", "events": ["zone.cleaned"], "secret": "0123456789abcdef"})).await;
+    send_json(&app, "POST", &format!("/api/v1/zones/{zone_id}/clean"), &json!({})).await;
+    send_json(&app, "POST", &format!("/api/v1/zones/{zone_id}/clean"), &json!({})).await;
+
+    let res = send_json(&app, "GET", "/api/v1/notifications", &json!({})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let feed: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(feed.len(), 2);
+    assert_eq!(feed[0]["summary"], "Oven cleaned");
+    assert_eq!(feed[0]["data"]["zone_id"], zone_id);
+
+    let id = feed[0]["id"].as_str().unwrap();
+    let res = send_json(&app, "POST", &format!("/api/v1/notifications/{id}/read"), &json!({})).await;
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+    let res = send_json(&app, "GET", "/api/v1/notifications/unread", &json!({})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let unread: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(unread["unread"], 1);
+
+    send_json(&app, "POST", "/api/v1/notifications/read", &json!({})).await;
+    let res = send_json(&app, "GET", "/api/v1/notifications?unread=true", &json!({})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let feed: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+    assert!(feed.is_empty());
+}
+
+#[tokio::test]
+async fn signed_inbound_event_cleans_the_mapped_zone_once() {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+    let state = Arc::new(AppState::new(pool, &Config::default()).await.unwrap());
+    let app = Router::new().nest("/api/v1", api::routes()).with_state(state);
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Kitchen"})).await;
+    let room: serde_json::Value = serde_json::from_slice(&to_bytes(res.into_body(), usize::MAX).await.unwrap()).unwrap();
+    let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", room["id"].as_str().unwrap()), &json!({"name": "Floor", "frequency": "weekly"})).await;
+    let zone: serde_json::Value = serde_json::from_slice(&to_bytes(res.into_body(), usize::MAX).await.unwrap()).unwrap();
+
+    let secret = "vacuum-secret-0123";
+    let res = send_json(&app, "POST", "/api/v1/integrations", &json!({
+        "name": "Robot vacuum",
+        "secret": secret,
+        "mappings": [{"event": "vacuum.finished.kitchen", "zone_id": zone["id"]}],
+    }))
+    .await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let integration: serde_json::Value = serde_json::from_slice(&to_bytes(res.into_body(), usize::MAX).await.unwrap()).unwrap();
+    assert!(integration.get("secret").is_none());
+    let uri = format!("/api/v1/integrations/inbound/{}", integration["id"].as_str().unwrap());
+
+    let call = |nonce: &str, signed_with: &str, body: serde_json::Value| {
+        let body = body.to_string();
+        let ts = chrono::Utc::now().timestamp();
+        let signature = webhooks::sign(signed_with, format!("{ts}.{nonce}.{body}").as_bytes());
+        Request::post(&uri)
+            .header("content-type", "application/json")
+            .header("x-timestamp", ts.to_string())
+            .header("x-nonce", nonce)
+            .header("x-signature", format!("sha256={signature}"))
+            .body(Body::from(body))
+            .unwrap()
+    };
+    let finished = json!({"event": "vacuum.finished.kitchen"});
+
+    let res = app.clone().oneshot(call("n-1", "wrong-secret-00000", finished.clone())).await.unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+    let res = app.clone().oneshot(call("n-1", secret, finished.clone())).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let result: serde_json::Value = serde_json::from_slice(&to_bytes(res.into_body(), usize::MAX).await.unwrap()).unwrap();
+    assert_eq!(result["cleaned"], true);
+    assert_eq!(result["zone_id"], zone["id"]);
+
+    // перехваченный вызов повторить нельзя
+    let res = app.clone().oneshot(call("n-1", secret, finished.clone())).await.unwrap();
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+
+    let res = app.clone().oneshot(call("n-2", secret, json!({"event": "button.pressed"}))).await.unwrap();
+    let result: serde_json::Value = serde_json::from_slice(&to_bytes(res.into_body(), usize::MAX).await.unwrap()).unwrap();
+    assert_eq!(result["cleaned"], false);
+
+    let res = send_json(&app, "GET", &format!("/api/v1/zones/{}/events", zone["id"].as_str().unwrap()), &json!({})).await;
+    let events: serde_json::Value = serde_json::from_slice(&to_bytes(res.into_body(), usize::MAX).await.unwrap()).unwrap();
+    let cleans: Vec<_> = events.as_array().unwrap().iter().filter(|e| e["change"]["kind"] == "cleaned").collect();
+    assert_eq!(cleans.len(), 1);
+    assert_eq!(cleans[0]["change"]["auto"], true);
+}
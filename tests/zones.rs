@@ -1,10 +1,13 @@
 use axum::{
     body::{to_bytes, Body},
     http::{Request, StatusCode},
-    routing::{get, post},
     Router,
 };
-use cleaner_api::{api::{rooms, zones}, models::{AppState, Frequency}};
+use cleaner_api::{
+    api,
+    config::Config,
+    models::{AppState, Frequency, Page},
+};
 use serde_json::json;
 use sqlx::sqlite::SqlitePoolOptions;
 use std::sync::Arc;
@@ -17,20 +20,27 @@ async fn test_app() -> Router {
         .await
         .unwrap();
     sqlx::migrate!("./migrations").run(&pool).await.unwrap();
-    let state = Arc::new(AppState { pool });
-    let api_routes = Router::new()
-        .route("/rooms", get(rooms::list_rooms).post(rooms::create_room))
-        .route(
-            "/rooms/:room_id/zones",
-            get(zones::list_zones).post(zones::create_zone),
-        )
-        .route(
-            "/zones/:id",
-            get(zones::get_zone).patch(zones::update_zone),
+    let state = Arc::new(AppState::new(pool, &Config::default()).await.unwrap());
+    Router::new().nest("/api/v1", api::routes()).with_state(state)
+}
+
+async fn send_json(
+    app: &Router,
+    method: &str,
+    uri: &str,
+    body: &serde_json::Value,
+) -> axum::response::Response {
+    app.clone()
+        .oneshot(
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
         )
-        .route("/zones/:id/clean", post(zones::clean_zone))
-        .route("/rooms/:id", get(rooms::get_room));
-    Router::new().nest("/api/v1", api_routes).with_state(state)
+        .await
+        .unwrap()
 }
 
 #[tokio::test]
@@ -135,8 +145,8 @@ async fn room_get_includes_zone_stats() {
         .await
         .unwrap();
     let body = to_bytes(zones_res.into_body(), usize::MAX).await.unwrap();
-    let zones: Vec<cleaner_api::models::ZoneView> = serde_json::from_slice(&body).unwrap();
-    let first_zone = zones.first().unwrap();
+    let zones: Page<cleaner_api::models::ZoneView> = serde_json::from_slice(&body).unwrap();
+    let first_zone = zones.items.first().unwrap();
     app.clone()
         .oneshot(
             Request::post(format!("/api/v1/zones/{}/clean", first_zone.id))
@@ -159,3 +169,1686 @@ async fn room_get_includes_zone_stats() {
     assert_eq!(room.zones_total, Some(2));
     assert_eq!(room.zones_cleaned_count, Some(1));
 }
+
+#[tokio::test]
+async fn clean_requires_checked_tasks() {
+    let app = test_app().await;
+
+    let res = app
+        .clone()
+        .oneshot(
+            Request::post("/api/v1/rooms")
+                .header("content-type", "application/json")
+                .body(Body::from(json!({"name": "Kitchen"}).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+
+    let res = app
+        .clone()
+        .oneshot(
+            Request::post(format!("/api/v1/rooms/{}/zones", room.id))
+                .header("content-type", "application/json")
+                .body(Body::from(json!({"name": "Sink", "frequency": Frequency::Daily}).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+
+    let res = app
+        .clone()
+        .oneshot(
+            Request::post(format!("/api/v1/zones/{}/tasks", zone.id))
+                .header("content-type", "application/json")
+                .body(Body::from(json!({"title": "Scrub basin"}).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let task: cleaner_api::models::ZoneTask = serde_json::from_slice(&body).unwrap();
+    assert!(task.required);
+
+    let clean_uri = format!("/api/v1/zones/{}/clean", zone.id);
+    let clean_body = json!({"require_tasks": true});
+    let res = send_json(&app, "POST", &clean_uri, &clean_body).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    let res = app
+        .clone()
+        .oneshot(
+            Request::patch(format!("/api/v1/zones/{}/tasks/{}", task.zone_id, task.id))
+                .header("content-type", "application/json")
+                .body(Body::from(json!({"checked": true}).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let res = send_json(&app, "POST", &clean_uri, &clean_body).await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    // after the clean the checklist is reset for the next cycle
+    let res = app
+        .clone()
+        .oneshot(
+            Request::get(format!("/api/v1/zones/{}/tasks", task.zone_id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let checklist: cleaner_api::models::Checklist = serde_json::from_slice(&body).unwrap();
+    assert!(checklist.tasks[0].checked_at.is_none());
+
+    // the user's own instructions come with the checklist
+    let instructions_uri = format!("/api/v1/zones/{}/instructions", task.zone_id);
+    let res = send_json(&app, "PUT", &instructions_uri, &json!({"instructions": "Dry the **grout** last"})).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let res = send_json(&app, "GET", &format!("/api/v1/zones/{}/tasks", task.zone_id), &json!({})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let checklist: cleaner_api::models::Checklist = serde_json::from_slice(&body).unwrap();
+    assert_eq!(checklist.instructions.as_deref(), Some("Dry the **grout** last"));
+    assert_eq!(checklist.tasks.len(), 1);
+
+    let res = send_json(&app, "PUT", &instructions_uri, &json!({"instructions": "x".repeat(17 * 1024)})).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    let res = send_json(&app, "PUT", &instructions_uri, &json!({"instructions": "  "})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(serde_json::from_slice::<serde_json::Value>(&body).unwrap(), json!({"instructions": null}));
+    let res = send_json(&app, "PUT", "/api/v1/zones/missing/instructions", &json!({"instructions": null})).await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn bulk_and_group_clean_reset_checklist() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Kitchen"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", room.id), &json!({"name": "Sink", "frequency": "daily"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+    let res = send_json(&app, "POST", &format!("/api/v1/zones/{}/tasks", zone.id), &json!({"title": "Scrub basin"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let task: cleaner_api::models::ZoneTask = serde_json::from_slice(&body).unwrap();
+    let res = send_json(&app, "POST", "/api/v1/groups", &json!({"name": "Evening"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let group: cleaner_api::models::ZoneGroup = serde_json::from_slice(&body).unwrap();
+    send_json(&app, "PUT", &format!("/api/v1/groups/{}/zones/{}", group.id, zone.id), &json!({})).await;
+
+    let task_uri = format!("/api/v1/zones/{}/tasks/{}", zone.id, task.id);
+    let tasks_uri = format!("/api/v1/zones/{}/tasks", zone.id);
+    for (uri, body) in [
+        ("/api/v1/zones/bulk/clean".to_string(), json!({"zone_ids": [zone.id]})),
+        (format!("/api/v1/groups/{}/clean", group.id), json!({})),
+    ] {
+        let res = send_json(&app, "PATCH", &task_uri, &json!({"checked": true})).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let res = send_json(&app, "POST", &uri, &body).await;
+        assert_eq!(res.status(), StatusCode::OK, "{uri}");
+
+        let res = send_json(&app, "GET", &tasks_uri, &json!({})).await;
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let checklist: cleaner_api::models::Checklist = serde_json::from_slice(&body).unwrap();
+        assert!(checklist.tasks[0].checked_at.is_none(), "{uri}");
+    }
+}
+
+#[tokio::test]
+async fn zone_events_replay_to_current_state() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Bath"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+
+    let res = send_json(
+        &app,
+        "POST",
+        &format!("/api/v1/rooms/{}/zones", room.id),
+        &json!({"name": "Tub", "frequency": Frequency::Weekly}),
+    )
+    .await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+
+    let zone_uri = format!("/api/v1/zones/{}", zone.id);
+    send_json(&app, "PATCH", &zone_uri, &json!({"name": "Bathtub"})).await;
+    send_json(&app, "PATCH", &zone_uri, &json!({"frequency": "daily"})).await;
+    let clean_uri = format!("/api/v1/zones/{}/clean", zone.id);
+    send_json(&app, "POST", &clean_uri, &json!({"cleaned_at": "2030-01-02T00:00:00Z"})).await;
+    // a late-arriving offline clean must not move last_cleaned_at backwards
+    let res = send_json(&app, "POST", &clean_uri, &json!({"cleaned_at": "2030-01-01T00:00:00Z"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let current: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+    assert_eq!(current.last_cleaned_at.unwrap().to_rfc3339(), "2030-01-02T00:00:00+00:00");
+
+    let res = app
+        .clone()
+        .oneshot(Request::get(format!("{zone_uri}/events")).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let mut history: Vec<cleaner_api::models::ZoneEvent> = serde_json::from_slice(&body).unwrap();
+    let kinds: Vec<&str> = history.iter().map(|e| e.change.kind()).collect();
+    assert_eq!(kinds, ["created", "renamed", "frequency_changed", "cleaned", "cleaned"]);
+
+    let replayed = cleaner_api::events::fold(&history).unwrap();
+    assert_eq!(replayed.name, current.name);
+    assert_eq!(replayed.frequency, current.frequency);
+    assert_eq!(replayed.last_cleaned_at, current.last_cleaned_at);
+
+    // arrival order does not matter
+    history.reverse();
+    let reordered = cleaner_api::events::fold(&history).unwrap();
+    assert_eq!(reordered.name, replayed.name);
+    assert_eq!(reordered.last_cleaned_at, replayed.last_cleaned_at);
+}
+
+#[tokio::test]
+async fn tag_filter_on_zone_listings() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Yard"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+
+    let zones_uri = format!("/api/v1/rooms/{}/zones", room.id);
+    let mut zone_ids = Vec::new();
+    for name in ["Deck", "Shed"] {
+        let res = send_json(&app, "POST", &zones_uri, &json!({"name": name, "frequency": "weekly"})).await;
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+        zone_ids.push(zone.id);
+    }
+
+    let res = send_json(&app, "POST", "/api/v1/tags", &json!({"name": "outdoors"})).await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let tag: cleaner_api::models::Tag = serde_json::from_slice(&body).unwrap();
+    let res = send_json(&app, "POST", "/api/v1/tags", &json!({"name": "outdoors"})).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    let res = send_json(&app, "PUT", &format!("/api/v1/zones/{}/tags/{}", zone_ids[0], tag.id), &json!(null)).await;
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+
+    for uri in [format!("{zones_uri}?tag={}", tag.id), format!("/api/v1/zones/due?tag={}", tag.id)] {
+        let res = app
+            .clone()
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let zones: Page<cleaner_api::models::ZoneView> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(zones.total, 1);
+        assert_eq!(zones.items[0].id, zone_ids[0]);
+    }
+}
+
+#[tokio::test]
+async fn notes_round_trip_and_cleaning_note_in_history() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Kitchen"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+
+    let res = send_json(
+        &app,
+        "POST",
+        &format!("/api/v1/rooms/{}/zones", room.id),
+        &json!({"name": "Kettle", "frequency": "monthly", "notes": "use citric acid"}),
+    )
+    .await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+    assert_eq!(zone.notes.as_deref(), Some("use citric acid"));
+
+    let res = send_json(
+        &app,
+        "POST",
+        &format!("/api/v1/zones/{}/clean", zone.id),
+        &json!({"note": "ran out of acid, used vinegar"}),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = app
+        .clone()
+        .oneshot(
+            Request::get(format!("/api/v1/zones/{}/events", zone.id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let history: Vec<cleaner_api::models::ZoneEvent> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        history.last().unwrap().change,
+        cleaner_api::models::ZoneChange::Cleaned {
+            note: Some("ran out of acid, used vinegar".into()),
+            auto: false,
+            cost_cents: None,
+            duration_minutes: None,
+        }
+    );
+}
+
+#[tokio::test]
+async fn zone_metadata_is_validated_and_returned() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Hall"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    let zones_uri = format!("/api/v1/rooms/{}/zones", room.id);
+
+    let res = send_json(&app, "POST", &zones_uri, &json!({"name": "Floor", "frequency": "daily", "metadata": [1, 2]})).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    let huge = "x".repeat(5000);
+    let res = send_json(&app, "POST", &zones_uri, &json!({"name": "Floor", "frequency": "daily", "metadata": {"blob": huge}})).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    let meta = json!({"ha_entity": "vacuum.hall"});
+    let res = send_json(&app, "POST", &zones_uri, &json!({"name": "Floor", "frequency": "daily", "metadata": meta})).await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+
+    let res = app
+        .clone()
+        .oneshot(Request::get(format!("/api/v1/zones/{}", zone.id)).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+    assert_eq!(zone.metadata, Some(meta));
+}
+
+#[tokio::test]
+async fn cleaning_uses_up_linked_supplies() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Bath"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", room.id), &json!({"name": "Tub", "frequency": "weekly"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+
+    let res = send_json(&app, "POST", "/api/v1/supplies", &json!({"name": "Cleaner", "unit": "ml", "quantity": 100, "low_threshold": 40})).await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let supply: cleaner_api::models::Supply = serde_json::from_slice(&body).unwrap();
+
+    let link_uri = format!("/api/v1/zones/{}/supplies/{}", zone.id, supply.id);
+    let res = send_json(&app, "PUT", &link_uri, &json!({"usage": -1})).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    let res = send_json(&app, "PUT", &link_uri, &json!({"usage": 50})).await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = send_json(&app, "POST", &format!("/api/v1/zones/{}/clean", zone.id), &json!({})).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let res = send_json(&app, "POST", "/api/v1/zones/bulk/clean", &json!({"zone_ids": [zone.id, "missing"]})).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let bulk: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(bulk["updated"], 1);
+    assert_eq!(bulk["results"][0]["status"], "ok");
+    assert_eq!(bulk["results"][1]["status"], "not_found");
+
+    // остаток не уходит в минус
+    let res = app
+        .clone()
+        .oneshot(Request::get("/api/v1/supplies/low").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let low: Vec<cleaner_api::models::Supply> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(low.len(), 1);
+    assert_eq!(low[0].quantity, 0.0);
+}
+
+#[tokio::test]
+async fn auto_zones_skip_due_list_and_clean_themselves() {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+    let state = Arc::new(AppState::new(pool, &Config::default()).await.unwrap());
+    let app = Router::new().nest("/api/v1", api::routes()).with_state(state.clone());
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Hall"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    let zones_uri = format!("/api/v1/rooms/{}/zones", room.id);
+    send_json(&app, "POST", &zones_uri, &json!({"name": "Rug", "frequency": "daily"})).await;
+    let res = send_json(&app, "POST", &zones_uri, &json!({
+        "name": "Floor", "frequency": "daily", "auto": true,
+        "source": "home_assistant", "external_id": "vacuum.hall"
+    })).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let floor: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+    assert!(floor.auto);
+
+    let res = app
+        .clone()
+        .oneshot(Request::get("/api/v1/zones/due").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let due: Page<cleaner_api::models::ZoneView> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(due.items.iter().map(|z| z.name.as_str()).collect::<Vec<_>>(), ["Rug"]);
+
+    // по расписанию: зона ещё ни разу не убиралась
+    assert_eq!(api::zones::run_auto_clean(&state).await.unwrap(), 1);
+    assert_eq!(api::zones::run_auto_clean(&state).await.unwrap(), 0);
+
+    let res = send_json(&app, "POST", "/api/v1/zones/auto/clean", &json!({"source": "home_assistant", "external_id": "vacuum.hall"})).await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let history = cleaner_api::events::list(&state.pool, &floor.id).await.unwrap();
+    let autos = history
+        .iter()
+        .filter(|e| matches!(e.change, cleaner_api::models::ZoneChange::Cleaned { auto: true, .. }))
+        .count();
+    assert_eq!(autos, 2);
+}
+
+#[tokio::test]
+async fn today_caps_due_zones_and_carries_over_the_rest() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Flat"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    let zones_uri = format!("/api/v1/rooms/{}/zones", room.id);
+    for name in ["A", "B", "C"] {
+        send_json(&app, "POST", &zones_uri, &json!({"name": name, "frequency": "daily"})).await;
+    }
+
+    let res = send_json(&app, "PATCH", "/api/v1/settings", &json!({"max_zones_per_day": 2})).await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = app
+        .clone()
+        .oneshot(Request::get("/api/v1/today").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let today: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(today["zones"].as_array().unwrap().len(), 2);
+    assert_eq!(today["carried_over"], 1);
+
+    send_json(&app, "PATCH", "/api/v1/settings", &json!({"max_zones_per_day": 0})).await;
+    let res = app
+        .clone()
+        .oneshot(Request::get("/api/v1/today").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let today: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(today["zones"].as_array().unwrap().len(), 3);
+    assert_eq!(today["max_zones_per_day"], serde_json::Value::Null);
+}
+
+#[tokio::test]
+async fn costs_sum_purchases_and_paid_cleanings() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Flat"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", room.id), &json!({"name": "Windows", "frequency": "monthly"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+
+    let res = send_json(&app, "POST", "/api/v1/supplies", &json!({"name": "Glass cleaner", "quantity": 0})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let supply: cleaner_api::models::Supply = serde_json::from_slice(&body).unwrap();
+    let purchase = json!({"quantity": 2, "price_cents": 450, "vendor": "Corner shop", "purchased_at": "2025-09-03T10:00:00Z"});
+    let res = send_json(&app, "POST", &format!("/api/v1/supplies/{}/purchases", supply.id), &purchase).await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+
+    let clean = json!({"cleaned_at": "2025-09-10T10:00:00Z", "cost_cents": 3000});
+    send_json(&app, "POST", &format!("/api/v1/zones/{}/clean", zone.id), &clean).await;
+
+    let res = app
+        .clone()
+        .oneshot(Request::get(format!("/api/v1/supplies/{}", supply.id)).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let supply: cleaner_api::models::Supply = serde_json::from_slice(&body).unwrap();
+    assert_eq!(supply.quantity, 2.0);
+
+    let res = app
+        .clone()
+        .oneshot(Request::get("/api/v1/stats/costs?period=month").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let costs: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        costs,
+        json!([{"period": "2025-09", "supplies_cents": 450, "services_cents": 3000, "total_cents": 3450}])
+    );
+}
+
+#[tokio::test]
+async fn costs_are_scoped_by_home() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/homes", &json!({"name": "Cottage"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let cottage: cleaner_api::models::Home = serde_json::from_slice(&body).unwrap();
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Sauna", "home_id": cottage.id})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", room.id), &json!({"name": "Benches", "frequency": "monthly"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+
+    // одно средство только для зоны коттеджа, другое ни к чему не привязано
+    for (name, price) in [("Wood oil", 900), ("Sponges", 100)] {
+        let res = send_json(&app, "POST", "/api/v1/supplies", &json!({"name": name, "quantity": 0})).await;
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let supply: cleaner_api::models::Supply = serde_json::from_slice(&body).unwrap();
+        let purchase = json!({"quantity": 1, "price_cents": price, "purchased_at": "2025-09-03T10:00:00Z"});
+        send_json(&app, "POST", &format!("/api/v1/supplies/{}/purchases", supply.id), &purchase).await;
+        if name == "Wood oil" {
+            let res = send_json(&app, "PUT", &format!("/api/v1/zones/{}/supplies/{}", zone.id, supply.id), &json!({})).await;
+            assert!(res.status().is_success());
+        }
+    }
+    let clean = json!({"cleaned_at": "2025-09-10T10:00:00Z", "cost_cents": 3000});
+    send_json(&app, "POST", &format!("/api/v1/zones/{}/clean", zone.id), &clean).await;
+
+    let totals = |app: Router, uri: String| async move {
+        let res = send_json(&app, "GET", &uri, &json!({})).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let costs: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        (costs[0]["supplies_cents"].as_i64().unwrap(), costs[0]["services_cents"].as_i64().unwrap())
+    };
+    assert_eq!(totals(app.clone(), "/api/v1/stats/costs".into()).await, (100, 0));
+    assert_eq!(totals(app.clone(), format!("/api/v1/stats/costs?home_id={}", cottage.id)).await, (1000, 3000));
+}
+
+#[tokio::test]
+async fn weekday_zone_is_due_on_the_next_listed_day() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Flat"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    let zones_uri = format!("/api/v1/rooms/{}/zones", room.id);
+
+    let res = send_json(&app, "POST", &zones_uri, &json!({"name": "Bins", "frequency": "weekdays"})).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    let res = send_json(&app, "POST", &zones_uri, &json!({"name": "Bins", "frequency": "weekdays", "weekdays": [1, 8]})).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    let res = send_json(&app, "POST", &zones_uri, &json!({"name": "Bins", "frequency": "weekdays", "weekdays": [4, 1]})).await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+    assert_eq!(zone.weekdays, Some(vec![1, 4]));
+
+    // понедельник 2025-09-01 → следующий срок в четверг 2025-09-04
+    let res = send_json(&app, "POST", &format!("/api/v1/zones/{}/clean", zone.id), &json!({"cleaned_at": "2025-09-01T18:30:00Z"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+    assert_eq!(zone.next_due_at.unwrap().to_rfc3339(), "2025-09-04T00:00:00+00:00");
+
+    let res = send_json(&app, "PATCH", &format!("/api/v1/zones/{}", zone.id), &json!({"frequency": "daily"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+    assert_eq!(zone.weekdays, None);
+}
+
+#[tokio::test]
+async fn biweekly_quarterly_and_yearly_frequencies() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "House"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    let zones_uri = format!("/api/v1/rooms/{}/zones", room.id);
+
+    for (frequency, expected) in [
+        (Frequency::Biweekly, "2024-03-14T09:00:00+00:00"),
+        (Frequency::Quarterly, "2024-05-29T09:00:00+00:00"),
+        (Frequency::Yearly, "2025-02-28T09:00:00+00:00"),
+    ] {
+        let res = send_json(&app, "POST", &zones_uri, &json!({"name": "Gutters", "frequency": frequency})).await;
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+        let res = send_json(&app, "POST", &format!("/api/v1/zones/{}/clean", zone.id), &json!({"cleaned_at": "2024-02-29T09:00:00Z"})).await;
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+        assert_eq!(zone.frequency, frequency);
+        assert_eq!(zone.next_due_at.unwrap().to_rfc3339(), expected);
+    }
+}
+
+#[tokio::test]
+async fn timezone_moves_due_dates_to_local_midnight() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "PATCH", "/api/v1/settings", &json!({"timezone": "Mars/Olympus"})).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    let res = send_json(&app, "PATCH", "/api/v1/settings", &json!({"timezone": "Europe/Moscow"})).await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Flat"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", room.id), &json!({"name": "Sink", "frequency": "daily"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+
+    // 22:30 UTC — уже 2 сентября в Москве, значит срок наступает в полночь 3-го по Москве
+    let res = send_json(&app, "POST", &format!("/api/v1/zones/{}/clean", zone.id), &json!({"cleaned_at": "2025-09-01T22:30:00Z"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let cleaned: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+    assert_eq!(cleaned.next_due_at.unwrap().to_rfc3339(), "2025-09-02T21:00:00+00:00");
+
+    send_json(&app, "PATCH", "/api/v1/settings", &json!({"timezone": ""})).await;
+    let res = send_json(&app, "GET", &format!("/api/v1/zones/{}", zone.id), &json!({})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+    assert_eq!(zone.next_due_at.unwrap().to_rfc3339(), "2025-09-02T22:30:00+00:00");
+}
+
+#[tokio::test]
+async fn suggestion_picks_a_due_zone_and_keeps_it_for_the_day() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "GET", "/api/v1/suggestion", &json!({})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let empty: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(empty["zone"], serde_json::Value::Null);
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Hall"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    let zones_uri = format!("/api/v1/rooms/{}/zones", room.id);
+    let res = send_json(&app, "POST", &zones_uri, &json!({"name": "Mirror", "frequency": "weekly", "estimated_minutes": 5})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let mirror: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+    assert_eq!(mirror.estimated_minutes, Some(5));
+    let res = send_json(&app, "POST", &zones_uri, &json!({"name": "Shoe rack", "frequency": "weekly"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let rack: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+    send_json(&app, "POST", &format!("/api/v1/zones/{}/clean", rack.id), &json!({})).await;
+
+    for _ in 0..2 {
+        let res = send_json(&app, "GET", "/api/v1/suggestion", &json!({})).await;
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let pick: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(pick["zone"]["id"], mirror.id.as_str());
+    }
+
+    let res = send_json(&app, "PATCH", &format!("/api/v1/zones/{}", mirror.id), &json!({"estimated_minutes": 0})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let mirror: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+    assert_eq!(mirror.estimated_minutes, None);
+}
+
+#[tokio::test]
+async fn paused_zone_is_left_out_until_resumed() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Garage"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", room.id), &json!({"name": "Floor", "frequency": "monthly"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+    let pause_uri = format!("/api/v1/zones/{}/pause", zone.id);
+
+    let res = send_json(&app, "PUT", &pause_uri, &json!({"until": "2020-01-01T00:00:00Z"})).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    let until = chrono::Utc::now() + chrono::Duration::days(3);
+    let res = send_json(&app, "PUT", &pause_uri, &json!({"until": until})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let paused: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+    assert!(!paused.is_due);
+    assert_eq!(paused.next_due_at, paused.paused_until);
+
+    let res = send_json(&app, "GET", "/api/v1/zones/due?within=7d", &json!({})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let due: Page<cleaner_api::models::ZoneView> = serde_json::from_slice(&body).unwrap();
+    assert!(due.items.is_empty());
+    let res = send_json(&app, "GET", "/api/v1/stats/overview", &json!({})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let overview: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(overview["due_zones"], 0);
+
+    let res = send_json(&app, "DELETE", &pause_uri, &json!({})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let resumed: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+    assert!(resumed.is_due);
+    assert_eq!(resumed.paused_until, None);
+}
+
+#[tokio::test]
+async fn weekly_costs_follow_the_first_day_of_the_week() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/supplies", &json!({"name": "Sponges", "quantity": 0})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let supply: cleaner_api::models::Supply = serde_json::from_slice(&body).unwrap();
+    // суббота и воскресенье одной ISO-недели
+    for day in ["2025-09-06T10:00:00Z", "2025-09-07T10:00:00Z"] {
+        let purchase = json!({"quantity": 1, "price_cents": 100, "purchased_at": day});
+        send_json(&app, "POST", &format!("/api/v1/supplies/{}/purchases", supply.id), &purchase).await;
+    }
+
+    let weeks = |app: Router| async move {
+        let res = app.oneshot(Request::get("/api/v1/stats/costs?period=week").body(Body::empty()).unwrap()).await.unwrap();
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let costs: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+        costs.into_iter().map(|c| c["total_cents"].as_i64().unwrap()).collect::<Vec<_>>()
+    };
+    assert_eq!(weeks(app.clone()).await, vec![200]);
+
+    let res = send_json(&app, "PATCH", "/api/v1/settings", &json!({"week_starts_on": 8})).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    send_json(&app, "PATCH", "/api/v1/settings", &json!({"week_starts_on": 7})).await;
+    assert_eq!(weeks(app.clone()).await, vec![100, 100]);
+}
+
+#[tokio::test]
+async fn due_zones_page_by_cursor_and_count() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Study"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    let zones_uri = format!("/api/v1/rooms/{}/zones", room.id);
+    let mut ids = Vec::new();
+    for name in ["Desk", "Shelf", "Lamp", "Rug"] {
+        let res = send_json(&app, "POST", &zones_uri, &json!({"name": name, "frequency": "weekly"})).await;
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+        ids.push(zone.id);
+    }
+    // Desk давно просрочен, Rug только что убран — вне горизонта
+    send_json(&app, "POST", &format!("/api/v1/zones/{}/clean", ids[0]), &json!({"cleaned_at": "2025-01-01T00:00:00Z"})).await;
+    send_json(&app, "POST", &format!("/api/v1/zones/{}/clean", ids[3]), &json!({})).await;
+
+    let res = send_json(&app, "GET", "/api/v1/zones/due?within=soon", &json!({})).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    // overflowing horizons are rejected, not a panic
+    for within in ["9223372036854775807w", "9999999999999d", "106751991167d"] {
+        let res = send_json(&app, "GET", &format!("/api/v1/zones/due?within={within}"), &json!({})).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST, "{within}");
+        let res = send_json(&app, "GET", &format!("/api/v1/stats/overview?due_within={within}"), &json!({})).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST, "{within}");
+    }
+    let res = send_json(&app, "GET", "/api/v1/zones/due?within=1d&count_only=true", &json!({})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let count: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(count["count"], 3);
+
+    let mut seen = Vec::new();
+    let mut uri = "/api/v1/zones/due?within=1d&limit=2".to_string();
+    loop {
+        let res = send_json(&app, "GET", &uri, &json!({})).await;
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let page: Page<cleaner_api::models::ZoneView> = serde_json::from_slice(&body).unwrap();
+        assert!(page.items.len() <= 2);
+        assert_eq!(page.total, 3);
+        seen.extend(page.items.into_iter().map(|z| z.name));
+        match page.next_cursor {
+            Some(c) => uri = format!("/api/v1/zones/due?within=1d&limit=2&cursor={c}"),
+            None => break,
+        }
+    }
+    // никогда не убранные — первыми, затем по сроку
+    assert_eq!(seen.len(), 3);
+    assert_eq!(seen[2], "Desk");
+}
+
+#[tokio::test]
+async fn overview_counts_zones_due_soon() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Balcony"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", room.id), &json!({"name": "Plants", "frequency": "weekly"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+    let five_days_ago = chrono::Utc::now() - chrono::Duration::days(5);
+    send_json(&app, "POST", &format!("/api/v1/zones/{}/clean", zone.id), &json!({"cleaned_at": five_days_ago})).await;
+
+    let res = send_json(&app, "GET", "/api/v1/stats/overview?due_within=soon", &json!({})).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    let res = send_json(&app, "GET", "/api/v1/stats/overview?due_within=3d", &json!({})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let overview: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(overview["due_zones"], 0);
+    assert_eq!(overview["due_soon"], 1);
+}
+
+#[tokio::test]
+async fn frequency_change_can_restart_the_schedule() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Attic"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", room.id), &json!({"name": "Boxes", "frequency": "weekly"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+    let zone_uri = format!("/api/v1/zones/{}", zone.id);
+    send_json(&app, "POST", &format!("{zone_uri}/clean"), &json!({"cleaned_at": "2025-01-01T00:00:00Z"})).await;
+
+    let res = send_json(&app, "PATCH", &zone_uri, &json!({"frequency": "monthly", "reschedule": "now"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+    assert!(!zone.is_due);
+    assert!(zone.schedule_anchor_at.is_some());
+
+    let res = send_json(&app, "GET", &format!("{zone_uri}/events"), &json!({})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let events: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+    let change = &events.last().unwrap()["change"];
+    assert_eq!(change["kind"], "frequency_changed");
+    assert_eq!(change["previous_frequency"], "weekly");
+
+    // по умолчанию — от последней уборки
+    let res = send_json(&app, "PATCH", &zone_uri, &json!({"frequency": "weekly"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+    assert!(zone.is_due);
+    assert_eq!(zone.schedule_anchor_at, None);
+}
+
+#[tokio::test]
+async fn breakdown_groups_zones_by_room_frequency_and_tag() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Hall"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    let mut zones = Vec::new();
+    for (name, frequency) in [("Mirror", "weekly"), ("Shoes", "weekly"), ("Lamp", "monthly")] {
+        let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", room.id), &json!({"name": name, "frequency": frequency})).await;
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+        zones.push(zone.id);
+    }
+    send_json(&app, "POST", &format!("/api/v1/zones/{}/clean", zones[2]), &json!({})).await;
+    let res = send_json(&app, "POST", "/api/v1/tags", &json!({"name": "glass"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let tag: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    send_json(&app, "PUT", &format!("/api/v1/zones/{}/tags/{}", zones[0], tag["id"].as_str().unwrap()), &json!({})).await;
+
+    let res = send_json(&app, "GET", "/api/v1/stats/breakdown", &json!({})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let groups: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(groups, json!([{"key": room.id, "label": "Hall", "zones": 3, "due": 2}]));
+
+    let res = send_json(&app, "GET", "/api/v1/stats/breakdown?by=frequency", &json!({})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let groups: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(groups[0], json!({"key": "weekly", "label": "weekly", "zones": 2, "due": 2}));
+    assert_eq!(groups[1], json!({"key": "monthly", "label": "monthly", "zones": 1, "due": 0}));
+
+    let res = send_json(&app, "GET", "/api/v1/stats/breakdown?by=tag", &json!({})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let groups: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(groups[0], json!({"key": null, "label": null, "zones": 2, "due": 1}));
+    assert_eq!(groups[1]["label"], "glass");
+
+    let res = send_json(&app, "GET", "/api/v1/stats/breakdown?by=color", &json!({})).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn put_creates_zone_under_client_id_then_updates_it() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Garage"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    let id = uuid::Uuid::new_v4().to_string();
+    let uri = format!("/api/v1/rooms/{}/zones/{id}", room.id);
+
+    let res = send_json(&app, "PUT", &format!("/api/v1/rooms/{}/zones/not-a-uuid", room.id), &json!({"name": "Floor", "frequency": "weekly"})).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    let res = send_json(&app, "PUT", &uri, &json!({"name": "Floor", "frequency": "weekly"})).await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+    assert_eq!(zone.id, id);
+
+    let res = send_json(&app, "PUT", &uri, &json!({"name": "Floor", "frequency": "monthly", "icon": "broom", "source": "sheet", "external_id": "A1"})).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+    assert_eq!(zone.frequency, Frequency::Monthly);
+    assert_eq!(zone.icon.as_deref(), Some("broom"));
+    assert_eq!((zone.source.as_deref(), zone.external_id.as_deref()), (Some("sheet"), Some("A1")));
+
+    // PUT replaces the whole zone: what the body leaves out is cleared
+    let res = send_json(&app, "PUT", &uri, &json!({"name": "Floor", "frequency": "monthly"})).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let res = send_json(&app, "GET", &format!("/api/v1/zones/{id}"), &json!({})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+    assert_eq!(zone.icon, None);
+    assert_eq!((zone.source, zone.external_id), (None, None));
+
+    let other = format!("/api/v1/rooms/{}/zones/{}", room.id, uuid::Uuid::new_v4());
+    send_json(&app, "PUT", &other, &json!({"name": "Wall", "frequency": "weekly", "source": "sheet", "external_id": "B2"})).await;
+    let res = send_json(&app, "PUT", &uri, &json!({"name": "Floor", "frequency": "monthly", "source": "sheet", "external_id": "B2"})).await;
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+    let res = send_json(&app, "PUT", &format!("/api/v1/rooms/{}/zones/{}", room.id, uuid::Uuid::new_v4()), &json!({"name": "Door", "frequency": "weekly", "source": "sheet", "external_id": "B2"})).await;
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Attic"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let attic: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    let res = send_json(&app, "PUT", &format!("/api/v1/rooms/{}/zones/{id}", attic.id), &json!({"name": "Floor", "frequency": "weekly"})).await;
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+
+    // смена внешней ссылки попадает в историю зоны
+    let res = send_json(&app, "GET", &format!("/api/v1/zones/{id}/events"), &json!({})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let history: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let refs: Vec<&serde_json::Value> = history.as_array().unwrap().iter().filter(|e| e["change"]["kind"] == "external_ref_changed").map(|e| &e["change"]).collect();
+    assert_eq!(refs, [&json!({"kind": "external_ref_changed", "source": "sheet", "external_id": "A1"}), &json!({"kind": "external_ref_changed", "source": null, "external_id": null})]);
+
+    let res = send_json(&app, "GET", &format!("/api/v1/rooms/{}/zones", room.id), &json!({})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zones: Page<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(zones.total, 2);
+
+    send_json(&app, "DELETE", &format!("/api/v1/zones/{id}"), &json!({})).await;
+    let res = send_json(&app, "PUT", &uri, &json!({"name": "Floor", "frequency": "weekly"})).await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn badge_counts_due_and_overdue_zones() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Study"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    let mut zones = Vec::new();
+    for name in ["Desk", "Shelf", "Chair"] {
+        let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", room.id), &json!({"name": name, "frequency": "daily"})).await;
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+        zones.push(zone.id);
+    }
+    // Desk — убрана только что, Shelf — просрочена на полдня, Chair — не убиралась
+    send_json(&app, "POST", &format!("/api/v1/zones/{}/clean", zones[0]), &json!({})).await;
+    let cleaned_at = chrono::Utc::now() - chrono::Duration::hours(36);
+    send_json(&app, "POST", &format!("/api/v1/zones/{}/clean", zones[1]), &json!({"cleaned_at": cleaned_at})).await;
+
+    let res = send_json(&app, "GET", "/api/v1/badge", &json!({})).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert!(res.headers()["cache-control"].to_str().unwrap().contains("max-age"));
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let badge: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(badge, json!({"due": 2, "overdue": 1}));
+}
+
+#[tokio::test]
+async fn metrics_export_due_zones_and_cleans() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Pantry"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    let mut zones = Vec::new();
+    for name in ["Shelves", "Floor"] {
+        let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", room.id), &json!({"name": name, "frequency": "weekly"})).await;
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+        zones.push(zone.id);
+    }
+    send_json(&app, "POST", &format!("/api/v1/zones/{}/clean", zones[0]), &json!({})).await;
+
+    let res = send_json(&app, "GET", "/api/v1/metrics", &json!({})).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert!(res.headers()["content-type"].to_str().unwrap().starts_with("text/plain"));
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let text = String::from_utf8(body.to_vec()).unwrap();
+    assert!(text.contains("# TYPE cleaner_cleans_total counter"));
+    assert!(text.lines().any(|l| l == "cleaner_zones 2"));
+    assert!(text.lines().any(|l| l == "cleaner_zones_due 1"));
+    assert!(text.lines().any(|l| l == "cleaner_cleans_last_hour 1"));
+}
+
+#[tokio::test]
+async fn zones_filter_and_sort_across_rooms() {
+    let app = test_app().await;
+
+    let mut rooms = Vec::new();
+    for name in ["Kitchen", "Bath"] {
+        let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": name})).await;
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+        rooms.push(room.id);
+    }
+    let mut ids = Vec::new();
+    for (room, name, frequency) in [(0, "Sink", "daily"), (0, "Oven", "monthly"), (1, "Tub", "daily"), (1, "Mirror", "weekly")] {
+        let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", rooms[room]), &json!({"name": name, "frequency": frequency})).await;
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+        ids.push(zone.id);
+    }
+    send_json(&app, "POST", &format!("/api/v1/zones/{}/clean", ids[1]), &json!({})).await;
+
+    let names = |page: Page<cleaner_api::models::ZoneView>| page.items.into_iter().map(|z| z.name).collect::<Vec<_>>();
+    let res = send_json(&app, "GET", "/api/v1/zones?sort=name&order=desc", &json!({})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(names(serde_json::from_slice(&body).unwrap()), ["Tub", "Sink", "Oven", "Mirror"]);
+
+    let res = send_json(&app, "GET", "/api/v1/zones?frequency=daily&sort=name", &json!({})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(names(serde_json::from_slice(&body).unwrap()), ["Sink", "Tub"]);
+
+    let res = send_json(&app, "GET", &format!("/api/v1/zones?room_id={}&is_due=false", rooms[0]), &json!({})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(names(serde_json::from_slice(&body).unwrap()), ["Oven"]);
+
+    // не убранные — раньше всех
+    let res = send_json(&app, "GET", &format!("/api/v1/rooms/{}/zones?sort=next_due_at&order=desc", rooms[0]), &json!({})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(names(serde_json::from_slice(&body).unwrap()), ["Oven", "Sink"]);
+
+    let res = send_json(&app, "GET", "/api/v1/rooms?sort=name", &json!({})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let page: Page<cleaner_api::models::RoomView> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(page.items.iter().map(|r| r.name.as_str()).collect::<Vec<_>>(), ["Bath", "Kitchen"]);
+
+    let res = send_json(&app, "GET", "/api/v1/zones?sort=dust", &json!({})).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn focus_fits_quick_due_zones_into_the_time_budget() {
+    let app = test_app().await;
+
+    let mut rooms = Vec::new();
+    for name in ["Kitchen", "Bath"] {
+        let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": name})).await;
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+        rooms.push(room.id);
+    }
+    for (room, name, minutes) in [(0, "Counter", json!(10)), (0, "Fridge", json!(25)), (1, "Sink", json!(15)), (1, "Tiles", json!(null))] {
+        send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", rooms[room]), &json!({"name": name, "frequency": "weekly", "estimated_minutes": minutes})).await;
+    }
+
+    let res = send_json(&app, "GET", "/api/v1/focus?minutes=30", &json!({})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let focus: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(focus["planned_minutes"], 25);
+    let mut picked: Vec<(String, String)> = focus["rooms"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .flat_map(|r| r["zones"].as_array().unwrap().iter().map(move |z| (r["room_name"].as_str().unwrap().to_string(), z["name"].as_str().unwrap().to_string())))
+        .collect();
+    picked.sort();
+    assert_eq!(picked, [("Bath".to_string(), "Sink".to_string()), ("Kitchen".to_string(), "Counter".to_string())]);
+
+    let res = send_json(&app, "GET", "/api/v1/focus?minutes=0", &json!({})).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn missing_or_deleted_parents_answer_404() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Hall"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", room.id), &json!({"name": "Mirror", "frequency": "weekly"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+
+    send_json(&app, "DELETE", &format!("/api/v1/zones/{}", zone.id), &json!({})).await;
+    for (method, uri) in [("GET", format!("/api/v1/zones/{}", zone.id)), ("GET", format!("/api/v1/zones/{}/tasks", zone.id)), ("POST", format!("/api/v1/zones/{}/tasks", zone.id))] {
+        let res = send_json(&app, method, &uri, &json!({"title": "Wipe"})).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND, "{method} {uri}");
+    }
+
+    send_json(&app, "DELETE", &format!("/api/v1/rooms/{}", room.id), &json!({})).await;
+    let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", room.id), &json!({"name": "Floor", "frequency": "weekly"})).await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    let res = send_json(&app, "GET", &format!("/api/v1/rooms/{}", room.id), &json!({})).await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn bulk_create_inserts_all_zones_or_none() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Kitchen"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    let uri = format!("/api/v1/rooms/{}/zones/bulk", room.id);
+
+    let bad = json!([{"name": "Sink", "frequency": "daily"}, {"name": " ", "frequency": "weekly"}]);
+    let res = send_json(&app, "POST", &uri, &bad).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let err: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert!(err["message"].as_str().unwrap().contains("zones[1]"));
+
+    let zones = json!([
+        {"name": "Sink", "frequency": "daily"},
+        {"name": "Oven", "frequency": "monthly"},
+        {"name": "Floor", "frequency": "weekly", "source": "tody", "external_id": "z-1"},
+    ]);
+    let res = send_json(&app, "POST", &uri, &zones).await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let created: Vec<cleaner_api::models::ZoneView> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(created.iter().map(|z| z.name.as_str()).collect::<Vec<_>>(), ["Sink", "Oven", "Floor"]);
+
+    // тот же external_id второй раз — откатывается весь список
+    let again = json!([{"name": "Shelf", "frequency": "weekly"}, {"name": "Floor", "frequency": "weekly", "source": "tody", "external_id": "z-1"}]);
+    let res = send_json(&app, "POST", &uri, &again).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    let res = send_json(&app, "GET", &format!("/api/v1/rooms/{}/zones", room.id), &json!({})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let page: Page<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(page.total, 3);
+}
+
+#[tokio::test]
+async fn bulk_update_and_delete_report_each_zone() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Bath"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    let zones = json!([{"name": "Sink", "frequency": "daily"}, {"name": "Tub", "frequency": "weekly"}]);
+    let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones/bulk", room.id), &zones).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let created: Vec<cleaner_api::models::ZoneView> = serde_json::from_slice(&body).unwrap();
+    let ids: Vec<&str> = created.iter().map(|z| z.id.as_str()).collect();
+
+    let res = send_json(&app, "POST", "/api/v1/zones/bulk/update", &json!({"zone_ids": [ids[0], "missing", ids[1]], "changes": {"frequency": "monthly"}})).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let bulk: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(bulk["updated"], 2);
+    let statuses: Vec<&str> = bulk["results"].as_array().unwrap().iter().map(|r| r["status"].as_str().unwrap()).collect();
+    assert_eq!(statuses, ["ok", "not_found", "ok"]);
+    let res = send_json(&app, "GET", &format!("/api/v1/zones/{}", ids[1]), &json!({})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let tub: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+    assert_eq!(tub.frequency, Frequency::Monthly);
+
+    let res = send_json(&app, "POST", "/api/v1/zones/bulk/update", &json!({"zone_ids": [ids[0]], "changes": {"frequency": "custom"}})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let bulk: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(bulk["results"][0]["status"], "validation_error");
+
+    let res = send_json(&app, "POST", "/api/v1/zones/bulk/delete", &json!({"zone_ids": [ids[0], ids[1], "missing"]})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let bulk: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(bulk["updated"], 2);
+    assert_eq!(bulk["results"][2]["status"], "not_found");
+    let res = send_json(&app, "GET", &format!("/api/v1/zones/{}", ids[0]), &json!({})).await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn zone_groups_span_rooms_and_clean_together() {
+    let app = test_app().await;
+
+    let mut zone_ids = Vec::new();
+    for room in ["Kitchen", "Hall"] {
+        let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": room})).await;
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+        let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", room.id), &json!({"name": "Floor", "frequency": "weekly"})).await;
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+        zone_ids.push(zone.id);
+    }
+
+    let res = send_json(&app, "POST", "/api/v1/groups", &json!({"name": "Guest-ready"})).await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let group: cleaner_api::models::ZoneGroup = serde_json::from_slice(&body).unwrap();
+    for id in &zone_ids {
+        let res = send_json(&app, "PUT", &format!("/api/v1/groups/{}/zones/{id}", group.id), &json!({})).await;
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+    }
+    let res = send_json(&app, "PUT", &format!("/api/v1/groups/{}/zones/missing", group.id), &json!({})).await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+    let res = send_json(&app, "GET", "/api/v1/today", &json!({})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let today: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(today["groups"], json!([{"id": group.id, "name": "Guest-ready", "zones": 2, "done": 0}]));
+
+    let res = send_json(&app, "POST", &format!("/api/v1/groups/{}/clean", group.id), &json!({"only_due": true})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let bulk: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(bulk["updated"], 2);
+
+    let res = send_json(&app, "GET", "/api/v1/today", &json!({})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let today: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(today["groups"][0]["done"], 2);
+    assert_eq!(today["zones"], json!([]));
+
+    send_json(&app, "DELETE", &format!("/api/v1/groups/{}", group.id), &json!({})).await;
+    let res = send_json(&app, "GET", &format!("/api/v1/groups/{}/zones", group.id), &json!({})).await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    let res = send_json(&app, "GET", &format!("/api/v1/zones/{}", zone_ids[0]), &json!({})).await;
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn patch_null_clears_and_absent_keeps() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Study", "icon": "book"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    let new_zone = json!({"name": "Desk", "icon": "lamp", "notes": "wipe", "frequency": "custom", "custom_interval_days": 3});
+    let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", room.id), &new_zone).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+    let uri = format!("/api/v1/zones/{}", zone.id);
+
+    let res = send_json(&app, "PATCH", &uri, &json!({"icon": null, "name": "Desk top"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+    assert_eq!(zone.icon, None);
+    assert_eq!(zone.notes.as_deref(), Some("wipe"));
+
+    let res = send_json(&app, "PATCH", &uri, &json!({"frequency": "weekly", "custom_interval_days": null, "notes": null})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+    assert_eq!(zone.custom_interval_days, None);
+    assert_eq!(zone.notes, None);
+
+    // custom без интервала по-прежнему ошибка
+    let res = send_json(&app, "PATCH", &uri, &json!({"frequency": "custom"})).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    let uri = format!("/api/v1/rooms/{}", room.id);
+    let res = send_json(&app, "PATCH", &uri, &json!({"name": "Office"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    assert_eq!(room.icon.as_deref(), Some("book"));
+    let res = send_json(&app, "PATCH", &uri, &json!({"icon": null})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    assert_eq!(room.icon, None);
+}
+
+#[tokio::test]
+async fn bulk_operation_runs_in_background_and_reports_progress() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Garage"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    let zones: Vec<serde_json::Value> = (0..60).map(|i| json!({"name": format!("Shelf {i}"), "frequency": "weekly"})).collect();
+    let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones/bulk", room.id), &json!(zones)).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let created: Vec<cleaner_api::models::ZoneView> = serde_json::from_slice(&body).unwrap();
+    let mut ids: Vec<&str> = created.iter().map(|z| z.id.as_str()).collect();
+    ids.push("missing");
+
+    let res = send_json(&app, "POST", "/api/v1/operations", &json!({"kind": "clean", "zone_ids": ids})).await;
+    assert_eq!(res.status(), StatusCode::ACCEPTED);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let op: cleaner_api::models::Operation = serde_json::from_slice(&body).unwrap();
+    assert_eq!(op.total, 61);
+
+    let mut op = op;
+    for _ in 0..100 {
+        let res = send_json(&app, "GET", &format!("/api/v1/operations/{}", op.id), &json!({})).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        op = serde_json::from_slice(&body).unwrap();
+        if op.status == "done" {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    assert_eq!(op.status, "done");
+    assert_eq!((op.processed, op.updated), (61, 60));
+    assert_eq!(op.results.last().unwrap().status, cleaner_api::models::BulkStatus::NotFound);
+    let res = send_json(&app, "GET", &format!("/api/v1/zones/{}", ids[0]), &json!({})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+    assert!(zone.last_cleaned_at.is_some());
+
+    let res = send_json(&app, "POST", "/api/v1/operations", &json!({"kind": "delete", "zone_ids": []})).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    let res = send_json(&app, "GET", "/api/v1/operations/missing", &json!({})).await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn bulk_operation_runs_once_when_worker_and_scheduler_overlap() {
+    let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+    let state = Arc::new(AppState::new(pool.clone(), &Config::default()).await.unwrap());
+    let app = Router::new().nest("/api/v1", api::routes()).with_state(state.clone());
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Garage"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    let zones: Vec<serde_json::Value> = (0..60).map(|i| json!({"name": format!("Shelf {i}"), "frequency": "weekly"})).collect();
+    let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones/bulk", room.id), &json!(zones)).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let created: Vec<cleaner_api::models::ZoneView> = serde_json::from_slice(&body).unwrap();
+    let ids: Vec<&str> = created.iter().map(|z| z.id.as_str()).collect();
+
+    // запрос запускает свой обработчик, а такты планировщика идут одновременно с ним
+    let res = send_json(&app, "POST", "/api/v1/operations", &json!({"kind": "clean", "zone_ids": ids})).await;
+    assert_eq!(res.status(), StatusCode::ACCEPTED);
+    let (a, b) = tokio::join!(api::operations::run_pending(&state), api::operations::run_pending(&state));
+    a.unwrap();
+    b.unwrap();
+    api::operations::run_pending(&state).await.unwrap();
+
+    let (cleaned,): (i64,) = sqlx::query_as("SELECT COUNT(1) FROM zone_events WHERE kind = 'cleaned'")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(cleaned, 60);
+}
+
+#[tokio::test]
+async fn streaks_count_consecutive_cleaning_days() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Kitchen"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", room.id), &json!({"name": "Sink", "frequency": "daily"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+
+    let res = send_json(&app, "GET", "/api/v1/stats/streaks", &json!({})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let none: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(none, json!({"current": 0, "longest": 0, "total_cleanings": 0, "last_cleaned_on": null}));
+
+    // два раза в один день считаются одним днём серии
+    for days_ago in [8, 7, 6, 5, 2, 1, 0, 0] {
+        let at = chrono::Utc::now() - chrono::Duration::days(days_ago);
+        send_json(&app, "POST", &format!("/api/v1/zones/{}/clean", zone.id), &json!({"cleaned_at": at})).await;
+    }
+    let res = send_json(&app, "GET", "/api/v1/stats/streaks", &json!({})).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let streaks: cleaner_api::api::stats::Streaks = serde_json::from_slice(&body).unwrap();
+    assert_eq!((streaks.current, streaks.longest, streaks.total_cleanings), (3, 4, 8));
+    assert_eq!(streaks.last_cleaned_on, Some(chrono::Utc::now().date_naive()));
+}
+
+#[tokio::test]
+async fn heatmap_counts_cleanings_per_day() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Bath"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", room.id), &json!({"name": "Tub", "frequency": "daily"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+    for days_ago in [3, 3, 0, 30] {
+        let at = chrono::Utc::now() - chrono::Duration::days(days_ago);
+        send_json(&app, "POST", &format!("/api/v1/zones/{}/clean", zone.id), &json!({"cleaned_at": at})).await;
+    }
+
+    let today = chrono::Utc::now().date_naive();
+    let from = today - chrono::Duration::days(6);
+    let res = send_json(&app, "GET", &format!("/api/v1/stats/heatmap?from={from}&to={today}"), &json!({})).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let map: cleaner_api::api::stats::Heatmap = serde_json::from_slice(&body).unwrap();
+    let counts: Vec<i64> = map.days.iter().map(|d| d.count).collect();
+    assert_eq!(counts, [0, 0, 0, 2, 0, 0, 1]);
+    assert_eq!((map.days[0].date, map.max), (from, 2));
+
+    let res = send_json(&app, "GET", &format!("/api/v1/stats/heatmap?from={today}&to={from}"), &json!({})).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    // крайние даты chrono дают 400, а не панику
+    for query in ["to=-262143-01-01", "from=%2B262142-12-20&to=%2B262142-12-31"] {
+        let res = send_json(&app, "GET", &format!("/api/v1/stats/heatmap?{query}"), &json!({})).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST, "{query}");
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let err: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(err["message"].as_str().unwrap().contains("supported range"), "{err}");
+    }
+}
+
+#[tokio::test]
+async fn exports_cleanings_and_zones_as_csv_or_json() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Hall, upstairs"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", room.id), &json!({"name": "Rug", "frequency": "weekly"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+    send_json(&app, "POST", &format!("/api/v1/zones/{}/clean", zone.id), &json!({"note": "said \"done\""})).await;
+
+    let res = send_json(&app, "GET", "/api/v1/export/cleanings.csv", &json!({})).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers()["content-type"], "text/csv; charset=utf-8");
+    assert!(res.headers().get("etag").is_none());
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let csv = String::from_utf8(body.to_vec()).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("occurred_at,zone_id,zone_name"));
+    assert!(lines[1].contains(&format!(",Rug,{},\"Hall, upstairs\",\"said \"\"done\"\"\",false,", room.id)));
+
+    let res = app
+        .clone()
+        .oneshot(
+            Request::get("/api/v1/export/zones.csv")
+                .header("accept", "application/json")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.headers()["content-type"], "application/json");
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zones: Vec<cleaner_api::api::export::ZoneRow> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(zones.len(), 1);
+    assert_eq!((zones[0].id.as_str(), zones[0].frequency.as_str()), (zone.id.to_string().as_str(), "weekly"));
+    assert!(zones[0].last_cleaned_at.is_some());
+}
+
+#[tokio::test]
+async fn trends_count_on_time_and_late_cleanings() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Hall"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", room.id), &json!({"name": "Floor", "frequency": "daily"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+    // первая уборка не в счёт; затем вовремя, с опозданием на три дня, вовремя
+    for days_ago in [10, 9, 5, 4] {
+        let at = chrono::Utc::now() - chrono::Duration::days(days_ago);
+        send_json(&app, "POST", &format!("/api/v1/zones/{}/clean", zone.id), &json!({"cleaned_at": at})).await;
+    }
+
+    let res = send_json(&app, "GET", "/api/v1/stats/trends?granularity=month", &json!({})).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let buckets: Vec<cleaner_api::api::stats::TrendBucket> = serde_json::from_slice(&body).unwrap();
+    assert!(buckets.windows(2).all(|w| w[0].period > w[1].period));
+    let on_time: i64 = buckets.iter().map(|b| b.on_time).sum();
+    let late: i64 = buckets.iter().map(|b| b.late).sum();
+    assert_eq!((on_time, late), (2, 1));
+
+    let res = send_json(&app, "GET", "/api/v1/stats/trends?granularity=day", &json!({})).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn overdue_buckets_zones_by_lateness() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Garage"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    // ежедневные: убрана 41 день назад — просрочка 40 дней, 6 — 5, 3 — 2, сегодня — без просрочки
+    for (name, days_ago) in [("A", 41), ("B", 6), ("C", 3), ("D", 0)] {
+        let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", room.id), &json!({"name": name, "frequency": "daily"})).await;
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+        let at = chrono::Utc::now() - chrono::Duration::days(days_ago);
+        send_json(&app, "POST", &format!("/api/v1/zones/{}/clean", zone.id), &json!({"cleaned_at": at})).await;
+    }
+
+    let res = send_json(&app, "GET", "/api/v1/stats/overdue", &json!({})).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let buckets: Vec<cleaner_api::api::stats::OverdueBucket> = serde_json::from_slice(&body).unwrap();
+    let labels: Vec<&str> = buckets.iter().map(|b| b.label.as_str()).collect();
+    assert_eq!(labels, ["30+", "8-30", "4-7", "1-3"]);
+    let names: Vec<Vec<&str>> = buckets.iter().map(|b| b.zones.iter().map(|z| z.name.as_str()).collect()).collect();
+    assert_eq!(names, [vec!["A"], vec![], vec!["B"], vec!["C"]]);
+    assert_eq!(buckets[0].zones[0].days_overdue, 40);
+}
+
+#[tokio::test]
+async fn cached_stats_follow_zone_changes() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Cellar"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    let counts = |app: Router| async move {
+        let res = send_json(&app, "GET", "/api/v1/zones/due?count_only=true", &json!({})).await;
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let due: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let res = send_json(&app, "GET", "/api/v1/badge", &json!({})).await;
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let badge: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        (due["count"].as_i64().unwrap(), badge["due"].as_i64().unwrap())
+    };
+    assert_eq!(counts(app.clone()).await, (0, 0));
+
+    // кэш живёт секунды, но новая зона видна сразу
+    let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", room.id), &json!({"name": "Shelves", "frequency": "monthly"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+    assert_eq!(counts(app.clone()).await, (1, 1));
+
+    send_json(&app, "POST", &format!("/api/v1/zones/{}/clean", zone.id), &json!({})).await;
+    assert_eq!(counts(app.clone()).await, (0, 0));
+}
+
+#[tokio::test]
+async fn time_spent_sums_timed_cleanings() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Study"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    let mut zones = Vec::new();
+    for name in ["Desk", "Shelf"] {
+        let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", room.id), &json!({"name": name, "frequency": "daily"})).await;
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+        zones.push(zone.id);
+    }
+    for (zone, minutes) in [(&zones[0], json!(10)), (&zones[0], json!(20)), (&zones[0], json!(null)), (&zones[1], json!(5))] {
+        let res = send_json(&app, "POST", &format!("/api/v1/zones/{zone}/clean"), &json!({"duration_minutes": minutes})).await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+    let res = send_json(&app, "POST", &format!("/api/v1/zones/{}/clean", zones[1]), &json!({"duration_minutes": 0})).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    let res = send_json(&app, "GET", "/api/v1/stats/time", &json!({})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let by_zone: Vec<cleaner_api::api::stats::TimeSpent> = serde_json::from_slice(&body).unwrap();
+    let rows: Vec<(&str, i64, i64, f64)> =
+        by_zone.iter().map(|t| (t.label.as_str(), t.timed_cleanings, t.total_minutes, t.average_minutes)).collect();
+    assert_eq!(rows, [("Desk", 2, 30, 15.0), ("Shelf", 1, 5, 5.0)]);
+
+    let res = send_json(&app, "GET", "/api/v1/stats/time?by=room", &json!({})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let by_room: Vec<cleaner_api::api::stats::TimeSpent> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(by_room.len(), 1);
+    assert_eq!((by_room[0].key.as_str(), by_room[0].total_minutes), (room.id.as_str(), 35));
+}
+
+#[test]
+fn monthly_schedules_follow_the_calendar() {
+    use chrono::{DateTime, Utc};
+    use cleaner_api::models::compute_next_due;
+
+    let at = |s: &str| s.parse::<DateTime<Utc>>().unwrap();
+    let next = |last: &str, freq: Frequency| compute_next_due(Some(at(last)), freq, None, None).unwrap();
+
+    // same day of the month, clamped to the end of shorter months
+    assert_eq!(next("2025-01-15T09:00:00Z", Frequency::Monthly), at("2025-02-15T09:00:00Z"));
+    assert_eq!(next("2025-01-31T09:00:00Z", Frequency::Monthly), at("2025-02-28T09:00:00Z"));
+    assert_eq!(next("2024-01-31T09:00:00Z", Frequency::Monthly), at("2024-02-29T09:00:00Z"));
+
+    // second Tuesday of June → second Tuesday of July
+    assert_eq!(next("2025-06-10T18:30:00Z", Frequency::MonthlyWeekday), at("2025-07-08T18:30:00Z"));
+    // fifth (last) Friday of May → last Friday of June, which is the fourth
+    assert_eq!(next("2025-05-30T08:00:00Z", Frequency::MonthlyWeekday), at("2025-06-27T08:00:00Z"));
+    assert_eq!(next("2025-12-09T08:00:00Z", Frequency::MonthlyWeekday), at("2026-01-13T08:00:00Z"));
+    assert_eq!("monthly_weekday".parse::<Frequency>(), Ok(Frequency::MonthlyWeekday));
+}
+
+#[tokio::test]
+async fn database_rejects_unknown_frequencies() {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+    let now = chrono::Utc::now();
+    sqlx::query("INSERT INTO rooms(id, name, created_at, updated_at) VALUES ('r1', 'Hall', ?1, ?1)")
+        .bind(now)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let insert = |frequency: &'static str| {
+        sqlx::query(
+            r#"INSERT INTO zones(id, room_id, name, frequency, created_at, updated_at)
+               VALUES (?1, 'r1', 'Floor', ?1, ?2, ?2)"#,
+        )
+        .bind(frequency)
+        .bind(now)
+        .execute(&pool)
+    };
+    assert!(insert("monthly_weekday").await.is_ok());
+    let err = insert("fortnightly").await.unwrap_err();
+    assert!(err.to_string().contains("unknown zone frequency"), "{err}");
+    let err = sqlx::query("UPDATE zones SET frequency = 'sometimes'").execute(&pool).await.unwrap_err();
+    assert!(err.to_string().contains("unknown zone frequency"), "{err}");
+
+    let (frequency,): (Frequency,) = sqlx::query_as("SELECT frequency FROM zones").fetch_one(&pool).await.unwrap();
+    assert_eq!(frequency, Frequency::MonthlyWeekday);
+}
+
+#[tokio::test]
+async fn cached_overview_follows_zone_and_room_writes() {
+    let app = test_app().await;
+    let overview = |app: Router| async move {
+        let res = send_json(&app, "GET", "/api/v1/stats/overview", &json!({})).await;
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let o: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        (o["rooms_total"].as_i64().unwrap(), o["zones_total"].as_i64().unwrap(), o["due_zones"].as_i64().unwrap())
+    };
+    assert_eq!(overview(app.clone()).await, (0, 0, 0));
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Attic"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    assert_eq!(overview(app.clone()).await, (1, 0, 0));
+
+    let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", room.id), &json!({"name": "Beams", "frequency": "monthly"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+    assert_eq!(overview(app.clone()).await, (1, 1, 1));
+
+    send_json(&app, "POST", &format!("/api/v1/zones/{}/clean", zone.id), &json!({})).await;
+    assert_eq!(overview(app.clone()).await, (1, 1, 0));
+
+    send_json(&app, "DELETE", &format!("/api/v1/zones/{}", zone.id), &json!({})).await;
+    send_json(&app, "DELETE", &format!("/api/v1/rooms/{}", room.id), &json!({})).await;
+    assert_eq!(overview(app.clone()).await, (0, 0, 0));
+}
+
+#[tokio::test]
+async fn reorder_zones_rejects_duplicate_ids() {
+    let app = test_app().await;
+
+    let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": "Hall"})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+    let mut ids = Vec::new();
+    for name in ["Mirror", "Rug"] {
+        let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", room.id), &json!({"name": name, "frequency": "weekly"})).await;
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let zone: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+        ids.push(zone.id);
+    }
+
+    let uri = format!("/api/v1/rooms/{}/zones/reorder", room.id);
+    let res = send_json(&app, "POST", &uri, &json!({"ids": [ids[1], ids[0], ids[1]]})).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    let res = send_json(&app, "POST", &uri, &json!({"ids": [ids[1], ids[0]]})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let zones: Vec<cleaner_api::models::ZoneView> = serde_json::from_slice(&body).unwrap();
+    let names: Vec<&str> = zones.iter().map(|z| z.name.as_str()).collect();
+    assert_eq!(names, ["Rug", "Mirror"]);
+}
+
+#[tokio::test]
+async fn external_id_upsert_stays_in_its_room() {
+    let app = test_app().await;
+
+    let mut rooms = Vec::new();
+    for name in ["Kitchen", "Bath"] {
+        let res = send_json(&app, "POST", "/api/v1/rooms", &json!({"name": name})).await;
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let room: cleaner_api::models::RoomView = serde_json::from_slice(&body).unwrap();
+        rooms.push(room.id);
+    }
+    let zone = json!({"name": "Sink", "frequency": "daily", "source": "tody", "external_id": "z-1"});
+    let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", rooms[0]), &zone).await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let created: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+
+    let again = json!({"name": "Sink & tap", "frequency": "daily", "source": "tody", "external_id": "z-1"});
+    let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", rooms[0]), &again).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let updated: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+    assert_eq!(updated.id, created.id);
+
+    // тот же external_id из другой комнаты не переписывает чужую зону
+    let res = send_json(&app, "POST", &format!("/api/v1/rooms/{}/zones", rooms[1]), &zone).await;
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+    let res = send_json(&app, "GET", &format!("/api/v1/zones/{}", created.id), &json!({})).await;
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let kept: cleaner_api::models::ZoneView = serde_json::from_slice(&body).unwrap();
+    assert_eq!((kept.name.as_str(), kept.room_id.as_str()), ("Sink & tap", rooms[0].as_str()));
+}